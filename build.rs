@@ -0,0 +1,52 @@
+//! Embeds build information for `engine::core::build_info` at compile time.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_owned())
+}
+
+fn main() {
+    let commit = match git(&["rev-parse", "--short=12", "HEAD"]) {
+        Some(commit) if !commit.is_empty() => {
+            let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).is_some_and(|status| !status.is_empty());
+            if dirty { format!("{}-dirty", commit) } else { commit }
+        }
+        _ => "unknown".to_owned(),
+    };
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| name.strip_prefix("CARGO_FEATURE_").map(|feature| feature.to_ascii_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=MALKMUSL_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=MALKMUSL_BUILD_PROFILE={}", env::var("PROFILE").unwrap_or_else(|_| "unknown".to_owned()));
+    println!("cargo:rustc-env=MALKMUSL_BUILD_TARGET={}", env::var("TARGET").unwrap_or_else(|_| "unknown".to_owned()));
+    println!("cargo:rustc-env=MALKMUSL_BUILD_FEATURES={}", features.join(","));
+
+    // Any rerun-if-changed line stops Cargo from rerunning this script when
+    // sources change, so the commit is refreshed when HEAD, the branch it
+    // points to or the index move. The `-dirty` suffix is best-effort: editing
+    // a file without staging it keeps the suffix of the last run.
+    println!("cargo:rerun-if-changed=build.rs");
+    let Some(git_dir) = git(&["rev-parse", "--git-dir"]) else {
+        return;
+    };
+    let mut watched = vec!["HEAD".to_owned(), "index".to_owned(), "packed-refs".to_owned()];
+    if let Some(head_ref) = git(&["rev-parse", "--symbolic-full-name", "HEAD"]).filter(|head_ref| head_ref.starts_with("refs/")) {
+        watched.push(head_ref);
+    }
+    for path in watched {
+        let path = Path::new(&git_dir).join(path);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
@@ -2,3 +2,4 @@ pub mod loader;
 pub mod texture_loader;
 pub mod texture_tilesets;
 pub mod crypt;
+pub mod plugin;
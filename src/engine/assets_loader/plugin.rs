@@ -0,0 +1,46 @@
+use crate::engine::console_logger::logger;
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::Plugin;
+use crate::engine::core::app::schedule::Stage;
+use crate::engine::core::ecs::system::Res;
+
+use super::texture_tilesets::get_texture_atlas;
+
+/// The texture atlases loaded before the game starts, see `AssetLoaderPlugin`.
+pub struct PreloadedAtlases(pub Vec<&'static str>);
+
+/// Loads texture atlases in the `PreInit` stage, so the first frames do not
+/// stall on decoding them. Atlases not preloaded are loaded on first use.
+pub(crate) struct AssetLoaderPlugin {
+    atlases: Vec<&'static str>,
+}
+
+#[allow(dead_code)]
+impl AssetLoaderPlugin {
+    /// Preloads the atlases named as in map files, e.g. `"OUTSIDE_ATLAS"`.
+    pub fn with_atlases(atlases: &[&'static str]) -> Self {
+        AssetLoaderPlugin { atlases: atlases.to_vec() }
+    }
+}
+
+impl Default for AssetLoaderPlugin {
+    fn default() -> Self {
+        AssetLoaderPlugin::with_atlases(&["OUTSIDE_ATLAS"])
+    }
+}
+
+impl Plugin for AssetLoaderPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.world_mut().insert_resource(PreloadedAtlases(self.atlases.clone()));
+        app.add_systems(Stage::PreInit, preload_atlases);
+    }
+}
+
+fn preload_atlases(atlases: Res<PreloadedAtlases>) {
+    for name in &atlases.0 {
+        match get_texture_atlas(name) {
+            Some(atlas) => atlas.lock().expect("Failed to lock texture atlas").ensure_loaded(),
+            None => println!("{}", logger::warn_assets(&format!("Cannot preload unknown atlas {}", name))),
+        }
+    }
+}
@@ -1,9 +1,7 @@
 #![allow(dead_code, unused_variables)]
 use crate::{logger_warn_assetloader, logger_error_assetloader, logger_info_assetloader};
 use crate::engine::assets_loader::loader::*;
-use crate::engine::assets_loader;
 use crate::engine::console_logger::logger;
-use crate::engine::core::renderer::d2::background_tiles::Tile;
 use glium::texture::RawImage2d;
 use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba};
 use glium::{texture::SrgbTexture2d, Display};
@@ -28,7 +26,7 @@ pub struct TextureAtlas {
 
 impl TextureAtlas {
     pub fn new(path: &str, atlas_name: &str, texture_size: [u32; 2]) -> TextureAtlas {
-        let asset_path = format!("{}/{}{}.png", ASSET_FOLDER, path, atlas_name);
+        let asset_path = format!("{}/{}{}.png", asset_root(), path, atlas_name);
         logger_info_assetloader!("Loading tileset from {}", asset_path);
         let image = image::open(&asset_path).map_err(|e| {
             logger_error_assetloader!("Error opening image: {:?}", e);
@@ -63,47 +61,69 @@ impl TextureAtlas {
         self.atlas_height / self.texture_size[1]
     }
 
-    pub fn load_texture_from_tileset_to_map(&mut self, display: &Display) {
-    
+    /// Slices the atlas image into `texture_size` pieces and stores them in
+    /// `textures`, numbered row by row starting at the top-left corner.
+    pub fn load_texture_from_tileset_to_map(&mut self) {
         let rows = self.get_rows();
-        if rows <= 0 {logger_error_assetloader!("Failed to get Rows form image!");} 
+        if rows == 0 {logger_error_assetloader!("Failed to get Rows form image!");}
 
         let cols = self.get_columns();
-        if rows <= 0 {logger_error_assetloader!("Failed to get Collums form image!");} 
+        if cols == 0 {logger_error_assetloader!("Failed to get Collums form image!");}
 
-        let max = rows * cols;
+        let texture_width = self.texture_size[0];
+        let texture_height = self.texture_size[1];
 
         let mut id: u32 = 0;
-    
+
         for col in 0..cols {
             for row in 0..rows {
-                if id < max {
-                    let texture_width = self.texture_size[0] as u32;
-                    let texture_height = self.texture_size[1] as u32;
-
-                    let y = col * texture_width;
-                    let x = row * texture_height;
-
-                    logger_warn_assetloader!("Loading texture [{}] at position ({}, {}) in atlas", id, x, y);
-
-                    let cropped_image = self.atlas_image.view(x, y, texture_width, texture_height);
-        
-        
-                    let texture_data = cropped_image.to_image();
-                    self.textures.insert(id, texture_data);
-        
-                    id += 1;
-                } else {
-                    // Exit the loop when id reaches max
-                    return;
-                }
+                let y = col * texture_height;
+                let x = row * texture_width;
+
+                logger_warn_assetloader!("Loading texture [{}] at position ({}, {}) in atlas", id, x, y);
+
+                let cropped_image = self.atlas_image.view(x, y, texture_width, texture_height);
+                self.textures.insert(id, cropped_image.to_image());
+
+                id += 1;
             }
         }
-         
-        // If the loop completes without finding the position, it's out of range
-        if id > max{logger_error_assetloader!("Failed to load Texture <{}>: the Texture Position is out of range! Max Rows: {} Columns: {}", self.atlas_name.to_uppercase(), rows, cols);}
-        // You might want to return a default texture or handle this case appropriately
-        assets_loader::loader::load_texture(&display.clone(), "apple.png");
+    }
+
+    /// Makes sure the atlas has been sliced into textures.
+    pub fn ensure_loaded(&mut self) {
+        if self.textures.is_empty() {
+            self.load_texture_from_tileset_to_map();
+        }
+    }
+
+    /// Returns the pixel data of the texture with the given id, if it exists.
+    pub fn get_texture(&self, id: u32) -> Option<&ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.textures.get(&id)
+    }
+
+    /// Returns the texture coordinates of the texture with the given id inside
+    /// the whole atlas image as `[left, bottom, right, top]`.
+    ///
+    /// `v = 0` is the bottom row of the image, matching atlas images uploaded
+    /// with `RawImage2d::from_raw_rgba_reversed`. Ids are numbered like in
+    /// `load_texture_from_tileset_to_map`.
+    pub fn uv_rect(&self, id: u32) -> Option<[f32; 4]> {
+        let [texture_width, texture_height] = self.texture_size;
+        let per_row = self.get_rows();
+        if per_row == 0 || id >= per_row * self.get_columns() {
+            return None;
+        }
+        let x = (id % per_row * texture_width) as f32;
+        let y = (id / per_row * texture_height) as f32;
+        let width = self.atlas_width as f32;
+        let height = self.atlas_height as f32;
+        Some([
+            x / width,
+            1.0 - (y + texture_height as f32) / height,
+            (x + texture_width as f32) / width,
+            1.0 - y / height,
+        ])
     }
 
     pub fn load_texture_from_map(&self, id: u32, display: Display) -> SrgbTexture2d {
@@ -119,7 +139,7 @@ impl TextureAtlas {
             }
         };
 
-        let image_dimensions = (32 as u32, 32 as u32);
+        let image_dimensions = texture_data.dimensions();
         let raw_image = RawImage2d::from_raw_rgba_reversed(&texture_data.clone().into_raw(), image_dimensions);
 
         match SrgbTexture2d::new(&display, raw_image) {
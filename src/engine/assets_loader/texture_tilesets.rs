@@ -138,3 +138,63 @@ lazy_static! {
         Arc::new(Mutex::new(atlas))
     };
 }
+
+/// Names of every tileset atlas, as used in map files.
+pub const ATLAS_NAMES: [&str; 22] = [
+    "BIKE_SHOP_INTERIOR_ATLAS",
+    "BOAT_ATLAS",
+    "CAVES_ATLAS",
+    "DEPARTMENT_STORE_INTERIOR_ATLAS",
+    "DUNGEON_CAVE_ATLAS",
+    "DUNGEON_FOREST_ATLAS",
+    "FACTORY_INTERIOR_ATLAS",
+    "GAME_CORNER_INTERIOR_ATLAS",
+    "GRAVEYARD_TOWER_INTERIOR_ATLAS",
+    "GYMS_INTERIOR_ATLAS",
+    "HARBOUR_INTERIOR_ATLAS",
+    "INTERIOR_GENERAL_ATLAS",
+    "MANSION_INTERIOR_ATLAS",
+    "MART_INTERIOR_ATLAS",
+    "MULTIPLAYER_ROOMS_ATLAS",
+    "MUSEUM_INTERIOR_ATLAS",
+    "POKE_CENTRE_INTERIOR_ATLAS",
+    "RUINS_INTERIOR_ATLAS",
+    "TRAINER_TOWER_INTERIOR_ATLAS",
+    "UNDERGROUND_PATH_ATLAS",
+    "UNDERWATER_ATLAS",
+    "OUTSIDE_ATLAS",
+];
+
+/// Returns the `'static` spelling of an atlas name, or `None` for unknown atlases.
+pub fn get_atlas_name(name: &str) -> Option<&'static str> {
+    ATLAS_NAMES.iter().copied().find(|atlas_name| *atlas_name == name)
+}
+
+/// Looks up a tileset atlas by the name used in map files, e.g. `"OUTSIDE_ATLAS"`.
+pub fn get_texture_atlas(name: &str) -> Option<&'static Arc<Mutex<TextureAtlas>>> {
+    match name {
+        "BIKE_SHOP_INTERIOR_ATLAS" => Some(&BIKE_SHOP_INTERIOR_ATLAS),
+        "BOAT_ATLAS" => Some(&BOAT_ATLAS),
+        "CAVES_ATLAS" => Some(&CAVES_ATLAS),
+        "DEPARTMENT_STORE_INTERIOR_ATLAS" => Some(&DEPARTMENT_STORE_INTERIOR_ATLAS),
+        "DUNGEON_CAVE_ATLAS" => Some(&DUNGEON_CAVE_ATLAS),
+        "DUNGEON_FOREST_ATLAS" => Some(&DUNGEON_FOREST_ATLAS),
+        "FACTORY_INTERIOR_ATLAS" => Some(&FACTORY_INTERIOR_ATLAS),
+        "GAME_CORNER_INTERIOR_ATLAS" => Some(&GAME_CORNER_INTERIOR_ATLAS),
+        "GRAVEYARD_TOWER_INTERIOR_ATLAS" => Some(&GRAVEYARD_TOWER_INTERIOR_ATLAS),
+        "GYMS_INTERIOR_ATLAS" => Some(&GYMS_INTERIOR_ATLAS),
+        "HARBOUR_INTERIOR_ATLAS" => Some(&HARBOUR_INTERIOR_ATLAS),
+        "INTERIOR_GENERAL_ATLAS" => Some(&INTERIOR_GENERAL_ATLAS),
+        "MANSION_INTERIOR_ATLAS" => Some(&MANSION_INTERIOR_ATLAS),
+        "MART_INTERIOR_ATLAS" => Some(&MART_INTERIOR_ATLAS),
+        "MULTIPLAYER_ROOMS_ATLAS" => Some(&MULTIPLAYER_ROOMS_ATLAS),
+        "MUSEUM_INTERIOR_ATLAS" => Some(&MUSEUM_INTERIOR_ATLAS),
+        "POKE_CENTRE_INTERIOR_ATLAS" => Some(&POKE_CENTRE_INTERIOR_ATLAS),
+        "RUINS_INTERIOR_ATLAS" => Some(&RUINS_INTERIOR_ATLAS),
+        "TRAINER_TOWER_INTERIOR_ATLAS" => Some(&TRAINER_TOWER_INTERIOR_ATLAS),
+        "UNDERGROUND_PATH_ATLAS" => Some(&UNDERGROUND_PATH_ATLAS),
+        "UNDERWATER_ATLAS" => Some(&UNDERWATER_ATLAS),
+        "OUTSIDE_ATLAS" => Some(&OUTSIDE_ATLAS),
+        _ => None,
+    }
+}
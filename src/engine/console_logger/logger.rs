@@ -1,10 +1,8 @@
 #![allow(dead_code)]
 use chrono::Local;
 use crate::engine::assets_loader::loader::ASSET_PREFIX;
-use crate::engine::core::renderer::core::GAME_STATE_DEBUG;
-use crate::engine::core::entity::player::Player;
-use crate::engine::core::entity::player::{PLAYER_DEBUG, PLAYER_MOVEMENT_DEBUG};
-use crate::engine::core::metadata::{DEBUG, COLOR_YELLOW, COLOR_CYAN};
+use crate::engine::core::config::{log_enabled, LogLevel, Subsystem};
+use crate::engine::core::metadata::{COLOR_YELLOW, COLOR_CYAN};
 
 use crate::engine::core::metadata;
 use crate::engine::core::renderer::core::GameStatus;
@@ -32,7 +30,7 @@ use crate::engine::core::renderer::core::opengl::OPENGL_PREFIX;
 /// ```
 pub fn set_color(color: metadata::Color, value: &str) -> String {
     let color = format!("\x1b[38;2;{};{};{}m{}", (color.red * 255.0) as u8, (color.green * 255.0) as u8, (color.blue * 255.0) as u8, value);
-    return color;
+    color
 }
 
 /// Resets the text color and formatting to the default settings.
@@ -51,8 +49,7 @@ pub fn set_color(color: metadata::Color, value: &str) -> String {
 /// println!("{}This text has been reset.", reset_code);
 /// ```
 pub fn reset_color() -> String {
-    let color = format!("\x1b[0m");
-    return color;
+    "\x1b[0m".to_string()
 }
 
 /// Gets the current local time and formats it as a string.
@@ -73,7 +70,7 @@ pub fn reset_color() -> String {
 /// ```
 pub fn get_time() -> String {
     let time = Local::now().format("[%Y-%m-%d][%H:%M:%S]").to_string();
-    return time;
+    time
 }
 
 /// Logs an informational message with a timestamp and green "[INFO]" prefix.
@@ -101,7 +98,7 @@ pub fn info(args: &str) -> String{
     let color =  metadata::COLOR_GREEN;
     let prefix = set_color(color, "[INFO] ");
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
-    let msg = reset_color() + &format!("{}", args);
+    let msg = reset_color() + args;
 
     let log_message = format!("{}{}{}", time, prefix, msg);
     log_message
@@ -110,7 +107,7 @@ pub fn info(args: &str) -> String{
 pub fn info_assets(args: &str) -> String{
     let color =  metadata::COLOR_GREEN;
     let prefix = set_color(color, "[INFO] ");
-    let perfix_2 = set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str());
+    let perfix_2 = set_color(COLOR_YELLOW, ASSET_PREFIX.as_str());
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
     let msg = reset_color() + &format!(" {}", args);
 
@@ -121,7 +118,7 @@ pub fn info_assets(args: &str) -> String{
 pub fn info_opengl(args: &str) -> String{
     let color =  metadata::COLOR_GREEN;
     let prefix = set_color(color, "[INFO] ");
-    let perfix_2 = set_color(COLOR_CYAN, *&OPENGL_PREFIX.as_str());
+    let perfix_2 = set_color(COLOR_CYAN, OPENGL_PREFIX.as_str());
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
     let msg = reset_color() + &format!(" {}", args);
 
@@ -164,9 +161,9 @@ pub fn warn(args: &str) -> String{
 pub fn warn_assets(args: &str) -> String{
     let color =  metadata::COLOR_YELLOW;
     let prefix = set_color(color, "[WARN] ");
-    let perfix_2 = set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str());
+    let perfix_2 = set_color(COLOR_YELLOW, ASSET_PREFIX.as_str());
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
-    let msg = reset_color() + &format!("{}", args);
+    let msg = reset_color() + args;
 
     let log_message = format!("{}{}{}{}", time, prefix, perfix_2, msg);
     log_message
@@ -175,7 +172,7 @@ pub fn warn_assets(args: &str) -> String{
 pub fn warn_opengl(args: &str) -> String{
     let color =  metadata::COLOR_YELLOW;
     let prefix = set_color(color, "[WARN] ");
-    let perfix_2 = set_color(COLOR_CYAN, *&OPENGL_PREFIX.as_str());
+    let perfix_2 = set_color(COLOR_CYAN, OPENGL_PREFIX.as_str());
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
     let msg = reset_color() + &format!(" {}", args);
 
@@ -207,7 +204,7 @@ pub fn error(args: &str) -> String{
     let color =  metadata::COLOR_RED;
     let prefix = set_color(color, "[ERROR] ");
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
-    let msg = reset_color() + &format!("{}", args);
+    let msg = reset_color() + args;
 
     let log_message = format!("{}{}{}", time, prefix, msg);
     log_message
@@ -216,9 +213,9 @@ pub fn error(args: &str) -> String{
 pub fn error_assets(args: &str) -> String{
     let color =  metadata::COLOR_RED;
     let prefix = set_color(color, "[ERROR] ");
-    let perfix_2 = set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str());
+    let perfix_2 = set_color(COLOR_YELLOW, ASSET_PREFIX.as_str());
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
-    let msg = reset_color() + &format!("{}", args);
+    let msg = reset_color() + args;
 
     let log_message = format!("{}{}{}{}", time, prefix, perfix_2, msg);
     log_message
@@ -227,9 +224,9 @@ pub fn error_assets(args: &str) -> String{
 pub fn error_opengl(args: &str) -> String{
     let color =  metadata::COLOR_RED;
     let prefix = set_color(color, "[ERROR] ");
-    let perfix_2 = set_color(COLOR_CYAN, *&OPENGL_PREFIX.as_str());
+    let perfix_2 = set_color(COLOR_CYAN, OPENGL_PREFIX.as_str());
     let time = set_color(metadata::COLOR_DARK_GREY, get_time().as_str())+ " ";
-    let msg = reset_color() + &format!("{}", args);
+    let msg = reset_color() + args;
 
     let log_message = format!("{}{}{}{}", time, prefix, perfix_2, msg);
     log_message
@@ -238,7 +235,7 @@ pub fn error_opengl(args: &str) -> String{
 /// Logs the game state with optional debugging information.
 /// 
 /// This function logs the current game state along with optional debugging information
-/// if debugging flags are enabled (DEBUG or GAME_STATE_DEBUG). It supports different
+/// if the `game_state` subsystem logs at `info` level, see `EngineConfig`. It supports different
 /// game states such as "Running," "Paused," and "Stopped" with corresponding log prefixes
 /// and messages. The logged message is printed to the console and returned as a formatted string.
 /// 
@@ -249,7 +246,7 @@ pub fn error_opengl(args: &str) -> String{
 /// 
 /// # Returns
 /// 
-/// A formatted string containing the log message, or an empty string if not in debugging mode.
+/// A formatted string containing the log message, or an empty string if game state logging is disabled.
 /// 
 /// # Example
 /// 
@@ -260,8 +257,8 @@ pub fn error_opengl(args: &str) -> String{
 /// println!("{}", log_result);
 /// ```
 pub fn game_state(state: GameStatus, exit_code: i8) -> String {
-    // Check if either DEBUG or GAME_STATE_DEBUG is enabled
-    if DEBUG || GAME_STATE_DEBUG {
+    // Check if game state messages are logged
+    if log_enabled(Subsystem::GameState, LogLevel::Info) {
         // Match the game state to determine the log message
         match state {
             GameStatus::Running => {
@@ -298,13 +295,14 @@ pub fn game_state(state: GameStatus, exit_code: i8) -> String {
 /// Logs player movement with optional debugging information.
 /// 
 /// This function logs player movement along with optional debugging information
-/// if debugging flags are enabled (DEBUG, PLAYER_DEBUG, or PLAYER_MOVEMENT_DEBUG).
+/// if the `player_movement` subsystem logs at `debug` level, see `EngineConfig`.
 /// It logs the player's name, keycode pressed, and current position. The logged message
 /// is printed to the console and returned as a formatted string.
 /// 
 /// # Arguments
 /// 
-/// * `player` - A mutable reference to the player object.
+/// * `name` - The name of the moving entity.
+/// * `position` - The entity's world position.
 /// * `keycode` - The keycode pressed by the player.
 /// 
 /// # Returns
@@ -314,17 +312,16 @@ pub fn game_state(state: GameStatus, exit_code: i8) -> String {
 /// # Example
 /// 
 /// ```
-/// let mut player = Player::new("Alice");
 /// let keycode = "W";
-/// let log_result = debug_player_movement(&mut player, keycode);
+/// let log_result = debug_player_movement("Alice", [0.0, 0.0], keycode);
 /// println!("{}", log_result);
 /// ```
-pub fn debug_player_movement(player: &mut Player, keycode: &str) -> String {
-    // Check if DEBUG or PLAYER_DEBUG or PLAYER_MOVEMENT_DEBUG is enabled
-    if DEBUG || PLAYER_DEBUG || PLAYER_MOVEMENT_DEBUG {
+pub fn debug_player_movement(name: &str, position: [f32; 2], keycode: &str) -> String {
+    // Check if player movement is logged
+    if log_enabled(Subsystem::PlayerMovement, LogLevel::Debug) {
         // Prepare the log message components
         let prefix = set_color(metadata::COLOR_MAGENTA, "[PlayerMovement]") + &reset_color();
-        let name = set_color(metadata::COLOR_CYAN, format!("[{}]", player.name).as_str()) + &reset_color();
+        let name = set_color(metadata::COLOR_CYAN, format!("[{}]", name).as_str()) + &reset_color();
         
         // Create the log message
         let log = format!(
@@ -332,12 +329,12 @@ pub fn debug_player_movement(player: &mut Player, keycode: &str) -> String {
             prefix,
             name,
             keycode,
-            player.position[0],
-            player.position[1]
+            position[0],
+            position[1]
         );
 
         // Log the message as an informational message
-        info(log.as_str());
+        println!("{}", info(log.as_str()));
 
         // Return the logged message
         log
@@ -52,46 +52,52 @@ macro_rules! logger_error {
     };
 }
 
-//AssetLoader messages
+//AssetLoader messages, filtered by the `assets` log level
 #[macro_export]
 macro_rules! logger_info_assetloader {
     ($($args:tt)*) => {
-        let color = COLOR_GREEN;
-        let prefix = set_color(color, "[INFO] ");
-        let perfix_2 = format!("{} ", set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str()));
-        let time = set_color(COLOR_DARK_GREY, get_time().as_str())+ " ";
-        let msg = reset_color() +format_args!($($args)*).to_string().as_str();
-
-        let log_message = format!("{}{}{} {}", time, prefix, perfix_2, msg);
-        println!("{}", log_message.as_str());
+        if $crate::engine::core::config::log_enabled($crate::engine::core::config::Subsystem::Assets, $crate::engine::core::config::LogLevel::Info) {
+            let color = COLOR_GREEN;
+            let prefix = set_color(color, "[INFO] ");
+            let perfix_2 = format!("{} ", set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str()));
+            let time = set_color(COLOR_DARK_GREY, get_time().as_str())+ " ";
+            let msg = reset_color() +format_args!($($args)*).to_string().as_str();
+
+            let log_message = format!("{}{}{} {}", time, prefix, perfix_2, msg);
+            println!("{}", log_message.as_str());
+        }
     };
 }
 
 #[macro_export]
 macro_rules! logger_warn_assetloader {
     ($($args:tt)*) => {
-        let color = COLOR_YELLOW;
-        let prefix = set_color(color, "[WARN] ");
-        let perfix_2 = format!("{} ", set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str()));
-        let time = set_color(COLOR_DARK_GREY, get_time().as_str())+ " ";
-        let msg = reset_color() +format_args!($($args)*).to_string().as_str();
-
-        let log_message = format!("{}{}{} {}", time, prefix, perfix_2, msg);
-        println!("{}", log_message.as_str());
+        if $crate::engine::core::config::log_enabled($crate::engine::core::config::Subsystem::Assets, $crate::engine::core::config::LogLevel::Warn) {
+            let color = COLOR_YELLOW;
+            let prefix = set_color(color, "[WARN] ");
+            let perfix_2 = format!("{} ", set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str()));
+            let time = set_color(COLOR_DARK_GREY, get_time().as_str())+ " ";
+            let msg = reset_color() +format_args!($($args)*).to_string().as_str();
+
+            let log_message = format!("{}{}{} {}", time, prefix, perfix_2, msg);
+            println!("{}", log_message.as_str());
+        }
     };
 }
 
 #[macro_export]
 macro_rules! logger_error_assetloader {
     ($($args:tt)*) => {
-        let color =  COLOR_RED;
-        let prefix = set_color(color, "[ERROR] ");
-        let perfix_2 = set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str());
-        let time = set_color(COLOR_DARK_GREY, get_time().as_str())+ " ";
-        let msg = reset_color() +format_args!($($args)*).to_string().as_str();
-
-        let log_message = format!("{}{}{} {}", time, prefix, perfix_2, msg);
-        println!("{}", log_message.as_str());
+        if $crate::engine::core::config::log_enabled($crate::engine::core::config::Subsystem::Assets, $crate::engine::core::config::LogLevel::Error) {
+            let color =  COLOR_RED;
+            let prefix = set_color(color, "[ERROR] ");
+            let perfix_2 = set_color(COLOR_YELLOW, *&ASSET_PREFIX.as_str());
+            let time = set_color(COLOR_DARK_GREY, get_time().as_str())+ " ";
+            let msg = reset_color() +format_args!($($args)*).to_string().as_str();
+
+            let log_message = format!("{}{}{} {}", time, prefix, perfix_2, msg);
+            println!("{}", log_message.as_str());
+        }
     };
 }
 
@@ -1,2 +1,3 @@
 pub mod logger;
-pub mod macros;
\ No newline at end of file
+pub mod macros;
+pub mod plugin;
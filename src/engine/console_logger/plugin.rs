@@ -0,0 +1,30 @@
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::Plugin;
+use crate::engine::core::app::AppState;
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::renderer::core::GameStatus;
+
+use super::logger;
+
+/// Logs the state changes of the game: pausing, resuming and quitting.
+pub(crate) struct LoggerPlugin;
+
+impl Plugin for LoggerPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.on_enter(AppState::Paused, log_paused);
+        app.on_exit(AppState::Paused, log_resumed);
+        app.on_enter(AppState::Quitting, log_quitting);
+    }
+}
+
+fn log_paused(_world: &mut World) {
+    println!("{}", logger::game_state(GameStatus::Paused, 21));
+}
+
+fn log_resumed(_world: &mut World) {
+    println!("{}", logger::game_state(GameStatus::Running, 22));
+}
+
+fn log_quitting(_world: &mut World) {
+    println!("{}", logger::game_state(GameStatus::Stopped, 0));
+}
@@ -0,0 +1,365 @@
+use std::collections::HashSet;
+use std::env;
+use std::process;
+
+use crate::engine::console_logger::logger;
+use crate::engine::core::cli::{self, CliArgs, CliCommand};
+use crate::engine::core::config::{self, EngineConfig};
+use crate::engine::core::ecs::events::{update_events, Events};
+use crate::engine::core::ecs::system::IntoSystem;
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::renderer::d2::tile_bench;
+use crate::engine::core::renderer::plugin::Backend;
+use crate::engine::core::validate;
+use crate::engine::core::world::{migrate, tiled};
+use crate::engine::core::time::fixed_timestep::DEFAULT_TICK_RATE;
+
+use super::plugin::Plugin;
+use super::schedule::{IntoSystemConfig, Schedule, Stage};
+use super::{App, AppState, Runner};
+
+/// Size of the game window, or of the rendered image when running headless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowSettings {
+    pub width: u32,
+    pub height: u32,
+    pub vsync: bool,
+    pub fullscreen: bool,
+}
+
+impl WindowSettings {
+    pub fn new(width: u32, height: u32) -> Self {
+        WindowSettings { width, height, vsync: true, fullscreen: false }
+    }
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        WindowSettings::new(800, 600)
+    }
+}
+
+/// Everything the runner needs to know about the game besides the world and schedule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppSettings {
+    pub game_name: String,
+    pub game_version: String,
+    pub window: WindowSettings,
+    /// The backend to render with. `None` lets the renderer pick one from the command line.
+    pub backend: Option<Backend>,
+    pub tick_rate: f64,
+}
+
+/// Configures and creates an `App`.
+///
+/// Engine subsystems and games register themselves as `Plugin`s, which add
+/// resources, systems and state hooks to the builder.
+///
+/// # Example
+///
+/// ```rust
+/// AppBuilder::new("MyGame", "1.0")
+///     .with_command_line()
+///     .insert_resource(WorldTime::load("./saves/world_time.ron")?)
+///     .add_plugin(DefaultPlugins)
+///     .add_plugin(MyGamePlugin)
+///     .run();
+/// ```
+pub(crate) struct AppBuilder {
+    settings: AppSettings,
+    world: World,
+    schedule: Schedule,
+    runner: Option<Runner>,
+    plugins: HashSet<String>,
+}
+
+#[allow(dead_code)]
+impl AppBuilder {
+    pub fn new(game_name: &str, game_version: &str) -> Self {
+        AppBuilder {
+            settings: AppSettings {
+                game_name: game_name.to_owned(),
+                game_version: game_version.to_owned(),
+                window: WindowSettings::default(),
+                backend: None,
+                tick_rate: DEFAULT_TICK_RATE,
+            },
+            world: World::new(),
+            schedule: Schedule::new(),
+            runner: None,
+            plugins: HashSet::new(),
+        }
+    }
+
+    pub fn with_window(mut self, window: WindowSettings) -> Self {
+        self.settings.window = window;
+        self
+    }
+
+    /// Renders with `backend` instead of choosing one from the command line.
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.settings.backend = Some(backend);
+        self
+    }
+
+    /// Sets how many fixed simulation ticks run per second, independent of the frame rate.
+    pub fn with_tick_rate(mut self, tick_rate: f64) -> Self {
+        self.settings.tick_rate = tick_rate;
+        self
+    }
+
+    /// Runs the engine with `config`, see `EngineConfig::load`. Takes the window
+    /// and backend from it and makes it the config every subsystem reads.
+    pub fn with_config(mut self, config: EngineConfig) -> Self {
+        self.settings.window = WindowSettings {
+            width: config.window.width,
+            height: config.window.height,
+            vsync: config.window.vsync,
+            fullscreen: config.window.fullscreen,
+        };
+        self.settings.backend = config.backend.as_deref().and_then(Backend::from_name);
+        config::set_config(config);
+        self
+    }
+
+    /// Applies the parts of the command line that are not config values: the
+    /// `--headless` frame count and screenshot, and the `LaunchOptions` resource
+    /// holding `--map` and `--spawn`.
+    pub fn with_cli(mut self, cli: &CliArgs) -> Self {
+        if cli.headless {
+            self.settings.backend = Some(Backend::Headless {
+                frames: cli.headless_frames(),
+                screenshot: cli.screenshot.clone(),
+            });
+        }
+        self.world.insert_resource(cli.launch_options());
+        self
+    }
+
+    /// Parses the process arguments and loads the engine config with them, see
+    /// `CliArgs::parse`, `EngineConfig::load`, `with_config` and `with_cli`.
+    ///
+    /// `--help` and `--version` print their text and exit the process, tool
+    /// commands like `migrate-map`, `import-tiled`, `validate` and `bench-tiles` run and exit with code 1 if they fail. Invalid
+    /// arguments or config values are printed and exit the process with code 2.
+    pub fn with_command_line(self) -> Self {
+        let mut args = env::args();
+        let binary = args.next().unwrap_or_else(|| "game".to_owned());
+        let args: Vec<String> = args.collect();
+
+        let cli = match CliArgs::parse(&args) {
+            Ok(CliCommand::Run(cli)) => cli,
+            Ok(CliCommand::Help) => {
+                println!("{}", cli::help_text(&binary));
+                process::exit(0);
+            }
+            Ok(CliCommand::Version) => {
+                println!("{}", cli::version_text(&self.settings.game_name, &self.settings.game_version));
+                process::exit(0);
+            }
+            Ok(command) => {
+                // Tools resolve default paths like the asset root from the config file and environment
+                let config = EngineConfig::load(&CliArgs::default()).unwrap_or_else(|err| {
+                    eprintln!("{}", logger::error(&err.to_string()));
+                    process::exit(2);
+                });
+                config::set_config(config);
+                run_tool_command(command)
+            }
+            Err(err) => {
+                eprintln!("{}", logger::error(&err.to_string()));
+                process::exit(2);
+            }
+        };
+        let config = EngineConfig::load(&cli).unwrap_or_else(|err| {
+            eprintln!("{}", logger::error(&err.to_string()));
+            process::exit(2);
+        });
+
+        self.with_config(config).with_cli(&cli)
+    }
+
+    /// Stores `resource` in the world, replacing one of the same type.
+    pub fn insert_resource<R: 'static>(mut self, resource: R) -> Self {
+        self.world.insert_resource(resource);
+        self
+    }
+
+    /// Builds `plugin` into the app. A plugin is only added once, adding it
+    /// again logs a warning and does nothing.
+    pub fn add_plugin(mut self, plugin: impl Plugin) -> Self {
+        self.register_plugin(&plugin);
+        self
+    }
+
+    /// Like `add_plugin`, for plugins adding other plugins from `Plugin::build`.
+    pub fn register_plugin(&mut self, plugin: &dyn Plugin) {
+        if !self.plugins.insert(plugin.name().to_owned()) {
+            println!("{}", logger::warn(&format!("Plugin {} was already added, skipping it", plugin.name())));
+            return;
+        }
+        plugin.build(self);
+    }
+
+    pub fn has_plugin(&self, name: &str) -> bool {
+        self.plugins.contains(name)
+    }
+
+    /// Inserts the default value of `R` unless the world already has one, so
+    /// resources inserted by the game take precedence over plugin defaults.
+    pub fn init_resource<R: Default + 'static>(&mut self) -> &mut Self {
+        if !self.world.has_resource::<R>() {
+            self.world.insert_resource(R::default());
+        }
+        self
+    }
+
+    /// Adds the `Events<T>` resource and ages it at the start of every tick,
+    /// see `Events`. Adding the same event type twice does nothing.
+    pub fn add_event<T: 'static>(&mut self) -> &mut Self {
+        if !self.world.has_resource::<Events<T>>() {
+            self.world.insert_resource(Events::<T>::default());
+            self.schedule.add_system(Stage::PreUpdate, update_events::<T>);
+        }
+        self
+    }
+
+    /// Adds a system to `stage`, see `App::add_systems`.
+    pub fn add_systems<M>(&mut self, stage: Stage, system: impl IntoSystemConfig<M>) -> &mut Self {
+        self.schedule.add_system(stage, system);
+        self
+    }
+
+    /// Runs `system` every time the application enters `state`.
+    pub fn on_enter<M>(&mut self, state: AppState, system: impl IntoSystem<M>) -> &mut Self {
+        self.schedule.on_enter(state, system);
+        self
+    }
+
+    /// Runs `system` every time the application leaves `state`.
+    pub fn on_exit<M>(&mut self, state: AppState, system: impl IntoSystem<M>) -> &mut Self {
+        self.schedule.on_exit(state, system);
+        self
+    }
+
+    /// Sets the function that takes over the built `App` and drives its game
+    /// loop, usually set by the `RendererPlugin`.
+    pub fn set_runner(&mut self, runner: impl FnOnce(App) + 'static) -> &mut Self {
+        self.runner = Some(Box::new(runner));
+        self
+    }
+
+    pub fn settings(&self) -> &AppSettings {
+        &self.settings
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn build(self) -> App {
+        App {
+            settings: self.settings,
+            world: self.world,
+            schedule: self.schedule,
+            runner: self.runner,
+        }
+    }
+
+    /// Builds the app and runs it, see `App::run`.
+    pub fn run(self) {
+        self.build().run();
+    }
+}
+
+/// Runs a tool command like `validate` and exits the process, with code 1 if
+/// it fails. The engine config has to be set before, so default paths follow
+/// the configured asset root.
+fn run_tool_command(command: CliCommand) -> ! {
+    match command {
+        CliCommand::MigrateMap(args) => match migrate::run_migrate_map(&args) {
+            Ok(migration) => {
+                for note in &migration.notes {
+                    println!("{}", logger::warn(note));
+                }
+                let dry_run = if args.dry_run { " (dry run, nothing written)" } else { "" };
+                println!("{}", logger::info(&format!("{}{}", migration.summary(), dry_run)));
+                process::exit(0);
+            }
+            Err(err) => {
+                eprintln!("{}", logger::error(&err.to_string()));
+                process::exit(1);
+            }
+        },
+        CliCommand::ImportTiled(args) => match tiled::run_import_tiled(&args) {
+            Ok(import) => {
+                for note in &import.notes {
+                    println!("{}", logger::warn(note));
+                }
+                let dry_run = if args.dry_run { " (dry run, nothing written)" } else { "" };
+                println!("{}", logger::info(&format!("{}{}", import.summary(), dry_run)));
+                process::exit(0);
+            }
+            Err(err) => {
+                eprintln!("{}", logger::error(&err.to_string()));
+                process::exit(1);
+            }
+        },
+        CliCommand::Validate(args) => {
+            let validation = validate::run_validate(&args);
+            for problem in &validation.problems {
+                eprintln!("{}", logger::error(&problem.to_string()));
+            }
+            println!("{}", logger::info(&validation.summary()));
+            process::exit(if validation.is_ok() { 0 } else { 1 });
+        }
+        CliCommand::BenchTiles(args) => {
+            println!("{}", tile_bench::run_tile_benchmark(args.size, args.frames, args.backend));
+            process::exit(0);
+        }
+        CliCommand::Run(_) | CliCommand::Help | CliCommand::Version => unreachable!("{:?} is not a tool command", command),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::ecs::system::ResMut;
+
+    #[derive(Default)]
+    struct Counter(u32);
+
+    fn count_up(mut counter: ResMut<Counter>) {
+        counter.0 += 1;
+    }
+
+    struct CountPlugin;
+
+    impl Plugin for CountPlugin {
+        fn build(&self, app: &mut AppBuilder) {
+            app.init_resource::<Counter>();
+            app.add_systems(Stage::Startup, count_up);
+        }
+    }
+
+    #[test]
+    fn plugins_are_only_built_once() {
+        let mut app = AppBuilder::new("Test", "0.0.0")
+            .add_plugin(CountPlugin)
+            .add_plugin(CountPlugin)
+            .build();
+        app.startup();
+        assert_eq!(app.world().resource::<Counter>().unwrap().0, 1);
+        assert_eq!(app.world().resource::<AppState>().as_deref(), Some(&AppState::Running));
+    }
+
+    #[test]
+    fn inserted_resources_win_over_plugin_defaults() {
+        let mut app = AppBuilder::new("Test", "0.0.0")
+            .insert_resource(Counter(41))
+            .add_plugin(CountPlugin)
+            .build();
+        app.startup();
+        assert_eq!(app.world().resource::<Counter>().unwrap().0, 42);
+    }
+}
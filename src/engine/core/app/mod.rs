@@ -1,10 +1,16 @@
+pub mod builder;
+pub mod plugin;
+pub mod schedule;
 
-use std::env;
+use std::fmt;
 
-use crate::engine::core::metadata::{ENGINE_NAME, ENGINE_VERSION};
-use crate::engine::core::renderer;
+use crate::engine::console_logger::logger;
+use crate::engine::core::ecs::world::World;
 
-#[derive(PartialEq, Clone)]
+use builder::{AppBuilder, AppSettings};
+use schedule::{Schedule, Stage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[allow(dead_code)]
 pub enum AppState {
     PreInit,
@@ -14,247 +20,120 @@ pub enum AppState {
     Quitting,
 }
 
-impl ToString for AppState {
-    fn to_string(&self) -> String {
+impl fmt::Display for AppState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            AppState::PreInit => String::from("PreInit"),
-            AppState::Init => String::from("Init"),
-            AppState::Running => String::from("Running"),
-            AppState::Paused => String::from("Paused"),
-            AppState::Quitting => String::from("Quitting"),
+            AppState::PreInit => write!(f, "PreInit"),
+            AppState::Init => write!(f, "Init"),
+            AppState::Running => write!(f, "Running"),
+            AppState::Paused => write!(f, "Paused"),
+            AppState::Quitting => write!(f, "Quitting"),
         }
     }
 }
 
+/// Takes over a built `App` and drives its game loop, see `AppBuilder::set_runner`.
+pub type Runner = Box<dyn FnOnce(App)>;
+
 /// The `App` struct represents the core of a game application, managing its state and systems.
 ///
-/// This implementation provides essential functionality for creating, configuring, and running a game application.
-/// It allows you to initialize the game environment, register system functions for different application states,
-/// and execute those systems   based on the current state. Additionally, it handles the selection of graphics APIs
-/// via command-line arguments and transitions the application state from `PreInit` to `Init` and finally to `Running`.
+/// It owns the ECS `World` and a `Schedule` that runs the registered systems stage by stage, see `Stage`.
+/// An `App` is configured with an `AppBuilder`, to which the engine's subsystems and the game itself add
+/// their resources and systems as `Plugin`s. Running the app transitions the application state from
+/// `PreInit` to `Init` and finally to `Running`, then hands it over to the runner, usually the game loop
+/// of the backend picked by the `RendererPlugin`.
 ///
 /// # Examples
 ///
 /// ```
-/// // Create an instance of the game application
-/// let mut game = App::new("MyGame", "1.0", 800.0, 600.0, true);
-///
-/// // Register and execute system functions
-/// game.add_systems(AppState::Init, || {
-///     // Initialization logic goes here
-/// });
-///
-/// let state_transition = game.enable_system();
+/// struct MyGamePlugin;
 ///
-/// if state_transition {
-///     // The state transition from Init to Running occurred
-///     // Perform additional actions here if needed
+/// impl Plugin for MyGamePlugin {
+///     fn build(&self, app: &mut AppBuilder) {
+///         app.add_systems(Stage::Startup, |world: &mut World| {
+///             // Initialization logic goes here, e.g. spawning entities
+///             spawn_player(world, "makmusl", [0.0, 0.0]);
+///         });
+///         app.add_systems(Stage::Update, regenerate_hp.after("movement_system"));
+///         app.on_enter(AppState::Paused, show_pause_menu);
+///     }
 /// }
 ///
-/// // Run the game application
-/// let updated_game = game.run();
+/// App::builder("MyGame", "1.0")
+///     .with_window(WindowSettings::new(800, 600))
+///     .add_plugin(DefaultPlugins)
+///     .add_plugin(MyGamePlugin)
+///     .run();
 /// ```
-///
-/// In this example, the `App` struct is used to create and manage a game application, including initializing systems
-/// and running the application loop.
-
-#[derive(Clone)]
 pub(crate) struct App {
-    game_name: String,
-    game_version: String,
-    game_width: u32,
-    game_height: u32,
-    state: AppState,
-    systems: Vec<(AppState, fn())>,
-    debug: bool,
+    settings: AppSettings,
+    world: World,
+    schedule: Schedule,
+    runner: Option<Runner>,
 }
-#[allow(dead_code, unused_assignments, unused_variables)]
-impl App {
 
-    /// Creates a new instance of the `App` struct with the specified parameters.
+#[allow(dead_code)]
+impl App {
+    /// Starts configuring a game, see `AppBuilder`.
     ///
     /// # Arguments
     ///
     /// * `game_name` - A string representing the name of the game.
     /// * `game_version` - A string representing the version of the game.
-    /// * `game_width` - The width of the game window as a floating-point number.
-    /// * `game_height` - The height of the game window as a floating-point number.
-    /// * `debug` - A boolean indicating whether debug mode is enabled.
-    ///
-    /// # Returns
-    ///
-    /// A new `App` instance initialized with the provided values and default state.
-
-    pub fn new(game_name: &str, game_version: &str, game_width: u32, game_height: u32, debug: bool) -> Self {
-        let args: Vec<String> = env::args().collect();
-        let app = App {
-            state: AppState::PreInit,
-            systems: Vec::new(),
-            game_name: game_name.to_owned(),
-            game_version: game_version.to_owned(),
-            game_width: game_width,
-            game_height: game_height,
-            debug: debug,
-        };
-        app
+    pub fn builder(game_name: &str, game_version: &str) -> AppBuilder {
+        AppBuilder::new(game_name, game_version)
     }
 
-    /// Runs the game application, initializing systems based on the current application state and command-line arguments.
-    ///
-    /// This function sets up the game environment, initializes necessary systems, and creates the game window based on the specified or default graphics API.
-    /// It also transitions the application state from `PreInit` to `Init` and finally to `Running` as the game progresses.
+    /// Runs the game application.
     ///
-    /// # Returns
+    /// The `PreInit`, `Init` and `Startup` stages run once, see `startup`. The app is then handed over to
+    /// its runner, which runs the tick stages on every simulation tick, the `Render` stage on every frame and
+    /// the `Shutdown` stage once the app is quitting.
     ///
-    /// A new `App` instance representing the updated game application state after running.
-    ///
-    /// # Remarks
-    ///
-    /// - The function checks for command-line arguments to determine the graphics API to use (`--opengl` or `--vulkano`) or defaults to OpenGL if none are specified.
-    /// - Different systems are initialized based on the current application state, and messages are printed to indicate the loading process.
-    /// - The `AppState` enum is used to manage the application state transitions.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// let game = App::new("MyGame", "1.0", 800.0, 600.0, true);
-    /// let updated_game = game.run();
-    /// ```
-
-    pub fn run(mut self) -> Self {
-        let args: Vec<String> = env::args().collect();
-        let mut app_state = self.state.clone();
-        let engine_verison: &str = &*ENGINE_VERSION;
-
-        if app_state == AppState::PreInit {
-            self.add_systems(AppState::PreInit, || {
-                println!("Loading PreInit Systems...");
-            });
-            self.state = AppState::Init;
-            app_state = self.state.clone();
-        }
-
-        if app_state == AppState::Init {
-            self.add_systems(AppState::Init, || {
-                println!("Loading Init Systems...");
-            });
-            self.state = AppState::Running;
-            app_state = self.state.clone();
-        }
-
-        if self.state == AppState::Running {
-            self.add_systems(AppState::Running, || {
-                println!("Loading Running Systems...");
-            });
+    /// Without a runner, e.g. when the `RendererPlugin` was not added, the app only runs its start-up stages.
+    pub fn run(mut self) {
+        self.startup();
+        match self.runner.take() {
+            Some(runner) => runner(self),
+            None => println!("{}", logger::warn("No runner set, add the RendererPlugin to open a window")),
         }
+    }
 
-        // Access the command-line arguments
-        // app.args[0] is the name of the program itself
-        // app.args[1] is the first argument, app.args[2] is the second argument, and so on
-        // You can use pattern matching or if statements to handle different arguments
+    pub fn settings(&self) -> &AppSettings {
+        &self.settings
+    }
 
-        // Example: Check if the "--opengl" argument is present
-        if args.contains(&String::from("--opengl")) {
-            // Code to enable OpenGL
-            let name = format!("{} v{}", self.game_name, self.game_version);
-            renderer::core::opengl::create_opengl_window(&name, self.game_width, self.game_height);
-        } else if args.contains(&String::from("--vulkano")) {
-            let name = format!("{} v{} - [{} v{} - Vulkan]", self.game_name, self.game_version, ENGINE_NAME, engine_verison);
-            renderer::core::vulkano::create_vulkano_window(&name, self.game_width, self.game_height);
-        } else {
-            println!("No graphics API specified. Please specify a graphics API with the --opengl or --vulkano flag. Starting fallback OpenGL renderer...");
-            let name = format!("{} v{}", self.game_name, self.game_version);
-            renderer::core::opengl::create_opengl_window(&name, self.game_width, self.game_height);
-        }
-        App { 
-            game_name: self.game_name, 
-            game_version: self.game_version, 
-            game_width: self.game_width, 
-            game_height: self.game_height, 
-            state: AppState::Running, 
-            systems: self.systems, 
-            debug: self.debug,
-        }
+    pub fn world(&self) -> &World {
+        &self.world
     }
 
-    /// Adds a system function to the game application for a specific application state.
-    ///
-    /// This function allows you to register a system function to be executed when the game transitions
-    /// to a specific application state. Systems are functions that perform tasks or updates related to
-    /// specific game states, such as initialization or running the game loop.
-    ///
-    /// # Arguments
-    ///
-    /// * `state` - The target application state for which the system function will be executed.
-    /// * `system` - A function that represents the system to be executed when the specified state is reached.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// // Define a custom system function
-    /// fn custom_init_system() {
-    ///     // Initialization logic goes here
-    /// }
-    ///
-    /// // Create an instance of the game application
-    /// let mut game = App::new("MyGame", "1.0", 800.0, 600.0, true);
-    ///
-    /// // Register the custom system to run during the Init state
-    /// game.add_systems(AppState::Init, custom_init_system);
-    /// ```
-    ///
-    /// In this example, the `custom_init_system` function will be executed when the game transitions to the `Init` state.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
 
-    pub fn add_systems(&mut self, state: AppState, system: fn()) {
-        self.systems.push((state, system));
+    /// Splits the app into its world and schedule, for runners owning the game loop.
+    pub fn into_parts(self) -> (AppSettings, World, Schedule) {
+        (self.settings, self.world, self.schedule)
     }
 
-    /// Executes registered system functions based on the current application state.
-    ///
-    /// This function iterates through the registered system functions and executes the one associated
-    /// with the current application state. It is typically called to perform specific tasks or updates
-    /// during different stages of the game, such as initialization or running the game loop.
+    /// Runs the start-up stages and moves the application to `AppState::Running`.
     ///
-    /// # Returns
-    ///
-    /// A boolean indicating whether the state transition from `Init` to `Running` occurred during the execution.
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// // Create an instance of the game application
-    /// let mut game = App::new("MyGame", "1.0", 800.0, 600.0, true);
-    ///
-    /// // Register and execute system functions
-    /// game.add_systems(AppState::Init, || {
-    ///     // Initialization logic goes here
-    /// });
-    ///
-    /// let state_transition = game.enable_system();
-    ///
-    /// if state_transition {
-    ///     // The state transition from Init to Running occurred
-    ///     // Perform additional actions here if needed
-    /// }
-    /// ```
-    ///
-    /// In this example, the `enable_system` function executes registered system functions based on the
-    /// current state, and it returns `true` when transitioning from `Init` to `Running`.
-
-    pub fn enable_system(&mut self) -> bool {
-        // Code to run the app based on the current state and systems
-        for (system_state, system) in &self.systems {
-            if *system_state == self.state {
-                system();
-            }
-        }
-    
-        // Set the state to Running after the window is created
-        if self.state == AppState::Init {
-            self.state = AppState::Running;
-            true
-        } else {
-            false
+    /// The `PreInit` stage runs in `AppState::PreInit`, the `Init` and `Startup`
+    /// stages in `AppState::Init`. Calling `startup` again does nothing.
+    pub fn startup(&mut self) {
+        if self.world.has_resource::<AppState>() {
+            return;
         }
+        self.schedule.set_state(&mut self.world, AppState::PreInit);
+        println!("Loading PreInit Systems...");
+        self.schedule.run_stage(Stage::PreInit, &mut self.world);
+
+        self.schedule.set_state(&mut self.world, AppState::Init);
+        println!("Loading Init Systems...");
+        self.schedule.run_stage(Stage::Init, &mut self.world);
+        self.schedule.run_stage(Stage::Startup, &mut self.world);
+
+        self.schedule.set_state(&mut self.world, AppState::Running);
+        println!("Loading Running Systems...");
     }
-}
\ No newline at end of file
+}
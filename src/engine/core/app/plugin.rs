@@ -0,0 +1,101 @@
+use crate::engine::assets_loader::plugin::AssetLoaderPlugin;
+use crate::engine::console_logger::plugin::LoggerPlugin;
+use crate::engine::core::creature::plugin::CreaturePlugin;
+use crate::engine::core::ecs::input::InputPlugin;
+use crate::engine::core::ecs::system::{Res, ResMut};
+use crate::engine::core::ecs::systems;
+use crate::engine::core::renderer::plugin::RendererPlugin;
+use crate::engine::core::time::calendar::WorldTime;
+use crate::engine::core::time::clock::GameClock;
+use crate::engine::core::world::plugin::WorldPlugin;
+
+use super::builder::AppBuilder;
+use super::schedule::{in_state, IntoSystemConfig, Stage};
+use super::AppState;
+
+/// A self-contained part of a game, e.g. the renderer or the game's own content.
+///
+/// Plugins add their resources, systems and state hooks to the `AppBuilder`,
+/// so subsystems and games can be added without changing the `App` itself.
+///
+/// # Example
+///
+/// ```rust
+/// struct HealthPlugin;
+///
+/// impl Plugin for HealthPlugin {
+///     fn build(&self, app: &mut AppBuilder) {
+///         app.init_resource::<HealthConfig>();
+///         app.add_systems(Stage::Update, regenerate_hp.run_if(in_state(AppState::Running)));
+///     }
+/// }
+/// ```
+pub(crate) trait Plugin {
+    /// Adds the plugin's resources and systems to `app`.
+    fn build(&self, app: &mut AppBuilder);
+
+    /// Name used to add every plugin only once, by default the type name.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// The simulation core: the `GameClock`, the `WorldTime` calendar and the
+/// systems moving entities.
+///
+/// | Stage     | System                            |
+/// |-----------|-----------------------------------|
+/// | PreUpdate | `advance_world_time`              |
+/// | Update    | `ai_system`, `movement_system`    |
+///
+/// Entering `AppState::Paused` pauses the `GameClock`, leaving it resumes the clock.
+pub(crate) struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<GameClock>();
+        app.init_resource::<WorldTime>();
+
+        app.add_systems(Stage::PreUpdate, advance_world_time);
+        app.add_systems(Stage::Update, systems::ai_system.run_if(in_state(AppState::Running)));
+        app.add_systems(Stage::Update, systems::movement_system
+            .after("ai_system")
+            .run_if(in_state(AppState::Running)));
+
+        app.on_enter(AppState::Paused, pause_clock);
+        app.on_exit(AppState::Paused, resume_clock);
+    }
+}
+
+/// Moves the in-world calendar by the clock's scaled delta.
+fn advance_world_time(clock: Res<GameClock>, mut world_time: ResMut<WorldTime>) {
+    world_time.advance(clock.delta());
+}
+
+fn pause_clock(mut clock: ResMut<GameClock>) {
+    clock.pause();
+}
+
+fn resume_clock(mut clock: ResMut<GameClock>) {
+    clock.resume();
+}
+
+/// Every plugin the engine ships with: `LoggerPlugin`, `CorePlugin`,
+/// `InputPlugin`, `AssetLoaderPlugin`, `WorldPlugin`, `CreaturePlugin` and
+/// `RendererPlugin`.
+///
+/// Plugins added before `DefaultPlugins` replace the default ones, e.g. a
+/// `WorldPlugin::with_map("twinleaf_town")`.
+pub(crate) struct DefaultPlugins;
+
+impl Plugin for DefaultPlugins {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_plugin(&LoggerPlugin);
+        app.register_plugin(&CorePlugin);
+        app.register_plugin(&InputPlugin);
+        app.register_plugin(&AssetLoaderPlugin::default());
+        app.register_plugin(&WorldPlugin::default());
+        app.register_plugin(&CreaturePlugin);
+        app.register_plugin(&RendererPlugin);
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::engine::core::ecs::system::{BoxedSystem, IntoSystem};
+use crate::engine::core::ecs::world::World;
+
+use super::AppState;
+
+/// The stages a `Schedule` runs its systems in.
+///
+/// `PreInit`, `Init` and `Startup` run once when the app starts, `PreUpdate`,
+/// `Update` and `PostUpdate` on every simulation tick, `Render` once per
+/// rendered frame and `Shutdown` once before the app exits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    PreInit,
+    Init,
+    Startup,
+    PreUpdate,
+    Update,
+    PostUpdate,
+    Render,
+    Shutdown,
+}
+
+#[allow(dead_code)]
+impl Stage {
+    pub const ALL: [Stage; 8] = [
+        Stage::PreInit,
+        Stage::Init,
+        Stage::Startup,
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::Render,
+        Stage::Shutdown,
+    ];
+
+    /// The stages run on every simulation tick, in order.
+    pub const TICK: [Stage; 3] = [Stage::PreUpdate, Stage::Update, Stage::PostUpdate];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Decides whether a system runs, see `SystemConfig::run_if`.
+pub type Condition = Box<dyn Fn(&World) -> bool>;
+
+/// Runs the system only while the app is in `state`.
+pub fn in_state(state: AppState) -> impl Fn(&World) -> bool {
+    move |world: &World| current_state(world) == state
+}
+
+/// Runs the system in every state but `state`.
+#[allow(dead_code)]
+pub fn not_in_state(state: AppState) -> impl Fn(&World) -> bool {
+    move |world: &World| current_state(world) != state
+}
+
+/// The state stored in `world`, `PreInit` if the schedule never set one.
+pub fn current_state(world: &World) -> AppState {
+    world.resource::<AppState>().map_or(AppState::PreInit, |state| *state)
+}
+
+/// A system together with its label, ordering constraints and run conditions.
+pub struct SystemConfig {
+    system: BoxedSystem,
+    label: String,
+    before: Vec<String>,
+    after: Vec<String>,
+    conditions: Vec<Condition>,
+}
+
+#[allow(dead_code)]
+impl SystemConfig {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn should_run(&self, world: &World) -> bool {
+        self.conditions.iter().all(|condition| condition(world))
+    }
+}
+
+/// Adds labels, ordering and conditions to systems before they are added to a
+/// `Schedule`. Implemented for every system function and for `SystemConfig`
+/// itself, so the calls can be chained.
+///
+/// # Example
+///
+/// ```rust
+/// app.add_systems(Stage::Update, movement_system
+///     .after("input_system")
+///     .run_if(in_state(AppState::Running)));
+/// ```
+#[allow(dead_code)]
+pub trait IntoSystemConfig<Marker>: Sized {
+    fn into_config(self) -> SystemConfig;
+
+    /// Replaces the default label, the function name.
+    fn label(self, label: &str) -> SystemConfig {
+        let mut config = self.into_config();
+        config.label = label.to_owned();
+        config
+    }
+
+    /// Runs the system before every system labeled `label` in the same stage.
+    fn before(self, label: &str) -> SystemConfig {
+        let mut config = self.into_config();
+        config.before.push(label.to_owned());
+        config
+    }
+
+    /// Runs the system after every system labeled `label` in the same stage.
+    fn after(self, label: &str) -> SystemConfig {
+        let mut config = self.into_config();
+        config.after.push(label.to_owned());
+        config
+    }
+
+    /// Only runs the system while `condition` returns `true`. Several conditions all have to hold.
+    fn run_if(self, condition: impl Fn(&World) -> bool + 'static) -> SystemConfig {
+        let mut config = self.into_config();
+        config.conditions.push(Box::new(condition));
+        config
+    }
+}
+
+impl IntoSystemConfig<()> for SystemConfig {
+    fn into_config(self) -> SystemConfig {
+        self
+    }
+}
+
+impl<Marker, S: IntoSystem<Marker>> IntoSystemConfig<Marker> for S {
+    fn into_config(self) -> SystemConfig {
+        let system = self.into_system();
+        SystemConfig {
+            label: system.name().to_owned(),
+            system,
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+        }
+    }
+}
+
+/// Returned when the ordering constraints of a stage contradict each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduleError {
+    pub stage: Stage,
+    pub systems: Vec<String>,
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Systems in stage {} have cyclic before/after constraints: {}", self.stage, self.systems.join(", "))
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+#[derive(Default)]
+struct StageSystems {
+    systems: Vec<SystemConfig>,
+    /// Execution order as indices into `systems`, recomputed after a system was added.
+    order: Option<Vec<usize>>,
+}
+
+/// Runs systems by `Stage` and switches between `AppState`s.
+///
+/// Within a stage systems run in the order they were added, unless `before`
+/// and `after` constraints say otherwise. Constraints naming a label no system
+/// in the stage has are ignored, so systems can refer to optional ones.
+///
+/// The current `AppState` is kept as resource in the `World`. `set_state`
+/// runs the exit hooks of the old and the enter hooks of the new state.
+///
+/// # Example
+///
+/// ```rust
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(Stage::Update, ai_system);
+/// schedule.add_system(Stage::Update, movement_system.after("ai_system"));
+/// schedule.on_enter(AppState::Paused, |clock: ResMut<GameClock>| clock.pause());
+///
+/// schedule.set_state(&mut world, AppState::Running);
+/// for stage in Stage::TICK {
+///     schedule.run_stage(stage, &mut world);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Schedule {
+    stages: HashMap<Stage, StageSystems>,
+    on_enter: Vec<(AppState, BoxedSystem)>,
+    on_exit: Vec<(AppState, BoxedSystem)>,
+}
+
+#[allow(dead_code)]
+impl Schedule {
+    pub fn new() -> Self {
+        Schedule::default()
+    }
+
+    pub fn add_system<M>(&mut self, stage: Stage, system: impl IntoSystemConfig<M>) {
+        let stage_systems = self.stages.entry(stage).or_default();
+        stage_systems.systems.push(system.into_config());
+        stage_systems.order = None;
+    }
+
+    /// Runs `system` whenever the app enters `state`.
+    pub fn on_enter<M>(&mut self, state: AppState, system: impl IntoSystem<M>) {
+        self.on_enter.push((state, system.into_system()));
+    }
+
+    /// Runs `system` whenever the app leaves `state`.
+    pub fn on_exit<M>(&mut self, state: AppState, system: impl IntoSystem<M>) {
+        self.on_exit.push((state, system.into_system()));
+    }
+
+    /// Labels of the systems in `stage`, in execution order.
+    pub fn system_labels(&mut self, stage: Stage) -> Result<Vec<String>, ScheduleError> {
+        let order = self.order(stage)?;
+        let systems = &self.stages[&stage].systems;
+        Ok(order.iter().map(|index| systems[*index].label.clone()).collect())
+    }
+
+    /// Runs every system of `stage` whose conditions hold.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stage's ordering constraints contain a cycle.
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World) {
+        let order = self.order(stage).unwrap_or_else(|e| panic!("{}", e));
+        let Some(stage_systems) = self.stages.get_mut(&stage) else {
+            return;
+        };
+        for index in order {
+            let config = &mut stage_systems.systems[index];
+            if config.should_run(world) {
+                config.system.run(world);
+            }
+        }
+    }
+
+    /// Switches to `next`, running the exit hooks of the current and the enter
+    /// hooks of the new state. Does nothing if the app already is in `next`.
+    pub fn set_state(&mut self, world: &mut World, next: AppState) {
+        let previous = world.resource::<AppState>().map(|state| *state);
+        if previous == Some(next) {
+            return;
+        }
+        for (state, system) in self.on_exit.iter_mut() {
+            if Some(*state) == previous {
+                system.run(world);
+            }
+        }
+        world.insert_resource(next);
+        for (state, system) in self.on_enter.iter_mut() {
+            if *state == next {
+                system.run(world);
+            }
+        }
+    }
+
+    fn order(&mut self, stage: Stage) -> Result<Vec<usize>, ScheduleError> {
+        let Some(stage_systems) = self.stages.get_mut(&stage) else {
+            return Ok(Vec::new());
+        };
+        if let Some(order) = &stage_systems.order {
+            return Ok(order.clone());
+        }
+        let order = sort_systems(stage, &stage_systems.systems)?;
+        stage_systems.order = Some(order.clone());
+        Ok(order)
+    }
+}
+
+/// Orders `systems` so every `before`/`after` constraint holds, keeping the
+/// insertion order where the constraints allow it.
+fn sort_systems(stage: Stage, systems: &[SystemConfig]) -> Result<Vec<usize>, ScheduleError> {
+    let with_label = |label: &str| -> Vec<usize> {
+        systems.iter().enumerate().filter(|(_, config)| config.label == label).map(|(index, _)| index).collect()
+    };
+
+    // successors[a] contains b if a has to run before b
+    let mut successors: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); systems.len()];
+    for (index, config) in systems.iter().enumerate() {
+        for label in &config.before {
+            successors[index].extend(with_label(label).into_iter().filter(|other| *other != index));
+        }
+        for label in &config.after {
+            for other in with_label(label) {
+                if other != index {
+                    successors[other].insert(index);
+                }
+            }
+        }
+    }
+
+    let mut predecessor_count = vec![0; systems.len()];
+    for targets in &successors {
+        for target in targets {
+            predecessor_count[*target] += 1;
+        }
+    }
+
+    let mut ready: BTreeSet<usize> = (0..systems.len()).filter(|index| predecessor_count[*index] == 0).collect();
+    let mut order = Vec::with_capacity(systems.len());
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for target in &successors[index] {
+            predecessor_count[*target] -= 1;
+            if predecessor_count[*target] == 0 {
+                ready.insert(*target);
+            }
+        }
+    }
+
+    if order.len() != systems.len() {
+        return Err(ScheduleError {
+            stage,
+            systems: (0..systems.len())
+                .filter(|index| predecessor_count[*index] > 0)
+                .map(|index| systems[index].label.clone())
+                .collect(),
+        });
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::ecs::system::ResMut;
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    fn first(mut log: ResMut<Log>) {
+        log.0.push("first");
+    }
+
+    fn second(mut log: ResMut<Log>) {
+        log.0.push("second");
+    }
+
+    fn third(mut log: ResMut<Log>) {
+        log.0.push("third");
+    }
+
+    fn world_with_log() -> World {
+        let mut world = World::new();
+        world.insert_resource(Log::default());
+        world
+    }
+
+    #[test]
+    fn before_and_after_override_insertion_order() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, third.after("second"));
+        schedule.add_system(Stage::Update, second);
+        schedule.add_system(Stage::Update, first.before("third").before("second"));
+
+        let mut world = world_with_log();
+        schedule.run_stage(Stage::Update, &mut world);
+        assert_eq!(world.resource::<Log>().unwrap().0, vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn cycles_are_reported_with_system_labels() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, first.after("second"));
+        schedule.add_system(Stage::Update, second.after("first"));
+        schedule.add_system(Stage::Update, third);
+
+        let error = schedule.system_labels(Stage::Update).unwrap_err();
+        assert_eq!(error.systems, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn run_if_follows_the_app_state() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, first.run_if(in_state(AppState::Running)));
+        schedule.add_system(Stage::Update, second.run_if(not_in_state(AppState::Running)));
+
+        let mut world = world_with_log();
+        schedule.set_state(&mut world, AppState::Running);
+        schedule.run_stage(Stage::Update, &mut world);
+        schedule.set_state(&mut world, AppState::Paused);
+        schedule.run_stage(Stage::Update, &mut world);
+        assert_eq!(world.resource::<Log>().unwrap().0, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn state_changes_run_exit_and_enter_hooks() {
+        let mut schedule = Schedule::new();
+        schedule.on_exit(AppState::Running, first);
+        schedule.on_enter(AppState::Paused, second);
+        schedule.on_exit(AppState::Paused, third);
+
+        let mut world = world_with_log();
+        schedule.set_state(&mut world, AppState::Running);
+        schedule.set_state(&mut world, AppState::Paused);
+        schedule.set_state(&mut world, AppState::Paused);
+        schedule.set_state(&mut world, AppState::Running);
+        assert_eq!(world.resource::<Log>().unwrap().0, vec!["first", "second", "third"]);
+        assert_eq!(current_state(&world), AppState::Running);
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// How and from what the engine binary was built, embedded at compile time by `build.rs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The crate version from `Cargo.toml`, the same as `ENGINE_VERSION`.
+    pub version: &'static str,
+    /// The abbreviated git commit, or `unknown`. Suffixed with `-dirty` for
+    /// uncommitted changes on a best-effort basis, see `build.rs`.
+    pub git_commit: &'static str,
+    /// The cargo profile, `debug` or `release`.
+    pub profile: &'static str,
+    /// The target triple, e.g. `x86_64-unknown-linux-gnu`.
+    pub target: &'static str,
+    /// The enabled cargo features, comma separated.
+    features: &'static str,
+}
+
+/// The build information of this binary.
+pub const BUILD_INFO: BuildInfo = BuildInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    git_commit: env!("MALKMUSL_GIT_COMMIT"),
+    profile: env!("MALKMUSL_BUILD_PROFILE"),
+    target: env!("MALKMUSL_BUILD_TARGET"),
+    features: env!("MALKMUSL_BUILD_FEATURES"),
+};
+
+impl BuildInfo {
+    /// The enabled cargo features in alphabetical order.
+    pub fn features(&self) -> Vec<&'static str> {
+        self.features.split(',').filter(|feature| !feature.is_empty()).collect()
+    }
+
+    /// One line per field, as printed by `--version`.
+    pub fn details(&self) -> String {
+        let features = self.features();
+        format!(
+            "version:  {}\ncommit:   {}\nprofile:  {}\ntarget:   {}\nfeatures: {}",
+            self.version,
+            self.git_commit,
+            self.profile,
+            self.target,
+            if features.is_empty() { "none".to_owned() } else { features.join(", ") },
+        )
+    }
+}
+
+/// A one-line summary, e.g. `v0.0.3 (1a2b3c4d5e6f, debug, x86_64-unknown-linux-gnu)`.
+impl fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{} ({}, {}, {})", self.version, self.git_commit, self.profile, self.target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_info_is_embedded() {
+        assert_eq!(BUILD_INFO.version, env!("CARGO_PKG_VERSION"));
+        assert!(!BUILD_INFO.git_commit.is_empty());
+        assert_eq!(BUILD_INFO.profile, if cfg!(debug_assertions) { "debug" } else { "release" });
+        assert!(BUILD_INFO.to_string().starts_with(&format!("v{} (", BUILD_INFO.version)));
+        assert!(BUILD_INFO.details().contains(&format!("target:   {}", BUILD_INFO.target)));
+    }
+}
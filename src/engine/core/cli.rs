@@ -0,0 +1,499 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::engine::core::build_info::BUILD_INFO;
+use crate::engine::core::metadata::{ENGINE_NAME, ENGINE_VERSION};
+use crate::engine::core::renderer::d2::tile_bench::{BenchBackend, DEFAULT_BENCH_FRAMES, DEFAULT_BENCH_SIZE};
+
+/// Frames rendered by `--headless` runs without `--frames`.
+pub const DEFAULT_HEADLESS_FRAMES: u32 = 1;
+
+/// A command-line flag as listed by `--help`.
+pub struct Flag {
+    pub name: &'static str,
+    /// Placeholder of the flag's value, `None` for switches.
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+/// Every flag the game binary accepts, in the order `--help` lists them.
+pub const FLAGS: [Flag; 18] = [
+    Flag { name: "--config", value: Some("<path>"), help: "Load the engine config from <path> instead of engine.toml" },
+    Flag { name: "--backend", value: Some("<name>"), help: "Render with opengl, vulkano or headless" },
+    Flag { name: "--opengl", value: None, help: "Same as --backend opengl" },
+    Flag { name: "--vulkano", value: None, help: "Same as --backend vulkano" },
+    Flag { name: "--width", value: Some("<px>"), help: "Window width in pixels" },
+    Flag { name: "--height", value: Some("<px>"), help: "Window height in pixels" },
+    Flag { name: "--fullscreen", value: None, help: "Open the window in borderless fullscreen" },
+    Flag { name: "--vsync", value: None, help: "Wait for vertical sync" },
+    Flag { name: "--no-vsync", value: None, help: "Do not wait for vertical sync" },
+    Flag { name: "--log-level", value: Some("<filter>"), help: "Log level, e.g. info or info,opengl=debug" },
+    Flag { name: "--asset-root", value: Some("<dir>"), help: "Directory the assets are loaded from" },
+    Flag { name: "--map", value: Some("<name>"), help: "Start on the map directory <name> below assets/maps" },
+    Flag { name: "--spawn", value: Some("<x,y>"), help: "Spawn the player at world position x,y" },
+    Flag { name: "--headless", value: None, help: "Render on the CPU without opening a window" },
+    Flag { name: "--frames", value: Some("<n>"), help: "Frames to render with --headless (default 1)" },
+    Flag { name: "--screenshot", value: Some("<file.png>"), help: "Save the last --headless frame as PNG" },
+    Flag { name: "--help", value: None, help: "Print this help and exit" },
+    Flag { name: "--version", value: None, help: "Print the version and exit" },
+];
+
+/// Tool commands given as first argument instead of flags, in the order `--help` lists them.
+pub const COMMANDS: [Flag; 4] = [
+    Flag { name: "migrate-map", value: Some("<path> [--output <path>] [--dry-run]"), help: "Upgrade a map file or directory to the current map version" },
+    Flag {
+        name: "import-tiled",
+        value: Some("<file.tmj|file.tmx> [--name <name>] [--output <path>] [--origin <x,y>] [--atlas <tileset>=<ATLAS>]... [--dry-run]"),
+        help: "Convert a Tiled map into assets/maps/<name>/map.ron",
+    },
+    Flag { name: "validate", value: Some("[<asset dir>]"), help: "Check every map, species, move and encounter table and the references between them" },
+    Flag { name: "bench-tiles", value: Some("[--size <n>] [--frames <n>] [--backend software|opengl]"), help: "Measure the frame cost of a <n>x<n> tile map (default 256x256)" },
+];
+
+/// What the command line asks the binary to do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CliCommand {
+    Run(CliArgs),
+    Help,
+    Version,
+    /// `migrate-map`, see `world::migrate::migrate`.
+    MigrateMap(MigrateMapArgs),
+    /// `import-tiled`, see `world::tiled::import_tiled`.
+    ImportTiled(ImportTiledArgs),
+    /// `validate`, see `validate::validate_assets`.
+    Validate(ValidateArgs),
+    /// `bench-tiles`, see `tile_bench::run_tile_benchmark`.
+    BenchTiles(BenchTilesArgs),
+}
+
+/// The arguments of `migrate-map`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MigrateMapArgs {
+    /// A map directory or map file.
+    pub input: String,
+    /// Where to write the map instead of next to or over the input.
+    pub output: Option<String>,
+    /// Only report what would be written.
+    pub dry_run: bool,
+}
+
+/// The arguments of `import-tiled`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportTiledArgs {
+    /// A `.tmj` or `.tmx` file.
+    pub input: String,
+    /// The map name, the file name without extension if not given.
+    pub name: Option<String>,
+    /// Where to write the map instead of `maps/<name>/map.ron` below the
+    /// configured asset root.
+    pub output: Option<String>,
+    /// Grid position of the bottom-left tile.
+    pub origin: Option<[i32; 2]>,
+    /// Texture atlases of Tiled tilesets, as tileset name and atlas name.
+    pub atlases: Vec<(String, String)>,
+    /// Only report what would be written.
+    pub dry_run: bool,
+}
+
+/// The arguments of `validate`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidateArgs {
+    /// The asset directory to check instead of the configured asset root.
+    pub root: Option<String>,
+}
+
+/// The arguments of `bench-tiles`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchTilesArgs {
+    /// Edge length of the map in tiles.
+    pub size: u32,
+    pub frames: u32,
+    pub backend: BenchBackend,
+}
+
+impl Default for BenchTilesArgs {
+    fn default() -> Self {
+        BenchTilesArgs { size: DEFAULT_BENCH_SIZE, frames: DEFAULT_BENCH_FRAMES, backend: BenchBackend::default() }
+    }
+}
+
+/// A config value set on the command line, see `EngineConfig::set`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigOverride {
+    /// The flag as written, used in error messages.
+    pub flag: String,
+    pub key: &'static str,
+    pub value: String,
+}
+
+/// The parsed command line of a `CliCommand::Run`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CliArgs {
+    /// The `--config` file.
+    pub config: Option<String>,
+    /// Config values in the order given, applied on top of the config file and environment.
+    pub overrides: Vec<ConfigOverride>,
+    pub map: Option<String>,
+    pub spawn: Option<[f32; 2]>,
+    pub headless: bool,
+    pub frames: Option<u32>,
+    pub screenshot: Option<String>,
+}
+
+/// A command line that cannot be run, shown together with a hint to `--help`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CliError {
+    pub message: String,
+}
+
+impl CliError {
+    fn new(message: impl Into<String>) -> Self {
+        CliError { message: message.into() }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\nRun with --help to list the available options.", self.message)
+    }
+}
+
+impl Error for CliError {}
+
+/// Headless settings and start-up overrides a game reads from the command line,
+/// stored as `World` resource by `AppBuilder::with_cli`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LaunchOptions {
+    /// The map to start on instead of the game's own start map.
+    pub map: Option<String>,
+    /// Where to spawn the player instead of the game's spawn point.
+    pub spawn: Option<[f32; 2]>,
+}
+
+#[allow(dead_code)]
+impl CliArgs {
+    /// Parses the arguments after the program name.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let args: Vec<String> = std::env::args().skip(1).collect();
+    /// match CliArgs::parse(&args)? {
+    ///     CliCommand::Run(cli) => run_game(cli),
+    ///     CliCommand::Help => println!("{}", help_text("MyGame")),
+    ///     CliCommand::Version => println!("{}", version_text("MyGame", "1.0")),
+    /// }
+    /// ```
+    pub fn parse(args: &[String]) -> Result<CliCommand, CliError> {
+        match args.first().map(String::as_str) {
+            Some("migrate-map") => return parse_migrate_map(&args[1..]),
+            Some("import-tiled") => return parse_import_tiled(&args[1..]),
+            Some("validate") => return parse_validate(&args[1..]),
+            Some("bench-tiles") => return parse_bench_tiles(&args[1..]),
+            _ => (),
+        }
+        let mut cli = CliArgs::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "-h" {
+                return Ok(CliCommand::Help);
+            }
+            if arg == "-V" {
+                return Ok(CliCommand::Version);
+            }
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (arg.as_str(), None),
+            };
+            let Some(flag) = FLAGS.iter().find(|flag| flag.name == name) else {
+                return Err(CliError::new(format!("Unknown argument '{}'", arg)));
+            };
+            let value = match (flag.value, inline_value) {
+                (Some(_), Some(value)) => value,
+                (Some(placeholder), None) => match args.next() {
+                    Some(value) => value.clone(),
+                    None => return Err(CliError::new(format!("{} expects a value {}", name, placeholder))),
+                },
+                (None, Some(_)) => return Err(CliError::new(format!("{} does not take a value", name))),
+                (None, None) => String::new(),
+            };
+
+            match name {
+                "--help" => return Ok(CliCommand::Help),
+                "--version" => return Ok(CliCommand::Version),
+                "--config" => cli.config = Some(value),
+                "--backend" => cli.push_override(name, "backend", value),
+                "--opengl" | "--vulkano" => cli.push_override(name, "backend", name.trim_start_matches("--").to_owned()),
+                "--width" => cli.push_override(name, "width", value),
+                "--height" => cli.push_override(name, "height", value),
+                "--fullscreen" => cli.push_override(name, "fullscreen", "true".to_owned()),
+                "--vsync" => cli.push_override(name, "vsync", "true".to_owned()),
+                "--no-vsync" => cli.push_override(name, "vsync", "false".to_owned()),
+                "--log-level" => cli.push_override(name, "log_level", value),
+                "--asset-root" => cli.push_override(name, "asset_root", value),
+                "--map" => cli.map = Some(value),
+                "--spawn" => cli.spawn = Some(parse_position(&value)?),
+                "--headless" => {
+                    cli.headless = true;
+                    cli.push_override(name, "backend", "headless".to_owned());
+                }
+                "--frames" => cli.frames = Some(parse_frames(&value)?),
+                "--screenshot" => cli.screenshot = Some(value),
+                _ => unreachable!("{} is listed in FLAGS but not handled", name),
+            }
+        }
+
+        if !cli.headless {
+            if cli.frames.is_some() {
+                return Err(CliError::new("--frames only applies to --headless runs"));
+            }
+            if cli.screenshot.is_some() {
+                return Err(CliError::new("--screenshot only applies to --headless runs"));
+            }
+        }
+        Ok(CliCommand::Run(cli))
+    }
+
+    fn push_override(&mut self, flag: &str, key: &'static str, value: String) {
+        self.overrides.push(ConfigOverride { flag: flag.to_owned(), key, value });
+    }
+
+    /// The frames a `--headless` run renders.
+    pub fn headless_frames(&self) -> u32 {
+        self.frames.unwrap_or(DEFAULT_HEADLESS_FRAMES)
+    }
+
+    pub fn launch_options(&self) -> LaunchOptions {
+        LaunchOptions { map: self.map.clone(), spawn: self.spawn }
+    }
+}
+
+fn parse_migrate_map(args: &[String]) -> Result<CliCommand, CliError> {
+    let mut migrate = MigrateMapArgs::default();
+    let mut input = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            "--dry-run" => migrate.dry_run = true,
+            "--output" => match args.next() {
+                Some(output) => migrate.output = Some(output.clone()),
+                None => return Err(CliError::new("--output expects a value <path>")),
+            },
+            flag if flag.starts_with('-') => return Err(CliError::new(format!("Unknown migrate-map argument '{}'", flag))),
+            path if input.is_none() => input = Some(path.to_owned()),
+            path => return Err(CliError::new(format!("migrate-map takes one path, got a second one '{}'", path))),
+        }
+    }
+    migrate.input = input.ok_or_else(|| CliError::new("migrate-map expects the map file or directory to upgrade"))?;
+    Ok(CliCommand::MigrateMap(migrate))
+}
+
+fn parse_import_tiled(args: &[String]) -> Result<CliCommand, CliError> {
+    let mut import = ImportTiledArgs::default();
+    let mut input = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |placeholder: &str| args.next().cloned().ok_or_else(|| CliError::new(format!("{} expects a value {}", arg, placeholder)));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            "--dry-run" => import.dry_run = true,
+            "--name" => import.name = Some(value("<name>")?),
+            "--output" => import.output = Some(value("<path>")?),
+            "--origin" => {
+                let origin = value("<x,y>")?;
+                let invalid = || CliError::new(format!("--origin expects a tile like -7,-7, got '{}'", origin));
+                let (x, y) = origin.split_once(',').ok_or_else(invalid)?;
+                import.origin = Some([x.trim().parse().map_err(|_| invalid())?, y.trim().parse().map_err(|_| invalid())?]);
+            }
+            "--atlas" => {
+                let mapping = value("<tileset>=<ATLAS>")?;
+                let (tileset, atlas) = mapping.split_once('=')
+                    .ok_or_else(|| CliError::new(format!("--atlas expects <tileset>=<ATLAS>, got '{}'", mapping)))?;
+                import.atlases.push((tileset.to_owned(), atlas.to_owned()));
+            }
+            flag if flag.starts_with('-') => return Err(CliError::new(format!("Unknown import-tiled argument '{}'", flag))),
+            path if input.is_none() => input = Some(path.to_owned()),
+            path => return Err(CliError::new(format!("import-tiled takes one file, got a second one '{}'", path))),
+        }
+    }
+    import.input = input.ok_or_else(|| CliError::new("import-tiled expects the .tmj or .tmx file to convert"))?;
+    Ok(CliCommand::ImportTiled(import))
+}
+
+fn parse_validate(args: &[String]) -> Result<CliCommand, CliError> {
+    let mut validate = ValidateArgs::default();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            flag if flag.starts_with('-') => return Err(CliError::new(format!("Unknown validate argument '{}'", flag))),
+            dir if validate.root.is_none() => validate.root = Some(dir.to_owned()),
+            dir => return Err(CliError::new(format!("validate takes one asset directory, got a second one '{}'", dir))),
+        }
+    }
+    Ok(CliCommand::Validate(validate))
+}
+
+fn parse_bench_tiles(args: &[String]) -> Result<CliCommand, CliError> {
+    let mut bench = BenchTilesArgs::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let target = match arg.as_str() {
+            "-h" | "--help" => return Ok(CliCommand::Help),
+            "--size" => &mut bench.size,
+            "--frames" => &mut bench.frames,
+            "--backend" => {
+                let name = args.next().ok_or_else(|| CliError::new("--backend expects a value <name>"))?;
+                bench.backend = BenchBackend::from_name(name).ok_or_else(|| {
+                    CliError::new(format!("Unknown bench-tiles backend '{}', expected one of {}", name, BenchBackend::NAMES.join(", ")))
+                })?;
+                continue;
+            }
+            other => return Err(CliError::new(format!("Unknown bench-tiles argument '{}'", other))),
+        };
+        let value = args.next().ok_or_else(|| CliError::new(format!("{} expects a value <n>", arg)))?;
+        *target = match value.trim().parse() {
+            Ok(number) if number > 0 => number,
+            _ => return Err(CliError::new(format!("{} expects a positive number, got '{}'", arg, value))),
+        };
+    }
+    Ok(CliCommand::BenchTiles(bench))
+}
+
+fn parse_position(value: &str) -> Result<[f32; 2], CliError> {
+    let invalid = || CliError::new(format!("--spawn expects a position like 0.5,-0.2, got '{}'", value));
+    let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+    let x: f32 = x.trim().parse().map_err(|_| invalid())?;
+    let y: f32 = y.trim().parse().map_err(|_| invalid())?;
+    if !x.is_finite() || !y.is_finite() {
+        return Err(invalid());
+    }
+    Ok([x, y])
+}
+
+fn parse_frames(value: &str) -> Result<u32, CliError> {
+    match value.trim().parse() {
+        Ok(frames) if frames > 0 => Ok(frames),
+        _ => Err(CliError::new(format!("--frames expects a positive number, got '{}'", value))),
+    }
+}
+
+/// The `--help` text, listing every flag of `FLAGS`.
+pub fn help_text(binary: &str) -> String {
+    let width = FLAGS.iter().map(|flag| flag_usage(flag).len()).max().unwrap_or(0);
+    let mut text = format!("Usage: {} [OPTIONS]\n       {} <COMMAND>\n\nOptions:\n", binary, binary);
+    for flag in &FLAGS {
+        text += &format!("  {:width$}  {}\n", flag_usage(flag), flag.help, width = width);
+    }
+    text += "\nCommands:\n";
+    for command in &COMMANDS {
+        text += &format!("  {}\n      {}\n", flag_usage(command), command.help);
+    }
+    text += "\nConfig values are read from engine.toml, then MALKMUSL_* environment variables, then these options.";
+    text
+}
+
+fn flag_usage(flag: &Flag) -> String {
+    match flag.value {
+        Some(placeholder) => format!("{} {}", flag.name, placeholder),
+        None => flag.name.to_owned(),
+    }
+}
+
+/// The `--version` text: the game's version followed by `ENGINE_VERSION` and
+/// the rest of the `BuildInfo`.
+pub fn version_text(game_name: &str, game_version: &str) -> String {
+    format!("{} {}\n{} v{}\n{}", game_name, game_version, ENGINE_NAME, ENGINE_VERSION, BUILD_INFO.details())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(values: &[&str]) -> Result<CliCommand, CliError> {
+        let args: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        CliArgs::parse(&args)
+    }
+
+    fn run(values: &[&str]) -> CliArgs {
+        match parse(values) {
+            Ok(CliCommand::Run(cli)) => cli,
+            other => panic!("expected a run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_flags_and_values() {
+        let cli = run(&["--width", "1024", "--height=768", "--fullscreen", "--map", "twinleaf_town", "--spawn", "0.5,-0.2"]);
+        let keys: Vec<(&str, &str)> = cli.overrides.iter().map(|o| (o.key, o.value.as_str())).collect();
+        assert_eq!(keys, [("width", "1024"), ("height", "768"), ("fullscreen", "true")]);
+        assert_eq!(cli.map.as_deref(), Some("twinleaf_town"));
+        assert_eq!(cli.spawn, Some([0.5, -0.2]));
+        assert!(!cli.headless);
+
+        let cli = run(&["--headless", "--frames", "3", "--screenshot", "out.png"]);
+        assert_eq!(cli.headless_frames(), 3);
+        assert_eq!(cli.screenshot.as_deref(), Some("out.png"));
+        assert_eq!(cli.overrides[0].value, "headless");
+
+        assert_eq!(parse(&["--map", "x", "--help"]), Ok(CliCommand::Help));
+        assert_eq!(parse(&["-V"]), Ok(CliCommand::Version));
+    }
+
+    #[test]
+    fn rejects_unknown_and_incomplete_arguments() {
+        let message = |values: &[&str]| parse(values).unwrap_err().message;
+        assert_eq!(message(&["--widht", "5"]), "Unknown argument '--widht'");
+        assert_eq!(message(&["--map"]), "--map expects a value <name>");
+        assert_eq!(message(&["--fullscreen=yes"]), "--fullscreen does not take a value");
+        assert!(message(&["--spawn", "1;2"]).contains("--spawn expects a position"));
+        assert!(message(&["--headless", "--frames", "0"]).contains("positive number"));
+        assert_eq!(message(&["--frames", "2"]), "--frames only applies to --headless runs");
+        assert!(message(&["migrate-map"]).contains("expects the map file"));
+        assert!(message(&["migrate-map", "a", "b"]).contains("one path"));
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse(&["migrate-map", "maps/test.ron", "--output", "maps/test/map.ron", "--dry-run"]), Ok(CliCommand::MigrateMap(MigrateMapArgs {
+            input: "maps/test.ron".to_owned(),
+            output: Some("maps/test/map.ron".to_owned()),
+            dry_run: true,
+        })));
+        assert_eq!(parse(&["bench-tiles", "--size", "64"]), Ok(CliCommand::BenchTiles(BenchTilesArgs { size: 64, ..BenchTilesArgs::default() })));
+        assert_eq!(parse(&["bench-tiles", "--backend", "opengl"]), Ok(CliCommand::BenchTiles(BenchTilesArgs { backend: BenchBackend::OpenGL, ..BenchTilesArgs::default() })));
+        assert!(parse(&["bench-tiles", "--frames", "0"]).is_err());
+        assert!(parse(&["bench-tiles", "--backend", "vulkano"]).is_err());
+        assert_eq!(parse(&["import-tiled", "route.tmj", "--origin", "-7,-7", "--atlas", "beach=UNDERWATER_ATLAS", "--name", "route_201"]), Ok(CliCommand::ImportTiled(ImportTiledArgs {
+            input: "route.tmj".to_owned(),
+            name: Some("route_201".to_owned()),
+            origin: Some([-7, -7]),
+            atlases: vec![("beach".to_owned(), "UNDERWATER_ATLAS".to_owned())],
+            ..ImportTiledArgs::default()
+        })));
+        assert!(parse(&["import-tiled", "route.tmj", "--atlas", "beach"]).unwrap_err().message.contains("<tileset>=<ATLAS>"));
+        assert_eq!(parse(&["validate"]), Ok(CliCommand::Validate(ValidateArgs::default())));
+        assert_eq!(parse(&["validate", "mod/assets"]), Ok(CliCommand::Validate(ValidateArgs { root: Some("mod/assets".to_owned()) })));
+        assert!(parse(&["validate", "a", "b"]).is_err());
+        // Commands are only recognized as first argument.
+        assert!(parse(&["--map", "x", "migrate-map"]).is_err());
+    }
+
+    #[test]
+    fn help_lists_every_flag() {
+        let help = help_text("malkmusl-game-engine");
+        for flag in &FLAGS {
+            assert!(help.contains(flag.name), "{} missing from help", flag.name);
+        }
+        for command in &COMMANDS {
+            assert!(help.contains(command.name), "{} missing from help", command.name);
+        }
+        let version = version_text("Game", "1.0");
+        assert!(version.contains(&format!("v{}", ENGINE_VERSION)));
+        assert!(version.contains(BUILD_INFO.git_commit));
+    }
+}
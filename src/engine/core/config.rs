@@ -0,0 +1,593 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use glium::glutin::event::VirtualKeyCode;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::assets_loader::loader::ASSET_FOLDER;
+use crate::engine::core::cli::CliArgs;
+use crate::engine::core::ecs::input::key_from_name;
+
+/// Files looked for in the working directory when no config path is given.
+pub const DEFAULT_CONFIG_FILES: [&str; 2] = ["engine.toml", "engine.ron"];
+
+/// Prefix of the environment variables overriding config values, e.g. `MALKMUSL_WIDTH`.
+pub const ENV_PREFIX: &str = "MALKMUSL_";
+
+/// Largest window width or height accepted by the config.
+pub const MAX_WINDOW_SIZE: u32 = 16384;
+
+/// Names accepted for `backend`, see `Backend::from_name`.
+pub const BACKEND_NAMES: [&str; 3] = ["opengl", "vulkano", "headless"];
+
+/// Largest `world.stream_radius`, 8 keeps 17x17 chunks loaded.
+pub const MAX_STREAM_RADIUS: u32 = 8;
+
+/// Highest `movement.walk_speed` in tiles per second.
+pub const MAX_WALK_SPEED: f32 = 32.0;
+
+/// Config values that can be overridden from the command line and the environment.
+///
+/// | Key          | Flag                          | Environment variable    |
+/// |--------------|-------------------------------|-------------------------|
+/// | `width`      | `--width 1024`                | `MALKMUSL_WIDTH`        |
+/// | `height`     | `--height 768`                | `MALKMUSL_HEIGHT`       |
+/// | `vsync`      | `--vsync`, `--no-vsync`       | `MALKMUSL_VSYNC`        |
+/// | `fullscreen` | `--fullscreen`                | `MALKMUSL_FULLSCREEN`   |
+/// | `backend`    | `--backend opengl`, `--opengl`| `MALKMUSL_BACKEND`      |
+/// | `log_level`  | `--log-level info,opengl=debug` | `MALKMUSL_LOG_LEVEL`  |
+/// | `asset_root` | `--asset-root ./assets`       | `MALKMUSL_ASSET_ROOT`   |
+pub const OVERRIDE_KEYS: [&str; 7] = ["width", "height", "vsync", "fullscreen", "backend", "log_level", "asset_root"];
+
+lazy_static! {
+    static ref ACTIVE_CONFIG: RwLock<Arc<EngineConfig>> = RwLock::new(Arc::new(EngineConfig::default()));
+}
+
+/// Returns the config the engine runs with, the defaults until `set_config` is called.
+pub fn config() -> Arc<EngineConfig> {
+    ACTIVE_CONFIG.read().expect("Failed to read the engine config").clone()
+}
+
+/// Makes `config` the config the engine runs with, see `AppBuilder::with_config`.
+pub fn set_config(config: EngineConfig) {
+    *ACTIVE_CONFIG.write().expect("Failed to write the engine config") = Arc::new(config);
+}
+
+/// Whether messages of `level` are logged for `subsystem` under the active config.
+pub fn log_enabled(subsystem: Subsystem, level: LogLevel) -> bool {
+    config().log.enabled(subsystem, level)
+}
+
+/// How much a subsystem logs, from nothing to everything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(LogLevel::Off),
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            _ => Err(format!("unknown log level '{}', expected one of off, error, warn, info, debug", value)),
+        }
+    }
+}
+
+/// The parts of the engine with their own log level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Subsystem {
+    OpenGL,
+    Assets,
+    GameState,
+    Player,
+    PlayerMovement,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::OpenGL,
+        Subsystem::Assets,
+        Subsystem::GameState,
+        Subsystem::Player,
+        Subsystem::PlayerMovement,
+    ];
+
+    /// The key used for the subsystem in the `[log.subsystems]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::OpenGL => "opengl",
+            Subsystem::Assets => "assets",
+            Subsystem::GameState => "game_state",
+            Subsystem::Player => "player",
+            Subsystem::PlayerMovement => "player_movement",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Subsystem> {
+        Subsystem::ALL.into_iter().find(|subsystem| subsystem.name() == name)
+    }
+}
+
+/// Where and why loading the config failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// The file, flag or environment variable holding the bad value.
+    pub origin: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new(origin: &str, message: impl Into<String>) -> Self {
+        ConfigError { origin: origin.to_owned(), message: message.into() }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid engine config in {}: {}", self.origin, self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// The `[window]` table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: u32,
+    pub height: u32,
+    pub vsync: bool,
+    /// Opens the window in borderless fullscreen on the current monitor.
+    pub fullscreen: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig { width: 800, height: 600, vsync: true, fullscreen: false }
+    }
+}
+
+/// The `[log]` table: a default `level` and optional per-subsystem levels.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: LogLevel,
+    pub subsystems: BTreeMap<String, LogLevel>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        let mut subsystems = BTreeMap::new();
+        subsystems.insert(Subsystem::OpenGL.name().to_owned(), LogLevel::Debug);
+        LogConfig { level: LogLevel::Info, subsystems }
+    }
+}
+
+impl LogConfig {
+    pub fn level_for(&self, subsystem: Subsystem) -> LogLevel {
+        self.subsystems.get(subsystem.name()).copied().unwrap_or(self.level)
+    }
+
+    pub fn enabled(&self, subsystem: Subsystem, level: LogLevel) -> bool {
+        level != LogLevel::Off && level <= self.level_for(subsystem)
+    }
+
+    /// Applies a filter like `"debug"` or `"info,opengl=debug,player=off"`: a
+    /// bare level sets the default, `subsystem=level` pairs set single subsystems.
+    pub fn apply_filter(&mut self, filter: &str) -> Result<(), String> {
+        for directive in filter.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+            match directive.split_once('=') {
+                Some((name, level)) => {
+                    let subsystem = Subsystem::from_name(name.trim()).ok_or_else(|| unknown_subsystem(name.trim()))?;
+                    self.subsystems.insert(subsystem.name().to_owned(), level.parse()?);
+                }
+                None => self.level = directive.parse()?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unknown_subsystem(name: &str) -> String {
+    let names: Vec<&str> = Subsystem::ALL.iter().map(|subsystem| subsystem.name()).collect();
+    format!("unknown log subsystem '{}', expected one of {}", name, names.join(", "))
+}
+
+/// The `[assets]` table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AssetsConfig {
+    /// Directory all asset paths are relative to.
+    pub root: String,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        AssetsConfig { root: ASSET_FOLDER.to_owned() }
+    }
+}
+
+/// The `[world]` table, settings of the overworld: chunked worlds streamed
+/// around the camera and wild encounters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldConfig {
+    /// Chunks loaded in every direction around the camera's chunk, 1 keeps 3x3 chunks loaded.
+    pub stream_radius: u32,
+    /// Upper bound for the chunks kept in memory, in MiB.
+    pub memory_budget_mb: u32,
+    /// Seed of the wild encounter rolls, for reproducible runs. Random when unset.
+    pub encounter_seed: Option<u64>,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        WorldConfig { stream_radius: 1, memory_budget_mb: 64, encounter_seed: None }
+    }
+}
+
+impl WorldConfig {
+    pub fn memory_budget_bytes(&self) -> usize {
+        self.memory_budget_mb as usize * 1024 * 1024
+    }
+}
+
+/// The `[movement]` table, how fast entities walk from tile to tile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MovementConfig {
+    /// Tiles the player walks per second, running doubles it.
+    pub walk_speed: f32,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        MovementConfig { walk_speed: 4.0 }
+    }
+}
+
+/// The `[input]` table, key names as accepted by `key_from_name`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputConfig {
+    pub up: String,
+    pub left: String,
+    pub down: String,
+    pub right: String,
+    pub pause: String,
+    /// Held while walking to run at twice the speed.
+    pub run: String,
+    /// Pressed while standing still and facing water to fish.
+    pub fish: String,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig {
+            up: "W".to_owned(),
+            left: "A".to_owned(),
+            down: "S".to_owned(),
+            right: "D".to_owned(),
+            pause: "Escape".to_owned(),
+            run: "LShift".to_owned(),
+            fish: "F".to_owned(),
+        }
+    }
+}
+
+impl InputConfig {
+    fn bindings(&self) -> [(&'static str, &str); 7] {
+        [("up", &self.up), ("left", &self.left), ("down", &self.down), ("right", &self.right), ("pause", &self.pause), ("run", &self.run), ("fish", &self.fish)]
+    }
+
+    /// The movement keys in the order of `DIRECTIONS`: up, left, down, right.
+    pub fn movement_keys(&self) -> [VirtualKeyCode; 4] {
+        [&self.up, &self.left, &self.down, &self.right].map(|name| validated_key(name))
+    }
+
+    pub fn pause_key(&self) -> VirtualKeyCode {
+        validated_key(&self.pause)
+    }
+
+    pub fn run_key(&self) -> VirtualKeyCode {
+        validated_key(&self.run)
+    }
+
+    pub fn fish_key(&self) -> VirtualKeyCode {
+        validated_key(&self.fish)
+    }
+}
+
+fn validated_key(name: &str) -> VirtualKeyCode {
+    key_from_name(name).unwrap_or_else(|| panic!("Key binding '{}' was not validated", name))
+}
+
+/// Runtime settings of the engine, read from `engine.toml` or `engine.ron`.
+///
+/// Every table and value is optional, missing ones keep their defaults:
+///
+/// ```toml
+/// backend = "opengl"
+///
+/// [window]
+/// width = 800
+/// height = 600
+/// vsync = true
+///
+/// [log]
+/// level = "info"
+/// subsystems = { opengl = "debug", player_movement = "debug" }
+///
+/// [assets]
+/// root = "./src/assets"
+///
+/// [input]
+/// up = "W"
+/// left = "A"
+/// down = "S"
+/// right = "D"
+/// pause = "Escape"
+/// run = "LShift"
+/// fish = "F"
+///
+/// [world]
+/// stream_radius = 1
+/// memory_budget_mb = 64
+/// encounter_seed = 42
+///
+/// [movement]
+/// walk_speed = 4.0
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    /// The render backend, one of `BACKEND_NAMES`. Picked from the command line when unset.
+    pub backend: Option<String>,
+    pub window: WindowConfig,
+    pub log: LogConfig,
+    pub assets: AssetsConfig,
+    pub input: InputConfig,
+    pub world: WorldConfig,
+    pub movement: MovementConfig,
+}
+
+#[allow(dead_code)]
+impl EngineConfig {
+    /// Loads the config the way the engine does at startup.
+    ///
+    /// The file is given with `--config <path>` or `MALKMUSL_CONFIG`, otherwise the first of
+    /// `DEFAULT_CONFIG_FILES` in the working directory is used, or the defaults if there is none.
+    /// Environment variables then override the file and command-line flags override both,
+    /// see `OVERRIDE_KEYS`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let config = EngineConfig::load(&CliArgs::default()).unwrap_or_else(|err| panic!("{}", err));
+    /// App::builder("MyGame", "1.0").with_config(config).run();
+    /// ```
+    pub fn load(cli: &CliArgs) -> Result<Self, ConfigError> {
+        Self::load_with(cli, |name| env::var(name).ok())
+    }
+
+    /// Like `load`, with the environment lookup passed in.
+    pub fn load_with(cli: &CliArgs, env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let env_path = format!("{}CONFIG", ENV_PREFIX);
+
+        let explicit = cli.config.clone().or_else(|| env(&env_path));
+        let path = explicit.or_else(|| {
+            DEFAULT_CONFIG_FILES.iter().find(|file| Path::new(file).is_file()).map(|file| file.to_string())
+        });
+
+        let mut config = match &path {
+            Some(path) => Self::read_file(path)?,
+            None => EngineConfig::default(),
+        };
+
+        for key in OVERRIDE_KEYS {
+            let name = format!("{}{}", ENV_PREFIX, key.to_ascii_uppercase());
+            if let Some(value) = env(&name) {
+                config.set(key, &value).map_err(|message| ConfigError::new(&format!("environment variable {}", name), message))?;
+            }
+        }
+        for config_override in &cli.overrides {
+            config.set(config_override.key, &config_override.value)
+                .map_err(|message| ConfigError::new(&config_override.flag, message))?;
+        }
+
+        config.validate(path.as_deref().unwrap_or("the default config"))?;
+        Ok(config)
+    }
+
+    /// Parses a config file, TOML or RON depending on the extension, without validating it.
+    pub fn read_file(path: &str) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|err| ConfigError::new(path, format!("cannot read the file: {}", err)))?;
+        let is_ron = Path::new(path).extension().is_some_and(|extension| extension == "ron");
+        if is_ron {
+            Self::from_ron(&content).map_err(|message| ConfigError::new(path, message))
+        } else {
+            Self::from_toml(&content).map_err(|message| ConfigError::new(path, message))
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|err| err.to_string().trim_end().to_owned())
+    }
+
+    pub fn from_ron(content: &str) -> Result<Self, String> {
+        ron::de::from_str(content).map_err(|err| err.to_string())
+    }
+
+    /// Sets one of the `OVERRIDE_KEYS` from its textual value.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "width" => self.window.width = parse_size(value)?,
+            "height" => self.window.height = parse_size(value)?,
+            "vsync" => self.window.vsync = parse_bool(value)?,
+            "fullscreen" => self.window.fullscreen = parse_bool(value)?,
+            "backend" => self.backend = Some(parse_backend(value)?),
+            "log_level" => self.log.apply_filter(value)?,
+            "asset_root" => self.assets.root = value.to_owned(),
+            _ => return Err(format!("unknown config key '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Checks the values serde cannot: sizes, names, key bindings and the asset root.
+    /// `origin` names the source of the config in the error.
+    pub fn validate(&self, origin: &str) -> Result<(), ConfigError> {
+        let error = |message: String| Err(ConfigError::new(origin, message));
+
+        for (name, size) in [("window.width", self.window.width), ("window.height", self.window.height)] {
+            if let Err(message) = check_size(size) {
+                return error(format!("{}: {}", name, message));
+            }
+        }
+        if let Some(Err(message)) = self.backend.as_deref().map(parse_backend) {
+            return error(message);
+        }
+        for name in self.log.subsystems.keys() {
+            if Subsystem::from_name(name).is_none() {
+                return error(format!("log.subsystems: {}", unknown_subsystem(name)));
+            }
+        }
+        if !Path::new(&self.assets.root).is_dir() {
+            return error(format!("assets.root '{}' is not a directory", self.assets.root));
+        }
+        if self.world.stream_radius > MAX_STREAM_RADIUS {
+            return error(format!("world.stream_radius {} is out of range, expected 0 to {}", self.world.stream_radius, MAX_STREAM_RADIUS));
+        }
+        if self.world.memory_budget_mb == 0 {
+            return error("world.memory_budget_mb must be at least 1".to_owned());
+        }
+        if !(self.movement.walk_speed > 0.0 && self.movement.walk_speed <= MAX_WALK_SPEED) {
+            return error(format!("movement.walk_speed {} is out of range, expected more than 0 up to {}", self.movement.walk_speed, MAX_WALK_SPEED));
+        }
+
+        let mut bound: Vec<(&str, VirtualKeyCode)> = Vec::new();
+        for (action, name) in self.input.bindings() {
+            let Some(key) = key_from_name(name) else {
+                return error(format!("input.{}: unknown key '{}'", action, name));
+            };
+            if let Some((other, _)) = bound.iter().find(|(_, other_key)| *other_key == key) {
+                return error(format!("input.{} and input.{} are both bound to '{}'", other, action, name));
+            }
+            bound.push((action, key));
+        }
+        Ok(())
+    }
+}
+
+fn parse_size(value: &str) -> Result<u32, String> {
+    let size = value.trim().parse().map_err(|_| format!("'{}' is not a valid size in pixels", value))?;
+    check_size(size)?;
+    Ok(size)
+}
+
+fn check_size(size: u32) -> Result<(), String> {
+    if size == 0 || size > MAX_WINDOW_SIZE {
+        return Err(format!("{} is out of range, expected 1 to {}", size, MAX_WINDOW_SIZE));
+    }
+    Ok(())
+}
+
+fn parse_backend(value: &str) -> Result<String, String> {
+    let backend = value.trim().to_ascii_lowercase();
+    if !BACKEND_NAMES.contains(&backend.as_str()) {
+        return Err(format!("unknown backend '{}', expected one of {}", value, BACKEND_NAMES.join(", ")));
+    }
+    Ok(backend)
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("'{}' is not a valid boolean, expected true or false", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::cli::CliCommand;
+
+    fn cli(values: &[&str]) -> CliArgs {
+        let args: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        match CliArgs::parse(&args) {
+            Ok(CliCommand::Run(cli)) => cli,
+            other => panic!("expected a run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn partial_files_keep_the_defaults() {
+        let config = EngineConfig::from_toml("[window]\nwidth = 1024\n\n[log]\nsubsystems = { player = \"debug\" }\n").unwrap();
+        assert_eq!(config.window.width, 1024);
+        assert_eq!(config.window.height, 600);
+        assert_eq!(config.log.level_for(Subsystem::Player), LogLevel::Debug);
+        assert_eq!(config.log.level_for(Subsystem::GameState), LogLevel::Info);
+        assert!(config.validate("engine.toml").is_ok());
+
+        let ron = EngineConfig::from_ron("(backend: Some(\"headless\"), input: (pause: \"P\"))").unwrap();
+        assert_eq!(ron.backend.as_deref(), Some("headless"));
+        assert_eq!(ron.input.pause_key(), VirtualKeyCode::P);
+    }
+
+    #[test]
+    fn invalid_values_are_reported_with_their_origin() {
+        let err = EngineConfig::from_toml("[window]\nwidht = 1024\n").unwrap_err();
+        assert!(err.contains("widht"), "{}", err);
+
+        let mut config = EngineConfig::default();
+        config.input.left = "W".to_owned();
+        let err = config.validate("engine.toml").unwrap_err();
+        assert_eq!(err.origin, "engine.toml");
+        assert!(err.message.contains("input.up and input.left"), "{}", err);
+
+        config = EngineConfig::default();
+        config.window.height = 0;
+        assert!(config.validate("engine.toml").unwrap_err().message.contains("window.height"));
+
+        let err = EngineConfig::load_with(&cli(&["--backend", "directx"]), |_| None).unwrap_err();
+        assert_eq!(err.origin, "--backend");
+        assert!(err.message.contains("unknown backend 'directx'"), "{}", err);
+    }
+
+    #[test]
+    fn flags_override_the_environment() {
+        let env = |name: &str| match name {
+            "MALKMUSL_WIDTH" => Some("1280".to_owned()),
+            "MALKMUSL_HEIGHT" => Some("720".to_owned()),
+            "MALKMUSL_LOG_LEVEL" => Some("warn,player_movement=debug".to_owned()),
+            _ => None,
+        };
+        let config = EngineConfig::load_with(&cli(&["--width=1920", "--no-vsync", "--headless"]), env).unwrap();
+        assert_eq!((config.window.width, config.window.height), (1920, 720));
+        assert!(!config.window.vsync);
+        assert_eq!(config.backend.as_deref(), Some("headless"));
+        assert!(config.log.enabled(Subsystem::PlayerMovement, LogLevel::Debug));
+        assert!(!config.log.enabled(Subsystem::GameState, LogLevel::Info));
+
+        let err = EngineConfig::load_with(&cli(&[]), |name| (name == "MALKMUSL_VSYNC").then(|| "maybe".to_owned())).unwrap_err();
+        assert_eq!(err.origin, "environment variable MALKMUSL_VSYNC");
+    }
+}
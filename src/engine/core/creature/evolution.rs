@@ -0,0 +1,529 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::core::time::calendar::DayPhase;
+
+use super::instance::Creature;
+use super::species::Species;
+
+/// What makes a creature check an evolution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum EvolutionMethod {
+    /// Gaining a level.
+    LevelUp,
+    /// Using an item on the creature, e.g. an evolution stone.
+    UseItem,
+    /// Being traded.
+    Trade,
+}
+
+impl EvolutionMethod {
+    pub fn name(self) -> &'static str {
+        match self {
+            EvolutionMethod::LevelUp => "level_up",
+            EvolutionMethod::UseItem => "use_item",
+            EvolutionMethod::Trade => "trade",
+        }
+    }
+}
+
+impl TryFrom<String> for EvolutionMethod {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.as_str() {
+            "level_up" => Ok(EvolutionMethod::LevelUp),
+            "use_item" => Ok(EvolutionMethod::UseItem),
+            "trade" => Ok(EvolutionMethod::Trade),
+            _ => Err(format!("unknown evolution method \"{}\", expected level_up, use_item or trade", name)),
+        }
+    }
+}
+
+impl From<EvolutionMethod> for String {
+    fn from(method: EvolutionMethod) -> Self {
+        method.name().to_owned()
+    }
+}
+
+/// When an evolution may happen, parsed from the `condition` of a species
+/// evolution:
+///
+/// | Condition                   | Holds when                                   |
+/// |-----------------------------|----------------------------------------------|
+/// | `level >= 16`               | the creature is at least level 16            |
+/// | `friendship >= 220`         | its friendship is at least 220               |
+/// | `item == fire_stone`        | a fire stone is used on it                   |
+/// | `held_item == kings_rock`   | it holds a king's rock                       |
+/// | `knows ancient_power`       | it knows the attack ancient_power            |
+/// | `time == night`             | it is night, see `DayPhase`                  |
+/// | `trade`                     | it is being traded                           |
+///
+/// Conditions combine with `and` and `or`, `and` binding tighter, and group
+/// with parentheses, e.g. `friendship >= 220 and (time == morning or time == day)`.
+/// An empty condition always holds.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Condition {
+    #[default]
+    Always,
+    Level(u32),
+    Friendship(u32),
+    Item(String),
+    HeldItem(String),
+    KnowsMove(String),
+    TimeOfDay(DayPhase),
+    Trade,
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+/// Why and where a creature checks its evolutions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvolutionTrigger {
+    LevelUp,
+    /// The id of the item used on the creature.
+    UseItem(String),
+    Trade,
+}
+
+impl EvolutionTrigger {
+    pub fn method(&self) -> EvolutionMethod {
+        match self {
+            EvolutionTrigger::LevelUp => EvolutionMethod::LevelUp,
+            EvolutionTrigger::UseItem(_) => EvolutionMethod::UseItem,
+            EvolutionTrigger::Trade => EvolutionMethod::Trade,
+        }
+    }
+}
+
+/// Everything besides the creature an evolution condition can depend on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvolutionContext {
+    pub trigger: EvolutionTrigger,
+    pub phase: DayPhase,
+}
+
+#[allow(dead_code)]
+impl Condition {
+    /// Parses a condition, see `Condition` for the syntax.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Ok(Condition::Always);
+        }
+        let mut parser = Parser { tokens: &tokens, position: 0 };
+        let condition = parser.any()?;
+        match parser.peek() {
+            None => Ok(condition),
+            Some(token) => Err(format!("unexpected `{}` in condition \"{}\"", token, source)),
+        }
+    }
+
+    /// Whether the condition holds whatever the creature and context, like
+    /// `Always` or an `All` of nothing but `Always`.
+    pub fn always_holds(&self) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::All(conditions) => conditions.iter().all(Condition::always_holds),
+            Condition::Any(conditions) => conditions.iter().any(Condition::always_holds),
+            _ => false,
+        }
+    }
+
+    pub fn evaluate(&self, creature: &Creature, context: &EvolutionContext) -> bool {
+        match self {
+            Condition::Always => true,
+            Condition::Level(level) => creature.level >= *level,
+            Condition::Friendship(friendship) => creature.friendship >= *friendship,
+            Condition::Item(item) => context.trigger == EvolutionTrigger::UseItem(item.clone()),
+            Condition::HeldItem(item) => creature.held_item.as_ref() == Some(item),
+            Condition::KnowsMove(attack) => creature.knows(attack),
+            Condition::TimeOfDay(phase) => context.phase == *phase,
+            Condition::Trade => context.trigger == EvolutionTrigger::Trade,
+            Condition::All(conditions) => conditions.iter().all(|condition| condition.evaluate(creature, context)),
+            Condition::Any(conditions) => conditions.iter().any(|condition| condition.evaluate(creature, context)),
+        }
+    }
+
+    /// Whether an `item == ...` test appears anywhere in the condition.
+    pub fn mentions_item(&self) -> bool {
+        match self {
+            Condition::Item(_) => true,
+            Condition::All(conditions) | Condition::Any(conditions) => conditions.iter().any(Condition::mentions_item),
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Condition::parse(&source)
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> Self {
+        condition.to_string()
+    }
+}
+
+/// Prints the condition in the syntax `Condition::parse` reads back. Nested
+/// groups are put in parentheses. Operands that always hold are left out of
+/// `All`, and an `Any` with such an operand prints as the empty condition.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, conditions: &[&Condition], separator: &str| {
+            for (index, condition) in conditions.iter().enumerate() {
+                if index > 0 {
+                    write!(f, " {} ", separator)?;
+                }
+                match condition {
+                    Condition::All(_) | Condition::Any(_) => write!(f, "({})", condition)?,
+                    _ => write!(f, "{}", condition)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Condition::Always => Ok(()),
+            Condition::Level(level) => write!(f, "level >= {}", level),
+            Condition::Friendship(friendship) => write!(f, "friendship >= {}", friendship),
+            Condition::Item(item) => write!(f, "item == {}", item),
+            Condition::HeldItem(item) => write!(f, "held_item == {}", item),
+            Condition::KnowsMove(attack) => write!(f, "knows {}", attack),
+            Condition::TimeOfDay(phase) => write!(f, "time == {}", phase_name(*phase)),
+            Condition::Trade => write!(f, "trade"),
+            Condition::All(conditions) => {
+                let conditions: Vec<&Condition> = conditions.iter().filter(|condition| !condition.always_holds()).collect();
+                join(f, &conditions, "and")
+            }
+            Condition::Any(conditions) if conditions.iter().any(Condition::always_holds) => Ok(()),
+            Condition::Any(conditions) => join(f, &conditions.iter().collect::<Vec<_>>(), "or"),
+        }
+    }
+}
+
+fn phase_name(phase: DayPhase) -> &'static str {
+    match phase {
+        DayPhase::Morning => "morning",
+        DayPhase::Day => "day",
+        DayPhase::Evening => "evening",
+        DayPhase::Night => "night",
+    }
+}
+
+/// Splits a condition into words, numbers, operators and parentheses.
+fn tokenize(source: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '(' | ')' => tokens.push(c.to_string()),
+            '>' | '=' => match chars.next() {
+                Some('=') => tokens.push(format!("{}=", c)),
+                _ => return Err(format!("expected `>=` or `==` in condition \"{}\"", source)),
+            },
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(next) = chars.next_if(|next| next.is_ascii_alphanumeric() || *next == '_') {
+                    word.push(next);
+                }
+                tokens.push(word);
+            }
+            c => return Err(format!("unexpected `{}` in condition \"{}\"", c, source)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        let token = self.tokens.get(self.position).map(String::as_str);
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &str, after: &str) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected `{}` after `{}`, found `{}`", expected, after, token)),
+            None => Err(format!("expected `{}` after `{}`", expected, after)),
+        }
+    }
+
+    /// A name or number after `after`.
+    fn value(&mut self, after: &str) -> Result<String, String> {
+        match self.next() {
+            Some(token) if !matches!(token, "(" | ")" | ">=" | "==" | "and" | "or") => Ok(token.to_owned()),
+            _ => Err(format!("expected a value after `{}`", after)),
+        }
+    }
+
+    fn number(&mut self, after: &str) -> Result<u32, String> {
+        let value = self.value(after)?;
+        value.parse().map_err(|_| format!("expected a number after `{}`, found `{}`", after, value))
+    }
+
+    fn any(&mut self) -> Result<Condition, String> {
+        let mut conditions = vec![self.all()?];
+        while self.peek() == Some("or") {
+            self.next();
+            conditions.push(self.all()?);
+        }
+        Ok(if conditions.len() == 1 { conditions.remove(0) } else { Condition::Any(conditions) })
+    }
+
+    fn all(&mut self) -> Result<Condition, String> {
+        let mut conditions = vec![self.atom()?];
+        while self.peek() == Some("and") {
+            self.next();
+            conditions.push(self.atom()?);
+        }
+        Ok(if conditions.len() == 1 { conditions.remove(0) } else { Condition::All(conditions) })
+    }
+
+    fn atom(&mut self) -> Result<Condition, String> {
+        let Some(token) = self.next().map(str::to_owned) else {
+            return Err("condition ends early".to_owned());
+        };
+        match token.as_str() {
+            "(" => {
+                let condition = self.any()?;
+                self.expect(")", "the condition in parentheses")?;
+                Ok(condition)
+            }
+            "level" => {
+                self.expect(">=", "level")?;
+                Ok(Condition::Level(self.number("level >=")?))
+            }
+            "friendship" => {
+                self.expect(">=", "friendship")?;
+                Ok(Condition::Friendship(self.number("friendship >=")?))
+            }
+            "item" => {
+                self.expect("==", "item")?;
+                Ok(Condition::Item(self.value("item ==")?))
+            }
+            "held_item" => {
+                self.expect("==", "held_item")?;
+                Ok(Condition::HeldItem(self.value("held_item ==")?))
+            }
+            "knows" => Ok(Condition::KnowsMove(self.value("knows")?)),
+            "time" => {
+                self.expect("==", "time")?;
+                let phase = self.value("time ==")?;
+                DayPhase::ALL.into_iter()
+                    .find(|candidate| phase_name(*candidate) == phase)
+                    .map(Condition::TimeOfDay)
+                    .ok_or_else(|| format!("unknown time `{}`, expected morning, day, evening or night", phase))
+            }
+            "trade" => Ok(Condition::Trade),
+            other => Err(format!(
+                "unknown condition `{}`, expected level, friendship, item, held_item, knows, time or trade", other,
+            )),
+        }
+    }
+}
+
+/// A species a creature can evolve into and when, e.g.
+/// `(method: "level_up", to: "ivysaur", condition: "level >= 16")`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Evolution {
+    pub method: EvolutionMethod,
+    /// Id of the species evolved into.
+    pub to: String,
+    #[serde(default, skip_serializing_if = "is_always")]
+    pub condition: Condition,
+}
+
+fn is_always(condition: &Condition) -> bool {
+    *condition == Condition::Always
+}
+
+#[allow(dead_code)]
+impl Evolution {
+    /// Checks what the condition syntax cannot: item conditions only apply to,
+    /// and are required by, the `use_item` method.
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.method, self.condition.mentions_item()) {
+            (EvolutionMethod::UseItem, false) => Err(format!("use_item evolution into {} needs an `item == ...` condition", self.to)),
+            (EvolutionMethod::LevelUp | EvolutionMethod::Trade, true) => {
+                Err(format!("{} evolution into {} tests the used item, which only use_item evolutions have", self.method.name(), self.to))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether `creature` evolves this way in `context`.
+    pub fn is_eligible(&self, creature: &Creature, context: &EvolutionContext) -> bool {
+        self.method == context.trigger.method() && self.condition.evaluate(creature, context)
+    }
+}
+
+/// The evolutions of `species` that `creature`, one of that species, is eligible for in `context`.
+///
+/// # Example
+///
+/// ```rust
+/// let context = EvolutionContext { trigger: EvolutionTrigger::LevelUp, phase: world_time.phase() };
+/// if let Some(evolution) = eligible_evolutions(bulbasaur, &creature, &context).first() {
+///     println!("{} is evolving into {}", creature.species, evolution.to);
+/// }
+/// ```
+#[allow(dead_code)]
+pub fn eligible_evolutions<'a>(species: &'a Species, creature: &Creature, context: &EvolutionContext) -> Vec<&'a Evolution> {
+    species.evolutions.iter().filter(|evolution| evolution.is_eligible(creature, context)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::creature::instance::MoveSlot;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn condition(source: &str) -> Condition {
+        Condition::parse(source).unwrap()
+    }
+
+    #[test]
+    fn parses_conditions() {
+        let table = [
+            ("", Condition::Always),
+            ("level >= 16", Condition::Level(16)),
+            ("friendship>=220", Condition::Friendship(220)),
+            ("item == fire_stone", Condition::Item("fire_stone".to_owned())),
+            ("held_item == kings_rock", Condition::HeldItem("kings_rock".to_owned())),
+            ("knows ancient_power", Condition::KnowsMove("ancient_power".to_owned())),
+            ("time == night", Condition::TimeOfDay(DayPhase::Night)),
+            ("trade", Condition::Trade),
+            ("trade and held_item == metal_coat", Condition::All(vec![Condition::Trade, Condition::HeldItem("metal_coat".to_owned())])),
+            ("level >= 20 or knows mimic and time == day", Condition::Any(vec![
+                Condition::Level(20),
+                Condition::All(vec![Condition::KnowsMove("mimic".to_owned()), Condition::TimeOfDay(DayPhase::Day)]),
+            ])),
+            ("friendship >= 220 and (time == morning or time == day)", Condition::All(vec![
+                Condition::Friendship(220),
+                Condition::Any(vec![Condition::TimeOfDay(DayPhase::Morning), Condition::TimeOfDay(DayPhase::Day)]),
+            ])),
+        ];
+        for (source, expected) in table {
+            let parsed = condition(source);
+            assert_eq!(parsed, expected, "{}", source);
+            assert_eq!(condition(&parsed.to_string()), parsed, "{} does not survive printing", source);
+        }
+    }
+
+    #[test]
+    fn printed_conditions_parse_back() {
+        let level = Condition::Level(5);
+        let trade = Condition::Trade;
+        let night = Condition::TimeOfDay(DayPhase::Night);
+        let table = [
+            Condition::All(vec![level.clone(), Condition::Any(vec![trade.clone(), night.clone()])]),
+            Condition::Any(vec![level.clone(), Condition::All(vec![trade.clone(), night.clone()])]),
+            Condition::All(vec![level.clone(), Condition::All(vec![trade.clone(), night.clone()])]),
+            Condition::Any(vec![Condition::Any(vec![level.clone(), trade.clone()]), night.clone()]),
+            Condition::All(vec![Condition::Any(vec![level.clone(), trade.clone()]), Condition::Any(vec![night.clone(), Condition::KnowsMove("mimic".to_owned())])]),
+        ];
+        for expected in table {
+            assert_eq!(condition(&expected.to_string()), expected, "{}", expected);
+        }
+
+        // (condition with operands that always hold, printed)
+        let table = [
+            (Condition::All(vec![level.clone(), Condition::Always]), "level >= 5"),
+            (Condition::All(vec![Condition::Always, level.clone(), Condition::All(vec![Condition::Always]), trade.clone()]), "level >= 5 and trade"),
+            (Condition::Any(vec![level.clone(), Condition::Always]), ""),
+            (Condition::All(vec![Condition::Always]), ""),
+        ];
+        for (source, printed) in table {
+            assert_eq!(source.to_string(), printed, "{:?}", source);
+            assert_eq!(condition(printed).always_holds(), source.always_holds(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_conditions() {
+        let table = [
+            ("level > 16", "expected `>=` or `==`"),
+            ("level == 16", "expected `>=` after `level`, found `==`"),
+            ("level >= sixteen", "expected a number after `level >=`, found `sixteen`"),
+            ("time == noon", "unknown time `noon`"),
+            ("weather == rain", "unknown condition `weather`"),
+            ("level >= 16 and", "condition ends early"),
+            ("(trade", "expected `)`"),
+            ("trade trade", "unexpected `trade`"),
+            ("knows \"mimic\"", "unexpected `\"`"),
+        ];
+        for (source, expected) in table {
+            let err = Condition::parse(source).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", source, err);
+        }
+        assert!(EvolutionMethod::try_from("level".to_owned()).unwrap_err().contains("unknown evolution method"));
+    }
+
+    #[test]
+    fn finds_the_eligible_evolutions() {
+        let mut species = Species::load("src/assets/pokemon/gen_1/1-3/bulbasaur.ron").unwrap();
+        let evolution = |method: EvolutionMethod, to: &str, source: &str| Evolution { method, to: to.to_owned(), condition: condition(source) };
+        species.evolutions = vec![
+            evolution(EvolutionMethod::LevelUp, "ivysaur", "level >= 16"),
+            evolution(EvolutionMethod::LevelUp, "night_form", "friendship >= 220 and time == night"),
+            evolution(EvolutionMethod::LevelUp, "mimic_form", "knows mimic"),
+            evolution(EvolutionMethod::UseItem, "leaf_form", "item == leaf_stone"),
+            evolution(EvolutionMethod::Trade, "coat_form", "held_item == metal_coat"),
+        ];
+        let mut creature = Creature::new(&species, 10, &mut StdRng::seed_from_u64(1));
+        let context = |trigger: EvolutionTrigger, phase: DayPhase| EvolutionContext { trigger, phase };
+        let eligible = |creature: &Creature, context: EvolutionContext| -> Vec<String> {
+            eligible_evolutions(&species, creature, &context).iter().map(|evolution| evolution.to.clone()).collect()
+        };
+
+        // (level, friendship, known attack, held item, trigger, phase, expected)
+        type Case<'a> = (u32, u32, Option<&'a str>, Option<&'a str>, EvolutionTrigger, DayPhase, &'a [&'a str]);
+        let table: [Case; 9] = [
+            (10, 70, None, None, EvolutionTrigger::LevelUp, DayPhase::Day, &[]),
+            (16, 70, None, None, EvolutionTrigger::LevelUp, DayPhase::Day, &["ivysaur"]),
+            (16, 70, None, None, EvolutionTrigger::Trade, DayPhase::Day, &[]),
+            (10, 220, None, None, EvolutionTrigger::LevelUp, DayPhase::Day, &[]),
+            (10, 220, None, None, EvolutionTrigger::LevelUp, DayPhase::Night, &["night_form"]),
+            (10, 70, Some("mimic"), None, EvolutionTrigger::LevelUp, DayPhase::Day, &["mimic_form"]),
+            (10, 70, None, None, EvolutionTrigger::UseItem("leaf_stone".to_owned()), DayPhase::Day, &["leaf_form"]),
+            (10, 70, None, None, EvolutionTrigger::UseItem("fire_stone".to_owned()), DayPhase::Day, &[]),
+            (10, 70, None, Some("metal_coat"), EvolutionTrigger::Trade, DayPhase::Day, &["coat_form"]),
+        ];
+        for (level, friendship, attack, held_item, trigger, phase, expected) in table {
+            creature.level = level;
+            creature.friendship = friendship;
+            creature.moves = attack.map(|attack| vec![MoveSlot::new(attack, 10)]).unwrap_or_default();
+            creature.held_item = held_item.map(str::to_owned);
+            assert_eq!(eligible(&creature, context(trigger.clone(), phase)), expected, "level {} friendship {} {:?} {:?}", level, friendship, trigger, phase);
+        }
+    }
+
+    #[test]
+    fn item_conditions_need_the_use_item_method() {
+        let evolution = |method: EvolutionMethod, source: &str| Evolution { method, to: "x".to_owned(), condition: condition(source) };
+        assert!(evolution(EvolutionMethod::UseItem, "item == fire_stone").validate().is_ok());
+        assert!(evolution(EvolutionMethod::UseItem, "time == night").validate().is_err());
+        assert!(evolution(EvolutionMethod::LevelUp, "item == fire_stone or level >= 5").validate().is_err());
+        assert!(evolution(EvolutionMethod::Trade, "").validate().is_ok());
+    }
+}
@@ -0,0 +1,366 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::moves::MoveDb;
+use super::species::{GenderRatio, Species, Stats, MAX_LEVEL};
+use super::stats::{stat_value, Nature, Stat, MAX_EV, MAX_EV_TOTAL, MAX_IV};
+
+/// Most attacks a creature knows at once.
+pub const MAX_MOVES: usize = 4;
+
+pub const MAX_FRIENDSHIP: u32 = 255;
+
+/// One in this many creatures is shiny.
+pub const SHINY_ODDS: u32 = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Gender {
+    Male,
+    Female,
+    Genderless,
+}
+
+impl Gender {
+    /// Rolls a gender with the chances of `ratio`, `Genderless` without one.
+    pub fn roll(ratio: Option<GenderRatio>, rng: &mut impl Rng) -> Gender {
+        match ratio {
+            None => Gender::Genderless,
+            Some(ratio) if rng.gen_range(0.0..100.0) < ratio.female => Gender::Female,
+            Some(_) => Gender::Male,
+        }
+    }
+}
+
+/// An attack a creature knows and the power points left to use it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveSlot {
+    pub attack: String,
+    pub pp: u32,
+    pub max_pp: u32,
+}
+
+impl MoveSlot {
+    pub fn new(attack: &str, max_pp: u32) -> Self {
+        MoveSlot { attack: attack.to_owned(), pp: max_pp, max_pp }
+    }
+}
+
+/// A level a creature reached by gaining experience, see `Creature::gain_exp`
+/// and the `CreatureLeveledUp` event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelUp {
+    pub level: u32,
+    /// The stats at the new level.
+    pub stats: Stats,
+    /// The attacks the species learns at the new level.
+    pub attacks: Vec<String>,
+}
+
+/// One creature of a species, e.g. a wild encounter or a member of the
+/// player's party. Everything derived from the species, like its stats, is
+/// computed from the `Species` passed in, which must be the one named by
+/// `species`.
+///
+/// # Example
+///
+/// ```rust
+/// let bulbasaur = species_db.get("bulbasaur").unwrap();
+/// let mut creature = Creature::new(bulbasaur, 5, &mut rng);
+/// for level_up in creature.gain_exp(bulbasaur, 400) {
+///     println!("grew to level {}, attack is now {}", level_up.level, level_up.stats.attack);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Creature {
+    /// Id of the species.
+    pub species: String,
+    pub level: u32,
+    /// Total experience, at least what `level` needs.
+    pub exp: u32,
+    /// Individual values, 0 to `MAX_IV` per stat.
+    pub ivs: Stats,
+    /// Effort values, at most `MAX_EV` per stat and `MAX_EV_TOTAL` in total.
+    pub evs: Stats,
+    pub nature: Nature,
+    /// Current hit points, 0 when fainted.
+    pub hp: u32,
+    /// At most `MAX_MOVES`.
+    pub moves: Vec<MoveSlot>,
+    pub friendship: u32,
+    pub shiny: bool,
+    pub gender: Gender,
+    /// Id of the item the creature holds.
+    #[serde(default)]
+    pub held_item: Option<String>,
+}
+
+#[allow(dead_code)]
+impl Creature {
+    /// A creature of `species` at `level` with random IVs, nature, gender and
+    /// shininess, the species' base friendship, full hit points and no attacks.
+    pub fn new(species: &Species, level: u32, rng: &mut impl Rng) -> Self {
+        let level = level.clamp(1, MAX_LEVEL);
+        let mut ivs = Stats::default();
+        for stat in Stat::ALL {
+            *ivs.get_mut(stat) = rng.gen_range(0..=MAX_IV);
+        }
+        let mut creature = Creature {
+            species: species.id.clone(),
+            level,
+            exp: species.growth_rate.exp_for_level(level),
+            ivs,
+            evs: Stats::default(),
+            nature: Nature::ALL[rng.gen_range(0..Nature::ALL.len())],
+            hp: 0,
+            moves: Vec::new(),
+            friendship: species.base_friendship.min(MAX_FRIENDSHIP),
+            shiny: rng.gen_range(0..SHINY_ODDS) == 0,
+            gender: Gender::roll(species.gender, rng),
+            held_item: None,
+        };
+        creature.hp = creature.max_hp(species);
+        creature
+    }
+
+    /// A wild creature of `species` at `level` like `Creature::new`, knowing
+    /// the species' default moveset with the power points of `moves`.
+    /// Attacks missing from `moves` are left out.
+    pub fn wild(species: &Species, level: u32, moves: &MoveDb, rng: &mut impl Rng) -> Self {
+        let mut creature = Creature::new(species, level, rng);
+        for attack in species.learnset.default_moveset(creature.level) {
+            if let Some(attack) = moves.get(attack) {
+                creature.learn(&attack.id, attack.pp);
+            }
+        }
+        creature
+    }
+
+    /// The value of `stat` at the current level.
+    pub fn stat(&self, species: &Species, stat: Stat) -> u32 {
+        debug_assert_eq!(species.id, self.species, "stats of a {} computed from another species", self.species);
+        stat_value(stat, species.base_stats.get(stat), self.ivs.get(stat), self.evs.get(stat), self.level, self.nature)
+    }
+
+    pub fn stats(&self, species: &Species) -> Stats {
+        let mut stats = Stats::default();
+        for stat in Stat::ALL {
+            *stats.get_mut(stat) = self.stat(species, stat);
+        }
+        stats
+    }
+
+    pub fn max_hp(&self, species: &Species) -> u32 {
+        self.stat(species, Stat::Hp)
+    }
+
+    pub fn is_fainted(&self) -> bool {
+        self.hp == 0
+    }
+
+    /// Loses `amount` hit points, fainting at 0.
+    pub fn damage(&mut self, amount: u32) {
+        self.hp = self.hp.saturating_sub(amount);
+    }
+
+    /// Restores all hit points and power points, like a healing centre.
+    pub fn heal(&mut self, species: &Species) {
+        self.hp = self.max_hp(species);
+        for slot in &mut self.moves {
+            slot.pp = slot.max_pp;
+        }
+    }
+
+    pub fn knows(&self, attack: &str) -> bool {
+        self.moves.iter().any(|slot| slot.attack == attack)
+    }
+
+    /// Learns `attack` with full power points. Returns `false` if the
+    /// creature already knows it or knows `MAX_MOVES` attacks.
+    pub fn learn(&mut self, attack: &str, max_pp: u32) -> bool {
+        if self.knows(attack) || self.moves.len() >= MAX_MOVES {
+            return false;
+        }
+        self.moves.push(MoveSlot::new(attack, max_pp));
+        true
+    }
+
+    /// Adds the EV yield of a defeated creature, within `MAX_EV` per stat and
+    /// `MAX_EV_TOTAL` in total.
+    pub fn gain_evs(&mut self, ev_yield: &Stats) {
+        for stat in Stat::ALL {
+            let room = (MAX_EV_TOTAL - self.evs.total()).min(MAX_EV - self.evs.get(stat));
+            *self.evs.get_mut(stat) += ev_yield.get(stat).min(room);
+        }
+    }
+
+    pub fn change_friendship(&mut self, amount: i32) {
+        self.friendship = self.friendship.saturating_add_signed(amount).min(MAX_FRIENDSHIP);
+    }
+
+    /// Experience still missing to the next level, 0 at `MAX_LEVEL`.
+    pub fn exp_to_next_level(&self, species: &Species) -> u32 {
+        if self.level >= MAX_LEVEL {
+            return 0;
+        }
+        species.growth_rate.exp_for_level(self.level + 1).saturating_sub(self.exp)
+    }
+
+    /// Adds `amount` experience and raises the level as far as it reaches,
+    /// returning one `LevelUp` per level gained. Hit points grow with the
+    /// maximum unless the creature has fainted.
+    ///
+    /// This only returns the level-ups, the `award_exp` system of
+    /// `CreaturePlugin` sends them as `CreatureLeveledUp` events.
+    pub fn gain_exp(&mut self, species: &Species, amount: u32) -> Vec<LevelUp> {
+        let rate = species.growth_rate;
+        self.exp = self.exp.saturating_add(amount).min(rate.exp_for_level(MAX_LEVEL));
+
+        let mut level_ups = Vec::new();
+        while self.level < MAX_LEVEL && self.exp >= rate.exp_for_level(self.level + 1) {
+            let old_max_hp = self.max_hp(species);
+            self.level += 1;
+            let stats = self.stats(species);
+            if !self.is_fainted() {
+                self.hp += stats.hp - old_max_hp;
+            }
+            let attacks = species.learnset.learned_at(self.level).map(str::to_owned).collect();
+            level_ups.push(LevelUp { level: self.level, stats, attacks });
+        }
+        level_ups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn bulbasaur() -> Species {
+        Species::load("src/assets/pokemon/gen_1/1-3/bulbasaur.ron").unwrap()
+    }
+
+    /// A level `level` bulbasaur with fixed IVs, no EVs and a neutral nature.
+    fn fixed(species: &Species, level: u32) -> Creature {
+        let mut creature = Creature::new(species, level, &mut StdRng::seed_from_u64(1));
+        creature.ivs = Stats { hp: 31, attack: 31, defense: 31, sp_attack: 31, sp_defense: 31, speed: 31 };
+        creature.nature = Nature::Hardy;
+        creature.hp = creature.max_hp(species);
+        creature
+    }
+
+    #[test]
+    fn new_creatures_start_at_their_level() {
+        let species = bulbasaur();
+        let mut rng = StdRng::seed_from_u64(7);
+        for level in [1, 5, 16, 50, 100] {
+            let creature = Creature::new(&species, level, &mut rng);
+            assert_eq!(creature.level, level);
+            assert_eq!(creature.exp, species.growth_rate.exp_for_level(level));
+            assert_eq!(creature.hp, creature.max_hp(&species));
+            assert_eq!(creature.friendship, 50);
+            assert!(Stat::ALL.iter().all(|stat| creature.ivs.get(*stat) <= MAX_IV));
+            assert!(creature.moves.is_empty());
+        }
+    }
+
+    #[test]
+    fn wild_creatures_know_their_default_moveset() {
+        let species = bulbasaur();
+        let moves = MoveDb::load_dir(std::path::Path::new("src/assets/moves")).unwrap();
+        let creature = Creature::wild(&species, 9, &moves, &mut StdRng::seed_from_u64(5));
+        let slots: Vec<(&str, u32)> = creature.moves.iter().map(|slot| (slot.attack.as_str(), slot.pp)).collect();
+        assert_eq!(slots, [("tackle", 35), ("vine_whip", 25), ("growth", 20), ("leech_seed", 10)]);
+
+        let creature = Creature::wild(&species, 9, &MoveDb::default(), &mut StdRng::seed_from_u64(5));
+        assert!(creature.moves.is_empty());
+    }
+
+    #[test]
+    fn rolls_gender_from_the_ratio() {
+        let mut rng = StdRng::seed_from_u64(3);
+        // (male, female, expected females of 1000)
+        let table = [(100.0, 0.0, 0..=0), (0.0, 100.0, 1000..=1000), (87.5, 12.5, 100..=150), (50.0, 50.0, 450..=550)];
+        for (male, female, expected) in table {
+            let females = (0..1000).filter(|_| Gender::roll(Some(GenderRatio { male, female }), &mut rng) == Gender::Female).count();
+            assert!(expected.contains(&females), "{} females for {} / {}", females, male, female);
+        }
+        assert_eq!(Gender::roll(None, &mut rng), Gender::Genderless);
+    }
+
+    #[test]
+    fn computes_the_stats_of_a_level() {
+        let species = bulbasaur();
+        // (level, hp, attack, defense, sp_attack, sp_defense, speed) with 31 IVs and no EVs.
+        let table = [
+            (1, 12, 6, 6, 6, 6, 6),
+            (5, 21, 11, 11, 13, 13, 11),
+            (50, 120, 69, 69, 85, 85, 65),
+            (100, 231, 134, 134, 166, 166, 126),
+        ];
+        for (level, hp, attack, defense, sp_attack, sp_defense, speed) in table {
+            let stats = fixed(&species, level).stats(&species);
+            assert_eq!(stats, Stats { hp, attack, defense, sp_attack, sp_defense, speed }, "level {}", level);
+        }
+    }
+
+    #[test]
+    fn gaining_exp_levels_up() {
+        let species = bulbasaur();
+        // (start level, exp gained, levels reached)
+        let table: [(u32, u32, &[u32]); 5] = [
+            (5, 0, &[]),
+            (5, 43, &[]),
+            (5, 44, &[6]),
+            (14, 1000, &[15, 16]),
+            (99, u32::MAX, &[100]),
+        ];
+        for (level, amount, expected) in table {
+            let mut creature = fixed(&species, level);
+            let levels: Vec<u32> = creature.gain_exp(&species, amount).iter().map(|level_up| level_up.level).collect();
+            assert_eq!(levels, expected, "level {} gaining {}", level, amount);
+            assert_eq!(creature.level, *expected.last().unwrap_or(&level));
+            assert_eq!(creature.hp, creature.max_hp(&species));
+        }
+
+        let mut creature = fixed(&species, 14);
+        let level_ups = creature.gain_exp(&species, 1000);
+        assert_eq!(level_ups[0].attacks, ["poison_powder", "sleep_powder"]);
+        assert_eq!(level_ups[1].stats, creature.stats(&species));
+
+        let mut creature = fixed(&species, MAX_LEVEL);
+        assert!(creature.gain_exp(&species, 5000).is_empty());
+        assert_eq!(creature.exp, species.growth_rate.exp_for_level(MAX_LEVEL));
+        assert_eq!(creature.exp_to_next_level(&species), 0);
+    }
+
+    #[test]
+    fn fainted_creatures_stay_fainted_on_level_up() {
+        let species = bulbasaur();
+        let mut creature = fixed(&species, 10);
+        creature.damage(1000);
+        assert!(creature.is_fainted());
+        creature.gain_exp(&species, 5000);
+        assert_eq!(creature.hp, 0);
+        creature.heal(&species);
+        assert_eq!(creature.hp, creature.max_hp(&species));
+    }
+
+    #[test]
+    fn effort_values_and_moves_are_capped() {
+        let species = bulbasaur();
+        let mut creature = fixed(&species, 5);
+        for _ in 0..200 {
+            creature.gain_evs(&Stats { attack: 2, speed: 1, ..Stats::default() });
+        }
+        assert_eq!((creature.evs.attack, creature.evs.speed), (MAX_EV, 200));
+        creature.gain_evs(&Stats { hp: 255, ..Stats::default() });
+        assert_eq!(creature.evs.total(), MAX_EV_TOTAL);
+
+        for attack in ["tackle", "growl", "tackle", "vine_whip", "growth", "leech_seed"] {
+            creature.learn(attack, 20);
+        }
+        let attacks: Vec<&str> = creature.moves.iter().map(|slot| slot.attack.as_str()).collect();
+        assert_eq!(attacks, ["tackle", "growl", "vine_whip", "growth"]);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod species;
+pub mod stats;
+pub mod instance;
+pub mod evolution;
+pub mod moves;
+pub mod plugin;
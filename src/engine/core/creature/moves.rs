@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::assets_loader::loader::asset_root;
+use crate::engine::core::world::map::{key_lines, ron_options, MapError};
+
+use super::species::{ElementType, Localized};
+
+/// Directory below the asset root holding lists of moves, one file per type.
+pub const MOVES_DIR_NAME: &str = "moves";
+
+/// Highest and lowest priority of a move.
+pub const MAX_PRIORITY: i32 = 5;
+pub const MIN_PRIORITY: i32 = -7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MoveCategory {
+    /// Damage from attack against defense.
+    Physical,
+    /// Damage from special attack against special defense.
+    Special,
+    /// No direct damage.
+    Status,
+}
+
+/// The creatures a move can hit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MoveTarget {
+    /// One other creature chosen by the user.
+    Selected,
+    /// A random opponent.
+    RandomOpponent,
+    AllOpponents,
+    /// Every creature on the field but the user.
+    AllOthers,
+    User,
+    Ally,
+    /// The user's side of the field, e.g. screens.
+    UserSide,
+    OpponentSide,
+    /// The whole field, e.g. weather and terrains.
+    All,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MoveFlag {
+    /// Makes contact with the target.
+    Contact,
+    /// Blocked by protect.
+    Protect,
+    /// Copied by mirror move.
+    Mirror,
+    /// Reflected by magic coat.
+    Reflectable,
+    /// Stolen by snatch.
+    Snatch,
+    /// Charges for a turn before hitting.
+    Charge,
+    /// The user must rest the turn after.
+    Recharge,
+    Sound,
+    Punch,
+    Bite,
+    Powder,
+    Pulse,
+    Bullet,
+    Dance,
+    Heal,
+}
+
+/// One attack, an entry of a file in `assets/moves/`:
+///
+/// ```ron
+/// (
+///     id: "vine_whip",
+///     names: (eng: "Vine Whip", ger: "Rankenhieb"),
+///     type: Grass,
+///     category: Physical,
+///     power: 45,
+///     accuracy: 100,
+///     pp: 25,
+///     target: Selected,
+///     flags: [Contact, Protect, Mirror],
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Move {
+    /// The name species learnsets use, e.g. `"vine_whip"`.
+    pub id: String,
+    pub names: Localized,
+    #[serde(rename = "type")]
+    pub element: ElementType,
+    pub category: MoveCategory,
+    /// `None` for status moves and moves whose power is computed in battle.
+    #[serde(default)]
+    pub power: Option<u32>,
+    /// In percent, `None` for moves that never miss.
+    #[serde(default)]
+    pub accuracy: Option<u32>,
+    pub pp: u32,
+    /// Higher priorities go first regardless of speed.
+    #[serde(default)]
+    pub priority: i32,
+    pub target: MoveTarget,
+    /// Id of the battle effect besides damage, empty for plain damage.
+    #[serde(default)]
+    pub effect: String,
+    #[serde(default)]
+    pub flags: Vec<MoveFlag>,
+}
+
+#[allow(dead_code)]
+impl Move {
+    /// The English name.
+    pub fn name(&self) -> &str {
+        &self.names.eng
+    }
+
+    pub fn has_flag(&self, flag: MoveFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    pub fn is_damaging(&self) -> bool {
+        self.category != MoveCategory::Status
+    }
+}
+
+/// Parses and validates a file of moves, `path` is only used in errors.
+pub fn parse_moves(source: &str, path: &str) -> Result<Vec<Move>, MapError> {
+    let moves: Vec<Move> = ron_options().from_str(source)
+        .map_err(|err| MapError::at(path, err.position.line, Some(err.position.col), err.code.to_string()))?;
+
+    let id_lines = key_lines(source, "id:");
+    for (index, attack) in moves.iter().enumerate() {
+        let at_entry = |message: String| match id_lines.get(index) {
+            Some(line) => MapError::at(path, *line, None, message),
+            None => MapError::new(path, message),
+        };
+        if attack.id.is_empty() || !attack.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+            return Err(at_entry(format!("move id {:?} is not snake_case", attack.id)));
+        }
+        if attack.names.eng.is_empty() {
+            return Err(at_entry(format!("{} has no English name", attack.id)));
+        }
+        if attack.category == MoveCategory::Status && attack.power.is_some() {
+            return Err(at_entry(format!("{} is a status move with power", attack.id)));
+        }
+        if attack.power == Some(0) {
+            return Err(at_entry(format!("{} has power 0, leave it out for moves without fixed power", attack.id)));
+        }
+        if attack.accuracy.is_some_and(|accuracy| accuracy == 0 || accuracy > 100) {
+            return Err(at_entry(format!("{} has accuracy {:?}, expected 1..=100", attack.id, attack.accuracy)));
+        }
+        if attack.pp == 0 {
+            return Err(at_entry(format!("{} has no power points", attack.id)));
+        }
+        if !(MIN_PRIORITY..=MAX_PRIORITY).contains(&attack.priority) {
+            return Err(at_entry(format!("{} has priority {}, expected {}..={}", attack.id, attack.priority, MIN_PRIORITY, MAX_PRIORITY)));
+        }
+    }
+    Ok(moves)
+}
+
+/// Every move by id, a `World` resource.
+///
+/// # Example
+///
+/// ```rust
+/// let moves = MoveDb::load_default()?;
+/// let slots: Vec<MoveSlot> = species.learnset.default_moveset(5).iter()
+///     .filter_map(|attack| moves.get(attack))
+///     .map(|attack| MoveSlot::new(&attack.id, attack.pp))
+///     .collect();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveDb {
+    moves: BTreeMap<String, Move>,
+}
+
+#[allow(dead_code)]
+impl MoveDb {
+    /// Loads every `.ron` file in `dir`, in path order.
+    pub fn load_dir(dir: &Path) -> Result<Self, MapError> {
+        let mut db = MoveDb::default();
+        for path in move_files(dir)? {
+            let path = path.display().to_string();
+            let source = fs::read_to_string(&path).map_err(|err| MapError::new(&path, format!("cannot read the file: {}", err)))?;
+            let id_lines = key_lines(&source, "id:");
+            for (index, attack) in parse_moves(&source, &path)?.into_iter().enumerate() {
+                if db.contains(&attack.id) {
+                    let message = format!("move id {} is used twice", attack.id);
+                    return Err(match id_lines.get(index) {
+                        Some(line) => MapError::at(&path, *line, None, message),
+                        None => MapError::new(&path, message),
+                    });
+                }
+                db.insert(attack);
+            }
+        }
+        Ok(db)
+    }
+
+    /// Loads `assets/moves/`, an empty database if the directory does not exist.
+    pub fn load_default() -> Result<Self, MapError> {
+        let dir = format!("{}/{}", asset_root(), MOVES_DIR_NAME);
+        if !Path::new(&dir).is_dir() {
+            return Ok(MoveDb::default());
+        }
+        Self::load_dir(Path::new(&dir))
+    }
+
+    /// Adds `attack`, replacing one with the same id.
+    pub fn insert(&mut self, attack: Move) {
+        self.moves.insert(attack.id.clone(), attack);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Move> {
+        self.moves.get(id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.moves.contains_key(id)
+    }
+
+    /// Every move in id order.
+    pub fn iter(&self) -> impl Iterator<Item = &Move> {
+        self.moves.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+/// Every move file in `dir` in path order.
+pub fn move_files(dir: &Path) -> Result<Vec<PathBuf>, MapError> {
+    let name = dir.display().to_string();
+    let entries = fs::read_dir(dir).map_err(|err| MapError::new(&name, format!("cannot read the directory: {}", err)))?;
+    let mut paths: Vec<PathBuf> = entries.filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == "ron"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::creature::species::SpeciesDb;
+
+    const MOVES_DIR: &str = "src/assets/moves";
+
+    fn sample(second: &str) -> String {
+        format!(r#"[
+    (
+        id: "tackle",
+        names: (eng: "Tackle"),
+        type: Normal,
+        category: Physical,
+        power: 40,
+        accuracy: 100,
+        pp: 35,
+        target: Selected,
+        flags: [Contact, Protect, Mirror],
+    ),
+    (
+        id: "protect",
+        names: (eng: "Protect"),
+        type: Normal,
+        category: Status,
+        pp: 10,
+        {}
+        target: User,
+        effect: "protect",
+    ),
+]"#, second)
+    }
+
+    #[test]
+    fn parses_a_move_file() {
+        let moves = parse_moves(&sample("priority: 4,"), "normal.ron").unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!((moves[0].element, moves[0].power, moves[0].accuracy), (ElementType::Normal, Some(40), Some(100)));
+        assert!(moves[0].has_flag(MoveFlag::Contact) && moves[0].is_damaging());
+        assert_eq!((moves[1].power, moves[1].accuracy, moves[1].priority), (None, None, 4));
+        assert_eq!(moves[1].effect, "protect");
+    }
+
+    #[test]
+    fn rejects_invalid_moves_with_the_line() {
+        // (second move's extra fields, line, message part)
+        let table = [
+            ("priority: 9,", Some(14), "priority 9"),
+            ("power: 40,", Some(14), "status move with power"),
+            ("accuracy: 101,", Some(14), "accuracy Some(101)"),
+            ("priority: 1 power: 40,", Some(19), "Expected"),
+        ];
+        for (fields, line, message) in table {
+            let err = parse_moves(&sample(fields), "normal.ron").unwrap_err();
+            assert_eq!(err.line, line, "{}", fields);
+            assert!(err.message.contains(message), "{}: {}", fields, err.message);
+        }
+    }
+
+    #[test]
+    fn loads_the_moves_directory() {
+        let db = MoveDb::load_dir(Path::new(MOVES_DIR)).unwrap();
+        let vine_whip = db.get("vine_whip").unwrap();
+        assert_eq!((vine_whip.name(), vine_whip.element, vine_whip.pp), ("Vine Whip", ElementType::Grass, 25));
+        assert_eq!(db.get("protect").map(|attack| attack.priority), Some(4));
+
+        let species = SpeciesDb::load_dir(Path::new("src/assets/pokemon")).unwrap();
+        for species in species.iter() {
+            for attack in species.learnset.attacks() {
+                assert!(db.contains(attack), "{} learns unknown move {}", species.id, attack);
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::engine::console_logger::logger;
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::Plugin;
+use crate::engine::core::app::schedule::{in_state, IntoSystemConfig, Stage};
+use crate::engine::core::app::AppState;
+use crate::engine::core::ecs::events::Events;
+use crate::engine::core::ecs::world::{Entity, World};
+
+use super::instance::{Creature, LevelUp};
+use super::moves::MoveDb;
+use super::species::SpeciesDb;
+
+/// Loads the species and moves as `SpeciesDb` and `MoveDb` resources and
+/// levels up `Creature` components as they are awarded experience.
+pub(crate) struct CreaturePlugin;
+
+impl Plugin for CreaturePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<ExpAwarded>();
+        app.add_event::<CreatureLeveledUp>();
+        app.add_systems(Stage::Init, load_creature_data);
+        app.add_systems(Stage::Update, award_exp.run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Send to give the `Creature` of `entity` experience, e.g. after a battle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpAwarded {
+    pub entity: Entity,
+    pub amount: u32,
+}
+
+/// Sent by `award_exp` for every level a `Creature` gains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreatureLeveledUp {
+    pub entity: Entity,
+    /// Id of the species.
+    pub species: String,
+    pub level_up: LevelUp,
+}
+
+fn load_creature_data(world: &mut World) {
+    let species = SpeciesDb::load_default().unwrap_or_else(|err| {
+        println!("{}", logger::warn_assets(&format!("Failed to load the species: {}", err)));
+        SpeciesDb::default()
+    });
+    let moves = MoveDb::load_default().unwrap_or_else(|err| {
+        println!("{}", logger::warn_assets(&format!("Failed to load the moves: {}", err)));
+        MoveDb::default()
+    });
+    world.insert_resource(species);
+    world.insert_resource(moves);
+}
+
+/// Adds the experience of every `ExpAwarded` to the `Creature` of its entity
+/// and sends a `CreatureLeveledUp` per level gained, see `Creature::gain_exp`.
+/// Awards to entities without a creature or of an unknown species are dropped.
+pub fn award_exp(world: &mut World) {
+    let (Some(mut awards), Some(mut level_ups), Some(species_db)) = (
+        world.resource_mut::<Events<ExpAwarded>>(),
+        world.resource_mut::<Events<CreatureLeveledUp>>(),
+        world.resource::<SpeciesDb>(),
+    ) else {
+        return;
+    };
+    for award in awards.drain() {
+        let Some(mut creature) = world.get_mut::<Creature>(award.entity) else {
+            continue;
+        };
+        let Some(species) = species_db.get(&creature.species) else {
+            println!("{}", logger::warn(&format!("Cannot award experience to a {}, it is not a species", creature.species)));
+            continue;
+        };
+        for level_up in creature.gain_exp(species, award.amount) {
+            level_ups.send(CreatureLeveledUp { entity: award.entity, species: species.id.clone(), level_up });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::creature::species::Species;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn awarding_exp_sends_level_ups() {
+        let bulbasaur = Species::load("src/assets/pokemon/gen_1/1-3/bulbasaur.ron").unwrap();
+        let creature = Creature::new(&bulbasaur, 14, &mut StdRng::seed_from_u64(1));
+        let mut species = SpeciesDb::default();
+        species.insert(bulbasaur);
+
+        let mut world = World::new();
+        world.insert_resource(species);
+        world.insert_resource(Events::<ExpAwarded>::default());
+        world.insert_resource(Events::<CreatureLeveledUp>::default());
+        let entity = world.spawn().with(creature).id();
+        let nobody = world.spawn().id();
+        {
+            let mut awards = world.resource_mut::<Events<ExpAwarded>>().unwrap();
+            awards.send(ExpAwarded { entity, amount: 1000 });
+            awards.send(ExpAwarded { entity: nobody, amount: 1000 });
+        }
+        award_exp(&mut world);
+
+        let level_ups: Vec<(Entity, u32)> = world.resource::<Events<CreatureLeveledUp>>().unwrap().iter()
+            .map(|event| (event.entity, event.level_up.level))
+            .collect();
+        assert_eq!(level_ups, [(entity, 15), (entity, 16)]);
+        assert_eq!(world.get::<Creature>(entity).unwrap().level, 16);
+
+        // Awards are used up, running again levels up nothing.
+        award_exp(&mut world);
+        assert_eq!(world.resource::<Events<CreatureLeveledUp>>().unwrap().len(), 2);
+    }
+}
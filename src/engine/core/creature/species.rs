@@ -0,0 +1,712 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::assets_loader::loader::asset_root;
+use crate::engine::core::world::map::{key_lines, ron_options, MapError};
+
+use super::evolution::Evolution;
+use super::instance::MAX_MOVES;
+use super::stats::Stat;
+
+/// Directory below the asset root holding one species per file, grouped into
+/// subdirectories by generation.
+pub const SPECIES_DIR_NAME: &str = "pokemon";
+
+/// The template new species are copied from, skipped when loading a directory.
+pub const TEMPLATE_FILE_NAME: &str = "blank.ron";
+
+/// Highest level a creature can reach.
+pub const MAX_LEVEL: u32 = 100;
+
+/// A text in every language the game ships. Missing languages are empty and
+/// fall back to English, see `Localized::get`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Localized {
+    pub eng: String,
+    pub jpn: String,
+    pub ger: String,
+    pub fr: String,
+    pub it: String,
+    pub esp: String,
+    pub kor: String,
+    #[serde(rename = "zh_Hans")]
+    pub zh_hans: String,
+    #[serde(rename = "zh_Hant")]
+    pub zh_hant: String,
+}
+
+#[allow(dead_code)]
+impl Localized {
+    /// The text in `language`, named like the RON keys (`"ger"`, `"zh_Hans"`),
+    /// or the English text if it is missing.
+    pub fn get(&self, language: &str) -> &str {
+        let text = match language {
+            "jpn" => &self.jpn,
+            "ger" => &self.ger,
+            "fr" => &self.fr,
+            "it" => &self.it,
+            "esp" => &self.esp,
+            "kor" => &self.kor,
+            "zh_Hans" => &self.zh_hans,
+            "zh_Hant" => &self.zh_hant,
+            _ => &self.eng,
+        };
+        if text.is_empty() { &self.eng } else { text }
+    }
+
+    /// Every non-empty text, English first.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        [&self.eng, &self.jpn, &self.ger, &self.fr, &self.it, &self.esp, &self.kor, &self.zh_hans, &self.zh_hant]
+            .into_iter()
+            .map(String::as_str)
+            .filter(|text| !text.is_empty())
+    }
+}
+
+/// Where a species appears in the national dex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DexIds {
+    pub national: u32,
+    /// The generation the species was introduced in.
+    pub generation: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ElementType {
+    Normal,
+    Fire,
+    Water,
+    Grass,
+    Electric,
+    Ice,
+    Fighting,
+    Poison,
+    Ground,
+    Flying,
+    Psychic,
+    Bug,
+    Rock,
+    Ghost,
+    Dragon,
+    Dark,
+    Steel,
+    Fairy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EggGroup {
+    Monster,
+    Water1,
+    Water2,
+    Water3,
+    Bug,
+    Flying,
+    Field,
+    Fairy,
+    Grass,
+    HumanLike,
+    Mineral,
+    Amorphous,
+    Ditto,
+    Dragon,
+    /// Cannot breed.
+    Undiscovered,
+}
+
+/// How much experience a species needs per level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GrowthRate {
+    Erratic,
+    Fast,
+    MediumFast,
+    MediumSlow,
+    Slow,
+    Fluctuating,
+}
+
+#[allow(dead_code)]
+impl GrowthRate {
+    /// Total experience a creature needs to reach `level`, 0 at level 1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// assert_eq!(GrowthRate::MediumSlow.exp_for_level(16), 2535);
+    /// assert_eq!(GrowthRate::MediumSlow.exp_for_level(100), 1_059_860);
+    /// ```
+    pub fn exp_for_level(self, level: u32) -> u32 {
+        let n = level.clamp(1, MAX_LEVEL) as i64;
+        if n == 1 {
+            return 0;
+        }
+        let cube = n * n * n;
+        let exp = match self {
+            GrowthRate::Erratic => match n {
+                ..=49 => cube * (100 - n) / 50,
+                50..=67 => cube * (150 - n) / 100,
+                68..=97 => cube * ((1911 - 10 * n) / 3) / 500,
+                _ => cube * (160 - n) / 100,
+            },
+            GrowthRate::Fast => 4 * cube / 5,
+            GrowthRate::MediumFast => cube,
+            GrowthRate::MediumSlow => (6 * cube - 75 * n * n + 500 * n - 700) / 5,
+            GrowthRate::Slow => 5 * cube / 4,
+            GrowthRate::Fluctuating => match n {
+                ..=14 => cube * ((n + 1) / 3 + 24) / 50,
+                15..=35 => cube * (n + 14) / 50,
+                _ => cube * (n / 2 + 32) / 50,
+            },
+        };
+        exp.max(0) as u32
+    }
+
+    /// The level a creature with `exp` total experience has.
+    pub fn level_for_exp(self, exp: u32) -> u32 {
+        (2..=MAX_LEVEL).take_while(|level| self.exp_for_level(*level) <= exp).last().unwrap_or(1)
+    }
+}
+
+/// One value per stat, used for base stats, the EV yield and the IVs, EVs
+/// and stats of a creature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Stats {
+    pub hp: u32,
+    pub attack: u32,
+    pub defense: u32,
+    pub sp_attack: u32,
+    pub sp_defense: u32,
+    pub speed: u32,
+}
+
+#[allow(dead_code)]
+impl Stats {
+    pub fn get(&self, stat: Stat) -> u32 {
+        match stat {
+            Stat::Hp => self.hp,
+            Stat::Attack => self.attack,
+            Stat::Defense => self.defense,
+            Stat::SpAttack => self.sp_attack,
+            Stat::SpDefense => self.sp_defense,
+            Stat::Speed => self.speed,
+        }
+    }
+
+    pub fn get_mut(&mut self, stat: Stat) -> &mut u32 {
+        match stat {
+            Stat::Hp => &mut self.hp,
+            Stat::Attack => &mut self.attack,
+            Stat::Defense => &mut self.defense,
+            Stat::SpAttack => &mut self.sp_attack,
+            Stat::SpDefense => &mut self.sp_defense,
+            Stat::Speed => &mut self.speed,
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.hp + self.attack + self.defense + self.sp_attack + self.sp_defense + self.speed
+    }
+}
+
+/// Chance in percent of a creature being male or female. Genderless species
+/// have no ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenderRatio {
+    pub male: f32,
+    pub female: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelUpAttack {
+    pub level: u32,
+    pub attack: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineAttack {
+    /// `TM` or `HM` followed by its number, e.g. `"TM06"`.
+    pub machine: String,
+    pub attack: String,
+}
+
+/// The attacks a species can learn, by attack id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Learnset {
+    /// In level order.
+    pub level_up: Vec<LevelUpAttack>,
+    pub machines: Vec<MachineAttack>,
+    pub egg: Vec<String>,
+    pub tutor: Vec<String>,
+}
+
+/// Questions about what a species learns, by attack id.
+///
+/// # Example
+///
+/// ```rust
+/// let learnset = &species_db.get("bulbasaur").unwrap().learnset;
+/// assert_eq!(learnset.learned_at(15).collect::<Vec<_>>(), ["poison_powder", "sleep_powder"]);
+/// assert_eq!(learnset.machine("TM06"), Some("toxic"));
+/// assert_eq!(learnset.default_moveset(9), ["tackle", "vine_whip", "growth", "leech_seed"]);
+/// ```
+#[allow(dead_code)]
+impl Learnset {
+    /// The attacks learned on reaching exactly `level`.
+    pub fn learned_at(&self, level: u32) -> impl Iterator<Item = &str> {
+        self.level_up.iter().filter(move |attack| attack.level == level).map(|attack| attack.attack.as_str())
+    }
+
+    /// Every attack learned by levelling up to `level`, once each in the
+    /// order they are learned.
+    pub fn learned_up_to(&self, level: u32) -> Vec<&str> {
+        let mut attacks: Vec<&str> = Vec::new();
+        for attack in self.level_up.iter().take_while(|attack| attack.level <= level) {
+            if !attacks.contains(&attack.attack.as_str()) {
+                attacks.push(&attack.attack);
+            }
+        }
+        attacks
+    }
+
+    /// The attacks a wild creature at `level` knows: the last `MAX_MOVES`
+    /// of `learned_up_to(level)`, older attacks forgotten first.
+    pub fn default_moveset(&self, level: u32) -> Vec<&str> {
+        let mut attacks = self.learned_up_to(level);
+        let forgotten = attacks.len().saturating_sub(MAX_MOVES);
+        attacks.drain(..forgotten);
+        attacks
+    }
+
+    /// The attack `machine`, e.g. `"TM06"`, teaches this species.
+    pub fn machine(&self, machine: &str) -> Option<&str> {
+        self.machines.iter().find(|entry| entry.machine == machine).map(|entry| entry.attack.as_str())
+    }
+
+    pub fn can_learn_machine(&self, machine: &str) -> bool {
+        self.machine(machine).is_some()
+    }
+
+    /// Whether `attack` is learned by levelling up, from a machine, an egg or a tutor.
+    pub fn can_learn(&self, attack: &str) -> bool {
+        self.attacks().any(|known| known == attack)
+    }
+
+    /// Every attack of the learnset, some possibly more than once.
+    pub fn attacks(&self) -> impl Iterator<Item = &str> {
+        let level_up = self.level_up.iter().map(|attack| attack.attack.as_str());
+        let machines = self.machines.iter().map(|attack| attack.attack.as_str());
+        level_up.chain(machines).chain(self.egg.iter().map(String::as_str)).chain(self.tutor.iter().map(String::as_str))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpritePair {
+    pub front: String,
+    pub back: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sprites {
+    pub normal: SpritePair,
+    pub shiny: SpritePair,
+}
+
+/// Everything creatures of one kind share, loaded from one file of
+/// `assets/pokemon/`. The id is the file name without extension, see
+/// `src/assets/pokemon/blank.ron` for the layout:
+///
+/// ```ron
+/// (
+///     dex: (national: 1, generation: 1),
+///     names: (eng: "Bulbasaur", ger: "Bisasam"),
+///     category: (eng: "Seed Pokémon"),
+///     description: (eng: "A strange seed was planted on its back at birth."),
+///     types: [Grass, Poison],
+///     base_stats: (hp: 45, attack: 49, defense: 49, sp_attack: 65, sp_defense: 65, speed: 45),
+///     ev_yield: (hp: 0, attack: 0, defense: 0, sp_attack: 1, sp_defense: 0, speed: 0),
+///     egg_groups: [Grass, Monster],
+///     egg_cycles: 20,
+///     gender: (male: 87.5, female: 12.5),
+///     catch_rate: 45,
+///     base_friendship: 50,
+///     base_exp: 64,
+///     growth_rate: MediumSlow,
+///     evolutions: [(method: "level_up", to: "ivysaur", condition: "level >= 16")],
+///     learnset: (level_up: [(level: 1, attack: "tackle")], machines: [(machine: "TM06", attack: "toxic")]),
+///     sprites: (
+///         normal: (front: "bulbasaur.png", back: "bulbasaur_back.png"),
+///         shiny: (front: "bulbasaur_shiny.png", back: "bulbasaur_shiny_back.png"),
+///     ),
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Species {
+    #[serde(skip)]
+    pub id: String,
+    pub dex: DexIds,
+    pub names: Localized,
+    #[serde(default)]
+    pub category: Localized,
+    #[serde(default)]
+    pub description: Localized,
+    /// One or two types, the main type first.
+    pub types: Vec<ElementType>,
+    pub base_stats: Stats,
+    pub ev_yield: Stats,
+    /// One or two egg groups.
+    pub egg_groups: Vec<EggGroup>,
+    pub egg_cycles: u32,
+    /// `None` for genderless species.
+    pub gender: Option<GenderRatio>,
+    pub catch_rate: u32,
+    pub base_friendship: u32,
+    pub base_exp: u32,
+    pub growth_rate: GrowthRate,
+    #[serde(default)]
+    pub evolutions: Vec<Evolution>,
+    #[serde(default)]
+    pub learnset: Learnset,
+    pub sprites: Sprites,
+}
+
+#[allow(dead_code)]
+impl Species {
+    /// Parses and validates a species file. The id is taken from the file
+    /// name of `path`, which is otherwise only used in errors.
+    pub fn parse(source: &str, path: &str) -> Result<Self, MapError> {
+        let mut species: Species = ron_options().from_str(source)
+            .map_err(|err| MapError::at(path, err.position.line, Some(err.position.col), err.code.to_string()))?;
+        species.id = Path::new(path).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+
+        let at_key = |key: &str, index: usize, message: String| match key_lines(source, key).get(index) {
+            Some(line) => MapError::at(path, *line, None, message),
+            None => MapError::new(path, message),
+        };
+        if species.dex.national == 0 {
+            return Err(at_key("national:", 0, "national dex id must be at least 1".to_owned()));
+        }
+        if species.names.eng.is_empty() {
+            return Err(at_key("names:", 0, "species has no English name".to_owned()));
+        }
+        if species.types.is_empty() || species.types.len() > 2 || species.types.first() == species.types.get(1) {
+            return Err(at_key("types:", 0, format!("expected one or two different types, found {:?}", species.types)));
+        }
+        if species.egg_groups.is_empty() || species.egg_groups.len() > 2 || species.egg_groups.first() == species.egg_groups.get(1) {
+            return Err(at_key("egg_groups:", 0, format!("expected one or two different egg groups, found {:?}", species.egg_groups)));
+        }
+        if let Some(GenderRatio { male, female }) = species.gender {
+            if male < 0.0 || female < 0.0 || (male + female - 100.0).abs() > 0.01 {
+                return Err(at_key("gender:", 0, format!("gender ratio {} / {} does not add up to 100, use `None` for genderless species", male, female)));
+            }
+        }
+        if species.catch_rate == 0 || species.catch_rate > 255 {
+            return Err(at_key("catch_rate:", 0, format!("catch rate {} is outside 1..=255", species.catch_rate)));
+        }
+        for (index, evolution) in species.evolutions.iter().enumerate() {
+            if evolution.to.is_empty() || evolution.to == species.id {
+                return Err(at_key("to:", index, format!("evolution target {:?} is not another species", evolution.to)));
+            }
+            evolution.validate().map_err(|message| at_key("to:", index, message))?;
+        }
+        for (index, attack) in species.learnset.level_up.iter().enumerate() {
+            if attack.level == 0 || attack.level > MAX_LEVEL {
+                return Err(at_key("level:", index, format!("{} is learned at level {}, expected 1..={}", attack.attack, attack.level, MAX_LEVEL)));
+            }
+        }
+        for (index, attack) in species.learnset.machines.iter().enumerate() {
+            if !is_machine_name(&attack.machine) {
+                return Err(at_key("machine:", index, format!("{:?} is not a machine name like \"TM06\" or \"HM01\"", attack.machine)));
+            }
+        }
+        species.learnset.level_up.sort_by_key(|attack| attack.level);
+        Ok(species)
+    }
+
+    pub fn load(path: &str) -> Result<Self, MapError> {
+        let source = fs::read_to_string(path).map_err(|err| MapError::new(path, format!("cannot read the file: {}", err)))?;
+        Self::parse(&source, path)
+    }
+
+    /// The English name.
+    pub fn name(&self) -> &str {
+        &self.names.eng
+    }
+
+    pub fn is_genderless(&self) -> bool {
+        self.gender.is_none()
+    }
+}
+
+/// `TM` or `HM` followed by digits.
+fn is_machine_name(name: &str) -> bool {
+    let digits = name.strip_prefix("TM").or_else(|| name.strip_prefix("HM"));
+    digits.is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Every species by national dex id, a `World` resource.
+///
+/// # Example
+///
+/// ```rust
+/// let species = SpeciesDb::load_default()?;
+/// let bulbasaur = species.by_dex(1).unwrap();
+/// assert_eq!(species.by_name("Bisasam"), Some(bulbasaur));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpeciesDb {
+    species: BTreeMap<u32, Species>,
+    ids: HashMap<String, u32>,
+}
+
+#[allow(dead_code)]
+impl SpeciesDb {
+    /// Loads every `.ron` file below `dir` except the template, in path order.
+    pub fn load_dir(dir: &Path) -> Result<Self, MapError> {
+        let mut db = SpeciesDb::default();
+        for path in species_files(dir)? {
+            let path = path.display().to_string();
+            let species = Species::load(&path)?;
+            if let Some(other) = db.species.get(&species.dex.national) {
+                return Err(MapError::new(&path, format!("national dex id {} is also used by {}", species.dex.national, other.id)));
+            }
+            if db.ids.contains_key(&species.id) {
+                return Err(MapError::new(&path, format!("species id {} is used twice", species.id)));
+            }
+            db.insert(species);
+        }
+        Ok(db)
+    }
+
+    /// Loads `assets/pokemon/`, an empty database if the directory does not exist.
+    pub fn load_default() -> Result<Self, MapError> {
+        let dir = format!("{}/{}", asset_root(), SPECIES_DIR_NAME);
+        if !Path::new(&dir).is_dir() {
+            return Ok(SpeciesDb::default());
+        }
+        Self::load_dir(Path::new(&dir))
+    }
+
+    /// Adds `species`, replacing one with the same national dex id.
+    pub fn insert(&mut self, species: Species) {
+        if let Some(old) = self.species.get(&species.dex.national) {
+            self.ids.remove(&old.id);
+        }
+        self.ids.insert(species.id.clone(), species.dex.national);
+        self.species.insert(species.dex.national, species);
+    }
+
+    /// The species with file name `id`, e.g. `"bulbasaur"`.
+    pub fn get(&self, id: &str) -> Option<&Species> {
+        self.ids.get(id).and_then(|national| self.species.get(national))
+    }
+
+    pub fn by_dex(&self, national: u32) -> Option<&Species> {
+        self.species.get(&national)
+    }
+
+    /// The species with id `name`, or with `name` as its name in any language
+    /// ignoring case.
+    pub fn by_name(&self, name: &str) -> Option<&Species> {
+        self.get(name).or_else(|| {
+            let name = name.to_lowercase();
+            self.species.values().find(|species| species.names.iter().any(|text| text.to_lowercase() == name))
+        })
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// Every species in national dex order.
+    pub fn iter(&self) -> impl Iterator<Item = &Species> {
+        self.species.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.species.is_empty()
+    }
+}
+
+/// Every species file below `dir` in path order, without the template.
+pub fn species_files(dir: &Path) -> Result<Vec<PathBuf>, MapError> {
+    let mut paths = Vec::new();
+    collect_ron_files(dir, &mut paths)?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// Adds every `.ron` file below `dir` to `paths`, skipping the template.
+fn collect_ron_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), MapError> {
+    let name = dir.display().to_string();
+    let entries = fs::read_dir(dir).map_err(|err| MapError::new(&name, format!("cannot read the directory: {}", err)))?;
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        if path.is_dir() {
+            collect_ron_files(&path, paths)?;
+        } else if path.extension().is_some_and(|extension| extension == "ron")
+            && path.file_name().is_some_and(|file| file != TEMPLATE_FILE_NAME)
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::creature::evolution::Condition;
+
+    const SPECIES_DIR: &str = "src/assets/pokemon";
+
+    fn sample(gender: &str, evolution: &str) -> String {
+        format!(r#"(
+    dex: (national: 7, generation: 1),
+    names: (eng: "Squirtle", ger: "Schiggy"),
+    types: [Water],
+    base_stats: (hp: 44, attack: 48, defense: 65, sp_attack: 50, sp_defense: 64, speed: 43),
+    ev_yield: (hp: 0, attack: 0, defense: 1, sp_attack: 0, sp_defense: 0, speed: 0),
+    egg_groups: [Monster, Water1],
+    egg_cycles: 20,
+    gender: {},
+    catch_rate: 45,
+    base_friendship: 50,
+    base_exp: 63,
+    growth_rate: MediumSlow,
+    evolutions: [
+        {},
+    ],
+    learnset: (
+        level_up: [(level: 3, attack: "tail_whip"), (level: 1, attack: "tackle")],
+        machines: [(machine: "TM06", attack: "toxic")],
+    ),
+    sprites: (
+        normal: (front: "squirtle.png", back: "squirtle_back.png"),
+        shiny: (front: "squirtle_shiny.png", back: "squirtle_shiny_back.png"),
+    ),
+)"#, gender, evolution)
+    }
+
+    #[test]
+    fn parses_a_species_file() {
+        let source = sample("(male: 87.5, female: 12.5)", r#"(method: "level_up", to: "wartortle", condition: "level >= 16")"#);
+        let species = Species::parse(&source, "gen_1/squirtle.ron").unwrap();
+        assert_eq!(species.id, "squirtle");
+        assert_eq!(species.names.get("ger"), "Schiggy");
+        assert_eq!(species.names.get("fr"), "Squirtle");
+        assert_eq!(species.types, vec![ElementType::Water]);
+        assert_eq!(species.base_stats.total(), 314);
+        assert_eq!(species.learnset.level_up[0].attack, "tackle");
+        assert_eq!(species.evolutions[0].to, "wartortle");
+        assert_eq!(species.evolutions[0].condition, Condition::Level(16));
+
+        let genderless = sample("None", r#"(method: "level_up", to: "wartortle")"#);
+        assert!(Species::parse(&genderless, "squirtle.ron").unwrap().is_genderless());
+    }
+
+    #[test]
+    fn rejects_invalid_species_with_the_line() {
+        let source = sample("(male: 87.5, female: 10.0)", r#"(method: "level_up", to: "wartortle")"#);
+        let err = Species::parse(&source, "squirtle.ron").unwrap_err();
+        assert_eq!((err.path.as_str(), err.line), ("squirtle.ron", Some(9)));
+
+        let source = sample("(male: 87.5, female: 12.5)", r#"(method: "level_up", to: "squirtle")"#);
+        assert_eq!(Species::parse(&source, "squirtle.ron").unwrap_err().line, Some(15));
+
+        let source = sample("(male: 87.5, female: 12.5)", r#"(method: "level_up" to: "wartortle")"#);
+        assert_eq!(Species::parse(&source, "squirtle.ron").unwrap_err().line, Some(15));
+
+        let source = sample("(male: 87.5, female: 12.5)", r#"(method: "level_up", to: "wartortle", condition: "level > 16")"#);
+        let err = Species::parse(&source, "squirtle.ron").unwrap_err();
+        assert_eq!(err.line, Some(15));
+        assert!(err.message.contains("expected `>=` or `==`"), "{}", err.message);
+
+        let source = sample("(male: 87.5, female: 12.5)", r#"(method: "evolve", to: "wartortle")"#);
+        assert!(Species::parse(&source, "squirtle.ron").unwrap_err().message.contains("unknown evolution method"));
+
+        let source = sample("(male: 87.5, female: 12.5)", r#"(method: "use_item", to: "wartortle", condition: "level >= 16")"#);
+        assert_eq!(Species::parse(&source, "squirtle.ron").unwrap_err().line, Some(15));
+    }
+
+    #[test]
+    fn growth_rates_match_the_experience_tables() {
+        use GrowthRate::*;
+        // (rate, level, total experience)
+        let table = [
+            (Erratic, 1, 0), (Erratic, 2, 15), (Erratic, 50, 125_000), (Erratic, 68, 257_834), (Erratic, 98, 583_539), (Erratic, 100, 600_000),
+            (Fast, 1, 0), (Fast, 2, 6), (Fast, 50, 100_000), (Fast, 100, 800_000),
+            (MediumFast, 1, 0), (MediumFast, 2, 8), (MediumFast, 50, 125_000), (MediumFast, 100, 1_000_000),
+            (MediumSlow, 1, 0), (MediumSlow, 2, 9), (MediumSlow, 5, 135), (MediumSlow, 16, 2535), (MediumSlow, 100, 1_059_860),
+            (Slow, 1, 0), (Slow, 2, 10), (Slow, 50, 156_250), (Slow, 100, 1_250_000),
+            (Fluctuating, 1, 0), (Fluctuating, 2, 4), (Fluctuating, 15, 1_957), (Fluctuating, 36, 46_656), (Fluctuating, 100, 1_640_000),
+        ];
+        for (rate, level, exp) in table {
+            assert_eq!(rate.exp_for_level(level), exp, "{:?} at level {}", rate, level);
+            assert_eq!(rate.level_for_exp(exp), level, "{:?} with {} exp", rate, exp);
+        }
+        assert_eq!(MediumSlow.level_for_exp(2534), 15);
+        assert_eq!(Fast.level_for_exp(u32::MAX), MAX_LEVEL);
+    }
+
+    #[test]
+    fn loads_the_species_directory() {
+        let db = SpeciesDb::load_dir(Path::new(SPECIES_DIR)).unwrap();
+        assert_eq!(db.iter().map(|species| species.id.as_str()).collect::<Vec<_>>(), ["bulbasaur", "ivysaur", "venusaur"]);
+        assert_eq!(db.by_dex(2).map(Species::name), Some("Ivysaur"));
+        assert_eq!(db.by_name("bisasam").map(|species| species.dex.national), Some(1));
+        assert_eq!(db.by_name("venusaur"), db.by_dex(3));
+        assert!(db.by_name("Squirtle").is_none());
+        for species in db.iter() {
+            for evolution in &species.evolutions {
+                assert!(db.contains(&evolution.to), "{} evolves into unknown {}", species.id, evolution.to);
+            }
+        }
+    }
+
+    #[test]
+    fn answers_learnset_questions() {
+        let bulbasaur = Species::load(&format!("{}/gen_1/1-3/bulbasaur.ron", SPECIES_DIR)).unwrap();
+        let learnset = &bulbasaur.learnset;
+        assert_eq!(learnset.learned_at(15).collect::<Vec<_>>(), ["poison_powder", "sleep_powder"]);
+        assert_eq!(learnset.learned_at(2).count(), 0);
+        // (level, default moveset)
+        let table: [(u32, &[&str]); 5] = [
+            (1, &["growl", "tackle"]),
+            (5, &["growl", "tackle", "vine_whip"]),
+            (9, &["tackle", "vine_whip", "growth", "leech_seed"]),
+            (15, &["leech_seed", "razor_leaf", "poison_powder", "sleep_powder"]),
+            (MAX_LEVEL, &["synthesis", "worry_seed", "double_edge", "solar_beam"]),
+        ];
+        for (level, expected) in table {
+            assert_eq!(learnset.default_moveset(level), expected, "level {}", level);
+        }
+        assert_eq!(learnset.learned_up_to(9).len(), 5);
+
+        assert_eq!(learnset.machine("TM06"), Some("toxic"));
+        assert!(learnset.can_learn_machine("TM06"));
+        assert!(!learnset.can_learn_machine("TM99"));
+        assert!(learnset.can_learn("amnesia") && learnset.can_learn("vine_whip") && !learnset.can_learn("ember"));
+    }
+
+    #[test]
+    fn template_matches_the_schema() {
+        let source = fs::read_to_string(format!("{}/{}", SPECIES_DIR, TEMPLATE_FILE_NAME)).unwrap();
+        let template: Species = ron_options().from_str(&source).unwrap();
+        assert_eq!(template.dex.national, 0);
+    }
+}
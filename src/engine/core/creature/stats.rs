@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+/// Highest individual value of a stat.
+pub const MAX_IV: u32 = 31;
+
+/// Most effort values a single stat can gain.
+pub const MAX_EV: u32 = 252;
+
+/// Most effort values a creature can gain over all stats.
+pub const MAX_EV_TOTAL: u32 = 510;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    Hp,
+    Attack,
+    Defense,
+    SpAttack,
+    SpDefense,
+    Speed,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 6] = [Stat::Hp, Stat::Attack, Stat::Defense, Stat::SpAttack, Stat::SpDefense, Stat::Speed];
+}
+
+/// Raises one stat by 10% and lowers another by 10%. The five natures
+/// raising and lowering the same stat are neutral.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Nature {
+    Hardy, Lonely, Brave, Adamant, Naughty,
+    Bold, Docile, Relaxed, Impish, Lax,
+    Timid, Hasty, Serious, Jolly, Naive,
+    Modest, Mild, Quiet, Bashful, Rash,
+    Calm, Gentle, Sassy, Careful, Quirky,
+}
+
+/// The stats natures change, in the order of the rows and columns of `Nature::ALL`.
+const NATURE_STATS: [Stat; 5] = [Stat::Attack, Stat::Defense, Stat::Speed, Stat::SpAttack, Stat::SpDefense];
+
+#[allow(dead_code)]
+impl Nature {
+    /// Every nature, five rows raising attack, defense, speed, special attack
+    /// and special defense, each lowering them in the same order.
+    pub const ALL: [Nature; 25] = [
+        Nature::Hardy, Nature::Lonely, Nature::Brave, Nature::Adamant, Nature::Naughty,
+        Nature::Bold, Nature::Docile, Nature::Relaxed, Nature::Impish, Nature::Lax,
+        Nature::Timid, Nature::Hasty, Nature::Serious, Nature::Jolly, Nature::Naive,
+        Nature::Modest, Nature::Mild, Nature::Quiet, Nature::Bashful, Nature::Rash,
+        Nature::Calm, Nature::Gentle, Nature::Sassy, Nature::Careful, Nature::Quirky,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The stat raised by 10%, `None` for neutral natures.
+    pub fn increased(self) -> Option<Stat> {
+        let (up, down) = (self.index() / 5, self.index() % 5);
+        (up != down).then_some(NATURE_STATS[up])
+    }
+
+    /// The stat lowered by 10%, `None` for neutral natures.
+    pub fn decreased(self) -> Option<Stat> {
+        let (up, down) = (self.index() / 5, self.index() % 5);
+        (up != down).then_some(NATURE_STATS[down])
+    }
+
+    /// The factor on `stat` in percent: 110, 90 or 100.
+    pub fn percent(self, stat: Stat) -> u32 {
+        if self.increased() == Some(stat) {
+            110
+        } else if self.decreased() == Some(stat) {
+            90
+        } else {
+            100
+        }
+    }
+}
+
+/// The value of `stat` at `level` from the species' base stat, the
+/// individual value and the effort values.
+///
+/// # Example
+///
+/// ```rust
+/// // A level 78 Garchomp with 12 IVs and 190 EVs in attack and an Adamant nature.
+/// assert_eq!(stat_value(Stat::Attack, 130, 12, 190, 78, Nature::Adamant), 278);
+/// ```
+pub fn stat_value(stat: Stat, base: u32, iv: u32, ev: u32, level: u32, nature: Nature) -> u32 {
+    let scaled = (2 * base + iv + ev / 4) * level / 100;
+    match stat {
+        Stat::Hp => scaled + level + 10,
+        _ => (scaled + 5) * nature.percent(stat) / 100,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natures_raise_and_lower_one_stat() {
+        // (nature, raised, lowered)
+        let table = [
+            (Nature::Hardy, None, None),
+            (Nature::Lonely, Some(Stat::Attack), Some(Stat::Defense)),
+            (Nature::Adamant, Some(Stat::Attack), Some(Stat::SpAttack)),
+            (Nature::Bold, Some(Stat::Defense), Some(Stat::Attack)),
+            (Nature::Timid, Some(Stat::Speed), Some(Stat::Attack)),
+            (Nature::Jolly, Some(Stat::Speed), Some(Stat::SpAttack)),
+            (Nature::Modest, Some(Stat::SpAttack), Some(Stat::Attack)),
+            (Nature::Quiet, Some(Stat::SpAttack), Some(Stat::Speed)),
+            (Nature::Calm, Some(Stat::SpDefense), Some(Stat::Attack)),
+            (Nature::Careful, Some(Stat::SpDefense), Some(Stat::SpAttack)),
+            (Nature::Quirky, None, None),
+        ];
+        for (nature, raised, lowered) in table {
+            assert_eq!((nature.increased(), nature.decreased()), (raised, lowered), "{:?}", nature);
+        }
+        assert_eq!(Nature::ALL.iter().filter(|nature| nature.increased().is_none()).count(), 5);
+        assert_eq!(Nature::Adamant.percent(Stat::Hp), 100);
+    }
+
+    #[test]
+    fn computes_stats() {
+        // A level 78 Garchomp: (stat, base, iv, ev, expected), Adamant.
+        let table = [
+            (Stat::Hp, 108, 24, 74, 289),
+            (Stat::Attack, 130, 12, 190, 278),
+            (Stat::Defense, 95, 30, 91, 193),
+            (Stat::SpAttack, 80, 16, 48, 135),
+            (Stat::SpDefense, 85, 23, 84, 171),
+            (Stat::Speed, 102, 5, 23, 171),
+        ];
+        for (stat, base, iv, ev, expected) in table {
+            assert_eq!(stat_value(stat, base, iv, ev, 78, Nature::Adamant), expected, "{:?}", stat);
+        }
+        assert_eq!(stat_value(Stat::Hp, 45, 0, 0, 1, Nature::Hardy), 11);
+        assert_eq!(stat_value(Stat::Speed, 45, MAX_IV, MAX_EV, 100, Nature::Timid), 207);
+    }
+}
@@ -0,0 +1,238 @@
+use glium::glutin::event::VirtualKeyCode;
+
+use crate::engine::core::metadata::Color;
+use crate::engine::core::renderer::core::backend::TextureHandle;
+use crate::engine::core::time::clock::{Timer, TimerMode};
+use crate::engine::core::world::collision::{tile_at, Direction, Step};
+
+use std::time::Duration;
+
+/// Unit vectors of the four movement directions, in the order up, left, down, right.
+pub const DIRECTIONS: [[f32; 2]; 4] = [[0.0, 1.0], [-1.0, 0.0], [0.0, -1.0], [1.0, 0.0]];
+
+/// Position of an entity in world units.
+///
+/// The position of the previous tick is kept as well, so the renderer can
+/// interpolate between the two, see `FixedTimestep::alpha`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transform {
+    pub position: [f32; 2],
+    pub previous_position: [f32; 2],
+}
+
+#[allow(dead_code)]
+impl Transform {
+    pub fn at(x: f32, y: f32) -> Self {
+        Transform {
+            position: [x, y],
+            previous_position: [x, y],
+        }
+    }
+
+    /// Moves the entity to `position` without interpolating from the old one.
+    pub fn teleport(&mut self, position: [f32; 2]) {
+        self.position = position;
+        self.previous_position = position;
+    }
+
+    /// Moves by `velocity` (units per second) for `dt` seconds.
+    pub fn translate(&mut self, velocity: [f32; 2], dt: f32) {
+        self.previous_position = self.position;
+        self.position[0] += velocity[0] * dt;
+        self.position[1] += velocity[1] * dt;
+    }
+
+    /// The position between the previous and the current tick.
+    pub fn interpolated(&self, alpha: f32) -> [f32; 2] {
+        [
+            self.previous_position[0] + (self.position[0] - self.previous_position[0]) * alpha,
+            self.previous_position[1] + (self.position[1] - self.previous_position[1]) * alpha,
+        ]
+    }
+}
+
+/// Movement in world units per second of game time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Velocity {
+    pub value: [f32; 2],
+}
+
+/// How an entity is drawn: a quad of `color`, or the `texture` if one is set.
+///
+/// The sprite is centered on the entity's `Transform`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    pub size: [f32; 2],
+    pub color: Color,
+    pub texture: Option<TextureHandle>,
+}
+
+#[allow(dead_code)]
+impl Sprite {
+    pub fn colored(size: f32, color: Color) -> Self {
+        Sprite {
+            size: [size, size],
+            color,
+            texture: None,
+        }
+    }
+
+    pub fn textured(size: f32, texture: TextureHandle) -> Self {
+        Sprite {
+            size: [size, size],
+            color: crate::engine::core::metadata::COLOR_WHITE,
+            texture: Some(texture),
+        }
+    }
+}
+
+/// Display name of an entity, used in log messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Name(pub String);
+
+/// Lets the keyboard steer the entity, see `InputState`.
+///
+/// Entities with a `GridMover` walk from tile to tile, all others move by their `Velocity`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputControlled {
+    /// Movement speed of entities moving by `Velocity`, in world units per second.
+    pub speed: f32,
+    /// Keys for up, left, down and right.
+    pub bindings: [VirtualKeyCode; 4],
+    /// Held to run, see `GridMover::running`.
+    pub run: Option<VirtualKeyCode>,
+    /// Pressed to fish, see `roll_encounters`.
+    pub fish: Option<VirtualKeyCode>,
+}
+
+#[allow(dead_code)]
+impl InputControlled {
+    pub fn new(speed: f32, bindings: [VirtualKeyCode; 4]) -> Self {
+        InputControlled { speed, bindings, run: None, fish: None }
+    }
+
+    pub fn with_run_key(mut self, run: VirtualKeyCode) -> Self {
+        self.run = Some(run);
+        self
+    }
+
+    pub fn with_fish_key(mut self, fish: VirtualKeyCode) -> Self {
+        self.fish = Some(fish);
+        self
+    }
+
+    pub fn wasd(speed: f32) -> Self {
+        InputControlled::new(speed, [VirtualKeyCode::W, VirtualKeyCode::A, VirtualKeyCode::S, VirtualKeyCode::D])
+    }
+}
+
+/// Moves the entity tile by tile instead of by `Velocity`.
+///
+/// The `intent` is set every tick by the keyboard or the `Ai`. Standing
+/// still, a direction the entity does not face only turns it; holding the
+/// direction for `TURN_DELAY` walks. A step always finishes, the `Transform`
+/// is interpolated from tile center to tile center, see `grid_movement_system`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridMover {
+    /// The tile the entity stands on, or leaves while a step is in progress.
+    pub tile: [i32; 2],
+    pub facing: Direction,
+    /// Walking speed in tiles per second.
+    pub speed: f32,
+    /// Doubles the speed.
+    pub running: bool,
+    /// The direction the entity wants to walk this tick.
+    pub intent: Option<Direction>,
+    /// A direction pressed during a step, walked when the step ends.
+    pub buffered: Option<Direction>,
+    /// The step in progress and how much of it is done, from 0 to 1.
+    pub step: Option<(Step, f32)>,
+    /// The step finished in the current tick, e.g. to roll encounters.
+    pub finished: Option<Step>,
+    /// Seconds until a turn in place turns into a walk.
+    pub turn_delay: f32,
+}
+
+#[allow(dead_code)]
+impl GridMover {
+    /// A mover standing on the tile containing the world `position`, facing down.
+    pub fn at(position: [f32; 2], speed: f32) -> Self {
+        GridMover {
+            tile: tile_at(position),
+            facing: Direction::Down,
+            speed,
+            running: false,
+            intent: None,
+            buffered: None,
+            step: None,
+            finished: None,
+            turn_delay: 0.0,
+        }
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// The speed of the current tick in tiles per second.
+    pub fn current_speed(&self) -> f32 {
+        if self.running { self.speed * 2.0 } else { self.speed }
+    }
+}
+
+/// Lets the entity move on its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ai {
+    pub behaviour: AiBehaviour,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AiBehaviour {
+    /// Stands still.
+    Idle,
+    /// Walks through `route` (indices into `DIRECTIONS`, `None` meaning a
+    /// pause), switching to the next leg whenever `leg_timer` finishes.
+    /// `speed` is in world units per second, a `GridMover` walks
+    /// `speed / TILE_SIZE` tiles per second.
+    Patrol {
+        speed: f32,
+        route: Vec<Option<usize>>,
+        leg: usize,
+        leg_timer: Timer,
+    },
+}
+
+#[allow(dead_code)]
+impl Ai {
+    pub fn idle() -> Self {
+        Ai { behaviour: AiBehaviour::Idle }
+    }
+
+    pub fn patrol(speed: f32, leg_duration: Duration, route: Vec<Option<usize>>) -> Self {
+        Ai {
+            behaviour: AiBehaviour::Patrol {
+                speed,
+                route,
+                leg: 0,
+                leg_timer: Timer::new(leg_duration, TimerMode::Repeating),
+            },
+        }
+    }
+
+    /// The index into `DIRECTIONS` to walk this tick, `None` to stand still,
+    /// and advances the patrol by `delta`.
+    pub fn next_direction(&mut self, delta: Duration) -> Option<usize> {
+        match &mut self.behaviour {
+            AiBehaviour::Idle => None,
+            AiBehaviour::Patrol { route, leg, leg_timer, .. } => {
+                if route.is_empty() {
+                    return None;
+                }
+                let direction = route[*leg % route.len()];
+                // The leg walked this tick is finished first, the next one starts with the following tick.
+                *leg = (*leg + leg_timer.tick(delta) as usize) % route.len();
+                direction
+            }
+        }
+    }
+}
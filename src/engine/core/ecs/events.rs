@@ -0,0 +1,151 @@
+use std::marker::PhantomData;
+
+use super::system::ResMut;
+
+/// A queue of events of type `T` stored as a resource, added with `AppBuilder::add_event`.
+///
+/// Events are kept for the tick they are sent in and the following one, so a
+/// system sees them no matter whether it runs before or after the sender.
+/// `iter` returns both ticks, so a reader running every tick would see each
+/// event twice: readers keep an `EventReader` to get every event once, and a
+/// single consumer can `drain` the queue instead.
+///
+/// ```rust
+/// world.resource_mut::<Events<EncounterTriggered>>().unwrap().send(encounter);
+///
+/// // e.g. in a resource of the battle plugin, kept between ticks
+/// let mut reader = EventReader::<EncounterTriggered>::default();
+/// for encounter in reader.read(&world.resource::<Events<EncounterTriggered>>().unwrap()) {
+///     start_battle(encounter);
+/// }
+/// ```
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    /// How many events were sent before the first of `previous`.
+    previous_start: usize,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Events { previous: Vec::new(), current: Vec::new(), previous_start: 0 }
+    }
+}
+
+#[allow(dead_code)]
+impl<T> Events<T> {
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// The events of the last and the current tick, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(&self.current)
+    }
+
+    /// Removes and returns every event, for a single consumer.
+    pub fn drain(&mut self) -> Vec<T> {
+        self.previous_start += self.len();
+        let mut events = std::mem::take(&mut self.previous);
+        events.append(&mut self.current);
+        events
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the events of the last tick, called once per tick.
+    pub fn update(&mut self) {
+        self.previous_start += self.previous.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /// How many events were ever sent, the position after the newest one.
+    fn sent(&self) -> usize {
+        self.previous_start + self.len()
+    }
+}
+
+/// Remembers which events of an `Events<T>` a reader has seen, so it gets
+/// every event once however often it reads.
+pub struct EventReader<T> {
+    /// Events before this position were read.
+    read: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        EventReader { read: 0, marker: PhantomData }
+    }
+}
+
+#[allow(dead_code)]
+impl<T> EventReader<T> {
+    /// The events sent since the last call, oldest first. Events that were
+    /// dropped before the reader got to them are skipped.
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let skip = self.read.saturating_sub(events.previous_start);
+        self.read = events.sent();
+        events.iter().skip(skip)
+    }
+}
+
+/// Ages the `Events<T>` by one tick, see `AppBuilder::add_event`.
+pub fn update_events<T: 'static>(mut events: ResMut<Events<T>>) {
+    events.update();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_live_for_two_ticks() {
+        let mut events = Events::default();
+        events.send(1);
+        events.update();
+        events.send(2);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [1, 2]);
+        events.update();
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), [2]);
+        assert_eq!(events.drain(), [2]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn readers_see_every_event_once() {
+        let mut events = Events::default();
+        let mut every_tick = EventReader::default();
+        let mut every_other_tick = EventReader::default();
+        let read = |reader: &mut EventReader<u32>, events: &Events<u32>| reader.read(events).copied().collect::<Vec<_>>();
+
+        events.send(1);
+        assert_eq!(read(&mut every_tick, &events), [1]);
+        assert!(read(&mut every_tick, &events).is_empty());
+        events.update();
+        events.send(2);
+        events.send(3);
+        assert_eq!(read(&mut every_tick, &events), [2, 3]);
+        assert_eq!(read(&mut every_other_tick, &events), [1, 2, 3]);
+        events.update();
+        events.update();
+        events.send(4);
+        assert_eq!(read(&mut every_tick, &events), [4]);
+
+        // Events dropped before reading are missed, the rest are still read once.
+        let mut late = EventReader::default();
+        events.update();
+        events.send(5);
+        assert_eq!(read(&mut late, &events), [4, 5]);
+        assert_eq!(events.drain(), [4, 5]);
+        events.send(6);
+        assert_eq!(read(&mut every_other_tick, &events), [6]);
+        assert_eq!(read(&mut late, &events), [6]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+
+use glium::glutin::event::{ElementState, VirtualKeyCode, WindowEvent};
+
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::Plugin;
+use crate::engine::core::app::schedule::{in_state, IntoSystemConfig, Stage};
+use crate::engine::core::app::AppState;
+
+use super::system::ResMut;
+use super::systems;
+
+/// Keyboard state as a `World` resource.
+///
+/// The window feeds every event into `handle_event`; systems read which keys
+/// are held, or were pressed since the last simulation tick.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    held: HashSet<VirtualKeyCode>,
+    just_pressed: HashSet<VirtualKeyCode>,
+}
+
+#[allow(dead_code)]
+impl InputState {
+    pub fn new() -> Self {
+        InputState::default()
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if let Some(keycode) = input.virtual_keycode {
+                match input.state {
+                    ElementState::Pressed => self.press(keycode),
+                    ElementState::Released => self.release(keycode),
+                }
+            }
+        }
+    }
+
+    pub fn press(&mut self, keycode: VirtualKeyCode) {
+        // Key repeat sends further presses while held, those are no new press.
+        if self.held.insert(keycode) {
+            self.just_pressed.insert(keycode);
+        }
+    }
+
+    pub fn release(&mut self, keycode: VirtualKeyCode) {
+        self.held.remove(&keycode);
+    }
+
+    pub fn is_held(&self, keycode: VirtualKeyCode) -> bool {
+        self.held.contains(&keycode)
+    }
+
+    /// `true` if `keycode` went down since the last simulation tick.
+    pub fn just_pressed(&self, keycode: VirtualKeyCode) -> bool {
+        self.just_pressed.contains(&keycode)
+    }
+
+    /// Forgets the presses of the last tick, called after every simulation tick.
+    pub fn end_tick(&mut self) {
+        self.just_pressed.clear();
+    }
+
+    /// Releases all keys, e.g. when the window loses focus.
+    pub fn clear(&mut self) {
+        self.held.clear();
+        self.just_pressed.clear();
+    }
+}
+
+/// Parses a key name as written in `engine.toml`, e.g. `"W"`, `"Up"`, `"Escape"`
+/// or `"LShift"`. Names are case-insensitive.
+pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+
+    let key = match name.to_ascii_uppercase().as_str() {
+        "A" => A,
+        "B" => B,
+        "C" => C,
+        "D" => D,
+        "E" => E,
+        "F" => F,
+        "G" => G,
+        "H" => H,
+        "I" => I,
+        "J" => J,
+        "K" => K,
+        "L" => L,
+        "M" => M,
+        "N" => N,
+        "O" => O,
+        "P" => P,
+        "Q" => Q,
+        "R" => R,
+        "S" => S,
+        "T" => T,
+        "U" => U,
+        "V" => V,
+        "W" => W,
+        "X" => X,
+        "Y" => Y,
+        "Z" => Z,
+        "0" | "KEY0" => Key0,
+        "1" | "KEY1" => Key1,
+        "2" | "KEY2" => Key2,
+        "3" | "KEY3" => Key3,
+        "4" | "KEY4" => Key4,
+        "5" | "KEY5" => Key5,
+        "6" | "KEY6" => Key6,
+        "7" | "KEY7" => Key7,
+        "8" | "KEY8" => Key8,
+        "9" | "KEY9" => Key9,
+        "F1" => F1,
+        "F2" => F2,
+        "F3" => F3,
+        "F4" => F4,
+        "F5" => F5,
+        "F6" => F6,
+        "F7" => F7,
+        "F8" => F8,
+        "F9" => F9,
+        "F10" => F10,
+        "F11" => F11,
+        "F12" => F12,
+        "UP" => Up,
+        "DOWN" => Down,
+        "LEFT" => Left,
+        "RIGHT" => Right,
+        "ESCAPE" | "ESC" => Escape,
+        "SPACE" => Space,
+        "RETURN" | "ENTER" => Return,
+        "TAB" => Tab,
+        "BACK" | "BACKSPACE" => Back,
+        "LSHIFT" => LShift,
+        "RSHIFT" => RShift,
+        "LCONTROL" | "LCTRL" => LControl,
+        "RCONTROL" | "RCTRL" => RControl,
+        "LALT" => LAlt,
+        "RALT" => RAlt,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Keyboard input: the `InputState` resource, `input_system` steering the
+/// `InputControlled` entities in `PreUpdate` and `end_input_tick` in `PostUpdate`.
+pub(crate) struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<InputState>();
+        app.add_systems(Stage::PreUpdate, systems::input_system.run_if(in_state(AppState::Running)));
+        app.add_systems(Stage::PostUpdate, end_input_tick);
+    }
+}
+
+/// Forgets the key presses handled during this tick.
+fn end_input_tick(mut input: ResMut<InputState>) {
+    input.end_tick();
+}
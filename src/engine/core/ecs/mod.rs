@@ -0,0 +1,6 @@
+pub mod components;
+pub mod events;
+pub mod input;
+pub mod system;
+pub mod systems;
+pub mod world;
@@ -0,0 +1,240 @@
+use std::cell::{Ref, RefMut};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use super::world::World;
+
+/// A unit of game logic run against the `World` by the `Schedule`.
+pub trait System {
+    /// Name used for ordering constraints and error messages, by default the function name.
+    fn name(&self) -> &str;
+
+    fn run(&mut self, world: &mut World);
+}
+
+pub type BoxedSystem = Box<dyn System>;
+
+/// Converts functions into systems.
+///
+/// Two kinds of functions are systems:
+///
+/// * Exclusive systems, `fn(&mut World)`, which may spawn and despawn entities.
+/// * Functions taking up to four `SystemParam`s, e.g.
+///   `fn(clock: Res<GameClock>, world: &World)`. Their parameters are fetched
+///   from the world before every run.
+///
+/// `Marker` only exists to keep the two implementations apart and is inferred.
+pub trait IntoSystem<Marker> {
+    fn into_system(self) -> BoxedSystem;
+}
+
+/// Something a system function can ask for as parameter.
+pub trait SystemParam {
+    type Item<'w>;
+
+    fn fetch(world: &World) -> Self::Item<'_>;
+}
+
+/// Shared access to the resource `T`. The system panics if the resource is missing,
+/// ask for `Option<Res<T>>` if it is optional.
+pub struct Res<'w, T: 'static> {
+    value: Ref<'w, T>,
+}
+
+impl<T: 'static> Deref for Res<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Exclusive access to the resource `T`. The system panics if the resource is missing,
+/// ask for `Option<ResMut<T>>` if it is optional.
+pub struct ResMut<'w, T: 'static> {
+    value: RefMut<'w, T>,
+}
+
+impl<T: 'static> Deref for ResMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: 'static> DerefMut for ResMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+fn missing_resource<T>() -> ! {
+    panic!("System requires the resource {}, which was never inserted", std::any::type_name::<T>())
+}
+
+impl<T: 'static> SystemParam for Res<'_, T> {
+    type Item<'w> = Res<'w, T>;
+
+    fn fetch(world: &World) -> Self::Item<'_> {
+        Res { value: world.resource::<T>().unwrap_or_else(|| missing_resource::<T>()) }
+    }
+}
+
+impl<T: 'static> SystemParam for ResMut<'_, T> {
+    type Item<'w> = ResMut<'w, T>;
+
+    fn fetch(world: &World) -> Self::Item<'_> {
+        ResMut { value: world.resource_mut::<T>().unwrap_or_else(|| missing_resource::<T>()) }
+    }
+}
+
+impl<T: 'static> SystemParam for Option<Res<'_, T>> {
+    type Item<'w> = Option<Res<'w, T>>;
+
+    fn fetch(world: &World) -> Self::Item<'_> {
+        world.resource::<T>().map(|value| Res { value })
+    }
+}
+
+impl<T: 'static> SystemParam for Option<ResMut<'_, T>> {
+    type Item<'w> = Option<ResMut<'w, T>>;
+
+    fn fetch(world: &World) -> Self::Item<'_> {
+        world.resource_mut::<T>().map(|value| ResMut { value })
+    }
+}
+
+/// Shared access to the whole world, enough to run queries, see `World::query`.
+impl SystemParam for &World {
+    type Item<'w> = &'w World;
+
+    fn fetch(world: &World) -> Self::Item<'_> {
+        world
+    }
+}
+
+/// Returns the function name of `F`, without the module path.
+fn short_type_name<F>() -> &'static str {
+    let name = std::any::type_name::<F>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+struct ExclusiveSystem<F> {
+    func: F,
+    name: &'static str,
+}
+
+impl<F: FnMut(&mut World)> System for ExclusiveSystem<F> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn run(&mut self, world: &mut World) {
+        (self.func)(world);
+    }
+}
+
+/// Marker for `IntoSystem` implemented by `fn(&mut World)`.
+pub struct ExclusiveMarker;
+
+impl<F> IntoSystem<ExclusiveMarker> for F
+where
+    F: FnMut(&mut World) + 'static,
+{
+    fn into_system(self) -> BoxedSystem {
+        Box::new(ExclusiveSystem { func: self, name: short_type_name::<F>() })
+    }
+}
+
+struct FunctionSystem<F, Params> {
+    func: F,
+    name: &'static str,
+    _params: PhantomData<fn() -> Params>,
+}
+
+macro_rules! impl_function_system {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<F, $($param: SystemParam + 'static),*> System for FunctionSystem<F, ($($param,)*)>
+        where
+            for<'a> &'a mut F: FnMut($($param),*) + FnMut($($param::Item<'_>),*),
+        {
+            fn name(&self) -> &str {
+                self.name
+            }
+
+            fn run(&mut self, world: &mut World) {
+                // Calling through a generic helper makes the compiler pick the
+                // `FnMut(Item)` signature instead of `FnMut(Param)`.
+                fn call<$($param),*>(mut func: impl FnMut($($param),*), $($param: $param),*) {
+                    func($($param),*);
+                }
+                let world: &World = world;
+                $(let $param = $param::fetch(world);)*
+                call(&mut self.func, $($param),*);
+            }
+        }
+
+        impl<F, $($param: SystemParam + 'static),*> IntoSystem<fn($($param,)*)> for F
+        where
+            F: 'static,
+            for<'a> &'a mut F: FnMut($($param),*) + FnMut($($param::Item<'_>),*),
+        {
+            fn into_system(self) -> BoxedSystem {
+                Box::new(FunctionSystem::<F, ($($param,)*)> {
+                    func: self,
+                    name: short_type_name::<F>(),
+                    _params: PhantomData,
+                })
+            }
+        }
+    };
+}
+
+impl_function_system!(A);
+impl_function_system!(A, B);
+impl_function_system!(A, B, C);
+impl_function_system!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter(u32);
+    struct Step(u32);
+
+    fn count_up(mut counter: ResMut<Counter>, step: Res<Step>) {
+        counter.0 += step.0;
+    }
+
+    fn count_entities(mut counter: ResMut<Counter>, world: &World) {
+        counter.0 += world.len() as u32;
+    }
+
+    fn spawn_one(world: &mut World) {
+        world.spawn();
+    }
+
+    #[test]
+    fn function_systems_fetch_their_parameters() {
+        let mut world = World::new();
+        world.insert_resource(Counter(0));
+        world.insert_resource(Step(3));
+
+        let mut systems = [count_up.into_system(), spawn_one.into_system(), count_entities.into_system()];
+        for system in systems.iter_mut() {
+            system.run(&mut world);
+        }
+        assert_eq!(world.resource::<Counter>().unwrap().0, 4);
+        assert_eq!(systems[0].name(), "count_up");
+    }
+
+    #[test]
+    #[should_panic(expected = "never inserted")]
+    fn missing_resources_name_the_type() {
+        let mut world = World::new();
+        world.insert_resource(Step(1));
+        count_up.into_system().run(&mut world);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::engine::console_logger::logger;
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::renderer::core::backend::{self, Quad, RenderBackend};
+use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+use crate::engine::core::time::clock::GameClock;
+
+use crate::engine::core::world::collision::Direction;
+
+use super::components::{Ai, AiBehaviour, GridMover, InputControlled, Name, Sprite, Transform, Velocity, DIRECTIONS};
+use super::input::InputState;
+use super::world::World;
+
+/// Key names used in movement log messages, in the order of `DIRECTIONS`.
+const DIRECTION_NAMES: [&str; 4] = ["Up", "Left", "Down", "Right"];
+
+/// Steers every `InputControlled` entity from the held keys: sets the
+/// velocity of free moving entities and the intent of `GridMover`s.
+///
+/// A key pressed this tick wins over keys held before, and is buffered if the
+/// mover is in the middle of a step.
+pub fn input_system(world: &mut World) {
+    let Some(input) = world.resource::<InputState>() else {
+        return;
+    };
+    world.query::<(&InputControlled, &Transform, Option<&Name>)>(|_, (controlled, transform, name)| {
+        for (index, keycode) in controlled.bindings.iter().enumerate() {
+            if input.just_pressed(*keycode) {
+                let name = name.map_or("Entity", |name| name.0.as_str());
+                logger::debug_player_movement(name, transform.position, DIRECTION_NAMES[index]);
+            }
+        }
+    });
+    world.query::<(&InputControlled, &mut Velocity)>(|_, (controlled, velocity)| {
+        let mut direction = [0.0, 0.0];
+        for (index, keycode) in controlled.bindings.iter().enumerate() {
+            if input.is_held(*keycode) {
+                direction[0] += DIRECTIONS[index][0];
+                direction[1] += DIRECTIONS[index][1];
+            }
+        }
+        velocity.value = [direction[0] * controlled.speed, direction[1] * controlled.speed];
+    });
+    world.query::<(&InputControlled, &mut GridMover)>(|_, (controlled, mover)| {
+        let key_direction = |index: usize| Direction::ALL[index];
+        let pressed = controlled.bindings.iter().position(|keycode| input.just_pressed(*keycode)).map(key_direction);
+        let held = controlled.bindings.iter().position(|keycode| input.is_held(*keycode)).map(key_direction);
+        mover.intent = pressed.or(held);
+        if pressed.is_some() && mover.is_moving() {
+            mover.buffered = pressed;
+        }
+        mover.running = controlled.run.is_some_and(|keycode| input.is_held(keycode));
+    });
+}
+
+/// Updates the velocity, and the intent and speed of a `GridMover`, of every
+/// entity with an `Ai` component. The AI advances once per tick, so an entity
+/// with both components moves the same way in both.
+pub fn ai_system(world: &mut World) {
+    let Some(delta) = world.resource::<GameClock>().map(|clock| clock.delta()) else {
+        return;
+    };
+    world.query::<(&mut Ai, Option<&mut Velocity>, Option<&mut GridMover>)>(|_, (ai, velocity, mover)| {
+        let direction = ai.next_direction(delta);
+        let speed = match &ai.behaviour {
+            AiBehaviour::Patrol { speed, .. } => *speed,
+            AiBehaviour::Idle => 0.0,
+        };
+        if let Some(velocity) = velocity {
+            velocity.value = match direction {
+                Some(direction) => [DIRECTIONS[direction][0] * speed, DIRECTIONS[direction][1] * speed],
+                None => [0.0, 0.0],
+            };
+        }
+        if let Some(mover) = mover {
+            mover.intent = direction.map(|direction| Direction::ALL[direction]);
+            if speed > 0.0 {
+                mover.speed = speed / TILE_SIZE;
+            }
+        }
+    });
+}
+
+/// Moves every entity by its velocity for the clock's delta.
+pub fn movement_system(world: &mut World) {
+    let Some(dt) = world.resource::<GameClock>().map(|clock| clock.delta_seconds()) else {
+        return;
+    };
+    world.query::<(&mut Transform, &Velocity)>(|_, (transform, velocity)| {
+        transform.translate(velocity.value, dt);
+    });
+}
+
+/// Centers the camera on the first `InputControlled` entity.
+pub fn camera_follow_system(world: &World, camera: &mut Camera2D, alpha: f32) {
+    let mut target = None;
+    world.query::<(&Transform, &InputControlled)>(|_, (transform, _)| {
+        target.get_or_insert(transform.interpolated(alpha));
+    });
+    if let Some(target) = target {
+        camera.follow(target);
+    }
+}
+
+/// Draws every entity with a `Sprite`, centered on its interpolated position,
+/// together with the `scenery` tiles of the map's decoration layers.
+///
+/// Everything is y-sorted by its bottom edge, so whatever stands further down
+/// the screen covers what stands behind it. An entity standing level with a
+/// scenery tile is drawn in front of it.
+pub fn draw_sprites_system(world: &World, backend: &mut dyn RenderBackend, alpha: f32, scenery: &[backend::Sprite]) {
+    let mut drawables: Vec<(f32, Drawable)> = scenery.iter()
+        .map(|sprite| (sprite.position[1], Drawable::Sprite(*sprite)))
+        .collect();
+
+    world.query::<(&Transform, &Sprite)>(|_, (transform, sprite)| {
+        let [x, y] = transform.interpolated(alpha);
+        let position = [x - sprite.size[0] / 2.0, y - sprite.size[1] / 2.0];
+        let drawable = match sprite.texture {
+            Some(texture) => Drawable::Sprite(backend::Sprite { position, size: sprite.size, texture }),
+            None => Drawable::Quad(Quad { position, size: sprite.size, color: sprite.color }),
+        };
+        drawables.push((position[1], drawable));
+    });
+
+    // Stable, so equal edges keep scenery below entities and entities in spawn order.
+    drawables.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    for (_, drawable) in drawables {
+        match drawable {
+            Drawable::Sprite(sprite) => backend.submit_sprite(&sprite),
+            Drawable::Quad(quad) => backend.submit_quad(&quad),
+        }
+    }
+}
+
+/// Something drawn by `draw_sprites_system`.
+enum Drawable {
+    Sprite(backend::Sprite),
+    Quad(Quad),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glium::glutin::event::VirtualKeyCode;
+    use std::time::Duration;
+
+    const SPEED: f32 = 0.6;
+
+    fn world_with_clock() -> World {
+        let mut world = World::new();
+        world.insert_resource(GameClock::new());
+        world.insert_resource(InputState::new());
+        world
+    }
+
+    fn tick(world: &mut World, seconds: f64) {
+        world.resource_mut::<GameClock>().unwrap().advance(Duration::from_secs_f64(seconds));
+        input_system(world);
+        ai_system(world);
+        movement_system(world);
+        world.resource_mut::<InputState>().unwrap().end_tick();
+    }
+
+    #[test]
+    fn held_keys_move_free_entities_by_speed_times_dt() {
+        let mut world = world_with_clock();
+        let player = world.spawn()
+            .with(Transform::at(0.0, 0.0))
+            .with(Velocity::default())
+            .with(InputControlled::wasd(SPEED))
+            .id();
+
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::D);
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::W);
+        tick(&mut world, 0.5);
+
+        let transform = *world.get::<Transform>(player).unwrap();
+        let expected = SPEED * 0.5;
+        assert!((transform.position[0] - expected).abs() < 1e-6);
+        assert!((transform.position[1] - expected).abs() < 1e-6);
+        assert_eq!(transform.previous_position, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn paused_clock_stops_movement() {
+        let mut world = world_with_clock();
+        let npc = world.spawn()
+            .with(Transform::at(1.0, 1.0))
+            .with(Velocity::default())
+            .with(Ai::patrol(SPEED, Duration::from_secs(1), vec![Some(3)]))
+            .id();
+        world.resource_mut::<GameClock>().unwrap().pause();
+        for _ in 0..10 {
+            tick(&mut world, 0.1);
+        }
+        assert_eq!(world.get::<Transform>(npc).unwrap().position, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn patrol_walks_its_route() {
+        let mut world = world_with_clock();
+        let entity = world.spawn()
+            .with(Transform::at(0.0, 0.0))
+            .with(Velocity::default())
+            .with(Ai::patrol(1.0, Duration::from_secs(1), vec![Some(3), None, Some(1)]))
+            .id();
+
+        for expected_x in [0.5, 1.0, 1.0, 1.0, 0.5, 0.0] {
+            tick(&mut world, 0.5);
+            let x = world.get::<Transform>(entity).unwrap().position[0];
+            assert!((x - expected_x).abs() < 1e-6, "expected x {} got {}", expected_x, x);
+        }
+    }
+
+    #[test]
+    fn ai_advances_once_for_velocity_and_grid_mover() {
+        let mut world = world_with_clock();
+        let entity = world.spawn()
+            .with(Velocity::default())
+            .with(GridMover::at([0.0, 0.0], 1.0))
+            .with(Ai::patrol(2.0 * TILE_SIZE, Duration::from_secs(1), vec![Some(3), None]))
+            .id();
+
+        world.resource_mut::<GameClock>().unwrap().advance(Duration::from_secs_f64(0.6));
+        ai_system(&mut world);
+        world.resource_mut::<GameClock>().unwrap().advance(Duration::from_secs_f64(0.6));
+        ai_system(&mut world);
+
+        // Both ticks walk the first leg, advancing twice per tick would have
+        // stopped the velocity in the second one.
+        let mover = world.get::<GridMover>(entity).unwrap();
+        assert_eq!(mover.intent, Some(Direction::ALL[3]));
+        assert!((mover.speed - 2.0).abs() < 1e-6);
+        assert_eq!(world.get::<Velocity>(entity).unwrap().value, [DIRECTIONS[3][0] * 2.0 * TILE_SIZE, DIRECTIONS[3][1] * 2.0 * TILE_SIZE]);
+    }
+}
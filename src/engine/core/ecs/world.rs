@@ -0,0 +1,478 @@
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// Any `'static` type can be attached to an entity as a component.
+pub trait Component: 'static {}
+
+impl<T: 'static> Component for T {}
+
+/// A handle to an entity of a `World`.
+///
+/// The generation makes sure a handle kept around after `despawn` does not
+/// suddenly point at a new entity that reused the same slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+#[allow(dead_code)]
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Type erased access to a `ComponentStorage`, so entities can be despawned
+/// without knowing the types of their components.
+trait AnyStorage {
+    fn remove_index(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Components of one type, indexed by entity slot.
+pub struct ComponentStorage<T> {
+    slots: Vec<Option<T>>,
+}
+
+impl<T> ComponentStorage<T> {
+    fn new() -> Self {
+        ComponentStorage { slots: Vec::new() }
+    }
+
+    fn insert(&mut self, index: usize, component: T) -> Option<T> {
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+        self.slots[index].replace(component)
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.slots.get(index).and_then(Option::as_ref)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.slots.get_mut(index).and_then(Option::as_mut)
+    }
+
+    fn take(&mut self, index: usize) -> Option<T> {
+        self.slots.get_mut(index).and_then(Option::take)
+    }
+}
+
+impl<T: Component> AnyStorage for ComponentStorage<T> {
+    fn remove_index(&mut self, index: usize) {
+        self.take(index);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Everything that exists in the game: entities with their components, plus
+/// global resources such as the `GameClock`.
+///
+/// Components are stored per type, every storage behind its own `RefCell`, so
+/// a query can read one component type while writing another. Borrowing the
+/// same type mutably twice at the same time panics with the type name.
+///
+/// # Example
+///
+/// ```rust
+/// let mut world = World::new();
+/// world.insert_resource(GameClock::new());
+/// let player = world.spawn()
+///     .with(Transform::at(0.0, 0.0))
+///     .with(Velocity::default())
+///     .id();
+///
+/// world.query::<(&mut Transform, &Velocity)>(|_entity, (transform, velocity)| {
+///     transform.translate(velocity.value, 1.0 / 60.0);
+/// });
+/// ```
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free_slots: Vec<u32>,
+    storages: HashMap<TypeId, RefCell<Box<dyn AnyStorage>>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+}
+
+#[allow(dead_code)]
+impl World {
+    pub fn new() -> Self {
+        World::default()
+    }
+
+    /// Creates an empty entity and returns a builder to attach components to it.
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        let entity = match self.free_slots.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity { index, generation: self.generations[index as usize] }
+            }
+            None => {
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity { index: self.alive.len() as u32 - 1, generation: 0 }
+            }
+        };
+        EntityBuilder { world: self, entity }
+    }
+
+    /// Removes `entity` and all of its components. Returns `false` if it was already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let index = entity.index as usize;
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_index(index);
+        }
+        self.alive[index] = false;
+        self.generations[index] += 1;
+        self.free_slots.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        self.alive.get(index).copied().unwrap_or(false) && self.generations[index] == entity.generation
+    }
+
+    /// Number of living entities.
+    pub fn len(&self) -> usize {
+        self.alive.iter().filter(|alive| **alive).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All living entities, in slot order.
+    pub fn entities(&self) -> Vec<Entity> {
+        (0..self.alive.len())
+            .filter_map(|index| self.entity_at(index))
+            .collect()
+    }
+
+    /// Attaches `component` to `entity`, replacing and returning a previous component of the same type.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "Cannot insert {} into despawned {:?}", std::any::type_name::<T>(), entity);
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(ComponentStorage::<T>::new())))
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .expect("Component storage type mismatch")
+            .insert(entity.index as usize, component)
+    }
+
+    /// Detaches and returns the component of type `T` from `entity`.
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()?
+            .take(entity.index as usize)
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        let storage = self.storage::<T>()?;
+        Ref::filter_map(storage, |storage| storage.get(entity.index as usize)).ok()
+    }
+
+    pub fn get_mut<T: Component>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        let storage = self.storage_mut::<T>()?;
+        RefMut::filter_map(storage, |storage| storage.get_mut(entity.index as usize)).ok()
+    }
+
+    /// Calls `f` for every entity that has all components requested by `Q`.
+    ///
+    /// `Q` is a component reference like `&Transform`, `&mut Velocity`, or a
+    /// tuple of up to four of them.
+    pub fn query<Q: Query>(&self, mut f: impl FnMut(Entity, Q::Item<'_>)) {
+        let Some(mut fetch) = Q::fetch(self) else {
+            return;
+        };
+        for index in 0..self.alive.len() {
+            let Some(entity) = self.entity_at(index) else {
+                continue;
+            };
+            if let Some(item) = Q::item(&mut fetch, index) {
+                f(entity, item);
+            }
+        }
+    }
+
+    /// Entities that have all components requested by `Q`, in slot order.
+    pub fn query_entities<Q: Query>(&self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        self.query::<Q>(|entity, _| entities.push(entity));
+        entities
+    }
+
+    /// Stores a global value, replacing a previous one of the same type.
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.resources.insert(TypeId::of::<R>(), RefCell::new(Box::new(resource)));
+    }
+
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        let resource = self.resources.remove(&TypeId::of::<R>())?;
+        resource.into_inner().downcast::<R>().ok().map(|resource| *resource)
+    }
+
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn resource<R: 'static>(&self) -> Option<Ref<'_, R>> {
+        let cell = self.resources.get(&TypeId::of::<R>())?;
+        let resource = cell.try_borrow().unwrap_or_else(|_| panic!("Resource {} is already borrowed mutably", std::any::type_name::<R>()));
+        Ref::filter_map(resource, |resource| resource.downcast_ref::<R>()).ok()
+    }
+
+    pub fn resource_mut<R: 'static>(&self) -> Option<RefMut<'_, R>> {
+        let cell = self.resources.get(&TypeId::of::<R>())?;
+        let resource = cell.try_borrow_mut().unwrap_or_else(|_| panic!("Resource {} is already borrowed", std::any::type_name::<R>()));
+        RefMut::filter_map(resource, |resource| resource.downcast_mut::<R>()).ok()
+    }
+
+    fn entity_at(&self, index: usize) -> Option<Entity> {
+        if self.alive[index] {
+            Some(Entity { index: index as u32, generation: self.generations[index] })
+        } else {
+            None
+        }
+    }
+
+    fn storage<T: Component>(&self) -> Option<Ref<'_, ComponentStorage<T>>> {
+        let cell = self.storages.get(&TypeId::of::<T>())?;
+        let storage = cell.try_borrow().unwrap_or_else(|_| panic!("Component {} is already borrowed mutably", std::any::type_name::<T>()));
+        Some(Ref::map(storage, |storage| {
+            storage.as_any().downcast_ref::<ComponentStorage<T>>().expect("Component storage type mismatch")
+        }))
+    }
+
+    fn storage_mut<T: Component>(&self) -> Option<RefMut<'_, ComponentStorage<T>>> {
+        let cell = self.storages.get(&TypeId::of::<T>())?;
+        let storage = cell.try_borrow_mut().unwrap_or_else(|_| panic!("Component {} is already borrowed", std::any::type_name::<T>()));
+        Some(RefMut::map(storage, |storage| {
+            storage.as_any_mut().downcast_mut::<ComponentStorage<T>>().expect("Component storage type mismatch")
+        }))
+    }
+}
+
+/// Returned by `World::spawn` to attach components to the new entity.
+pub struct EntityBuilder<'w> {
+    world: &'w mut World,
+    entity: Entity,
+}
+
+#[allow(dead_code)]
+impl EntityBuilder<'_> {
+    pub fn with<T: Component>(self, component: T) -> Self {
+        self.world.insert(self.entity, component);
+        self
+    }
+
+    pub fn id(self) -> Entity {
+        self.entity
+    }
+}
+
+/// The component access a `World::query` asks for.
+///
+/// Implemented for `&T` (read), `&mut T` (write), `Option<&T>`,
+/// `Option<&mut T>`, `With<T>` and tuples of those.
+pub trait Query {
+    /// The borrowed storages, held for the duration of the query.
+    type Fetch<'w>;
+    /// What the query callback receives per entity.
+    type Item<'f>;
+
+    /// Borrows the storages, or returns `None` if a component type was never inserted.
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>>;
+
+    /// The components of the entity in slot `index`, if it has all of them.
+    fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>>;
+}
+
+impl<T: Component> Query for &T {
+    type Fetch<'w> = Ref<'w, ComponentStorage<T>>;
+    type Item<'f> = &'f T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
+    }
+
+    fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>> {
+        fetch.get(index)
+    }
+}
+
+impl<T: Component> Query for &mut T {
+    type Fetch<'w> = RefMut<'w, ComponentStorage<T>>;
+    type Item<'f> = &'f mut T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage_mut::<T>()
+    }
+
+    fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>> {
+        fetch.get_mut(index)
+    }
+}
+
+/// Matches every entity, handing out the component `T` if it has one.
+impl<T: Component> Query for Option<&T> {
+    type Fetch<'w> = Option<Ref<'w, ComponentStorage<T>>>;
+    type Item<'f> = Option<&'f T>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage::<T>())
+    }
+
+    fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>> {
+        Some(fetch.as_ref().and_then(|storage| storage.get(index)))
+    }
+}
+
+/// Matches every entity, handing out the component `T` mutably if it has one.
+impl<T: Component> Query for Option<&mut T> {
+    type Fetch<'w> = Option<RefMut<'w, ComponentStorage<T>>>;
+    type Item<'f> = Option<&'f mut T>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage_mut::<T>())
+    }
+
+    fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>> {
+        Some(fetch.as_mut().and_then(|storage| storage.get_mut(index)))
+    }
+}
+
+/// Matches entities with the component `T` without borrowing it, e.g.
+/// `world.query::<(&mut Transform, With<InputControlled>)>(..)`.
+#[allow(dead_code)]
+pub struct With<T>(PhantomData<T>);
+
+impl<T: Component> Query for With<T> {
+    type Fetch<'w> = Ref<'w, ComponentStorage<T>>;
+    type Item<'f> = ();
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
+    }
+
+    fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>> {
+        fetch.get(index).map(|_| ())
+    }
+}
+
+macro_rules! impl_query_for_tuple {
+    ($($name:ident : $index:tt),+) => {
+        impl<$($name: Query),+> Query for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+            type Item<'f> = ($($name::Item<'f>,)+);
+
+            fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+                Some(($($name::fetch(world)?,)+))
+            }
+
+            fn item<'f>(fetch: &'f mut Self::Fetch<'_>, index: usize) -> Option<Self::Item<'f>> {
+                Some(($($name::item(&mut fetch.$index, index)?,)+))
+            }
+        }
+    };
+}
+
+impl_query_for_tuple!(A: 0);
+impl_query_for_tuple!(A: 0, B: 1);
+impl_query_for_tuple!(A: 0, B: 1, C: 2);
+impl_query_for_tuple!(A: 0, B: 1, C: 2, D: 3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(i32);
+    #[derive(Debug, PartialEq)]
+    struct Speed(i32);
+    struct Marker;
+
+    #[test]
+    fn query_visits_only_entities_with_all_components() {
+        let mut world = World::new();
+        let moving = world.spawn().with(Position(0)).with(Speed(2)).id();
+        let still = world.spawn().with(Position(5)).id();
+
+        world.query::<(&mut Position, &Speed)>(|_, (position, speed)| position.0 += speed.0);
+
+        assert_eq!(*world.get::<Position>(moving).unwrap(), Position(2));
+        assert_eq!(*world.get::<Position>(still).unwrap(), Position(5));
+        assert_eq!(world.query_entities::<&Position>(), vec![moving, still]);
+        assert!(world.query_entities::<(&Position, With<Marker>)>().is_empty());
+    }
+
+    #[test]
+    fn despawned_handles_stay_invalid_after_slot_reuse() {
+        let mut world = World::new();
+        let first = world.spawn().with(Position(1)).id();
+        assert!(world.despawn(first));
+        assert!(!world.despawn(first));
+
+        let second = world.spawn().id();
+        assert_eq!(second.index(), first.index());
+        assert!(!world.is_alive(first));
+        assert!(world.get::<Position>(second).is_none(), "components of despawned entities must not leak");
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn resources_are_stored_per_type() {
+        let mut world = World::new();
+        world.insert_resource(3u32);
+        *world.resource_mut::<u32>().unwrap() += 1;
+        assert_eq!(*world.resource::<u32>().unwrap(), 4);
+        assert!(world.resource::<i64>().is_none());
+        assert_eq!(world.remove_resource::<u32>(), Some(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn aliasing_mutable_queries_panic() {
+        let mut world = World::new();
+        world.spawn().with(Position(0));
+        world.query::<(&mut Position, &Position)>(|_, _| {});
+    }
+}
@@ -1,219 +1,48 @@
-use glium::{Surface, implement_vertex, uniform, Display};
-
-use crate::engine::{console_logger::logger, core::{renderer::core::opengl::{self, OPENGL_DEBUG}, metadata}};
-
-static DEBUG_ONCE: bool = true;
-static mut IS_DEBUGED: bool = false;
-pub const PLAYER_DEBUG: bool = false;
-pub const PLAYER_MOVEMENT_DEBUG: bool = false;
-
-const VERTEX_SHADER_SRC: &str = r#"
-    #version 140
-
-    in vec2 position;
-    uniform mat4 view;  // Uniform view matrix for aspect ratio control
-
-    void main() {
-        gl_Position = view * vec4(position, 0.0, 1.0);
-    }
-"#;
-
-const FRAGMENT_SHADER_SRC: &str = r#"
-    #version 140
-
-    out vec4 color;
-
-    void main() {
-        color = vec4(1.0, 0.0, 0.0, 1.0);
-    }
-"#;
-
-pub struct Player {
-    pub display: glium::Display,
-    pub name: String,
-    pub position: [f32; 2],
-    pub world_position: [f32; 2],
-    velocity: [f32; 2],
-    pub sprite_size: f32 ,
-    program: glium::Program,
-    vertex_buffer: glium::VertexBuffer<PlayerSprite>,
-    index_buffer: glium::IndexBuffer<u16>,
-    pub view_matrix: na::Matrix4<f32>,
-}
-
-#[derive(Copy, Clone)]
-struct PlayerSprite {
-    position: [f32; 2],
-}
-
-// you must pass the list of members to the macro
-implement_vertex!(PlayerSprite, position);
-#[allow(dead_code)]
-impl Player{
-    pub fn new(display: glium::Display, name: String) -> Player {
-
-        if  is_debugging_enabled(){println!("{}", logger::info_opengl("Creating Player ShaderProgram"))};
-        let program = glium::Program::from_source(&display, VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC, None).unwrap();
-    
-        if  opengl::is_debugging_enabled(){println!("{}", logger::info_opengl("Creating Player VertexBuffer"))};
-        let vertex_buffer = glium::VertexBuffer::new(&display, &[
-            PlayerSprite { position: [-0.05, -0.05] },
-            PlayerSprite { position: [0.05, -0.05] },
-            PlayerSprite { position: [0.05, 0.05] },
-            PlayerSprite { position: [-0.05, 0.05] },
-        ]).expect(&logger::error_opengl("Failed to create Player VertexBuffer"));
-    
-        if  is_debugging_enabled(){println!("{}", logger::info_opengl("Creating Player IndexBuffer"))};
-        let index_buffer = glium::IndexBuffer::new(
-            &display,
-            glium::index::PrimitiveType::TriangleStrip,
-            &[1 as u16, 2, 0, 3],
-        ).expect(&logger::error_opengl("Failed to create Player IndexBuffer"));
-
-        Player {
-            display: display.clone(),
-            name: name,
-            position: [0.0, 0.0],
-            world_position: [0.0, 0.0],
-            velocity: [0.0, 0.0],
-            sprite_size: 0.0,
-            program: program,
-            vertex_buffer: vertex_buffer,
-            index_buffer: index_buffer,
-            view_matrix:  set_view_matrix(display),
-        }
-        
-    }
-
-    pub fn set_position(&mut self, x: f32, y: f32) {
-        self.position = [x, y];
-    }
-
-    pub fn set_velocity(&mut self, x: f32, y: f32) {
-        self.velocity = [x, y];
-    }
-
-    pub fn update(&mut self, frame: &mut glium::Frame) {
-        self.world_position[0] += self.velocity[0];
-        self.world_position[1] += self.velocity[1];
-        self.sprite_size = 0.1;
-        self.set_velocity(0.0, 0.0);
-        self.update_view_matrix();
-        self.draw_sprite(frame);
-    }
-
-    pub fn update_camera(&mut self) -> na::Matrix4<f32> {
-        // Get the current window size
-        let (window_width, window_height) = self.display.get_framebuffer_dimensions();
-    
-        // Calculate the aspect ratio
-        let aspect_ratio = window_width as f32 / window_height as f32;
-    
-        // Calculate the desired square size in window coordinates
-        let desired_square_size = self.sprite_size / window_width as f32;
-    
-        // Calculate the position to center the viewport on the player
-        let camera_position = na::Point2::new(
-            self.world_position[0] - (window_width as f32 * 0.5 * desired_square_size),
-            self.world_position[1] - (window_height as f32 * 0.5 * desired_square_size / aspect_ratio),
-        );
-    
-        // Create a view matrix to center the viewport on the player
-        let view_matrix = na::Matrix4::new_translation(&na::Vector3::new(
-            -camera_position.x,
-            -camera_position.y,
-            0.0,
-        ));
-    
-        view_matrix
-    }
-
-    pub fn get_view_matrix(&mut self) -> na::Matrix4<f32>{
-        let matrix = self.view_matrix;
-        matrix
-    }
-
-    fn update_view_matrix(&mut self) {
-        self.view_matrix = set_view_matrix(self.display.clone());
-    }
-    
-    pub fn draw_sprite(&mut self, frame: &mut glium::Frame, ) {
-
-        if is_debugging_enabled(){ 
-            println!("{}",format!("{} {}", logger::warn_opengl("Player VertexBuffer size:"), self.vertex_buffer.get_size()));
-            println!("{}",format!("{} {}", logger::warn_opengl("Player IndexBuffer size:"), self.index_buffer.get_size()));
-        }
-
-        //if  opengl::is_debugging_enabled(){println!("{}", logger::info_opengl("Setting Player Uniforms"))};
-        // Set the sprite_size uniform
-        let uniforms = uniform! {
-            view: *self.view_matrix.as_ref(),
-        };
-        frame.draw(
-            &self.vertex_buffer,
-            &self.index_buffer,
-            &self.program,
-            &uniforms,
-            &Default::default(),
-        ).expect(&logger::error_opengl("Failed to draw Player to Frame"));
-        if DEBUG_ONCE {unsafe { IS_DEBUGED = true };}
-    }
-
-    pub fn handle_input(&mut self, event: &mut glium::glutin::event::WindowEvent) {
-        match event {
-            glium::glutin::event::WindowEvent::KeyboardInput { input, .. } => {
-                if let Some(keycode) = input.virtual_keycode {
-                    match keycode {
-                        glium::glutin::event::VirtualKeyCode::W => {logger::debug_player_movement(self, "W"); self.velocity[1] = 0.01},
-                        glium::glutin::event::VirtualKeyCode::A => {logger::debug_player_movement(self, "A"); self.velocity[0] = -0.01},
-                        glium::glutin::event::VirtualKeyCode::S => {logger::debug_player_movement(self, "S"); self.velocity[1] = -0.01},
-                        glium::glutin::event::VirtualKeyCode::D => {logger::debug_player_movement(self, "D"); self.velocity[0] = 0.01},
-                        _ => (),
-                    }
-                }
-            }
-            _ => (),
-        }
-    }
-    
-}
-
-
-pub fn set_view_matrix(display: Display)  -> na::Matrix4<f32>{
-    // Get the current window size
-    let (window_width, window_height) = display.get_framebuffer_dimensions();
-
-    // Calculate the aspect ratio
-    let aspect_ratio = window_width as f32 / window_height as f32;
-
-    // Calculate the desired square size in window coordinates
-
-    // Create a view matrix to control the aspect ratio
-    let view_matrix = na::Matrix4::new(
-        1.0, 0.0, 0.0, 0.0,
-        0.0, aspect_ratio, 0.0, 0.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    ); 
-    view_matrix
+use crate::engine::console_logger::logger;
+use crate::engine::core::ecs::components::{GridMover, InputControlled, Name, Sprite, Transform};
+use crate::engine::core::ecs::world::{Entity, World};
+use crate::engine::core::config::{self, log_enabled, LogLevel, Subsystem};
+use crate::engine::core::metadata::COLOR_RED;
+use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+use crate::engine::core::world::collision::tile_center;
+
+/// Edge length of the player's sprite in world units.
+pub const PLAYER_SPRITE_SIZE: f32 = 0.1;
+
+/// Spawns the player archetype: a red quad walking the tile grid, steered with
+/// the movement and run keys of the `[input]` config table, W/A/S/D and left
+/// shift by default, at the `[movement]` walk speed.
+///
+/// # Arguments
+///
+/// * `world` - The world the player is spawned into.
+/// * `name` - The player's name, shown in movement log messages.
+/// * `position` - The spawn position in world units, the player stands on the center of its tile.
+///
+/// # Example
+///
+/// ```rust
+/// let player = spawn_player(&mut world, "makmusl", [0.0, 0.0]);
+/// ```
+pub fn spawn_player(world: &mut World, name: &str, position: [f32; 2]) -> Entity {
+    if is_debugging_enabled() {println!("{}", logger::info_opengl("Creating Player"))};
+
+    let config = config::config();
+    let mover = GridMover::at(position, config.movement.walk_speed);
+    let [x, y] = tile_center(mover.tile);
+    let keys = InputControlled::new(config.movement.walk_speed * TILE_SIZE, config.input.movement_keys())
+        .with_run_key(config.input.run_key())
+        .with_fish_key(config.input.fish_key());
+
+    world.spawn()
+        .with(Name(name.to_owned()))
+        .with(Transform::at(x, y))
+        .with(mover)
+        .with(Sprite::colored(PLAYER_SPRITE_SIZE, COLOR_RED))
+        .with(keys)
+        .id()
 }
 
 pub fn is_debugging_enabled() -> bool {
-    if !DEBUG_ONCE {
-        if metadata::DEBUG || PLAYER_DEBUG || unsafe { OPENGL_DEBUG } {
-            return true;
-        }else{
-            return false;
-        }
-    }else{
-        if !unsafe { IS_DEBUGED }{
-            if metadata::DEBUG || PLAYER_DEBUG || unsafe { OPENGL_DEBUG } {
-                return true;
-            }else{
-                return false;
-            }
-        }else{
-            return false;
-        }
-    }
-}
\ No newline at end of file
+    log_enabled(Subsystem::Player, LogLevel::Debug)
+}
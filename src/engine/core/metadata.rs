@@ -1,17 +1,12 @@
-// Import the lazy_static crate for static variable initialization
-extern crate lazy_static;
-use lazy_static::lazy_static;
-
-// Import necessary modules for file operations
-use std::fs::File;
-use std::io::Read;
+use super::build_info::BUILD_INFO;
 
 // Define crate-level constant variables
 pub const ENGINE_NAME: &str = "malkmusl Rust Game Engine";
-pub const VSYNC: bool = false;
-pub const DEBUG: bool = false;
+// The engine version embedded at compile time, see `BuildInfo`
+pub const ENGINE_VERSION: &str = BUILD_INFO.version;
 
 // Define a structure for representing colors
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Color {
     pub red: f32,
     pub green: f32,
@@ -31,37 +26,3 @@ pub const COLOR_WHITE: Color = Color { red: 1.0, green: 1.0, blue: 1.0, alpha: 1
 #[allow(dead_code)]
 pub const COLOR_BLACK: Color = Color { red: 0.0, green: 0.0, blue: 0.0, alpha: 1.0 };
 pub const COLOR_DARK_GREY: Color = Color { red: 0.25, green: 0.25, blue: 0.25, alpha: 1.0 };
-
-// Declare a static variable for the engine version using lazy_static
-lazy_static! {
-    pub static ref ENGINE_VERSION: String = {
-        // Use the get_cargo_toml_version function to read the version
-        match get_cargo_toml_version() {
-            Some(version) => version,
-            None => "Version not found".to_string(),
-        }
-    };
-}
-
-// Function to read the version from Cargo.toml
-fn get_cargo_toml_version() -> Option<String> {
-    // Open the Cargo.toml file
-    let mut toml_file = File::open("Cargo.toml").ok()?;
-
-    // Read the contents of the Cargo.toml file into a string
-    let mut toml_contents = String::new();
-    toml_file.read_to_string(&mut toml_contents).ok()?;
-
-    // Parse the TOML contents into a TOML table
-    let toml_table: toml::Value = toml::from_str(&toml_contents).ok()?;
-
-    // Extract the version string from the TOML table
-    let version = toml_table
-        .as_table()?
-        .get("package")?
-        .get("version")?
-        .as_str()?
-        .to_owned();
-
-    Some(version)
-}
\ No newline at end of file
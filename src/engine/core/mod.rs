@@ -1,6 +1,12 @@
 pub mod metadata;
+pub mod build_info;
+pub mod config;
+pub mod cli;
 pub mod time;
 pub mod app;
 pub mod renderer;
+pub mod ecs;
 pub mod entity;
 pub mod world;
+pub mod creature;
+pub mod validate;
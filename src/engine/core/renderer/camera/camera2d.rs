@@ -1,34 +1,63 @@
-use crate::engine::core::entity::player::Player;
 
+/// A simple 2D camera looking at `position` in world coordinates.
+///
+/// World coordinates are mapped to clip space by translating by `-position`,
+/// scaling by `zoom` and correcting the y axis by the aspect ratio of the
+/// render target, so a square in the world stays square on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera2D {
+    pub position: [f32; 2],
+    pub zoom: f32,
+}
 
-pub fn update_camera_follow_player(
-    player: &mut Player,
-    _frame: &mut glium::Frame,
-    player_position: [f32; 2],
-) {
-    // Get the current window size
-    let (window_width, window_height) = player.display.get_framebuffer_dimensions();
-
-    // Calculate the aspect ratio
-    let aspect_ratio = window_width as f32 / window_height as f32;
-
-    // Calculate the desired square size in window coordinates
-    let desired_square_size = player.sprite_size / window_width as f32;
-
-    // Calculate the position to center the camera on the player
-    let camera_position = na::Point2::new(
-        player_position[0] - (window_width as f32 * 0.5 * desired_square_size),
-        player_position[1] - (window_height as f32 * 0.5 * desired_square_size / aspect_ratio),
-    );
-
-    // Create a camera transformation matrix to center on the player
-    let _camera_matrix = na::Matrix4::new_translation(&na::Vector3::new(
-        -camera_position.x,
-        -camera_position.y,
-        0.0,
-    ));
-
-    // Apply the camera matrix to the rendering of the player
-    //player.draw_sprite(frame, camera_matrix);
+impl Default for Camera2D {
+    fn default() -> Self {
+        Camera2D::new()
+    }
+}
 
+#[allow(dead_code)]
+impl Camera2D {
+    pub fn new() -> Camera2D {
+        Camera2D {
+            position: [0.0, 0.0],
+            zoom: 1.0,
+        }
+    }
+
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        self.position = [x, y];
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
+
+    /// Centers the camera on `target`.
+    pub fn follow(&mut self, target: [f32; 2]) {
+        self.position = target;
+    }
+
+    /// Returns the matrix transforming world coordinates into clip space.
+    pub fn view_projection(&self, aspect_ratio: f32) -> na::Matrix4<f32> {
+        let scale = na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(
+            self.zoom,
+            self.zoom * aspect_ratio,
+            1.0,
+        ));
+        let translation = na::Matrix4::new_translation(&na::Vector3::new(
+            -self.position[0],
+            -self.position[1],
+            0.0,
+        ));
+        scale * translation
+    }
+
+    /// Transforms a single point from world coordinates into clip space.
+    pub fn world_to_clip(&self, point: [f32; 2], aspect_ratio: f32) -> [f32; 2] {
+        [
+            (point[0] - self.position[0]) * self.zoom,
+            (point[1] - self.position[1]) * self.zoom * aspect_ratio,
+        ]
+    }
 }
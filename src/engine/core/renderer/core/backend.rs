@@ -0,0 +1,107 @@
+use crate::engine::core::metadata::Color;
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::renderer::d2::tile_mesh::TileLayerMesh;
+
+/// Identifies a single texture inside one of the named `TextureAtlas` instances
+/// from `texture_tilesets` (e.g. `"OUTSIDE_ATLAS"`, id `31`).
+///
+/// Backends resolve the handle themselves, so entities and tiles never need to
+/// know whether the texture lives on the GPU or in a CPU image buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    pub atlas: &'static str,
+    pub id: u32,
+}
+
+impl TextureHandle {
+    pub const fn new(atlas: &'static str, id: u32) -> Self {
+        TextureHandle { atlas, id }
+    }
+}
+
+/// A solid colored, axis aligned rectangle in world coordinates.
+///
+/// `position` is the bottom-left corner, `size` the width and height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub color: Color,
+}
+
+/// A textured, axis aligned rectangle in world coordinates.
+///
+/// `position` is the bottom-left corner, `size` the width and height. The
+/// texture is stretched over the whole rectangle, with the first row of the
+/// texture image at the top edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprite {
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+    pub texture: TextureHandle,
+}
+
+/// The interface every renderer implementation has to provide.
+///
+/// A frame is always produced in the same order:
+///
+/// ```rust
+/// backend.begin_frame(COLOR_BLACK);
+/// backend.set_camera(&camera);
+/// backend.set_tint(world_time.tint());
+/// backend.submit_sprites(&tiles);
+/// backend.submit_quad(&player_quad);
+/// backend.present();
+/// ```
+///
+/// Everything submitted between `begin_frame` and `present` is drawn in
+/// submission order, later submissions covering earlier ones.
+pub trait RenderBackend {
+    /// Short name of the backend, used for window titles and log messages.
+    fn name(&self) -> &'static str;
+
+    /// Returns the size of the render target in pixels.
+    fn dimensions(&self) -> (u32, u32);
+
+    /// Starts a new frame and clears the render target with `clear_color`.
+    fn begin_frame(&mut self, clear_color: Color);
+
+    /// Sets the camera used for every following submission of this frame.
+    fn set_camera(&mut self, camera: &Camera2D);
+
+    /// Sets a color multiplied onto every following submission, e.g. the
+    /// day/night tint of `WorldTime`. `COLOR_WHITE` leaves colors unchanged.
+    fn set_tint(&mut self, tint: Color);
+
+    /// Draws a solid colored quad.
+    fn submit_quad(&mut self, quad: &Quad);
+
+    /// Draws a textured sprite.
+    fn submit_sprite(&mut self, sprite: &Sprite);
+
+    /// Draws a list of textured sprites. Backends may override this to batch
+    /// sprites sharing a texture into fewer draw calls.
+    fn submit_sprites(&mut self, sprites: &[Sprite]) {
+        for sprite in sprites {
+            self.submit_sprite(sprite);
+        }
+    }
+
+    /// Draws a static tile layer. Backends may override this to keep the
+    /// layer's vertices between frames, upload only the chunks whose revision
+    /// changed and draw each atlas of the layer with a single call.
+    fn submit_tile_layer(&mut self, layer: &TileLayerMesh) {
+        for sprite in layer.sprites() {
+            self.submit_sprite(&sprite);
+        }
+    }
+
+    /// Finishes the current frame and makes it visible.
+    fn present(&mut self);
+
+    /// Returns the aspect ratio (width / height) of the render target.
+    fn aspect_ratio(&self) -> f32 {
+        let (width, height) = self.dimensions();
+        width as f32 / height.max(1) as f32
+    }
+}
@@ -1,8 +1,9 @@
+pub mod backend;
 pub mod opengl;
+pub mod scene;
+pub mod software;
 pub mod vulkano;
 
-pub const GAME_STATE_DEBUG: bool = true;
-
 #[derive(Copy, Clone, PartialEq)]
 pub enum  GameStatus {
     Running,
@@ -1,32 +1,35 @@
 extern crate glium;
 extern crate lazy_static;
 
-use glium::glutin::{ContextBuilder, NotCurrent};
+use glium::glutin::ContextBuilder;
+use glium::texture::SrgbTexture2d;
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+use glium::{implement_vertex, uniform, Blend, Display, DrawParameters, Frame, IndexBuffer, Program, Surface, VertexBuffer};
 use lazy_static::lazy_static;
 
-
-use glium::{Frame, Display};
-use tokio::sync::Mutex;
 use winit::dpi::LogicalSize;
 
-use winit::window::{WindowBuilder, Icon};
-use crate::engine::assets_loader;
-use crate::engine::assets_loader::loader::ASSET_FOLDER;
+use winit::window::{Fullscreen, WindowBuilder, Icon};
+use crate::engine::assets_loader::loader::asset_root;
 
-use crate::engine::assets_loader::texture_loader::TextureAtlas;
-use crate::engine::assets_loader::texture_tilesets::OUTSIDE_ATLAS;
+use crate::engine::assets_loader::texture_tilesets::get_texture_atlas;
 use crate::engine::console_logger::logger::{self, set_color};
-use crate::engine::core::entity::npc;
-use crate::engine::core::metadata::{ENGINE_NAME, ENGINE_VERSION, VSYNC, COLOR_CYAN, self};
-use crate::engine::core::entity::player;
-use crate::engine::core::renderer::d2::background_tiles;
-use crate::engine::core::renderer::camera::camera2d;
-use std::sync::Arc;
-
-use image::{ImageBuffer, Rgba};
-use std::collections::HashMap;
-
-use super::GameStatus;
+use crate::engine::core::config::{self, log_enabled, LogLevel, Subsystem};
+use crate::engine::core::build_info::BUILD_INFO;
+use crate::engine::core::metadata::{ENGINE_NAME, ENGINE_VERSION, COLOR_CYAN, COLOR_WHITE, Color};
+use crate::engine::core::app::builder::WindowSettings;
+use crate::engine::core::ecs::input::InputState;
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::app::schedule::{current_state, Schedule};
+use crate::engine::core::app::AppState;
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::renderer::d2::tile_mesh::{AtlasBatch, TileChunk, TileLayerMesh, TileVertex, CHUNK_SIZE};
+use crate::engine::core::time::fixed_timestep::FixedTimestep;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::backend::{Quad, RenderBackend, Sprite, TextureHandle};
+use super::scene;
 
 lazy_static! {
     pub static ref OPENGL_PREFIX: String = {
@@ -35,22 +38,70 @@ lazy_static! {
     };
 }
 
+const SOLID_VERTEX_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec2 position;
+    uniform mat4 view_projection;
+
+    void main() {
+        gl_Position = view_projection * vec4(position, 0.0, 1.0);
+    }
+"#;
+
+const SOLID_FRAGMENT_SHADER_SRC: &str = r#"
+    #version 140
+
+    out vec4 color;
+    uniform vec4 quad_color;
+    uniform vec4 tint;
+
+    void main() {
+        color = quad_color * tint;
+    }
+"#;
+
+const TEXTURED_VERTEX_SHADER_SRC: &str = r#"
+    #version 140
 
+    in vec2 position;
+    in vec2 tex_coords;
+    out vec2 v_tex_coords;
+    uniform mat4 view_projection;
+
+    void main() {
+        gl_Position = view_projection * vec4(position, 0.0, 1.0);
+        v_tex_coords = tex_coords;
+    }
+"#;
+
+const TEXTURED_FRAGMENT_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec2 v_tex_coords;
+    out vec4 color;
+    uniform sampler2D tex;
+    uniform vec4 tint;
+
+    void main() {
+        color = texture(tex, v_tex_coords) * tint;
+    }
+"#;
+
+/// How often frame time and tick counts are reported while debugging.
+const FRAME_REPORT_INTERVAL: Duration = Duration::from_secs(1);
 
-pub static mut OPENGL_DEBUG: bool = true;
 pub struct OpenGLWindow {
     event_loop: glium::glutin::event_loop::EventLoop<()>,
-    _wb: Arc<WindowBuilder>,
-    _cb: Arc<ContextBuilder<'static, NotCurrent>>,
-    display: Arc<Display>,
-    _shared_state: Arc<Mutex<u32>>,// To enforce lifetime constraints
+    display: Display,
 }
 
-impl<'a> OpenGLWindow {
-    pub fn new(game_width: u32, game_height: u32, app_name: &str, vsync: bool) -> Self {
-        let graphics_api = "OpenGL";
-        let engine_version: &str = &*ENGINE_VERSION;
-        let app_name = format!("{} - [{} v{} - {}]", app_name, ENGINE_NAME, engine_version, graphics_api);
+impl OpenGLWindow {
+    pub fn new(game_width: u32, game_height: u32, app_name: &str, vsync: bool, fullscreen: bool) -> Self {
+        if is_debugging_enabled() {
+            println!("{}", logger::info_opengl(&format!("Build {}", BUILD_INFO)));
+        }
+        let app_name = window_title(app_name);
 
         if is_debugging_enabled() {
             println!("{}", logger::info_opengl("Creating EventLoop"));
@@ -62,8 +113,9 @@ impl<'a> OpenGLWindow {
         }
         let wb = WindowBuilder::new()
             .with_inner_size(LogicalSize::new(game_width, game_height))
-            .with_title(app_name);
-        Self::set_icon(wb.clone());
+            .with_title(app_name)
+            .with_fullscreen(fullscreen.then_some(Fullscreen::Borderless(None)));
+        let wb = Self::set_icon(wb);
 
         if is_debugging_enabled() {
             println!("{}", logger::info_opengl("Creating ContextBuffer"));
@@ -73,307 +125,589 @@ impl<'a> OpenGLWindow {
         if is_debugging_enabled() {
             println!("{}", logger::info_opengl("Creating Display"));
         }
-        let display = Display::new(wb.clone(), cb.clone(), &event_loop)
+        let display = Display::new(wb, cb, &event_loop)
             .unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to create Display: {}", e))));
 
-        let shared_state = Arc::new(Mutex::new(0u32));
-
         OpenGLWindow {
-            event_loop: event_loop,
-            _wb: Arc::new(wb),
-            _cb: Arc::new(cb),
-            display: Arc::new(display),
-            _shared_state: shared_state,
+            event_loop,
+            display,
         }
     }
 
     pub fn get_display(&self) -> Display {
-        let display = Arc::clone(&self.display);
-        (*display).clone()
+        self.display.clone()
     }
 
     pub fn get_event_loop(self) -> glium::glutin::event_loop::EventLoop<()> {
-        let event_loop = self.event_loop;
-        event_loop
+        self.event_loop
     }
 
-    #[allow(dead_code)]
-    pub fn get_shared_state(&self) -> &Arc<Mutex<u32>> {
-        &self._shared_state
+    pub fn set_icon(wb: WindowBuilder) -> WindowBuilder {
+        // Load the PNG file
+        let icon_path = format!("{}/apple.png", asset_root());
+        let icon_image = image::open(icon_path).expect("Failed to open icon image");
+
+        // Convert the image to RGBA format
+        let rgba_image = icon_image.to_rgba8();
+
+        // Get the image dimensions
+        let width = rgba_image.width();
+        let height = rgba_image.height();
+
+        // Create a winit::window::Icon from the texture
+        let icon = Icon::from_rgba(rgba_image.into_raw(), width, height)
+            .expect("Failed to create icon from texture");
+
+        // Set the window icon and return the new WindowBuilder
+        wb.with_window_icon(Some(icon))
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct SpriteVertex {
+    position: [f32; 2],
+    tex_coords: [f32; 2],
+}
+
+implement_vertex!(SpriteVertex, position, tex_coords);
+implement_vertex!(TileVertex, position, tex_coords);
+
+/// Vertices of a full chunk, the size of the slot every chunk gets in the
+/// buffers of its `GpuTileBatch`.
+const CHUNK_SLOT_VERTICES: usize = (CHUNK_SIZE * CHUNK_SIZE * 4) as usize;
+
+/// Fills the unused end of a slot, quads of zero area draw nothing.
+const EMPTY_TILE_VERTEX: TileVertex = TileVertex { position: [0.0, 0.0], tex_coords: [0.0, 0.0] };
+
+/// The slot every chunk of an `AtlasBatch` occupies in its `GpuTileBatch`.
+#[derive(Default)]
+struct ChunkSlots {
+    /// Slot and uploaded revision of every chunk by its coordinates.
+    chunks: HashMap<[i32; 2], (usize, u64)>,
+    /// Slots of removed chunks, reused before new ones are added.
+    free: Vec<usize>,
+    /// Slots handed out so far, used or free.
+    len: usize,
+}
+
+impl ChunkSlots {
+    /// Gives new chunks of `batch` a slot and frees the slots of chunks it no
+    /// longer has. Returns the slots to write, with the chunk to upload or
+    /// `None` to clear the slot, in the order they must be written.
+    fn sync<'a>(&mut self, batch: &'a AtlasBatch) -> Vec<(usize, Option<&'a TileChunk>)> {
+        let current: HashSet<[i32; 2]> = batch.chunks().map(|chunk| chunk.coords).collect();
+        let mut writes = Vec::new();
+        let free = &mut self.free;
+        self.chunks.retain(|coords, (slot, _)| {
+            let kept = current.contains(coords);
+            if !kept {
+                free.push(*slot);
+                writes.push((*slot, None));
+            }
+            kept
+        });
+        for chunk in batch.chunks() {
+            match self.chunks.get_mut(&chunk.coords) {
+                Some((slot, revision)) if *revision != chunk.revision() => {
+                    *revision = chunk.revision();
+                    writes.push((*slot, Some(chunk)));
+                }
+                Some(_) => {}
+                None => {
+                    let slot = self.free.pop().unwrap_or_else(|| {
+                        self.len += 1;
+                        self.len - 1
+                    });
+                    self.chunks.insert(chunk.coords, (slot, chunk.revision()));
+                    writes.push((slot, Some(chunk)));
+                }
+            }
+        }
+        writes
     }
+}
 
-pub fn set_icon(wb: WindowBuilder) {
-    // Load the PNG file
+/// The GPU buffers of one `AtlasBatch` of a `TileLayerMesh`.
+///
+/// Every chunk owns a slot of `CHUNK_SLOT_VERTICES` vertices, so changing,
+/// adding or removing a chunk writes only its slot. The buffers are
+/// reallocated, at twice the slots, only when the batch outgrows them.
+struct GpuTileBatch {
+    atlas: &'static str,
+    slots: ChunkSlots,
+    vertex_buffer: VertexBuffer<TileVertex>,
+    index_buffer: IndexBuffer<u32>,
+}
 
-    let icon_path = format!("{}/apple.png", ASSET_FOLDER);
-    let icon_image = image::open(icon_path).expect("Failed to open icon image");
+impl GpuTileBatch {
+    /// Uploads every chunk of `batch` into new buffers.
+    fn new(display: &Display, batch: &AtlasBatch) -> Self {
+        let mut slots = ChunkSlots::default();
+        slots.sync(batch);
+        let (vertex_buffer, index_buffer) = Self::buffers(display, slots.len * 2);
+        let gpu_batch = GpuTileBatch { atlas: batch.atlas, slots, vertex_buffer, index_buffer };
+        gpu_batch.write_chunks(batch);
+        gpu_batch
+    }
 
-    // Convert the image to RGBA format
-    let rgba_image = icon_image.to_rgba8();
+    /// Writes the slots of the chunks of `batch` that were added, removed or
+    /// changed their revision since the last update.
+    fn update(&mut self, display: &Display, batch: &AtlasBatch) {
+        let writes = self.slots.sync(batch);
+        if self.slots.len * CHUNK_SLOT_VERTICES > self.vertex_buffer.len() {
+            (self.vertex_buffer, self.index_buffer) = Self::buffers(display, self.slots.len * 2);
+            self.write_chunks(batch);
+            return;
+        }
+        for (slot, chunk) in writes {
+            self.write_slot(slot, chunk.map_or(&[], |chunk| chunk.vertices()));
+        }
+    }
 
-    // Get the image dimensions
-    let width = rgba_image.width();
-    let height = rgba_image.height();
+    /// Empty buffers of `slots` slots.
+    fn buffers(display: &Display, slots: usize) -> (VertexBuffer<TileVertex>, IndexBuffer<u32>) {
+        let vertices = vec![EMPTY_TILE_VERTEX; slots * CHUNK_SLOT_VERTICES];
+        let indices: Vec<u32> = (0..vertices.len() as u32 / 4)
+            .flat_map(|quad| [0, 1, 2, 0, 2, 3].map(|corner| quad * 4 + corner))
+            .collect();
+        (
+            VertexBuffer::new(display, &vertices).expect("Failed to create Tile VertexBuffer"),
+            IndexBuffer::new(display, glium::index::PrimitiveType::TrianglesList, &indices)
+                .expect("Failed to create Tile IndexBuffer"),
+        )
+    }
 
+    /// Writes every chunk of `batch` to its slot.
+    fn write_chunks(&self, batch: &AtlasBatch) {
+        for chunk in batch.chunks() {
+            self.write_slot(self.slots.chunks[&chunk.coords].0, chunk.vertices());
+        }
+    }
 
-    // Create a winit::window::Icon from the texture
-    let icon = Icon::from_rgba(rgba_image.clone().to_vec(), width, height)
-        .expect("Failed to create icon from texture");
+    /// Writes `vertices` to the start of `slot` and clears the rest of it.
+    fn write_slot(&self, slot: usize, vertices: &[TileVertex]) {
+        let mut padded = vertices.to_vec();
+        padded.resize(CHUNK_SLOT_VERTICES, EMPTY_TILE_VERTEX);
+        if let Some(range) = self.vertex_buffer.slice(slot * CHUNK_SLOT_VERTICES..(slot + 1) * CHUNK_SLOT_VERTICES) {
+            range.write(&padded);
+        }
+    }
+}
 
-    // Set the window icon and return the new WindowBuilder
-    let _ = wb.with_window_icon(Some(icon));
+/// The glium implementation of `RenderBackend`.
+///
+/// Quads and sprites are drawn immediately into the current `Frame`. Textures
+/// are uploaded once per `TextureHandle` and cached for the lifetime of the
+/// backend. Tile layers keep their vertex and index buffers between frames.
+pub struct OpenGLBackend {
+    display: Display,
+    frame: Option<Frame>,
+    solid_program: Program,
+    textured_program: Program,
+    textures: HashMap<TextureHandle, Option<SrgbTexture2d>>,
+    /// Whole atlas images for tile layers, see `submit_tile_layer`.
+    atlas_textures: HashMap<&'static str, Option<SrgbTexture2d>>,
+    /// Buffers of the tile layers by `TileLayerMesh::id`.
+    tile_layers: HashMap<u64, HashMap<&'static str, GpuTileBatch>>,
+    /// Tile layers drawn since `begin_frame`, the others are dropped on `present`.
+    drawn_tile_layers: HashSet<u64>,
+    view_projection: na::Matrix4<f32>,
+    tint: Color,
 }
 
-// Add other methods as needed
+impl OpenGLBackend {
+    /// A backend drawing into a hidden window without vsync, for measurements
+    /// like `bench-tiles`. The event loop must outlive the backend.
+    pub fn hidden(width: u32, height: u32) -> (glium::glutin::event_loop::EventLoop<()>, Self) {
+        let event_loop = glium::glutin::event_loop::EventLoop::new();
+        let wb = WindowBuilder::new()
+            .with_inner_size(LogicalSize::new(width, height))
+            .with_visible(false);
+        let cb = ContextBuilder::new().with_vsync(false);
+        let display = Display::new(wb, cb, &event_loop)
+            .unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to create Display: {}", e))));
+        (event_loop, OpenGLBackend::new(display))
+    }
+
+    /// Blocks until the GPU has executed every command submitted so far, so
+    /// timings include the GPU's work.
+    pub fn finish_gpu(&self) {
+        self.display.finish();
+    }
+
+    pub fn new(display: Display) -> Self {
+        if is_debugging_enabled() {println!("{}", logger::info_opengl("Creating solid ShaderProgram"))};
+        let solid_program = Program::from_source(&display, SOLID_VERTEX_SHADER_SRC, SOLID_FRAGMENT_SHADER_SRC, None)
+            .unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to create solid ShaderProgram: {}", e))));
+
+        if is_debugging_enabled() {println!("{}", logger::info_opengl("Creating textured ShaderProgram"))};
+        let textured_program = Program::from_source(&display, TEXTURED_VERTEX_SHADER_SRC, TEXTURED_FRAGMENT_SHADER_SRC, None)
+            .unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to create textured ShaderProgram: {}", e))));
+
+        OpenGLBackend {
+            display,
+            frame: None,
+            solid_program,
+            textured_program,
+            textures: HashMap::new(),
+            atlas_textures: HashMap::new(),
+            tile_layers: HashMap::new(),
+            drawn_tile_layers: HashSet::new(),
+            view_projection: na::Matrix4::identity(),
+            tint: COLOR_WHITE,
+        }
+    }
+
+    fn draw_parameters() -> DrawParameters<'static> {
+        DrawParameters {
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        }
+    }
+
+    /// Uploads the texture behind `handle` to the GPU if that did not happen yet.
+    fn cache_texture(&mut self, handle: TextureHandle) {
+        let display = &self.display;
+        self.textures.entry(handle).or_insert_with(|| {
+            let atlas = get_texture_atlas(handle.atlas)?;
+            let mut atlas = atlas.lock().expect("Failed to lock TextureAtlas");
+            atlas.ensure_loaded();
+            atlas.get_texture(handle.id)?;
+            Some(atlas.load_texture_from_map(handle.id, display.clone()))
+        });
+    }
+
+    /// Uploads the whole image of `atlas` to the GPU if that did not happen yet.
+    fn cache_atlas_texture(&mut self, atlas: &'static str) {
+        let display = &self.display;
+        self.atlas_textures.entry(atlas).or_insert_with(|| {
+            let atlas = get_texture_atlas(atlas)?;
+            let atlas = atlas.lock().expect("Failed to lock TextureAtlas");
+            let image = atlas.atlas_image.to_rgba8();
+            let dimensions = image.dimensions();
+            let raw_image = glium::texture::RawImage2d::from_raw_rgba_reversed(&image.into_raw(), dimensions);
+            SrgbTexture2d::new(display, raw_image).ok()
+        });
+    }
+
+    /// Draws consecutive sprites sharing the same texture with a single draw call.
+    fn draw_sprite_batch(&mut self, sprites: &[Sprite]) {
+        let Some(first) = sprites.first() else {
+            return;
+        };
+        self.cache_texture(first.texture);
+        let Some(Some(texture)) = self.textures.get(&first.texture) else {
+            return;
+        };
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+
+        let mut vertices: Vec<SpriteVertex> = Vec::with_capacity(sprites.len() * 4);
+        let mut indices: Vec<u32> = Vec::with_capacity(sprites.len() * 6);
+        for sprite in sprites {
+            let base_index = vertices.len() as u32;
+            let [x, y] = sprite.position;
+            let [width, height] = sprite.size;
+            vertices.extend_from_slice(&[
+                SpriteVertex { position: [x, y], tex_coords: [0.0, 0.0] },
+                SpriteVertex { position: [x + width, y], tex_coords: [1.0, 0.0] },
+                SpriteVertex { position: [x + width, y + height], tex_coords: [1.0, 1.0] },
+                SpriteVertex { position: [x, y + height], tex_coords: [0.0, 1.0] },
+            ]);
+            indices.extend_from_slice(&[base_index, base_index + 1, base_index + 2, base_index, base_index + 2, base_index + 3]);
+        }
+
+        let vertex_buffer = VertexBuffer::new(&self.display, &vertices)
+            .expect("Failed to create Sprite VertexBuffer");
+        let index_buffer = IndexBuffer::new(&self.display, glium::index::PrimitiveType::TrianglesList, &indices)
+            .expect("Failed to create Sprite IndexBuffer");
+
+        let uniforms = uniform! {
+            view_projection: *self.view_projection.as_ref(),
+            tint: [self.tint.red, self.tint.green, self.tint.blue, 1.0f32],
+            tex: texture.sampled()
+                .magnify_filter(MagnifySamplerFilter::Nearest)
+                .minify_filter(MinifySamplerFilter::Nearest),
+        };
+        frame.draw(
+            &vertex_buffer,
+            &index_buffer,
+            &self.textured_program,
+            &uniforms,
+            &Self::draw_parameters(),
+        ).unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to draw Sprites to Frame: {}", e))));
+    }
 }
 
+impl RenderBackend for OpenGLBackend {
+    fn name(&self) -> &'static str {
+        "OpenGL"
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.display.get_framebuffer_dimensions()
+    }
+
+    fn begin_frame(&mut self, clear_color: Color) {
+        if let Some(frame) = self.frame.take() {
+            frame.finish().unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to finish Frame: {}", e))));
+        }
+        let mut frame = self.display.draw();
+        frame.clear_color(clear_color.red, clear_color.green, clear_color.blue, clear_color.alpha);
+        self.frame = Some(frame);
+    }
+
+    fn set_camera(&mut self, camera: &Camera2D) {
+        self.view_projection = camera.view_projection(self.aspect_ratio());
+    }
+
+    fn set_tint(&mut self, tint: Color) {
+        self.tint = tint;
+    }
 
-#[allow(unused_mut)]
-pub fn create_opengl_window(game_name: &str, game_width: u32, game_height: u32) {
-    let mut state = GameStatus::Running;
+    fn submit_quad(&mut self, quad: &Quad) {
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let [x, y] = quad.position;
+        let [width, height] = quad.size;
+        let vertex_buffer = VertexBuffer::new(&self.display, &[
+            SpriteVertex { position: [x, y], tex_coords: [0.0, 0.0] },
+            SpriteVertex { position: [x + width, y], tex_coords: [1.0, 0.0] },
+            SpriteVertex { position: [x + width, y + height], tex_coords: [1.0, 1.0] },
+            SpriteVertex { position: [x, y + height], tex_coords: [0.0, 1.0] },
+        ]).expect("Failed to create Quad VertexBuffer");
+        let index_buffer = IndexBuffer::new(
+            &self.display,
+            glium::index::PrimitiveType::TriangleStrip,
+            &[1u16, 2, 0, 3],
+        ).expect("Failed to create Quad IndexBuffer");
+
+        let uniforms = uniform! {
+            view_projection: *self.view_projection.as_ref(),
+            quad_color: [quad.color.red, quad.color.green, quad.color.blue, quad.color.alpha],
+            tint: [self.tint.red, self.tint.green, self.tint.blue, 1.0f32],
+        };
+        frame.draw(
+            &vertex_buffer,
+            &index_buffer,
+            &self.solid_program,
+            &uniforms,
+            &Self::draw_parameters(),
+        ).unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to draw Quad to Frame: {}", e))));
+    }
+
+    fn submit_sprite(&mut self, sprite: &Sprite) {
+        self.draw_sprite_batch(std::slice::from_ref(sprite));
+    }
 
-    let gl_window = OpenGLWindow::new(game_width, game_height, game_name, true);
+    fn submit_sprites(&mut self, sprites: &[Sprite]) {
+        // Keep the submission order intact, only merge runs sharing a texture.
+        for batch in sprites.chunk_by(|a, b| a.texture == b.texture) {
+            self.draw_sprite_batch(batch);
+        }
+    }
+
+    /// Keeps the layer's buffers between frames, writes only the slots of the
+    /// chunks that changed and draws every atlas of the layer with one call.
+    fn submit_tile_layer(&mut self, layer: &TileLayerMesh) {
+        self.drawn_tile_layers.insert(layer.id());
+        let display = &self.display;
+        let gpu_batches = self.tile_layers.entry(layer.id()).or_default();
+        gpu_batches.retain(|atlas, _| layer.batches().iter().any(|batch| batch.atlas == *atlas));
+        for batch in layer.batches() {
+            match gpu_batches.get_mut(batch.atlas) {
+                Some(gpu_batch) => gpu_batch.update(display, batch),
+                None => {
+                    gpu_batches.insert(batch.atlas, GpuTileBatch::new(display, batch));
+                }
+            }
+        }
+
+        for batch in layer.batches() {
+            self.cache_atlas_texture(batch.atlas);
+        }
+        let Some(frame) = self.frame.as_mut() else {
+            return;
+        };
+        let gpu_batches = &self.tile_layers[&layer.id()];
+        for gpu_batch in layer.batches().iter().map(|batch| &gpu_batches[batch.atlas]) {
+            if gpu_batch.vertex_buffer.len() == 0 {
+                continue;
+            }
+            let Some(Some(texture)) = self.atlas_textures.get(gpu_batch.atlas) else {
+                continue;
+            };
+            let uniforms = uniform! {
+                view_projection: *self.view_projection.as_ref(),
+                tint: [self.tint.red, self.tint.green, self.tint.blue, 1.0f32],
+                tex: texture.sampled()
+                    .magnify_filter(MagnifySamplerFilter::Nearest)
+                    .minify_filter(MinifySamplerFilter::Nearest),
+            };
+            frame.draw(
+                &gpu_batch.vertex_buffer,
+                &gpu_batch.index_buffer,
+                &self.textured_program,
+                &uniforms,
+                &Self::draw_parameters(),
+            ).unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to draw tile layer to Frame: {}", e))));
+        }
+    }
+
+    fn present(&mut self) {
+        if let Some(frame) = self.frame.take() {
+            frame.finish().unwrap_or_else(|e| panic!("{}", logger::error_opengl(&format!("Failed to finish Frame: {}", e))));
+        }
+        // Layers of an unloaded map are not drawn anymore, free their buffers.
+        let drawn = std::mem::take(&mut self.drawn_tile_layers);
+        self.tile_layers.retain(|id, _| drawn.contains(id));
+    }
+}
+
+/// The window title. While debugging it doubles as the debug overlay: it
+/// shows the full build, e.g. to tell screenshots of different commits
+/// apart, and `create_opengl_window` appends the frame rate once a second.
+pub fn window_title(app_name: &str) -> String {
+    let graphics_api = "OpenGL";
+    if is_debugging_enabled() {
+        format!("{} - [{} {} - {}]", app_name, ENGINE_NAME, BUILD_INFO, graphics_api)
+    } else {
+        format!("{} - [{} v{} - {}]", app_name, ENGINE_NAME, ENGINE_VERSION, graphics_api)
+    }
+}
+
+/// Opens the OpenGL window and runs the game loop until the window is closed.
+///
+/// The simulation is advanced in fixed steps of `1 / tick_rate` seconds on
+/// `MainEventsCleared`, independent of how many window events arrive or
+/// whether VSYNC is enabled. Rendering happens on `RedrawRequested`, with the
+/// leftover fraction of a tick passed on as interpolation alpha.
+///
+/// Every tick runs the tick stages of `schedule` against `world`, see
+/// `scene::fixed_update`. The pause key of the `[input]` config, Escape by default, switches between `AppState::Running` and
+/// `AppState::Paused`, closing the window enters `AppState::Quitting`.
+pub fn create_opengl_window(game_name: &str, window: WindowSettings, tick_rate: f64, mut world: World, mut schedule: Schedule) {
+    let gl_window = OpenGLWindow::new(window.width, window.height, game_name, window.vsync, window.fullscreen);
     let display = gl_window.get_display();
     let event_loop = gl_window.get_event_loop();
+    let title = window_title(game_name);
 
-    load_atlases(display.clone());
-    //output_textures(&OUTSIDE_ATLAS.lock().unwrap().textures);
-
-    let mut player = player::Player::new(display.clone(), "makmusl".to_string());
-    let mut npc = npc::NPC::new(display.clone());
+    let mut backend = OpenGLBackend::new(display.clone());
+    let mut camera = Camera2D::new();
+    let mut timestep = FixedTimestep::new(tick_rate);
+    let pause_key = config::config().input.pause_key();
 
     // 5. start EventsLoop
-    println!("{}", logger::warn_opengl("Starting EventLoop"));
+    println!("{}", logger::warn_opengl(&format!("Starting EventLoop with {} ticks per second", timestep.tick_rate())));
     event_loop.run(move |event, _, control_flow| {
         // 6. Handle events here
+        let state = current_state(&world);
         match event {
-            glium::glutin::event::Event::WindowEvent { mut event, .. } => {
+            glium::glutin::event::Event::WindowEvent { event, .. } => {
                 match event {
                     glium::glutin::event::WindowEvent::CloseRequested => {
                         *control_flow = glium::glutin::event_loop::ControlFlow::Exit;
-                        state = GameStatus::Stopped;
-                        logger::game_state(state, 0);
+                        scene::shutdown(&mut world, &mut schedule);
                     }
-                    _ => {
-                        if state == GameStatus::Running {
-                            player.handle_input(&mut event);
-                            npc.handle_input(&mut event);
+                    glium::glutin::event::WindowEvent::Focused(false) => {
+                        if let Some(mut input) = world.resource_mut::<InputState>() {
+                            input.clear();
                         }
                     }
-                }
-            },
-            glium::glutin::event::Event::DeviceEvent { event, .. } => {
-                match event {
-                    glium::glutin::event::DeviceEvent::Key(input) => {
-                        match input.virtual_keycode {
-                            Some(glium::glutin::event::VirtualKeyCode::Escape) => {
-                                if input.state == glium::glutin::event::ElementState::Pressed {
-                                    if state == GameStatus::Running{
-                                        state = GameStatus::Paused;
-                                        logger::game_state(state, 21);
-                                    }else{
-                                        state = GameStatus::Running;
-                                        logger::game_state(state, 22);
-                                    }
-                                }
+                    _ => {
+                        if state == AppState::Running {
+                            if let Some(mut input) = world.resource_mut::<InputState>() {
+                                input.handle_event(&event);
                             }
-                            _ => (),
                         }
                     }
+                }
+            },
+            glium::glutin::event::Event::DeviceEvent { event: glium::glutin::event::DeviceEvent::Key(input), .. }
+                if input.virtual_keycode == Some(pause_key)
+                    && input.state == glium::glutin::event::ElementState::Pressed =>
+            {
+                match state {
+                    AppState::Running => schedule.set_state(&mut world, AppState::Paused),
+                    AppState::Paused => schedule.set_state(&mut world, AppState::Running),
                     _ => (),
                 }
             },
-            _ => {
-                if state == GameStatus::Running {
+            glium::glutin::event::Event::MainEventsCleared => {
+                if state == AppState::Running {
                     *control_flow = glium::glutin::event_loop::ControlFlow::Poll;
-                    update_content(display.clone(), &mut player, &mut npc);
-                } else {
+                    for _ in 0..timestep.tick(Instant::now()) {
+                        scene::fixed_update(&mut world, timestep.step(), &mut schedule);
+                    }
+                    display.gl_window().window().request_redraw();
+                } else if state == AppState::Paused {
+                    // Do not simulate the time spent paused once the game resumes
+                    timestep.reset_clock();
                     *control_flow = glium::glutin::event_loop::ControlFlow::Wait;
                 }
             },
-        }        
+            glium::glutin::event::Event::RedrawRequested(_) => {
+                scene::render(&mut backend, &mut camera, &mut world, &mut schedule, timestep.alpha());
+                if let Some(report) = timestep.take_report(FRAME_REPORT_INTERVAL) {
+                    if is_debugging_enabled() {
+                        println!("{}", logger::info_opengl(&format!(
+                            "Frame time {:.2} ms ({:.0} FPS), {:.0} ticks/s, {} ticks total",
+                            report.average_frame_time.as_secs_f64() * 1000.0,
+                            report.frames_per_second,
+                            report.ticks_per_second,
+                            report.total_ticks,
+                        )));
+                        display.gl_window().window().set_title(&format!(
+                            "{} {:.0} FPS, {:.0} ticks/s",
+                            title, report.frames_per_second, report.ticks_per_second,
+                        ));
+                    }
+                }
+            },
+            _ => (),
+        }
     });
 }
 
-/// Updates the content of the game's display. This function is responsible for drawing
-/// the game's elements, including the player and NPCs, onto the display, updating the
-/// background tiles, and finishing the frame.
-///
-/// # Arguments
-///
-/// * `display` - A reference to the glium display where the game is rendered.
-/// * `player` - A mutable reference to the player object to update and draw.
-/// * `npc` - A mutable reference to the NPC (non-player character) object to update and draw.
-///
-/// This function performs the necessary drawing and updating operations, culminating
-/// in finishing the frame, making the changes visible on the game window.
-///
-/// # Example
-///
-/// ```rust
-/// let display = /* initialize your glium display */;
-/// let mut player = /* initialize your player */;
-/// let mut npc = /* initialize your NPC */;
-/// update_content(display, &mut player, &mut npc);
-/// ```
-///
-/// This function is a key part of the game loop, responsible for rendering and updating
-/// the visual elements of the game.
-
-pub fn update_content(display: glium::Display,player: &mut player::Player, _npc: &mut npc::NPC){
-    let mut frame = display.draw();
-    //draw_squareV2(display.clone(), &mut frame);
-    update_background_tiles(display.clone(), &mut frame, player);
-    update_player(player, &mut frame);
-    //update_npc(npc, &mut frame);
-    frame.finish().expect(&logger::error_opengl("Failed to finish Frame"));
-}
-
-/// Updates the player object and its interaction with the game frame. This function
-/// is responsible for advancing the player's state and handling camera adjustments
-/// to follow the player's position.
-///
-/// # Arguments
-///
-/// * `player` - A mutable reference to the player object to update.
-/// * `frame` - A mutable reference to the game frame where updates take place.
-///
-/// This function calls the `update` method on the player, allowing it to process
-/// its logic and animations. It also ensures that the camera follows the player's
-/// position, keeping them centered within the frame.
-///
-/// # Example
-///
-/// ```rust
-/// let mut player = /* initialize your player */;
-/// let mut frame = /* initialize your game frame */;
-/// update_player(&mut player, &mut frame);
-/// ```
-///
-/// This function plays a crucial role in keeping the player character up-to-date and
-/// managing the in-game camera's behavior.
-
-pub fn update_player(player: &mut player::Player, frame: &mut Frame) {
-    player.update(frame);
-    camera2d::update_camera_follow_player(player, frame, player.position)
-}
-
-/// Updates the NPC (non-player character) object within the game frame. This function
-/// is responsible for advancing the NPC's state and interactions with the game world.
-///
-/// # Arguments
-///
-/// * `npc` - A mutable reference to the NPC object to update.
-/// * `frame` - A mutable reference to the game frame where updates take place.
-///
-/// This function calls the `update` method on the NPC, allowing it to process its logic,
-/// animations, and interactions with the game world.
-///
-/// # Example
-///
-/// ```rust
-/// let mut npc = /* initialize your NPC */;
-/// let mut frame = /* initialize your game frame */;
-/// update_npc(&mut npc, &mut frame);
-/// ```
-///
-/// This function is essential for keeping the NPC character up-to-date within the game world.
-
-#[allow(dead_code)]
-pub fn update_npc(npc: &mut npc::NPC, mut frame: &mut Frame) {
-    npc.update(&mut frame);
-}
-
-/// Updates the background tiles within the game frame. This function is responsible for
-/// rendering and updating the background elements, such as tiles or grids, to create the
-/// game's visual environment.
-///
-/// # Arguments
-///
-/// * `display` - A glium display reference where the background tiles are drawn.
-/// * `frame` - A mutable reference to the game frame where updates take place.
-///
-/// This function typically calls specific drawing functions or modules to create the
-/// background tiles or grids, setting the visual backdrop for the game world.
-///
-/// # Example
-///
-/// ```rust
-/// let display = /* initialize your glium display */;
-/// let mut frame = /* initialize your game frame */;
-/// update_background_tiles(display, &mut frame);
-/// ```
-///
-/// This function plays a crucial role in maintaining the visual aspect of the game's
-/// environment.
-
-pub fn update_background_tiles(display: glium::Display, frame: &mut Frame, player: &mut player::Player){
-    //background_tiles::draw(display.clone(), frame, 10, 10, 0.5);
-    //testing::simple_square::draw_square_grid(&display, frame, 1, 3, 0.2);
-    let _ = assets_loader::loader::load_tiles_from_file("test");
-
-    let texture = assets_loader::loader::load_texture(&display, "moss_block.png");
-
-    //let atlas_texture = OUTSIDE_ATLAS.load_texture_from_atlas([1, 1], display.clone());
-
-    let mut background = background_tiles::BackgroundTiles::new(display);
-    background.draw(frame, 10, 10, player);
-    
-
-    // Call the draw_square_grid_with_texture function with the loaded texture
-    //testing::simple_square::draw_square_grid_with_texture(&display, frame, 5, 5, 0.2, &texture);
-    //testing::simple_square::draw_square_grid_with_texture_and_player(&display, frame, 32, 32, 0.1, &texture, player);
-    /*
-    let mut layer_0 = background_tiles::BackgroundTiles::new(display.clone());
-    let tile_0 = background_tiles::Tile::new([0.0,0.0], 0.1, texture);
-    let tile_1= background_tiles::Tile::new([1.0, 1.0], 0.1, texture2);
-    layer_0.add_tile(tile_0);
-    layer_0.add_tile(tile_1);
-    layer_0.draw(frame, player); 
-    */
-    
-    
-}
-
-#[allow(dead_code)]
-pub struct Level{
-    pub data: Vec<background_tiles::BackgroundTiles>
-}
-
-
-fn load_atlases(display: glium::Display){
-    let outside_atlas = OUTSIDE_ATLAS.lock().expect("Failed to lock OUTSIDE_ATLAS").load_texture_from_tileset_to_map(&display);
+pub fn is_debugging_enabled() -> bool {
+    log_enabled(Subsystem::OpenGL, LogLevel::Debug)
 }
 
-
-fn output_textures(textures: &HashMap<u32, ImageBuffer<Rgba<u8>, Vec<u8>>>) {
-    for (key, image_buffer) in textures {
-        // Process or print the key and image_buffer as needed
-        println!("Key: {}", key);
-
-        // For demonstration, let's print the dimensions of the image buffer
-        let (width, height) = image_buffer.dimensions();
-        println!("Image Dimensions: {} x {}", width, height);
-
-        // You can perform other operations on the image_buffer as needed
-        // ...
-
-        // Example: Access a pixel value
-
-        // Example: Iterate over all pixels
-        for (_, _, pixel) in image_buffer.enumerate_pixels() {
-            // Process each pixel as needed
-            // ...
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_slots_write_only_the_touched_chunks() {
+        let texture = TextureHandle::new("OUTSIDE_ATLAS", 1);
+        let mut mesh = TileLayerMesh::new(1.0);
+        mesh.set_tile([0, 0], Some(texture));
+        mesh.set_tile([CHUNK_SIZE, 0], Some(texture));
+        let mut slots = ChunkSlots::default();
+        let written = |writes: Vec<(usize, Option<&TileChunk>)>| -> Vec<(usize, Option<[i32; 2]>)> {
+            writes.into_iter().map(|(slot, chunk)| (slot, chunk.map(|chunk| chunk.coords))).collect()
+        };
+
+        assert_eq!(written(slots.sync(&mesh.batches()[0])), [(0, Some([0, 0])), (1, Some([1, 0]))]);
+        assert!(slots.sync(&mesh.batches()[0]).is_empty());
+
+        // (tile changed, slots written)
+        let table = [
+            ([1, 1], vec![(0, Some([0, 0]))]),
+            ([CHUNK_SIZE + 1, 0], vec![(1, Some([1, 0]))]),
+        ];
+        for (tile, expected) in table {
+            mesh.set_tile(tile, Some(texture));
+            assert_eq!(written(slots.sync(&mesh.batches()[0])), expected, "{:?}", tile);
         }
-    }
-}
-
-
 
-pub fn is_debugging_enabled() -> bool {
-    if metadata::DEBUG || unsafe { OPENGL_DEBUG } {
-        return true;
-    }else{
-        return false;
+        // A removed chunk frees its slot for the next new one.
+        mesh.set_tile([0, 0], None);
+        mesh.set_tile([1, 1], None);
+        mesh.set_tile([0, CHUNK_SIZE], Some(texture));
+        assert_eq!(written(slots.sync(&mesh.batches()[0])), [(0, None), (0, Some([0, 1]))]);
+        assert_eq!(slots.len, 2);
     }
-}
\ No newline at end of file
+}
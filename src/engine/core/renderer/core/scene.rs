@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use crate::engine::core::app::schedule::{Schedule, Stage};
+use crate::engine::core::app::AppState;
+use crate::engine::core::ecs::systems;
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::metadata::{Color, COLOR_BLACK, COLOR_WHITE};
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::time::calendar::WorldTime;
+use crate::engine::core::time::clock::GameClock;
+use crate::engine::core::world::plugin::LoadedMap;
+use crate::engine::core::world::warp::MapTransition;
+
+use super::backend::{Quad, RenderBackend};
+
+/// Advances the game world by one fixed simulation tick.
+///
+/// # Arguments
+///
+/// * `world` - The world to update, set up by the app's plugins, see `DefaultPlugins`.
+/// * `step` - The real time covered by one tick, `FixedTimestep::step`.
+/// * `schedule` - The schedule whose `PreUpdate`, `Update` and `PostUpdate` stages are run.
+///
+/// This function is called a fixed number of times per second by the game loop,
+/// independent of the frame rate, see `FixedTimestep`. Entities move by the
+/// clock's scaled delta, so they slow down or stop with `GameClock::set_time_scale`
+/// and `GameClock::pause`.
+pub fn fixed_update(world: &mut World, step: Duration, schedule: &mut Schedule) {
+    if let Some(mut clock) = world.resource_mut::<GameClock>() {
+        clock.advance(step);
+    }
+    for stage in Stage::TICK {
+        schedule.run_stage(stage, world);
+    }
+}
+
+/// Switches to `AppState::Quitting` and runs the `Shutdown` stage.
+pub fn shutdown(world: &mut World, schedule: &mut Schedule) {
+    schedule.set_state(world, AppState::Quitting);
+    schedule.run_stage(Stage::Shutdown, world);
+}
+
+/// Renders the game's display. This function is responsible for drawing the game's
+/// elements onto the render backend and presenting the frame: the ground layers of the
+/// `LoadedMap`, then every entity with a sprite y-sorted with the map's decoration
+/// tiles, then the map's overhead layers, and last the fade of a `MapTransition`.
+///
+/// # Arguments
+///
+/// * `backend` - The render backend the frame is drawn with.
+/// * `camera` - The camera following the player.
+/// * `world` - The world to draw. Its `WorldTime` phase decides the global tint.
+/// * `schedule` - The schedule whose `Render` stage runs before anything is drawn.
+/// * `alpha` - How far the frame lies between the last two simulation ticks.
+///
+/// This function works the same for every backend, so a scene rendered by the
+/// `SoftwareBackend` looks like the one shown in the OpenGL window.
+///
+/// # Example
+///
+/// ```rust
+/// let mut backend = SoftwareBackend::new(800, 600);
+/// let mut camera = Camera2D::new();
+/// let mut app = App::builder("MyGame", "1.0").add_plugin(CorePlugin).add_plugin(InputPlugin).build();
+/// app.startup();
+/// let (_, mut world, mut schedule) = app.into_parts();
+/// spawn_player(&mut world, "makmusl", [0.0, 0.0]);
+/// fixed_update(&mut world, Duration::from_secs_f64(1.0 / 60.0), &mut schedule);
+/// render(&mut backend, &mut camera, &mut world, &mut schedule, 1.0);
+/// ```
+pub fn render(backend: &mut dyn RenderBackend, camera: &mut Camera2D, world: &mut World, schedule: &mut Schedule, alpha: f32) {
+    schedule.run_stage(Stage::Render, world);
+    backend.begin_frame(COLOR_BLACK);
+    systems::camera_follow_system(world, camera, alpha);
+    backend.set_camera(camera);
+    let tint = world.resource::<WorldTime>().map_or(COLOR_WHITE, |world_time| world_time.tint());
+    backend.set_tint(tint);
+    let map = world.resource::<LoadedMap>();
+    if let Some(map) = &map {
+        map.draw_below(backend);
+    }
+    let scenery = map.as_ref().map(|map| map.scenery()).unwrap_or_default();
+    systems::draw_sprites_system(world, backend, alpha, &scenery);
+    if let Some(map) = &map {
+        map.draw_above(backend);
+    }
+    if let Some(fade) = world.resource::<MapTransition>().map(|transition| transition.fade()).filter(|fade| *fade > 0.0) {
+        draw_fade(backend, camera, fade);
+    }
+    backend.present();
+}
+
+/// Covers the whole view in black with the opacity `fade`.
+fn draw_fade(backend: &mut dyn RenderBackend, camera: &Camera2D, fade: f32) {
+    let half_size = [1.0 / camera.zoom, 1.0 / (camera.zoom * backend.aspect_ratio())];
+    backend.set_tint(COLOR_WHITE);
+    backend.submit_quad(&Quad {
+        position: [camera.position[0] - half_size[0], camera.position[1] - half_size[1]],
+        size: [2.0 * half_size[0], 2.0 * half_size[1]],
+        color: Color { alpha: fade, ..COLOR_BLACK },
+    });
+}
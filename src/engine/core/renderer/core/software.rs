@@ -0,0 +1,245 @@
+extern crate lazy_static;
+
+use std::collections::HashMap;
+
+use image::{ImageBuffer, Rgba, RgbaImage};
+use lazy_static::lazy_static;
+
+use crate::engine::assets_loader::texture_tilesets::get_texture_atlas;
+use crate::engine::console_logger::logger::{self, set_color};
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::app::schedule::Schedule;
+use crate::engine::core::app::AppState;
+use crate::engine::core::metadata::{Color, COLOR_MAGENTA, COLOR_WHITE};
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::renderer::d2::tile_mesh::TileLayerMesh;
+use crate::engine::core::time::fixed_timestep::FixedTimestep;
+
+use super::backend::{Quad, RenderBackend, Sprite, TextureHandle};
+use super::scene;
+
+lazy_static! {
+    pub static ref SOFTWARE_PREFIX: String = {
+        let prefix = "[Renderer - Software]";
+        set_color(COLOR_MAGENTA, prefix)
+    };
+}
+
+/// A headless renderer rasterizing every frame on the CPU into an RGBA image.
+///
+/// It needs neither a window nor a GPU, which makes it usable on CI machines
+/// to render whole scenes and compare the resulting frames. Textures are
+/// sampled with nearest filtering and blended with straight alpha, matching
+/// the OpenGL backend.
+pub struct SoftwareBackend {
+    width: u32,
+    height: u32,
+    target: RgbaImage,
+    camera: Camera2D,
+    tint: Color,
+    textures: HashMap<TextureHandle, Option<RgbaImage>>,
+    frames_presented: u64,
+}
+
+#[allow(dead_code)]
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareBackend {
+            width,
+            height,
+            target: ImageBuffer::new(width, height),
+            camera: Camera2D::new(),
+            tint: COLOR_WHITE,
+            textures: HashMap::new(),
+            frames_presented: 0,
+        }
+    }
+
+    /// Returns the image the last frame was rendered into.
+    pub fn frame(&self) -> &RgbaImage {
+        &self.target
+    }
+
+    /// Returns how many frames have been presented so far.
+    pub fn frames_presented(&self) -> u64 {
+        self.frames_presented
+    }
+
+    /// Writes the current frame to `path`. The image format is picked from the file extension.
+    pub fn save_frame(&self, path: &str) -> Result<(), image::ImageError> {
+        self.target.save(path)
+    }
+
+    /// Converts a world space rectangle into the covered pixel range
+    /// `[x0, y0, x1, y1)` of the render target, clamped to its size.
+    ///
+    /// A pixel is covered when its center lies inside the rectangle.
+    fn pixel_bounds(&self, position: [f32; 2], size: [f32; 2]) -> Option<(u32, u32, u32, u32, [f32; 4])> {
+        let aspect_ratio = self.aspect_ratio();
+        let bottom_left = self.camera.world_to_clip(position, aspect_ratio);
+        let top_right = self.camera.world_to_clip([position[0] + size[0], position[1] + size[1]], aspect_ratio);
+
+        // Clip space to pixel space, the image origin is the top-left corner.
+        let left = (bottom_left[0] + 1.0) * 0.5 * self.width as f32;
+        let right = (top_right[0] + 1.0) * 0.5 * self.width as f32;
+        let top = (1.0 - top_right[1]) * 0.5 * self.height as f32;
+        let bottom = (1.0 - bottom_left[1]) * 0.5 * self.height as f32;
+
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        let x0 = (left - 0.5).ceil().max(0.0);
+        let x1 = (right - 0.5).ceil().min(self.width as f32);
+        let y0 = (top - 0.5).ceil().max(0.0);
+        let y1 = (bottom - 0.5).ceil().min(self.height as f32);
+
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+
+        Some((x0 as u32, y0 as u32, x1 as u32, y1 as u32, [left, top, right, bottom]))
+    }
+
+    /// Copies the texture behind `handle` out of its atlas into the local cache.
+    fn cache_texture(&mut self, handle: TextureHandle) {
+        self.textures.entry(handle).or_insert_with(|| {
+            let atlas = get_texture_atlas(handle.atlas)?;
+            let mut atlas = atlas.lock().expect("Failed to lock TextureAtlas");
+            atlas.ensure_loaded();
+            atlas.get_texture(handle.id).cloned()
+        });
+    }
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn name(&self) -> &'static str {
+        "Software"
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn begin_frame(&mut self, clear_color: Color) {
+        let clear = color_to_rgba(clear_color);
+        for pixel in self.target.pixels_mut() {
+            *pixel = clear;
+        }
+    }
+
+    fn set_camera(&mut self, camera: &Camera2D) {
+        self.camera = *camera;
+    }
+
+    fn set_tint(&mut self, tint: Color) {
+        self.tint = tint;
+    }
+
+    fn submit_quad(&mut self, quad: &Quad) {
+        let Some((x0, y0, x1, y1, _)) = self.pixel_bounds(quad.position, quad.size) else {
+            return;
+        };
+        let color = apply_tint(color_to_rgba(quad.color), self.tint);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                blend(self.target.get_pixel_mut(x, y), color);
+            }
+        }
+    }
+
+    fn submit_sprite(&mut self, sprite: &Sprite) {
+        let Some((x0, y0, x1, y1, [left, top, right, bottom])) = self.pixel_bounds(sprite.position, sprite.size) else {
+            return;
+        };
+        self.cache_texture(sprite.texture);
+        let Some(Some(texture)) = self.textures.get(&sprite.texture) else {
+            return;
+        };
+        let (texture_width, texture_height) = texture.dimensions();
+        let tint = self.tint;
+        for y in y0..y1 {
+            // The first texture row belongs to the top edge of the sprite.
+            let v = ((y as f32 + 0.5 - top) / (bottom - top)).clamp(0.0, 1.0);
+            let ty = ((v * texture_height as f32) as u32).min(texture_height - 1);
+            for x in x0..x1 {
+                let u = ((x as f32 + 0.5 - left) / (right - left)).clamp(0.0, 1.0);
+                let tx = ((u * texture_width as f32) as u32).min(texture_width - 1);
+                blend(self.target.get_pixel_mut(x, y), apply_tint(*texture.get_pixel(tx, ty), tint));
+            }
+        }
+    }
+
+    /// Skips every chunk outside the view instead of testing each tile.
+    fn submit_tile_layer(&mut self, layer: &TileLayerMesh) {
+        for batch in layer.batches() {
+            for chunk in batch.chunks() {
+                let (position, size) = chunk.bounds(layer.tile_size());
+                if self.pixel_bounds(position, size).is_none() {
+                    continue;
+                }
+                for sprite in layer.chunk_sprites(batch, chunk) {
+                    self.submit_sprite(&sprite);
+                }
+            }
+        }
+    }
+
+    fn present(&mut self) {
+        self.frames_presented += 1;
+    }
+}
+
+fn color_to_rgba(color: Color) -> Rgba<u8> {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba([channel(color.red), channel(color.green), channel(color.blue), channel(color.alpha)])
+}
+
+/// Multiplies the color channels of `color` with `tint`, keeping its alpha.
+fn apply_tint(color: Rgba<u8>, tint: Color) -> Rgba<u8> {
+    if tint == COLOR_WHITE {
+        return color;
+    }
+    let channel = |value: u8, factor: f32| (value as f32 * factor.clamp(0.0, 1.0)).round() as u8;
+    Rgba([channel(color[0], tint.red), channel(color[1], tint.green), channel(color[2], tint.blue), color[3]])
+}
+
+/// Blends `source` over `destination` using straight alpha.
+fn blend(destination: &mut Rgba<u8>, source: Rgba<u8>) {
+    let alpha = source[3] as u32;
+    if alpha == 255 {
+        *destination = source;
+        return;
+    }
+    if alpha == 0 {
+        return;
+    }
+    let inverse = 255 - alpha;
+    for channel in 0..3 {
+        destination[channel] = ((source[channel] as u32 * alpha + destination[channel] as u32 * inverse + 127) / 255) as u8;
+    }
+    destination[3] = (alpha + (destination[3] as u32 * inverse + 127) / 255) as u8;
+}
+
+/// Renders `frames` frames of `world` without opening a window and returns
+/// the backend holding the last frame.
+///
+/// Every frame advances exactly one tick, so headless runs are deterministic.
+/// Afterwards the app enters `AppState::Quitting` and runs the `Shutdown` stage.
+pub fn run_headless(game_width: u32, game_height: u32, frames: u32, tick_rate: f64, mut world: World, mut schedule: Schedule) -> SoftwareBackend {
+    let mut backend = SoftwareBackend::new(game_width, game_height);
+    let mut camera = Camera2D::new();
+    let mut timestep = FixedTimestep::new(tick_rate);
+    schedule.set_state(&mut world, AppState::Running);
+
+    println!("{} Rendering {} frame(s) with the {} backend", logger::warn(&SOFTWARE_PREFIX), frames, backend.name());
+    for _ in 0..frames {
+        let step = timestep.step();
+        for _ in 0..timestep.advance(step) {
+            scene::fixed_update(&mut world, step, &mut schedule);
+        }
+        scene::render(&mut backend, &mut camera, &mut world, &mut schedule, timestep.alpha());
+    }
+    scene::shutdown(&mut world, &mut schedule);
+    backend
+}
@@ -1,96 +1,42 @@
-use glium::{Frame, implement_vertex, uniform, Display, Surface, VertexBuffer, IndexBuffer};
-use glium::texture::SrgbTexture2d;
-
-use crate::engine::assets_loader::texture_loader;
-use crate::engine::assets_loader::texture_tilesets::OUTSIDE_ATLAS;
 use crate::engine::console_logger::logger;
-use crate::engine::core::entity::player::Player;
-use crate::engine::core::metadata;
-use crate::engine::core::renderer::core::opengl::OPENGL_DEBUG;
+use crate::engine::core::config::{log_enabled, LogLevel, Subsystem};
+use crate::engine::core::renderer::core::backend::{RenderBackend, Sprite, TextureHandle};
 
 static DEBUG_ONCE: bool = true;
 static mut IS_DEBUGED: bool = false; //always false
 
-const VERTEX_SHADER_SRC: &str = r#"
-    #version 140
-    
-    in vec2 position;
-    out vec2 v_tex_coords;
-            
-    uniform mat4 view;
-    uniform mat4 camera;
-            
-    void main() {
-        gl_Position = view * camera * vec4(position, 0.0, 1.0);
-        v_tex_coords = position;
-    }
-"#;
-
-const FRAGMENT_SHADER_SRC: &str = r#"
-    #version 140
-    
-    in vec2 v_tex_coords;
-    out vec4 color;
-    
-    uniform sampler2D tex;
-    
-    void main() {
-        color = texture(tex, v_tex_coords);
-    }
-"#;
+/// World size of a single background tile, matching the player's sprite size.
+pub const TILE_SIZE: f32 = 0.1;
 
-#[derive(Copy, Clone)]
-pub struct TileVertex {
-    position: [f32; 2],
-}
+/// The texture used for the placeholder background grid.
+pub const BACKGROUND_TEXTURE: TextureHandle = TextureHandle::new("OUTSIDE_ATLAS", 31);
 
 #[allow(dead_code)]
 pub struct BackgroundTiles {
-    display: Display,
     tiles: Vec<Tile>,
-    program: glium::Program,
-    vertex_buffer: VertexBuffer<TileVertex>,
-    index_buffer: IndexBuffer<u16>,
-    pub view_matrix: na::Matrix4<f32>,
+    tile_size: f32,
+    texture: TextureHandle,
 }
 
 #[allow(dead_code)]
 pub struct Tile {
     pub position: [f32; 2],
     sprite_size: f32,
-    texture: SrgbTexture2d,
+    texture: TextureHandle,
 }
 
-implement_vertex!(TileVertex, position);
+impl Default for BackgroundTiles {
+    fn default() -> Self {
+        BackgroundTiles::new()
+    }
+}
 
 impl BackgroundTiles {
-    pub fn new(display: Display) -> Self {
-
-        let view_matrix = set_view_matrix(&display);
-
-        if  is_debugging_enabled(){println!("{}", logger::info_opengl("Creating BackgroundTiles ShaderProgram"))};
-        let program = glium::Program::from_source(&display, VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC, None)
-            .expect(&logger::error_opengl("Failed to create BackgroundTiles ShaderProgram"));
-
-        if  is_debugging_enabled(){println!("{}", logger::info_opengl("Creating BackgroundTiles VertexBuffer"))};
-        let vertex_buffer = glium::VertexBuffer::empty_persistent(&display, 1)
-            .expect(&logger::error_opengl("Failed to create BackgroundTiles VertexBuffer"));
-
-        if  is_debugging_enabled(){println!("{}", logger::info_opengl("Creating BackgroundTiles IndexBuffer"))}; 
-        let index_buffer = glium::IndexBuffer::empty_persistent(
-            &display,
-            glium::index::PrimitiveType::TriangleStrip,
-            1,
-        )
-        .expect(&logger::error_opengl("Failed to create BackgroundTiles IndexBuffer"));
-
+    pub fn new() -> Self {
         BackgroundTiles {
-            display: display.clone(),
             tiles: Vec::new(),
-            vertex_buffer,
-            index_buffer,
-            program,
-            view_matrix,
+            tile_size: TILE_SIZE,
+            texture: BACKGROUND_TEXTURE,
         }
     }
 
@@ -99,102 +45,48 @@ impl BackgroundTiles {
         self.tiles.push(tile);
     }
 
-    pub fn draw(
-        &mut self,
-        frame: &mut Frame,
-        rows: usize,
-        columns: usize,
-        player: &mut Player,
-    ) {
-        let mut vertices: Vec<TileVertex> = Vec::new();
-        let mut indices: Vec<u16> = Vec::new();
+    /// Builds one sprite per cell of a `rows` x `columns` grid centered on the
+    /// world origin, followed by every tile added with `add_tile`.
+    pub fn sprites(&self, rows: usize, columns: usize) -> Vec<Sprite> {
+        let mut sprites = Vec::with_capacity(rows * columns + self.tiles.len());
 
-                // Set initial position to (-1, -1)
-        let mut x = -1.0;
-        let mut y = -1.0;
-
-        // Calculate square size based on the screen dimensions
-        let screen_width = 32.0;
-        let screen_height = 32.0;
-
-        let square_width = screen_width / columns as f32;
-        let square_height = screen_height / rows as f32;
+        let origin_x = -(columns as f32 * self.tile_size) / 2.0;
+        let origin_y = -(rows as f32 * self.tile_size) / 2.0;
 
         for i in 0..rows {
             for j in 0..columns {
-                // Update x and y for each tile
-                x = -1.0 + j as f32 * square_width;
-                y = -1.0 + i as f32 * square_height;
-
-                let square_vertices = vec![
-                    TileVertex { position: [x, y] },
-                    TileVertex { position: [x + square_width, y] },
-                    TileVertex { position: [x + square_width, y + square_height] },
-                    TileVertex { position: [x, y + square_height] },
-                ];
-
-                vertices.extend(square_vertices.iter());
-
-                let base_index = (i * columns + j) * 4;
-                let square_indices: Vec<u16> = vec![
-                    base_index as u16 + 1,
-                    base_index as u16 + 2,
-                    base_index as u16,
-                    base_index as u16 + 3,
-                ];
-
-                indices.extend(square_indices.iter());
+                sprites.push(Sprite {
+                    position: [origin_x + j as f32 * self.tile_size, origin_y + i as f32 * self.tile_size],
+                    size: [self.tile_size, self.tile_size],
+                    texture: self.texture,
+                });
             }
         }
 
-        self.vertex_buffer = glium::VertexBuffer::empty_persistent(&self.display, vertices.len())
-            .expect(&logger::error_opengl("Failed to create BackgroundTiles VertexBuffer"));
-        self.vertex_buffer.write(vertices.as_slice());
+        sprites.extend(self.tiles.iter().map(|tile| Sprite {
+            position: tile.position,
+            size: [tile.sprite_size, tile.sprite_size],
+            texture: tile.texture,
+        }));
 
-        self.index_buffer = glium::IndexBuffer::empty_persistent(
-            &self.display,
-            glium::index::PrimitiveType::TriangleStrip,
-            indices.len(),
-        )
-        .expect(&logger::error_opengl("Failed to create BackgroundTiles IndexBuffer"));
-        self.index_buffer.write(indices.as_slice());
+        sprites
+    }
 
-        if is_debugging_enabled(){ 
-            println!("{}",format!("{} {}", logger::warn_opengl("Background Vertices length:"), vertices.len()));
-            println!("{}",format!("{} {}", logger::warn_opengl("Background Indices length"), indices.len()));
+    pub fn draw(&self, backend: &mut dyn RenderBackend, rows: usize, columns: usize) {
+        let sprites = self.sprites(rows, columns);
 
-            println!("{}",format!("{} {}", logger::warn_opengl("Background VertexBuffer size:"), self.vertex_buffer.get_size()));
-            println!("{}",format!("{} {}", logger::warn_opengl("Background IndexBuffer size:"), self.index_buffer.get_size()));
+        if is_debugging_enabled() {
+            println!("{} {}", logger::warn_opengl("Background Sprites length:"), sprites.len());
         }
-        self.vertex_buffer.write(vertices.as_slice());
-        
-        let texture = OUTSIDE_ATLAS.lock().expect("Failed to Wrepp texture").load_texture_from_map(31, self.display.clone());
-
-        let camera_matrix = player.update_camera();
-
-        let uniforms = uniform! {
-            view: *self.view_matrix.as_ref(),
-            camera: *camera_matrix.as_ref(),
-            tex: texture,
-        };
-
-        frame.clear_color(0.0, 0.0, 0.0, 0.0);
-        frame
-            .draw(
-                &self.vertex_buffer,
-                &self.index_buffer,
-                &self.program,
-                &uniforms,
-                &Default::default(),
-            )
-            .expect(&logger::error_opengl("Failed to draw BackgroundTiles to Frame"));
+
+        backend.submit_sprites(&sprites);
         if DEBUG_ONCE {unsafe { IS_DEBUGED = true };}
     }
 }
 
 impl Tile {
     #[allow(dead_code)]
-    pub fn new(position: [f32; 2], sprite_size: f32, texture: SrgbTexture2d) -> Self {
+    pub fn new(position: [f32; 2], sprite_size: f32, texture: TextureHandle) -> Self {
         Tile {
             position,
             sprite_size,
@@ -203,34 +95,11 @@ impl Tile {
     }
 }
 
-pub fn set_view_matrix(display: &Display) -> na::Matrix4<f32> {
-    let (window_width, window_height) = display.get_framebuffer_dimensions();
-    let aspect_ratio = window_width as f32 / window_height as f32;
-    let view_matrix = na::Matrix4::new(
-        1.0, 0.0, 0.0, 0.0,
-        0.0, aspect_ratio, 0.0, 0.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0,
-    );
-    view_matrix
-}
-
 pub fn is_debugging_enabled() -> bool {
-    if !DEBUG_ONCE {
-        if metadata::DEBUG || unsafe { OPENGL_DEBUG } {
-            return true;
-        }else{
-            return false;
-        }
-    }else{
-        if !unsafe { IS_DEBUGED }{
-            if metadata::DEBUG || unsafe { OPENGL_DEBUG } {
-                return true;
-            }else{
-                return false;
-            }
-        }else{
-            return false;
-        }
+    let enabled = log_enabled(Subsystem::OpenGL, LogLevel::Debug);
+    if DEBUG_ONCE {
+        enabled && !unsafe { IS_DEBUGED }
+    } else {
+        enabled
     }
-}
\ No newline at end of file
+}
@@ -1,2 +1,7 @@
 pub mod background_tiles;
-pub mod testing;
\ No newline at end of file
+pub mod testing;
+pub mod tile_bench;
+pub mod tile_map;
+pub mod tile_mesh;
+#[cfg(test)]
+pub mod snapshot;
\ No newline at end of file
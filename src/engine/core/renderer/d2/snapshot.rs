@@ -0,0 +1,232 @@
+//! Golden-image snapshot testing for rendered scenes.
+//!
+//! A scene is rendered with the headless `SoftwareBackend` and compared pixel
+//! by pixel against a PNG stored in `tests/golden/`. Channels may differ by up
+//! to a per-pixel tolerance. On failure the rendered frame and a diff image
+//! (mismatching pixels in red over a dimmed copy of the golden image) are
+//! written to `target/snapshots/`.
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to (re)create the golden images
+//! after an intended rendering change.
+
+use std::fs;
+use std::path::PathBuf;
+
+use image::{Rgba, RgbaImage};
+
+use crate::engine::core::metadata::COLOR_BLACK;
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::renderer::core::backend::RenderBackend;
+use crate::engine::core::renderer::core::software::SoftwareBackend;
+use crate::engine::core::renderer::d2::tile_map::TileMap;
+use crate::engine::core::world::map::LayerKind;
+
+pub const GOLDEN_FOLDER: &str = "./tests/golden";
+pub const DIFF_FOLDER: &str = "./target/snapshots";
+
+/// The result of comparing two equally sized images.
+pub struct ImageDiff {
+    /// Number of pixels with at least one channel outside the tolerance.
+    pub mismatched_pixels: u64,
+    /// The largest difference found in any channel of any pixel.
+    pub max_channel_delta: u8,
+    /// Visualisation of the mismatching pixels.
+    pub diff_image: RgbaImage,
+}
+
+impl ImageDiff {
+    pub fn matches(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares `actual` against `expected`, allowing every channel of every pixel
+/// to differ by at most `tolerance`.
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Result<ImageDiff, String> {
+    if actual.dimensions() != expected.dimensions() {
+        return Err(format!(
+            "image size mismatch: rendered {}x{}, expected {}x{}",
+            actual.width(), actual.height(), expected.width(), expected.height()
+        ));
+    }
+
+    let mut diff_image = RgbaImage::new(expected.width(), expected.height());
+    let mut mismatched_pixels = 0;
+    let mut max_channel_delta = 0;
+
+    for ((actual_pixel, expected_pixel), diff_pixel) in actual.pixels().zip(expected.pixels()).zip(diff_image.pixels_mut()) {
+        let delta = actual_pixel.0.iter()
+            .zip(expected_pixel.0.iter())
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap_or(0);
+        max_channel_delta = max_channel_delta.max(delta);
+
+        *diff_pixel = if delta > tolerance {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = expected_pixel.0;
+            let grey = ((r as u32 + g as u32 + b as u32) / 3 / 3) as u8;
+            Rgba([grey, grey, grey, 255])
+        };
+    }
+
+    Ok(ImageDiff { mismatched_pixels, max_channel_delta, diff_image })
+}
+
+/// Renders every layer of `map` with the camera centered on `camera_position`.
+pub fn render_map(map: &str, camera_position: [f32; 2], width: u32, height: u32) -> RgbaImage {
+    let tile_map = TileMap::load(map)
+        .unwrap_or_else(|e| panic!("Failed to read map {}", e));
+
+    let mut camera = Camera2D::new();
+    camera.follow(camera_position);
+
+    let mut backend = SoftwareBackend::new(width, height);
+    backend.begin_frame(COLOR_BLACK);
+    backend.set_camera(&camera);
+    for kind in [LayerKind::Ground, LayerKind::Decoration, LayerKind::Overhead] {
+        tile_map.draw(&mut backend, kind);
+    }
+    backend.present();
+    backend.frame().clone()
+}
+
+/// Compares `actual` against the golden image `tests/golden/<name>.png` and
+/// panics with a description of the difference if they do not match.
+pub fn assert_snapshot(name: &str, actual: &RgbaImage, tolerance: u8) {
+    let golden_path = PathBuf::from(GOLDEN_FOLDER).join(format!("{}.png", name));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(GOLDEN_FOLDER).expect("Failed to create golden image folder");
+        actual.save(&golden_path).expect("Failed to write golden image");
+        return;
+    }
+
+    let expected = match image::open(&golden_path) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => panic!(
+            "Missing golden image {} ({}). Run the tests with UPDATE_SNAPSHOTS=1 to create it.",
+            golden_path.display(), e
+        ),
+    };
+
+    let failure = match compare_images(actual, &expected, tolerance) {
+        Ok(diff) if diff.matches() => return,
+        Ok(diff) => {
+            let diff_path = PathBuf::from(DIFF_FOLDER).join(format!("{}.diff.png", name));
+            fs::create_dir_all(DIFF_FOLDER).expect("Failed to create snapshot diff folder");
+            diff.diff_image.save(&diff_path).expect("Failed to write diff image");
+            format!(
+                "{} pixel(s) differ by more than {} (max delta {}), diff written to {}",
+                diff.mismatched_pixels, tolerance, diff.max_channel_delta, diff_path.display()
+            )
+        }
+        Err(e) => e,
+    };
+
+    let actual_path = PathBuf::from(DIFF_FOLDER).join(format!("{}.actual.png", name));
+    fs::create_dir_all(DIFF_FOLDER).expect("Failed to create snapshot diff folder");
+    actual.save(&actual_path).expect("Failed to write rendered image");
+    panic!("Snapshot {} does not match {}: {}", name, golden_path.display(), failure);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::assets_loader::texture_tilesets::OUTSIDE_ATLAS;
+    use crate::engine::core::metadata::COLOR_WHITE;
+    use crate::engine::core::renderer::core::backend::{Quad, Sprite, TextureHandle};
+
+    const TOLERANCE: u8 = 2;
+
+    #[test]
+    fn compare_images_accepts_differences_within_tolerance() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let actual = RgbaImage::from_pixel(4, 4, Rgba([102, 98, 100, 255]));
+
+        let diff = compare_images(&actual, &expected, 2).unwrap();
+        assert!(diff.matches());
+        assert_eq!(diff.max_channel_delta, 2);
+    }
+
+    #[test]
+    fn compare_images_marks_mismatching_pixels() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(1, 2, Rgba([0, 50, 0, 255]));
+
+        let diff = compare_images(&actual, &expected, 2).unwrap();
+        assert_eq!(diff.mismatched_pixels, 1);
+        assert_eq!(diff.max_channel_delta, 50);
+        assert_eq!(*diff.diff_image.get_pixel(1, 2), Rgba([255, 0, 0, 255]));
+        assert_eq!(*diff.diff_image.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn compare_images_rejects_different_sizes() {
+        let expected = RgbaImage::new(4, 4);
+        let actual = RgbaImage::new(4, 3);
+        assert!(compare_images(&actual, &expected, 255).is_err());
+    }
+
+    #[test]
+    fn sprite_texture_is_not_flipped_or_shifted() {
+        // A 32x32 sprite covering the whole 32x32 target has to reproduce the
+        // atlas texture exactly, first texture row at the top of the frame.
+        let texture = TextureHandle::new("OUTSIDE_ATLAS", 31);
+        let expected = {
+            let mut atlas = OUTSIDE_ATLAS.lock().unwrap();
+            atlas.ensure_loaded();
+            atlas.get_texture(texture.id).unwrap().clone()
+        };
+
+        let mut backend = SoftwareBackend::new(32, 32);
+        backend.begin_frame(COLOR_BLACK);
+        backend.set_camera(&Camera2D::new());
+        backend.submit_sprite(&Sprite { position: [-1.0, -1.0], size: [2.0, 2.0], texture });
+        backend.present();
+
+        let mut composited = RgbaImage::from_pixel(32, 32, Rgba([0, 0, 0, 255]));
+        for (x, y, pixel) in expected.enumerate_pixels() {
+            let mut background = *composited.get_pixel(x, y);
+            let alpha = pixel[3] as u32;
+            for channel in 0..3 {
+                background[channel] = ((pixel[channel] as u32 * alpha + background[channel] as u32 * (255 - alpha) + 127) / 255) as u8;
+            }
+            composited.put_pixel(x, y, background);
+        }
+
+        let diff = compare_images(backend.frame(), &composited, 1).unwrap();
+        assert!(diff.matches(), "{} pixel(s) differ", diff.mismatched_pixels);
+    }
+
+    #[test]
+    fn quad_covers_expected_pixels() {
+        // With an 8x8 target one world unit covers four pixels, so a quad from
+        // (0, 0) to (0.5, 0.5) fills the 2x2 pixels right above the center.
+        let mut backend = SoftwareBackend::new(8, 8);
+        backend.begin_frame(COLOR_BLACK);
+        backend.set_camera(&Camera2D::new());
+        backend.submit_quad(&Quad { position: [0.0, 0.0], size: [0.5, 0.5], color: COLOR_WHITE });
+
+        let white = Rgba([255, 255, 255, 255]);
+        for (x, y, pixel) in backend.frame().enumerate_pixels() {
+            let inside = (4..6).contains(&x) && (2..4).contains(&y);
+            assert_eq!(*pixel == white, inside, "pixel ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn twinleaf_town_centered() {
+        let frame = render_map("twinleaf_town", [0.0, 0.0], 320, 240);
+        assert_snapshot("twinleaf_town_centered", &frame, TOLERANCE);
+    }
+
+    #[test]
+    fn twinleaf_town_camera_offset() {
+        let frame = render_map("twinleaf_town", [0.45, -0.3], 320, 240);
+        assert_snapshot("twinleaf_town_camera_offset", &frame, TOLERANCE);
+    }
+}
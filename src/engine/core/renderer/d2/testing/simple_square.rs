@@ -1,6 +1,5 @@
 use glium::{Frame, implement_vertex, Surface, uniform};
 
-use crate::engine::core::entity::player::Player;
 
 
 #[derive(Copy, Clone)]
@@ -22,7 +21,7 @@ pub fn draw_square_v2(display: glium::Display, frame: &mut Frame) {
     let index_buffer = glium::IndexBuffer::new(
         &display,
         glium::index::PrimitiveType::TriangleStrip,
-        &[1 as u16, 2, 0, 3],
+        &[1_u16, 2, 0, 3],
     ).unwrap();
 
     let vertex_shader_src = r#"
@@ -92,7 +91,7 @@ pub fn draw_square_grid(
             let y = i as f32 * square_size;
 
             // Define vertices for the current square
-            let square_vertices = vec![
+            let square_vertices = [
                 MyVertex { position: [x, y] },
                 MyVertex { position: [x + square_size, y] },
                 MyVertex { position: [x + square_size, y + square_size] },
@@ -204,7 +203,7 @@ pub fn draw_square_grid_with_texture(
             let y = i as f32 * square_size;
 
             // Define vertices for the current square without explicit texture coordinates
-            let square_vertices = vec![
+            let square_vertices = [
                 MyVertex { position: [x, y] },
                 MyVertex { position: [x + square_size, y] },
                 MyVertex { position: [x + square_size, y + square_size] },
@@ -294,7 +293,7 @@ pub fn draw_square_grid_with_texture_and_player(
     columns: usize,
     square_size: f32,
     texture: &glium::texture::SrgbTexture2d,
-    player: &mut Player // Add a texture parameter
+    player_position: [f32; 2]
 ) {
     let mut vertices: Vec<MyVertex> = Vec::new();
     let mut indices: Vec<u16> = Vec::new();
@@ -316,7 +315,11 @@ pub fn draw_square_grid_with_texture_and_player(
         0.0, 0.0, 0.0, 1.0,
     );
 
-    let camera_matrix = player.update_camera();
+    let camera_matrix = na::Matrix4::new_translation(&na::Vector3::new(
+        -player_position[0],
+        -player_position[1],
+        0.0,
+    ));
 
     // Generate vertices and indices for the grid of squares
     for i in 0..rows {
@@ -325,7 +328,7 @@ pub fn draw_square_grid_with_texture_and_player(
             let y = i as f32 * square_size;
 
             // Define vertices for the current square without explicit texture coordinates
-            let square_vertices = vec![
+            let square_vertices = [
                 MyVertex { position: [x, y] },
                 MyVertex { position: [x + square_size, y] },
                 MyVertex { position: [x + square_size, y + square_size] },
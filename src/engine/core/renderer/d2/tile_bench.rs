@@ -0,0 +1,170 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::engine::core::metadata::COLOR_BLACK;
+use crate::engine::core::renderer::camera::camera2d::Camera2D;
+use crate::engine::core::renderer::core::backend::{RenderBackend, Sprite, TextureHandle};
+use crate::engine::core::renderer::core::opengl::OpenGLBackend;
+use crate::engine::core::renderer::core::software::SoftwareBackend;
+use crate::engine::core::world::map::{LayerKind, Map, MapLayer, MapTile, TileProperties};
+
+use super::background_tiles::TILE_SIZE;
+use super::tile_mesh::TileLayerMesh;
+
+/// Map edge length of `bench-tiles` without `--size`.
+pub const DEFAULT_BENCH_SIZE: u32 = 256;
+
+/// Frames measured by `bench-tiles` without `--frames`.
+pub const DEFAULT_BENCH_FRAMES: u32 = 60;
+
+/// Size of the frames rendered by the benchmark.
+const BENCH_RESOLUTION: (u32, u32) = (800, 600);
+
+/// The backend `bench-tiles` draws with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BenchBackend {
+    /// The CPU rasterizer, runs without a window or GPU.
+    #[default]
+    Software,
+    /// A hidden OpenGL window, waiting for the GPU at the end of every frame.
+    /// Needs a display like running the game does.
+    OpenGL,
+}
+
+impl BenchBackend {
+    /// Names accepted by `bench-tiles --backend`.
+    pub const NAMES: [&'static str; 2] = ["software", "opengl"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "software" => Some(BenchBackend::Software),
+            "opengl" => Some(BenchBackend::OpenGL),
+            _ => None,
+        }
+    }
+}
+
+/// Frame costs of a square map drawn with one backend.
+pub struct TileBenchmark {
+    /// `RenderBackend::name` of the backend measured.
+    pub backend: &'static str,
+    pub size: u32,
+    pub frames: u32,
+    pub tiles: usize,
+    pub chunks: usize,
+    /// Draw calls a GPU backend needs for the layer, one per atlas.
+    pub draw_calls: usize,
+    /// Building the layer mesh from the map once.
+    pub build: Duration,
+    /// Changing one tile, which rebuilds its chunk.
+    pub set_tile: Duration,
+    /// A frame drawing the static mesh.
+    pub mesh_frame: Duration,
+    /// A frame changing one tile and drawing the mesh, which uploads the
+    /// changed chunk on GPU backends.
+    pub changed_tile_frame: Duration,
+    /// A frame building and submitting a sprite per tile, like the
+    /// background was drawn before tile meshes existed.
+    pub sprite_frame: Duration,
+}
+
+/// Measures the frame cost of a `size` x `size` map of `OUTSIDE_ATLAS` tiles
+/// centered on the camera, averaged over `frames` frames.
+pub fn run_tile_benchmark(size: u32, frames: u32, backend: BenchBackend) -> TileBenchmark {
+    match backend {
+        BenchBackend::Software => {
+            let mut backend = SoftwareBackend::new(BENCH_RESOLUTION.0, BENCH_RESOLUTION.1);
+            measure(&mut backend, size, frames, |_| {})
+        }
+        BenchBackend::OpenGL => {
+            let (_event_loop, mut backend) = OpenGLBackend::hidden(BENCH_RESOLUTION.0, BENCH_RESOLUTION.1);
+            measure(&mut backend, size, frames, |backend| backend.finish_gpu())
+        }
+    }
+}
+
+/// Runs the benchmark on `backend`, calling `finish` after every frame to wait
+/// for work the backend has not done yet.
+fn measure<B: RenderBackend>(backend: &mut B, size: u32, frames: u32, finish: impl Fn(&mut B)) -> TileBenchmark {
+    let map = bench_map(size);
+    let layer = &map.layers[0];
+    let camera = Camera2D::new();
+    let changed_tile = |frame: u32| ([frame as i32 % size as i32 - size as i32 / 2, 0], TextureHandle::new("OUTSIDE_ATLAS", frame % 16));
+
+    let start = Instant::now();
+    let mut mesh = TileLayerMesh::from_map_layer(&map, layer, TILE_SIZE);
+    let build = start.elapsed();
+
+    let start = Instant::now();
+    for frame in 0..frames {
+        let (position, texture) = changed_tile(frame);
+        mesh.set_tile(position, Some(texture));
+    }
+    let set_tile = start.elapsed() / frames.max(1);
+
+    let mesh_frame = time_frames(backend, &camera, frames, &finish, |backend, _| backend.submit_tile_layer(&mesh));
+    let changed_tile_frame = time_frames(backend, &camera, frames, &finish, |backend, frame| {
+        let (position, texture) = changed_tile(frame + 1);
+        mesh.set_tile(position, Some(texture));
+        backend.submit_tile_layer(&mesh);
+    });
+    let sprite_frame = time_frames(backend, &camera, frames, &finish, |backend, _| {
+        let sprites: Vec<Sprite> = layer.tiles.iter().map(|tile| Sprite {
+            position: [tile.position[0] as f32 * TILE_SIZE, tile.position[1] as f32 * TILE_SIZE],
+            size: [TILE_SIZE, TILE_SIZE],
+            texture: TextureHandle::new("OUTSIDE_ATLAS", tile.texture),
+        }).collect();
+        backend.submit_sprites(&sprites);
+    });
+
+    TileBenchmark {
+        backend: backend.name(),
+        size,
+        frames,
+        tiles: mesh.tile_count(),
+        chunks: mesh.batches().iter().map(|batch| batch.chunks().count()).sum(),
+        draw_calls: mesh.batches().iter().filter(|batch| batch.vertex_count() > 0).count(),
+        build,
+        set_tile,
+        mesh_frame,
+        changed_tile_frame,
+        sprite_frame,
+    }
+}
+
+fn time_frames<B: RenderBackend>(backend: &mut B, camera: &Camera2D, frames: u32, finish: impl Fn(&mut B), mut draw: impl FnMut(&mut B, u32)) -> Duration {
+    let start = Instant::now();
+    for frame in 0..frames {
+        backend.begin_frame(COLOR_BLACK);
+        backend.set_camera(camera);
+        draw(backend, frame);
+        backend.present();
+        finish(backend);
+    }
+    start.elapsed() / frames.max(1)
+}
+
+fn bench_map(size: u32) -> Map {
+    let half = size as i32 / 2;
+    let mut map = Map::new("bench", [size, size], [-half, -half], 32, vec!["OUTSIDE_ATLAS".to_owned()]);
+    let tiles = (0..size as i32 * size as i32).map(|index| MapTile {
+        position: [index % size as i32 - half, index / size as i32 - half],
+        tileset: None,
+        texture: (index % 16) as u32,
+        properties: TileProperties::default(),
+    }).collect();
+    map.layers.push(MapLayer { name: "ground".to_owned(), kind: LayerKind::Ground, tiles });
+    map
+}
+
+impl fmt::Display for TileBenchmark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        writeln!(f, "{}x{} map: {} tiles in {} chunks, {} draw call(s) per frame on the GPU", self.size, self.size, self.tiles, self.chunks, self.draw_calls)?;
+        writeln!(f, "  build mesh once      {:>9.3} ms", ms(self.build))?;
+        writeln!(f, "  set one tile         {:>9.3} ms", ms(self.set_tile))?;
+        writeln!(f, "  frame, tile mesh     {:>9.3} ms ({} backend, avg of {} frames)", ms(self.mesh_frame), self.backend, self.frames)?;
+        writeln!(f, "  frame, changed tile  {:>9.3} ms ({} backend, avg of {} frames)", ms(self.changed_tile_frame), self.backend, self.frames)?;
+        write!(f, "  frame, sprite a tile {:>9.3} ms ({} backend, avg of {} frames)", ms(self.sprite_frame), self.backend, self.frames)
+    }
+}
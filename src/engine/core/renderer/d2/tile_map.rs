@@ -0,0 +1,166 @@
+use crate::engine::core::renderer::core::backend::{RenderBackend, Sprite, TextureHandle};
+use crate::engine::core::world::map::{LayerKind, Map, MapError};
+
+use super::background_tiles::TILE_SIZE;
+use super::tile_mesh::TileLayerMesh;
+
+/// One layer of a `TileMap`.
+pub struct TileLayer {
+    pub name: String,
+    pub kind: LayerKind,
+    pub mesh: TileLayerMesh,
+}
+
+impl TileLayer {
+    pub fn sprites(&self) -> Vec<Sprite> {
+        self.mesh.sprites()
+    }
+}
+
+/// The drawable layers of a `Map`.
+///
+/// A frame draws the `Ground` layers first, then the entities y-sorted
+/// together with the `Decoration` tiles, then the `Overhead` layers, so
+/// entities walk behind trees and under roofs:
+///
+/// ```rust
+/// let tile_map = TileMap::load("twinleaf_town")?;
+/// tile_map.draw(backend, LayerKind::Ground);
+/// systems::draw_sprites_system(world, backend, alpha, &tile_map.scenery());
+/// tile_map.draw(backend, LayerKind::Overhead);
+/// ```
+#[derive(Default)]
+pub struct TileMap {
+    layers: Vec<TileLayer>,
+}
+
+#[allow(dead_code)]
+impl TileMap {
+    /// Builds the layers of `map`, every tile a `tile_size` square in world coordinates.
+    pub fn from_map(map: &Map, tile_size: f32) -> Self {
+        let layers = map.layers.iter().map(|layer| TileLayer {
+            name: layer.name.clone(),
+            kind: layer.kind,
+            mesh: TileLayerMesh::from_map_layer(map, layer, tile_size),
+        }).collect();
+        TileMap { layers }
+    }
+
+    /// Loads the map directory `name` below `assets/maps/`, see `Map::load_named`.
+    pub fn load(name: &str) -> Result<Self, MapError> {
+        Ok(Self::from_map(&Map::load_named(name)?, TILE_SIZE))
+    }
+
+    pub fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Estimated heap memory of every layer in bytes, see `TileLayerMesh::memory_bytes`.
+    pub fn memory_bytes(&self) -> usize {
+        self.layers.iter().map(|layer| layer.mesh.memory_bytes()).sum()
+    }
+
+    /// Replaces or removes the tile at the grid position of layer `name`, see
+    /// `TileLayerMesh::set_tile`. Returns `false` if the map has no such layer.
+    pub fn set_tile(&mut self, name: &str, position: [i32; 2], texture: Option<TextureHandle>) -> bool {
+        match self.layers.iter_mut().find(|layer| layer.name == name) {
+            Some(layer) => {
+                layer.mesh.set_tile(position, texture);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draws every layer of `kind` in file order, each with `submit_tile_layer`.
+    pub fn draw(&self, backend: &mut dyn RenderBackend, kind: LayerKind) {
+        for layer in self.layers.iter().filter(|layer| layer.kind == kind) {
+            backend.submit_tile_layer(&layer.mesh);
+        }
+    }
+
+    /// The tiles of the `Decoration` layers, drawn y-sorted with the entities.
+    pub fn scenery(&self) -> Vec<Sprite> {
+        self.layers.iter()
+            .filter(|layer| layer.kind == LayerKind::Decoration)
+            .flat_map(TileLayer::sprites)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::ecs::components::{Sprite as EntitySprite, Transform};
+    use crate::engine::core::ecs::systems;
+    use crate::engine::core::ecs::world::World;
+    use crate::engine::core::metadata::{COLOR_BLACK, COLOR_RED, COLOR_WHITE};
+    use crate::engine::core::renderer::camera::camera2d::Camera2D;
+    use crate::engine::core::renderer::core::software::SoftwareBackend;
+
+    fn map(kind: LayerKind) -> Map {
+        let source = format!(r#"(
+            version: 1,
+            header: (name: "layers", size: (3, 3), origin: (-1, -1), tile_size: 32, tilesets: ["OUTSIDE_ATLAS"]),
+            layers: [
+                (name: "ground", tiles: [(position: (0, 0), texture: 31), (position: (0, 1), texture: 31)]),
+                (name: "top", kind: {:?}, tiles: [(position: (0, 0), texture: 31)]),
+            ],
+        )"#, kind);
+        Map::parse(&source, "layers.ron").unwrap()
+    }
+
+    /// Renders `tile_map` around a red entity standing on tile (0, 0) and
+    /// returns whether the entity is visible at the center of the tile.
+    fn entity_visible(tile_map: &TileMap, entity_y: f32) -> bool {
+        let mut world = World::new();
+        world.spawn()
+            .with(Transform::at(TILE_SIZE / 2.0, entity_y))
+            .with(EntitySprite::colored(TILE_SIZE, COLOR_RED));
+
+        // One tile covers 16x16 pixels, the center pixel shows the middle of tile (0, 0).
+        let mut backend = SoftwareBackend::new(320, 320);
+        let mut camera = Camera2D::new();
+        camera.follow([TILE_SIZE / 2.0, TILE_SIZE / 2.0]);
+        backend.begin_frame(COLOR_BLACK);
+        backend.set_camera(&camera);
+        backend.set_tint(COLOR_WHITE);
+        tile_map.draw(&mut backend, LayerKind::Ground);
+        systems::draw_sprites_system(&world, &mut backend, 1.0, &tile_map.scenery());
+        tile_map.draw(&mut backend, LayerKind::Overhead);
+        backend.present();
+
+        backend.frame().get_pixel(160, 160).0 == [255, 0, 0, 255]
+    }
+
+    #[test]
+    fn sorts_layers_by_kind() {
+        let tile_map = TileMap::from_map(&map(LayerKind::Decoration), TILE_SIZE);
+        assert_eq!(tile_map.layers().len(), 2);
+        assert_eq!(tile_map.layer("top").unwrap().kind, LayerKind::Decoration);
+        assert_eq!(tile_map.scenery().len(), 1);
+        assert!(TileMap::from_map(&map(LayerKind::Overhead), TILE_SIZE).scenery().is_empty());
+    }
+
+    #[test]
+    fn entities_render_between_layers() {
+        let ground_only = TileMap::from_map(&map(LayerKind::Ground), TILE_SIZE);
+        assert!(entity_visible(&ground_only, TILE_SIZE / 2.0));
+
+        let overhead = TileMap::from_map(&map(LayerKind::Overhead), TILE_SIZE);
+        assert!(!entity_visible(&overhead, TILE_SIZE / 2.0), "overhead tiles cover entities");
+    }
+
+    #[test]
+    fn entities_are_y_sorted_with_decorations() {
+        let decoration = TileMap::from_map(&map(LayerKind::Decoration), TILE_SIZE);
+        // Standing below the decoration tile the entity is in front of it...
+        assert!(entity_visible(&decoration, TILE_SIZE * 0.4));
+        // ...and standing above it the entity walks behind it.
+        assert!(!entity_visible(&decoration, TILE_SIZE * 0.6));
+    }
+}
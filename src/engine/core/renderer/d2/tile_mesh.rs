@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::engine::assets_loader::texture_tilesets::{get_atlas_name, get_texture_atlas};
+use crate::engine::console_logger::logger;
+use crate::engine::core::renderer::core::backend::{Sprite, TextureHandle};
+use crate::engine::core::world::map::{Map, MapLayer};
+
+/// Edge length of a chunk in tiles.
+pub const CHUNK_SIZE: i32 = 16;
+
+static NEXT_MESH_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A corner of a tile quad in world coordinates with its texture coordinates
+/// inside the whole atlas, see `TextureAtlas::uv_rect`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileVertex {
+    pub position: [f32; 2],
+    pub tex_coords: [f32; 2],
+}
+
+/// The tiles of one `CHUNK_SIZE` x `CHUNK_SIZE` square of a layer that use
+/// the same atlas, with their quads already built.
+pub struct TileChunk {
+    /// Position of the chunk in chunks, tile (0, 0) lies in chunk (0, 0).
+    pub coords: [i32; 2],
+    tiles: BTreeMap<[i32; 2], u32>,
+    vertices: Vec<TileVertex>,
+    revision: u64,
+}
+
+#[allow(dead_code)]
+impl TileChunk {
+    fn new(coords: [i32; 2]) -> Self {
+        TileChunk { coords, tiles: BTreeMap::new(), vertices: Vec::new(), revision: 0 }
+    }
+
+    /// Four vertices per tile, in the order `bottom-left, bottom-right, top-right, top-left`.
+    pub fn vertices(&self) -> &[TileVertex] {
+        &self.vertices
+    }
+
+    /// Grows every time the chunk is rebuilt, so backends can tell which chunks to upload again.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// The world space bottom-left corner and size of the chunk.
+    pub fn bounds(&self, tile_size: f32) -> ([f32; 2], [f32; 2]) {
+        let extent = CHUNK_SIZE as f32 * tile_size;
+        ([self.coords[0] as f32 * extent, self.coords[1] as f32 * extent], [extent, extent])
+    }
+
+    /// Rebuilds the quads of every tile from the atlas UVs. Tiles whose id the
+    /// atlas does not have are left out.
+    fn rebuild(&mut self, atlas: &'static str, tile_size: f32) {
+        self.vertices.clear();
+        self.revision += 1;
+        let Some(atlas) = get_texture_atlas(atlas) else {
+            return;
+        };
+        let atlas = atlas.lock().expect("Failed to lock TextureAtlas");
+        for (&[x, y], &id) in &self.tiles {
+            let Some([left, bottom, right, top]) = atlas.uv_rect(id) else {
+                continue;
+            };
+            let (x, y) = (x as f32 * tile_size, y as f32 * tile_size);
+            self.vertices.extend_from_slice(&[
+                TileVertex { position: [x, y], tex_coords: [left, bottom] },
+                TileVertex { position: [x + tile_size, y], tex_coords: [right, bottom] },
+                TileVertex { position: [x + tile_size, y + tile_size], tex_coords: [right, top] },
+                TileVertex { position: [x, y + tile_size], tex_coords: [left, top] },
+            ]);
+        }
+    }
+}
+
+/// The chunks of a layer drawing from one atlas, drawn with a single call.
+pub struct AtlasBatch {
+    pub atlas: &'static str,
+    chunks: BTreeMap<[i32; 2], TileChunk>,
+}
+
+impl AtlasBatch {
+    pub fn chunks(&self) -> impl Iterator<Item = &TileChunk> {
+        self.chunks.values()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.chunks().map(|chunk| chunk.vertices.len()).sum()
+    }
+}
+
+/// The static geometry of a tile layer.
+///
+/// Tiles are grouped by atlas and split into chunks. The quads of a chunk are
+/// built once from the atlas UVs and only rebuilt when one of its tiles
+/// changes, see `set_tile`. Backends draw a layer with one call per atlas,
+/// usually a single one, see `RenderBackend::submit_tile_layer`.
+pub struct TileLayerMesh {
+    id: u64,
+    tile_size: f32,
+    batches: Vec<AtlasBatch>,
+}
+
+#[allow(dead_code)]
+impl TileLayerMesh {
+    pub fn new(tile_size: f32) -> Self {
+        TileLayerMesh {
+            id: NEXT_MESH_ID.fetch_add(1, Ordering::Relaxed),
+            tile_size,
+            batches: Vec::new(),
+        }
+    }
+
+    /// Builds the mesh of `layer` of `map`. Tiles of a tileset that is no
+    /// texture atlas are skipped.
+    pub fn from_map_layer(map: &Map, layer: &MapLayer, tile_size: f32) -> Self {
+        let mut mesh = TileLayerMesh::new(tile_size);
+        for tile in &layer.tiles {
+            let tileset = map.tileset_of(tile);
+            let Some(atlas) = get_atlas_name(tileset) else {
+                println!("{}", logger::warn_opengl(&format!("Skipping tile with unknown tileset {}", tileset)));
+                continue;
+            };
+            mesh.insert(tile.position, TextureHandle::new(atlas, tile.texture));
+        }
+        for batch in &mut mesh.batches {
+            for chunk in batch.chunks.values_mut() {
+                chunk.rebuild(batch.atlas, tile_size);
+            }
+        }
+        mesh
+    }
+
+    /// Identifies the mesh for backends caching its buffers.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn tile_size(&self) -> f32 {
+        self.tile_size
+    }
+
+    pub fn batches(&self) -> &[AtlasBatch] {
+        &self.batches
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.batches.iter().flat_map(AtlasBatch::chunks).map(TileChunk::tile_count).sum()
+    }
+
+    /// Estimated heap memory of the tiles and vertices in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        let tile_bytes = std::mem::size_of::<([i32; 2], u32)>();
+        self.batches.iter().flat_map(AtlasBatch::chunks).map(|chunk| {
+            std::mem::size_of::<TileChunk>() + chunk.tiles.len() * tile_bytes + chunk.vertices.capacity() * std::mem::size_of::<TileVertex>()
+        }).sum()
+    }
+
+    /// The texture of the tile at the grid position, if there is one.
+    pub fn tile(&self, position: [i32; 2]) -> Option<TextureHandle> {
+        let coords = chunk_coords(position);
+        self.batches.iter().find_map(|batch| {
+            let id = batch.chunks.get(&coords)?.tiles.get(&position)?;
+            Some(TextureHandle::new(batch.atlas, *id))
+        })
+    }
+
+    /// Replaces or removes the tile at the grid position and rebuilds the
+    /// chunks it left and entered, leaving every other chunk untouched.
+    pub fn set_tile(&mut self, position: [i32; 2], texture: Option<TextureHandle>) {
+        let coords = chunk_coords(position);
+        for batch in &mut self.batches {
+            if let Some(chunk) = batch.chunks.get_mut(&coords) {
+                if chunk.tiles.remove(&position).is_some() {
+                    chunk.rebuild(batch.atlas, self.tile_size);
+                }
+            }
+        }
+        if let Some(texture) = texture {
+            let batch = self.insert(position, texture);
+            let chunk = self.batches[batch].chunks.get_mut(&coords).expect("chunk was just inserted");
+            chunk.rebuild(texture.atlas, self.tile_size);
+        }
+        for batch in &mut self.batches {
+            batch.chunks.retain(|_, chunk| !chunk.tiles.is_empty());
+        }
+        self.batches.retain(|batch| !batch.chunks.is_empty());
+    }
+
+    /// Adds the tile without building its chunk and returns the index of its batch.
+    fn insert(&mut self, position: [i32; 2], texture: TextureHandle) -> usize {
+        let batch = match self.batches.iter().position(|batch| batch.atlas == texture.atlas) {
+            Some(batch) => batch,
+            None => {
+                self.batches.push(AtlasBatch { atlas: texture.atlas, chunks: BTreeMap::new() });
+                self.batches.len() - 1
+            }
+        };
+        let coords = chunk_coords(position);
+        self.batches[batch].chunks.entry(coords).or_insert_with(|| TileChunk::new(coords)).tiles.insert(position, texture.id);
+        batch
+    }
+
+    /// One sprite per tile, for backends drawing sprite by sprite.
+    pub fn sprites(&self) -> Vec<Sprite> {
+        self.batches.iter().flat_map(|batch| batch.chunks().flat_map(move |chunk| self.chunk_sprites(batch, chunk))).collect()
+    }
+
+    /// The sprites of the tiles of `chunk`.
+    pub fn chunk_sprites<'a>(&'a self, batch: &'a AtlasBatch, chunk: &'a TileChunk) -> impl Iterator<Item = Sprite> + 'a {
+        chunk.tiles.iter().map(move |(&[x, y], &id)| Sprite {
+            position: [x as f32 * self.tile_size, y as f32 * self.tile_size],
+            size: [self.tile_size, self.tile_size],
+            texture: TextureHandle::new(batch.atlas, id),
+        })
+    }
+}
+
+/// The chunk holding the tile at the grid position.
+pub fn chunk_coords(position: [i32; 2]) -> [i32; 2] {
+    [position[0].div_euclid(CHUNK_SIZE), position[1].div_euclid(CHUNK_SIZE)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+
+    fn map(tiles: &str) -> Map {
+        let source = format!(r#"(
+            version: 1,
+            header: (name: "mesh", size: (40, 20), origin: (-20, -4), tile_size: 32, tilesets: ["OUTSIDE_ATLAS", "CAVES_ATLAS"]),
+            layers: [(name: "ground", tiles: [{}])],
+        )"#, tiles);
+        Map::parse(&source, "mesh.ron").unwrap()
+    }
+
+    fn revisions(mesh: &TileLayerMesh) -> Vec<([i32; 2], u64)> {
+        mesh.batches().iter().flat_map(AtlasBatch::chunks).map(|chunk| (chunk.coords, chunk.revision())).collect()
+    }
+
+    #[test]
+    fn groups_tiles_by_atlas_and_chunk() {
+        let map = map(r#"(position: (-20, -4), texture: 1), (position: (0, 0), texture: 2), (position: (15, 15), texture: 3),
+            (position: (16, 0), texture: 4), (position: (1, 1), tileset: "CAVES_ATLAS", texture: 5)"#);
+        let mesh = TileLayerMesh::from_map_layer(&map, &map.layers[0], TILE_SIZE);
+
+        assert_eq!(mesh.batches().len(), 2);
+        assert_eq!(mesh.tile_count(), 5);
+        let outside: Vec<[i32; 2]> = mesh.batches()[0].chunks().map(|chunk| chunk.coords).collect();
+        assert_eq!(outside, [[-2, -1], [0, 0], [1, 0]]);
+        assert_eq!(mesh.batches()[0].vertex_count(), 16);
+        assert_eq!(mesh.tile([1, 1]), Some(TextureHandle::new("CAVES_ATLAS", 5)));
+        assert_eq!(mesh.sprites().len(), 5);
+    }
+
+    #[test]
+    fn vertices_use_the_atlas_uvs() {
+        let map = map("(position: (0, 0), texture: 0)");
+        let mesh = TileLayerMesh::from_map_layer(&map, &map.layers[0], TILE_SIZE);
+        let uv = get_texture_atlas("OUTSIDE_ATLAS").unwrap().lock().unwrap().uv_rect(0).unwrap();
+        let vertices = mesh.batches()[0].chunks().next().unwrap().vertices();
+
+        assert_eq!(vertices[0], TileVertex { position: [0.0, 0.0], tex_coords: [0.0, uv[1]] });
+        assert_eq!(vertices[2], TileVertex { position: [TILE_SIZE, TILE_SIZE], tex_coords: [uv[2], 1.0] });
+        assert!(uv[2] > 0.0 && uv[1] < 1.0);
+    }
+
+    #[test]
+    fn set_tile_only_rebuilds_the_changed_chunk() {
+        let map = map("(position: (0, 0), texture: 1), (position: (16, 0), texture: 1)");
+        let mut mesh = TileLayerMesh::from_map_layer(&map, &map.layers[0], TILE_SIZE);
+        let before = revisions(&mesh);
+
+        mesh.set_tile([17, 0], Some(TextureHandle::new("OUTSIDE_ATLAS", 2)));
+        let after = revisions(&mesh);
+        assert_eq!(after[0], before[0]);
+        assert_eq!(after[1].1, before[1].1 + 1);
+        assert_eq!(mesh.tile_count(), 3);
+
+        mesh.set_tile([0, 0], None);
+        assert_eq!(mesh.tile([0, 0]), None);
+        assert_eq!(revisions(&mesh), [([1, 0], after[1].1)], "empty chunks are dropped");
+    }
+}
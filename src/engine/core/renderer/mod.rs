@@ -1,3 +1,4 @@
 pub mod core;
 pub mod camera;
-pub mod d2;
\ No newline at end of file
+pub mod d2;
+pub mod plugin;
@@ -0,0 +1,75 @@
+use crate::engine::console_logger::logger;
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::Plugin;
+use crate::engine::core::app::App;
+use crate::engine::core::cli::DEFAULT_HEADLESS_FRAMES;
+use crate::engine::core::config::BACKEND_NAMES;
+use crate::engine::core::metadata::{ENGINE_NAME, ENGINE_VERSION};
+
+use super::core::{opengl, software, vulkano};
+
+/// The graphics API a game is rendered with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Backend {
+    OpenGL,
+    Vulkano,
+    /// Renders `frames` frames on the CPU, no window or GPU required, and
+    /// saves the last one as PNG to `screenshot`.
+    Headless { frames: u32, screenshot: Option<String> },
+}
+
+impl Backend {
+    /// Looks up a backend by its config name, one of `BACKEND_NAMES`.
+    /// Headless runs render `DEFAULT_HEADLESS_FRAMES` frames.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "opengl" => Some(Backend::OpenGL),
+            "vulkano" => Some(Backend::Vulkano),
+            "headless" => Some(Backend::Headless { frames: DEFAULT_HEADLESS_FRAMES, screenshot: None }),
+            _ => None,
+        }
+    }
+}
+
+/// Runs the app in the game loop of the backend chosen with `AppBuilder::with_backend`
+/// or the `backend` of the engine config, falling back to OpenGL.
+pub(crate) struct RendererPlugin;
+
+impl Plugin for RendererPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.set_runner(run_renderer);
+    }
+}
+
+fn run_renderer(app: App) {
+    let (settings, world, schedule) = app.into_parts();
+    let backend = settings.backend.unwrap_or_else(|| {
+        println!(
+            "No graphics API specified. Please pick one with --backend <name> or the backend key of engine.toml ({}). Starting fallback OpenGL renderer...",
+            BACKEND_NAMES.join(", ")
+        );
+        Backend::OpenGL
+    });
+    let window = settings.window;
+
+    match backend {
+        Backend::OpenGL => {
+            let name = format!("{} v{}", settings.game_name, settings.game_version);
+            opengl::create_opengl_window(&name, window, settings.tick_rate, world, schedule);
+        }
+        Backend::Vulkano => {
+            let name = format!("{} v{} - [{} v{} - Vulkan]", settings.game_name, settings.game_version, ENGINE_NAME, ENGINE_VERSION);
+            vulkano::create_vulkano_window(&name, window.width, window.height);
+        }
+        Backend::Headless { frames, screenshot } => {
+            let backend = software::run_headless(window.width, window.height, frames, settings.tick_rate, world, schedule);
+            if let Some(path) = screenshot {
+                match backend.save_frame(&path) {
+                    Ok(()) => println!("{}", logger::info(&format!("Saved screenshot to {}", path))),
+                    Err(err) => println!("{}", logger::error(&format!("Failed to save screenshot to {}: {}", path, err))),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::core::metadata::{Color, COLOR_WHITE};
+
+/// Number of in-game minutes in one in-game day.
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Default speed of the in-game clock: one in-game minute per second of game
+/// time, so a full day takes 24 minutes.
+pub const DEFAULT_MINUTES_PER_SECOND: f64 = 1.0;
+
+/// Global tint of each phase, multiplied onto everything the renderer draws.
+pub const MORNING_TINT: Color = Color { red: 1.0, green: 0.92, blue: 0.85, alpha: 1.0 };
+pub const DAY_TINT: Color = COLOR_WHITE;
+pub const EVENING_TINT: Color = Color { red: 1.0, green: 0.72, blue: 0.55, alpha: 1.0 };
+pub const NIGHT_TINT: Color = Color { red: 0.45, green: 0.5, blue: 0.8, alpha: 1.0 };
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+#[allow(dead_code)]
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// The weekday of in-game day `day`, day 0 being a Monday.
+    pub fn from_day(day: u32) -> Self {
+        Weekday::ALL[(day % 7) as usize]
+    }
+
+    pub fn next(self) -> Self {
+        Weekday::from_day(self as u32 + 1)
+    }
+}
+
+/// The coarse parts of the day that tinting, encounter tables and NPC
+/// schedules care about.
+///
+/// | Phase   | From  | Until |
+/// |---------|-------|-------|
+/// | Morning | 05:00 | 10:00 |
+/// | Day     | 10:00 | 17:00 |
+/// | Evening | 17:00 | 20:00 |
+/// | Night   | 20:00 | 05:00 |
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DayPhase {
+    Morning,
+    Day,
+    Evening,
+    Night,
+}
+
+#[allow(dead_code)]
+impl DayPhase {
+    pub const ALL: [DayPhase; 4] = [DayPhase::Morning, DayPhase::Day, DayPhase::Evening, DayPhase::Night];
+
+    /// The phase the minute `minute_of_day` (0 = midnight) falls into.
+    pub fn from_minute(minute_of_day: u32) -> Self {
+        match minute_of_day % MINUTES_PER_DAY {
+            300..=599 => DayPhase::Morning,
+            600..=1019 => DayPhase::Day,
+            1020..=1199 => DayPhase::Evening,
+            _ => DayPhase::Night,
+        }
+    }
+
+    /// The global tint the renderer applies during this phase.
+    pub fn tint(self) -> Color {
+        match self {
+            DayPhase::Morning => MORNING_TINT,
+            DayPhase::Day => DAY_TINT,
+            DayPhase::Evening => EVENING_TINT,
+            DayPhase::Night => NIGHT_TINT,
+        }
+    }
+}
+
+/// The in-world calendar: current day, time of day and how fast it advances.
+///
+/// `WorldTime` is advanced with the scaled delta of the `GameClock`, so it
+/// stops while the game is paused and slows down with the clock's time scale.
+/// It is serializable and meant to be stored in save files, see `save` and
+/// `load`.
+///
+/// # Example
+///
+/// ```rust
+/// let mut world_time = WorldTime::new();
+/// world_time.set_time(19, 30);
+/// world_time.advance(clock.delta());
+/// if world_time.phase() == DayPhase::Night {
+///     // night-only encounters
+/// }
+/// if world_time.is_between((8, 0), (18, 0)) {
+///     // the shop keeper is at the counter
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldTime {
+    day: u32,
+    minute_of_day: f64,
+    #[serde(default = "default_minutes_per_second")]
+    minutes_per_second: f64,
+}
+
+fn default_minutes_per_second() -> f64 {
+    DEFAULT_MINUTES_PER_SECOND
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        WorldTime::new()
+    }
+}
+
+#[allow(dead_code)]
+impl WorldTime {
+    /// Starts on day 0 (a Monday) at 10:00.
+    pub fn new() -> Self {
+        WorldTime {
+            day: 0,
+            minute_of_day: 600.0,
+            minutes_per_second: DEFAULT_MINUTES_PER_SECOND,
+        }
+    }
+
+    /// Advances the calendar by `delta` of game time.
+    pub fn advance(&mut self, delta: Duration) {
+        self.minute_of_day += delta.as_secs_f64() * self.minutes_per_second;
+        let days = (self.minute_of_day / MINUTES_PER_DAY as f64).floor();
+        self.day += days as u32;
+        self.minute_of_day -= days * MINUTES_PER_DAY as f64;
+    }
+
+    /// In-game minutes passing per second of game time.
+    pub fn rate(&self) -> f64 {
+        self.minutes_per_second
+    }
+
+    /// Sets how many in-game minutes pass per second of game time. Negative values are clamped to zero.
+    pub fn set_rate(&mut self, minutes_per_second: f64) {
+        self.minutes_per_second = minutes_per_second.max(0.0);
+    }
+
+    /// Jumps to `hour:minute` of the current day.
+    pub fn set_time(&mut self, hour: u32, minute: u32) {
+        self.minute_of_day = ((hour * 60 + minute) % MINUTES_PER_DAY) as f64;
+    }
+
+    /// Days passed since the start of the game.
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        Weekday::from_day(self.day)
+    }
+
+    /// Whole minutes since midnight.
+    pub fn minute_of_day(&self) -> u32 {
+        self.minute_of_day as u32
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.minute_of_day() / 60
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.minute_of_day() % 60
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        DayPhase::from_minute(self.minute_of_day())
+    }
+
+    /// The global tint of the current phase.
+    pub fn tint(&self) -> Color {
+        self.phase().tint()
+    }
+
+    /// `true` if the current time lies in `[start, end)`, both given as
+    /// `(hour, minute)`. Ranges crossing midnight, like `(22, 0)` to `(6, 0)`,
+    /// are supported.
+    pub fn is_between(&self, start: (u32, u32), end: (u32, u32)) -> bool {
+        let now = self.minute_of_day();
+        let start = start.0 * 60 + start.1;
+        let end = end.0 * 60 + end.1;
+        if start <= end {
+            (start..end).contains(&now)
+        } else {
+            now >= start || now < end
+        }
+    }
+
+    /// Writes the calendar as RON to `path`.
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let serialized = ron::ser::to_string_pretty(self, Default::default())?;
+        let mut file = File::create(path)?;
+        file.write_all(serialized.as_bytes())?;
+        Ok(())
+    }
+
+    /// Reads a calendar previously written by `save`.
+    pub fn load(path: &str) -> Result<WorldTime, Box<dyn std::error::Error>> {
+        let mut file = File::open(path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let world_time: WorldTime = ron::de::from_str(&content)?;
+        Ok(world_time)
+    }
+}
+
+impl std::fmt::Display for WorldTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Day {} ({:?}) {:02}:{:02}", self.day, self.weekday(), self.hour(), self.minute())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_rolls_over_into_the_next_day() {
+        let mut world_time = WorldTime::new();
+        world_time.set_time(23, 30);
+        world_time.set_rate(2.0);
+        world_time.advance(Duration::from_secs(30));
+        assert_eq!((world_time.day(), world_time.hour(), world_time.minute()), (1, 0, 30));
+        assert_eq!(world_time.weekday(), Weekday::Tuesday);
+
+        world_time.advance(Duration::from_secs(MINUTES_PER_DAY as u64 * 3));
+        assert_eq!(world_time.day(), 7);
+        assert_eq!(world_time.weekday(), Weekday::Monday);
+    }
+
+    #[test]
+    fn phases_follow_the_time_of_day() {
+        let mut world_time = WorldTime::new();
+        for (hour, phase) in [(4, DayPhase::Night), (5, DayPhase::Morning), (12, DayPhase::Day), (17, DayPhase::Evening), (20, DayPhase::Night)] {
+            world_time.set_time(hour, 0);
+            assert_eq!(world_time.phase(), phase, "{}:00", hour);
+        }
+        assert_eq!(world_time.tint(), NIGHT_TINT);
+    }
+
+    #[test]
+    fn is_between_handles_ranges_over_midnight() {
+        let mut world_time = WorldTime::new();
+        world_time.set_time(23, 0);
+        assert!(world_time.is_between((22, 0), (6, 0)));
+        assert!(!world_time.is_between((8, 0), (18, 0)));
+        world_time.set_time(6, 0);
+        assert!(!world_time.is_between((22, 0), (6, 0)));
+    }
+
+    #[test]
+    fn survives_a_save_round_trip() {
+        let mut world_time = WorldTime::new();
+        world_time.set_rate(4.0);
+        world_time.advance(Duration::from_secs(1000));
+
+        let path = std::env::temp_dir().join("malkmusl_world_time_test.ron");
+        let path = path.to_str().unwrap();
+        world_time.save(path).unwrap();
+        assert_eq!(WorldTime::load(path).unwrap(), world_time);
+        let _ = std::fs::remove_file(path);
+    }
+}
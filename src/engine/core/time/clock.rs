@@ -0,0 +1,389 @@
+use std::time::Duration;
+
+/// Whether a timer stops after finishing once or starts over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+/// A countdown advanced by game time.
+///
+/// Timers are plain values and can be owned by whatever needs them (an
+/// entity's cooldown, an animation frame); they only move when `tick` is
+/// called, usually with `GameClock::delta`.
+///
+/// # Example
+///
+/// ```rust
+/// let mut blink = Timer::new(Duration::from_millis(500), TimerMode::Repeating);
+/// blink.tick(clock.delta());
+/// if blink.just_finished() {
+///     visible = !visible;
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    finished: bool,
+    times_finished_this_tick: u32,
+}
+
+#[allow(dead_code)]
+impl Timer {
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Timer {
+            duration,
+            elapsed: Duration::ZERO,
+            mode,
+            finished: false,
+            times_finished_this_tick: 0,
+        }
+    }
+
+    /// Advances the timer by `delta` and returns how often it finished during
+    /// this tick. A repeating timer may finish several times if `delta` spans
+    /// more than one period; a one-shot timer finishes at most once.
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        self.times_finished_this_tick = 0;
+        if self.mode == TimerMode::Once && self.finished {
+            return 0;
+        }
+
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return 0;
+        }
+
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.finished = true;
+                self.times_finished_this_tick = 1;
+            }
+            TimerMode::Repeating if self.duration.is_zero() => {
+                self.elapsed = Duration::ZERO;
+                self.finished = true;
+                self.times_finished_this_tick = 1;
+            }
+            TimerMode::Repeating => {
+                let periods = (self.elapsed.as_nanos() / self.duration.as_nanos()) as u32;
+                self.elapsed -= self.duration * periods;
+                self.finished = true;
+                self.times_finished_this_tick = periods;
+            }
+        }
+        self.times_finished_this_tick
+    }
+
+    /// `true` once a one-shot timer ran out, or after a repeating timer finished at least one period.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// `true` if the timer finished during the last call to `tick`.
+    pub fn just_finished(&self) -> bool {
+        self.times_finished_this_tick > 0
+    }
+
+    pub fn times_finished_this_tick(&self) -> u32 {
+        self.times_finished_this_tick
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    /// Progress through the current period in `[0, 1]`.
+    pub fn fraction(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f64() / self.duration.as_secs_f64()) as f32
+    }
+
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.finished = false;
+        self.times_finished_this_tick = 0;
+    }
+}
+
+/// Identifies a callback scheduled on the `GameClock`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+struct ScheduledCallback {
+    id: TimerId,
+    timer: Timer,
+    callback: Box<dyn FnMut()>,
+}
+
+/// The game's notion of time.
+///
+/// The clock is advanced once per simulation tick with the real (unscaled)
+/// step, see `advance`. From that it derives the scaled `delta` handed to
+/// entities, so slow motion and pausing work without touching the tick rate.
+/// Callbacks scheduled with `schedule_once` and `schedule_repeating` run on
+/// game time as well.
+///
+/// Because `advance` takes the step as argument instead of reading the system
+/// clock, a sequence of `advance` calls always yields the same results, which
+/// tests rely on.
+pub struct GameClock {
+    delta: Duration,
+    raw_delta: Duration,
+    elapsed: Duration,
+    real_elapsed: Duration,
+    time_scale: f64,
+    paused: bool,
+    ticks: u64,
+    next_timer_id: u64,
+    scheduled: Vec<ScheduledCallback>,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock::new()
+    }
+}
+
+#[allow(dead_code)]
+impl GameClock {
+    pub fn new() -> Self {
+        GameClock {
+            delta: Duration::ZERO,
+            raw_delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            real_elapsed: Duration::ZERO,
+            time_scale: 1.0,
+            paused: false,
+            ticks: 0,
+            next_timer_id: 0,
+            scheduled: Vec::new(),
+        }
+    }
+
+    /// Advances the clock by `raw_delta` of real time, updates the scaled
+    /// `delta` and runs every scheduled callback that became due.
+    pub fn advance(&mut self, raw_delta: Duration) {
+        self.raw_delta = raw_delta;
+        self.real_elapsed += raw_delta;
+        self.ticks += 1;
+
+        self.delta = if self.paused {
+            Duration::ZERO
+        } else {
+            raw_delta.mul_f64(self.time_scale)
+        };
+        self.elapsed += self.delta;
+
+        let delta = self.delta;
+        for scheduled in self.scheduled.iter_mut() {
+            for _ in 0..scheduled.timer.tick(delta) {
+                (scheduled.callback)();
+            }
+        }
+        self.scheduled.retain(|scheduled| !(scheduled.timer.mode() == TimerMode::Once && scheduled.timer.finished()));
+    }
+
+    /// Scaled game time passed during the last tick. Zero while paused.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// `delta` in seconds, the unit entity speeds are expressed in.
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Real time passed during the last tick, ignoring time scale and pause.
+    pub fn raw_delta(&self) -> Duration {
+        self.raw_delta
+    }
+
+    /// Total scaled game time since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Total real time the clock was advanced by.
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+
+    /// Number of times `advance` was called.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Sets how fast game time runs compared to real time, e.g. `0.5` for slow motion.
+    /// Negative values are clamped to zero.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Runs `callback` once, after `delay` of game time.
+    pub fn schedule_once(&mut self, delay: Duration, callback: impl FnMut() + 'static) -> TimerId {
+        self.schedule(Timer::new(delay, TimerMode::Once), Box::new(callback))
+    }
+
+    /// Runs `callback` every `interval` of game time until it is cancelled.
+    pub fn schedule_repeating(&mut self, interval: Duration, callback: impl FnMut() + 'static) -> TimerId {
+        self.schedule(Timer::new(interval, TimerMode::Repeating), Box::new(callback))
+    }
+
+    /// Removes a scheduled callback. Returns `false` if it already ran or was cancelled.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let scheduled_before = self.scheduled.len();
+        self.scheduled.retain(|scheduled| scheduled.id != id);
+        self.scheduled.len() != scheduled_before
+    }
+
+    /// Returns the game time left until the callback `id` runs next.
+    pub fn time_until(&self, id: TimerId) -> Option<Duration> {
+        self.scheduled.iter().find(|scheduled| scheduled.id == id).map(|scheduled| scheduled.timer.remaining())
+    }
+
+    fn schedule(&mut self, timer: Timer, callback: Box<dyn FnMut()>) -> TimerId {
+        let id = TimerId(self.next_timer_id);
+        self.next_timer_id += 1;
+        self.scheduled.push(ScheduledCallback { id, timer, callback });
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn advance_accumulates_delta_and_elapsed() {
+        let mut clock = GameClock::new();
+        for _ in 0..5 {
+            clock.advance(STEP);
+        }
+        assert_eq!(clock.delta(), STEP);
+        assert_eq!(clock.elapsed(), Duration::from_millis(500));
+        assert_eq!(clock.real_elapsed(), Duration::from_millis(500));
+        assert_eq!(clock.ticks(), 5);
+    }
+
+    #[test]
+    fn time_scale_slows_game_time_but_not_real_time() {
+        let mut clock = GameClock::new();
+        clock.set_time_scale(0.5);
+        clock.advance(STEP);
+        assert_eq!(clock.delta(), Duration::from_millis(50));
+        assert_eq!(clock.raw_delta(), STEP);
+        assert_eq!(clock.real_elapsed(), STEP);
+    }
+
+    #[test]
+    fn pause_stops_game_time() {
+        let mut clock = GameClock::new();
+        clock.advance(STEP);
+        clock.pause();
+        clock.advance(STEP);
+        assert_eq!(clock.delta(), Duration::ZERO);
+        assert_eq!(clock.elapsed(), STEP);
+        clock.resume();
+        clock.advance(STEP);
+        assert_eq!(clock.elapsed(), STEP * 2);
+    }
+
+    #[test]
+    fn one_shot_timer_finishes_once() {
+        let mut timer = Timer::new(Duration::from_millis(250), TimerMode::Once);
+        assert_eq!(timer.tick(STEP), 0);
+        assert_eq!(timer.tick(STEP), 0);
+        assert_eq!(timer.tick(STEP), 1);
+        assert!(timer.finished() && timer.just_finished());
+        assert_eq!(timer.tick(STEP), 0);
+        assert!(timer.finished() && !timer.just_finished());
+        assert_eq!(timer.remaining(), Duration::ZERO);
+    }
+
+    #[test]
+    fn repeating_timer_carries_over_remainder() {
+        let mut timer = Timer::new(Duration::from_millis(250), TimerMode::Repeating);
+        assert_eq!(timer.tick(Duration::from_millis(600)), 2);
+        assert_eq!(timer.elapsed(), STEP);
+        assert_eq!(timer.tick(Duration::from_millis(150)), 1);
+        assert_eq!(timer.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn scheduled_callbacks_run_on_game_time() {
+        let mut clock = GameClock::new();
+        let once = Rc::new(Cell::new(0));
+        let repeating = Rc::new(Cell::new(0));
+
+        let counter = once.clone();
+        clock.schedule_once(Duration::from_millis(300), move || counter.set(counter.get() + 1));
+        let counter = repeating.clone();
+        let repeating_id = clock.schedule_repeating(Duration::from_millis(200), move || counter.set(counter.get() + 1));
+
+        for _ in 0..10 {
+            clock.advance(STEP);
+        }
+        assert_eq!(once.get(), 1);
+        assert_eq!(repeating.get(), 5);
+
+        assert!(clock.cancel(repeating_id));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(repeating.get(), 5);
+    }
+
+    #[test]
+    fn scheduled_callbacks_wait_while_paused() {
+        let mut clock = GameClock::new();
+        let fired = Rc::new(Cell::new(false));
+        let flag = fired.clone();
+        let id = clock.schedule_once(STEP, move || flag.set(true));
+
+        clock.pause();
+        clock.advance(Duration::from_secs(1));
+        assert!(!fired.get());
+        assert_eq!(clock.time_until(id), Some(STEP));
+
+        clock.resume();
+        clock.advance(STEP);
+        assert!(fired.get());
+        assert_eq!(clock.time_until(id), None);
+    }
+}
@@ -0,0 +1,233 @@
+use std::time::{Duration, Instant};
+
+/// Default rate of the simulation tick in Hz.
+pub const DEFAULT_TICK_RATE: f64 = 60.0;
+
+/// Frame times above this are clamped, so a long stall (window drag,
+/// breakpoint, slow disk) does not trigger hundreds of catch-up ticks.
+pub const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+/// Drives a fixed-timestep simulation from a variable frame rate.
+///
+/// Every frame the elapsed real time is added to an accumulator, and the
+/// simulation is ticked once for every full `step` inside it. What is left
+/// over is exposed as `alpha`, the fraction of a tick the rendered frame lies
+/// between the previous and the current simulation state.
+///
+/// # Example
+///
+/// ```rust
+/// let mut timestep = FixedTimestep::new(60.0);
+/// let ticks = timestep.tick(Instant::now());
+/// for _ in 0..ticks {
+///     // advance the simulation by timestep.step()
+/// }
+/// // render, interpolating with timestep.alpha()
+/// ```
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+    last_instant: Option<Instant>,
+    stats: FrameStats,
+}
+
+/// Frame time and tick counters collected by `FixedTimestep`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Duration of the last frame, before clamping.
+    pub frame_time: Duration,
+    /// Simulation ticks run during the last frame.
+    pub ticks_last_frame: u32,
+    /// Simulation ticks run since the timestep was created.
+    pub total_ticks: u64,
+    /// Frames since the timestep was created.
+    pub total_frames: u64,
+    /// Frames counted in the current reporting window.
+    window_frames: u32,
+    /// Ticks counted in the current reporting window.
+    window_ticks: u32,
+    /// Real time covered by the current reporting window.
+    window_time: Duration,
+}
+
+/// Averages over one reporting window, see `FixedTimestep::take_report`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameReport {
+    pub frames_per_second: f64,
+    pub ticks_per_second: f64,
+    pub average_frame_time: Duration,
+    pub total_ticks: u64,
+}
+
+#[allow(dead_code)]
+impl FixedTimestep {
+    /// Creates a timestep ticking `tick_rate` times per second.
+    pub fn new(tick_rate: f64) -> Self {
+        assert!(tick_rate > 0.0, "tick rate has to be positive, got {}", tick_rate);
+        FixedTimestep {
+            step: Duration::from_secs_f64(1.0 / tick_rate),
+            accumulator: Duration::ZERO,
+            last_instant: None,
+            stats: FrameStats::default(),
+        }
+    }
+
+    /// The simulated time advanced by a single tick.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// The number of ticks per second.
+    pub fn tick_rate(&self) -> f64 {
+        1.0 / self.step.as_secs_f64()
+    }
+
+    /// Measures the time since the previous call and returns how many ticks
+    /// have to be run this frame. The very first call only starts the clock.
+    pub fn tick(&mut self, now: Instant) -> u32 {
+        let frame_time = match self.last_instant {
+            Some(last) => now.saturating_duration_since(last),
+            None => Duration::ZERO,
+        };
+        self.last_instant = Some(now);
+        self.advance(frame_time)
+    }
+
+    /// Adds `frame_time` to the accumulator and returns how many ticks have to
+    /// be run. Unlike `tick` this does not read the system clock, so it can be
+    /// used for deterministic stepping.
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        self.accumulator += frame_time.min(MAX_FRAME_TIME);
+
+        let mut ticks = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            ticks += 1;
+        }
+
+        self.stats.frame_time = frame_time;
+        self.stats.ticks_last_frame = ticks;
+        self.stats.total_ticks += ticks as u64;
+        self.stats.total_frames += 1;
+        self.stats.window_frames += 1;
+        self.stats.window_ticks += ticks;
+        self.stats.window_time += frame_time;
+        ticks
+    }
+
+    /// How far the current frame lies between the last and the next tick, in `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+    }
+
+    /// Forgets the last measured instant, e.g. after the game was paused, so
+    /// the time spent paused is not simulated afterwards.
+    pub fn reset_clock(&mut self) {
+        self.last_instant = None;
+        self.accumulator = Duration::ZERO;
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Returns the averages of the current reporting window once it covers at
+    /// least `interval`, and starts a new window. `None` while the window has
+    /// no frames or no time to average over.
+    pub fn take_report(&mut self, interval: Duration) -> Option<FrameReport> {
+        if self.stats.window_time < interval || self.stats.window_frames == 0 || self.stats.window_time.is_zero() {
+            return None;
+        }
+        let seconds = self.stats.window_time.as_secs_f64();
+        let report = FrameReport {
+            frames_per_second: self.stats.window_frames as f64 / seconds,
+            ticks_per_second: self.stats.window_ticks as f64 / seconds,
+            average_frame_time: self.stats.window_time / self.stats.window_frames,
+            total_ticks: self.stats.total_ticks,
+        };
+        self.stats.window_frames = 0;
+        self.stats.window_ticks = 0;
+        self.stats.window_time = Duration::ZERO;
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// A 100 Hz timestep, 10 ms per tick.
+    fn timestep() -> FixedTimestep {
+        FixedTimestep::new(100.0)
+    }
+
+    #[test]
+    fn runs_one_tick_per_full_step() {
+        // (frame time in ms, ticks, alpha after the frame)
+        let table = [
+            (4, 0, 0.4),
+            (4, 0, 0.8),
+            (4, 1, 0.2),
+            (30, 3, 0.2),
+            (8, 1, 0.0),
+            (0, 0, 0.0),
+            (25, 2, 0.5),
+        ];
+        let mut timestep = timestep();
+        for (frame_time, ticks, alpha) in table {
+            assert_eq!(timestep.advance(ms(frame_time)), ticks, "{} ms", frame_time);
+            assert!((timestep.alpha() - alpha).abs() < 1e-6, "alpha {} after {} ms", timestep.alpha(), frame_time);
+            assert!((0.0..1.0).contains(&timestep.alpha()));
+        }
+        assert_eq!(timestep.stats().total_ticks, 7);
+        assert_eq!(timestep.stats().total_frames, 7);
+        assert_eq!(timestep.stats().ticks_last_frame, 2);
+    }
+
+    #[test]
+    fn clamps_long_frames() {
+        let mut timestep = timestep();
+        assert_eq!(timestep.advance(Duration::from_secs(1)), 25);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.stats().frame_time, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn reset_clock_clears_the_accumulator() {
+        let mut timestep = timestep();
+        timestep.advance(ms(7));
+        assert!(timestep.alpha() > 0.0);
+        timestep.reset_clock();
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(ms(7)), 0);
+        assert_eq!(timestep.tick(Instant::now()), 0);
+    }
+
+    #[test]
+    fn reports_averages_per_window() {
+        let mut timestep = timestep();
+        for _ in 0..19 {
+            timestep.advance(ms(50));
+            assert_eq!(timestep.take_report(Duration::from_secs(1)), None);
+        }
+        timestep.advance(ms(50));
+        let report = timestep.take_report(Duration::from_secs(1)).unwrap();
+        assert!((report.frames_per_second - 20.0).abs() < 1e-9, "{:?}", report);
+        assert!((report.ticks_per_second - 100.0).abs() < 1e-9, "{:?}", report);
+        assert_eq!(report.average_frame_time, ms(50));
+        assert_eq!(report.total_ticks, 100);
+
+        // The next window starts empty, a zero length frame has no rate either.
+        assert_eq!(timestep.take_report(Duration::ZERO), None);
+        timestep.advance(ms(0));
+        assert_eq!(timestep.take_report(Duration::ZERO), None);
+        timestep.advance(ms(20));
+        let report = timestep.take_report(Duration::ZERO).unwrap();
+        assert!((report.frames_per_second - 100.0).abs() < 1e-9, "{:?}", report);
+        assert!((report.ticks_per_second - 100.0).abs() < 1e-9, "{:?}", report);
+    }
+}
@@ -1,7 +1,8 @@
+/// Expands to the current local time formatted like the console logger's
+/// timestamps, e.g. `[2024-01-31][13:37:00]`.
 #[macro_export]
 macro_rules! get_current_time {
     () => {
-        let current_time = Local::now().format("[%Y-%m-%d][%H:%M:%S]").to_string();
-        current_time
+        chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]").to_string()
     };
-}
\ No newline at end of file
+}
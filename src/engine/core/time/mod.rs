@@ -1 +1,4 @@
+pub mod calendar;
+pub mod clock;
+pub mod fixed_timestep;
 pub mod macros;
\ No newline at end of file
@@ -0,0 +1,393 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::engine::assets_loader::loader::asset_root;
+use crate::engine::assets_loader::texture_tilesets::get_atlas_name;
+use crate::engine::core::cli::ValidateArgs;
+use crate::engine::core::creature::moves::{move_files, parse_moves, Move, MOVES_DIR_NAME};
+use crate::engine::core::creature::species::{species_files, Species, SPECIES_DIR_NAME};
+use crate::engine::core::world::encounter::{table_files, EncounterTable, ENCOUNTER_DIR_NAME};
+use crate::engine::core::world::map::{key_lines, string_lines, Map, MapError, MAP_FILE_NAME};
+use crate::engine::core::world::streaming::{chunk_path, WorldManifest, WORLD_FILE_NAME};
+
+/// The outcome of `validate`: how many files were checked and every problem
+/// found in file order, each pointing at its file and line where known.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Validation {
+    pub problems: Vec<MapError>,
+    pub maps: usize,
+    pub species: usize,
+    pub moves: usize,
+    pub encounter_tables: usize,
+}
+
+impl Validation {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// A one-line summary for the `validate` command.
+    pub fn summary(&self) -> String {
+        format!(
+            "Checked {} maps, {} species, {} moves and {} encounter tables: {} problems",
+            self.maps, self.species, self.moves, self.encounter_tables, self.problems.len(),
+        )
+    }
+}
+
+/// A parsed data file together with its source, to point cross-reference
+/// problems at the line of the reference.
+struct DataFile<T> {
+    path: String,
+    source: String,
+    data: T,
+}
+
+impl<T> DataFile<T> {
+    /// A problem at the `index`-th occurrence of `key` in the file, or at the
+    /// file if there is no such occurrence.
+    fn problem(&self, key: &str, index: usize, message: String) -> MapError {
+        self.problem_at(key_lines(&self.source, key).get(index), message)
+    }
+
+    /// A problem at the first string literal `value` in the file, or at the
+    /// file if there is none.
+    fn problem_at_string(&self, value: &str, message: String) -> MapError {
+        self.problem_at(string_lines(&self.source, value).first(), message)
+    }
+
+    fn problem_at(&self, line: Option<&usize>, message: String) -> MapError {
+        match line {
+            Some(line) => MapError::at(&self.path, *line, None, message),
+            None => MapError::new(&self.path, message),
+        }
+    }
+}
+
+/// Reads and parses `path`, adding the problem to `problems` if that fails.
+fn parse_file<T>(path: &Path, parse: fn(&str, &str) -> Result<T, MapError>, problems: &mut Vec<MapError>) -> Option<DataFile<T>> {
+    let path = path.display().to_string();
+    let parsed = fs::read_to_string(&path)
+        .map_err(|err| MapError::new(&path, format!("cannot read the file: {}", err)))
+        .and_then(|source| parse(&source, &path).map(|data| (source, data)));
+    match parsed {
+        Ok((source, data)) => Some(DataFile { path, source, data }),
+        Err(err) => {
+            problems.push(err);
+            None
+        }
+    }
+}
+
+/// Runs the `validate` command on its asset directory, `asset_root()` if not given.
+pub fn run_validate(args: &ValidateArgs) -> Validation {
+    let root = args.root.clone().unwrap_or_else(asset_root);
+    validate_assets(Path::new(&root))
+}
+
+/// Loads every map, world, species, move and encounter table below `root`
+/// and cross-checks what they reference: tilesets against the atlases of
+/// `get_texture_atlas` (by name, without decoding their images),
+/// warp and connection targets against the map directories, encounter table
+/// ids of tiles, species of encounter slots, evolution targets, that a
+/// machine teaches the same attack to every species and, if there is a moves
+/// directory, that every attack of a learnset is a move.
+///
+/// Unlike loading for the game, it does not stop at the first problem.
+///
+/// # Example
+///
+/// ```rust
+/// let validation = validate_assets(Path::new("./src/assets"));
+/// for problem in &validation.problems {
+///     // e.g. "./src/assets/pokemon/gen_1/1-3/ivysaur.ron:47: evolution target venusaurr is not a species"
+///     eprintln!("{}", problem);
+/// }
+/// ```
+pub fn validate_assets(root: &Path) -> Validation {
+    let mut validation = Validation::default();
+    if !root.is_dir() {
+        validation.problems.push(MapError::new(&root.display().to_string(), "the asset directory does not exist"));
+        return validation;
+    }
+    let problems = &mut validation.problems;
+
+    let (maps, worlds) = load_maps(&root.join("maps"), problems);
+    let map_names: HashSet<&str> = maps.keys().chain(worlds.keys()).map(String::as_str).collect();
+    let species = load_species(&root.join(SPECIES_DIR_NAME), problems);
+    let species_ids: HashSet<&str> = species.iter().map(|file| file.data.id.as_str()).collect();
+    let moves = load_moves(&root.join(MOVES_DIR_NAME), problems);
+    let move_ids: HashSet<&str> = moves.iter().flat_map(|file| &file.data).map(|attack| attack.id.as_str()).collect();
+    let tables = load_tables(&root.join(ENCOUNTER_DIR_NAME), problems);
+    let table_ids: HashSet<u32> = tables.iter().map(|file| file.data.id).collect();
+
+    for (file, chunks) in worlds.values() {
+        check_tilesets(file, &file.data.tilesets, problems);
+        for chunk in chunks {
+            check_encounter_tables(chunk, &table_ids, problems);
+        }
+    }
+    for file in maps.values() {
+        check_tilesets(file, &file.data.header.tilesets, problems);
+        check_encounter_tables(file, &table_ids, problems);
+        for (index, warp) in file.data.warps.iter().enumerate() {
+            if !map_names.contains(warp.target.as_str()) {
+                problems.push(file.problem("target:", index, format!("warp ({}, {}) leads to the unknown map {}", warp.tile[0], warp.tile[1], warp.target)));
+            }
+        }
+        for (index, connection) in file.data.connections.iter().enumerate() {
+            if !map_names.contains(connection.map.as_str()) {
+                problems.push(file.problem("edge:", index, format!("the {:?} connection joins the unknown map {}", connection.edge, connection.map)));
+            } else if worlds.contains_key(&connection.map) {
+                // Connected maps are loaded whole next to the map, a chunked world cannot be.
+                problems.push(file.problem("edge:", index, format!("the {:?} connection target {} is a chunked world", connection.edge, connection.map)));
+            }
+        }
+    }
+
+    // Machine name -> the attack it teaches and the species that says so first.
+    let mut machines: HashMap<&str, (&str, &str)> = HashMap::new();
+    for file in &species {
+        for (index, evolution) in file.data.evolutions.iter().enumerate() {
+            if !species_ids.contains(evolution.to.as_str()) {
+                problems.push(file.problem("to:", index, format!("evolution target {} is not a species", evolution.to)));
+            }
+        }
+        for (index, machine) in file.data.learnset.machines.iter().enumerate() {
+            let (attack, first) = *machines.entry(&machine.machine).or_insert((&machine.attack, &file.data.id));
+            if attack != machine.attack {
+                problems.push(file.problem("machine:", index, format!("{} teaches {} here but {} in {}", machine.machine, machine.attack, attack, first)));
+            }
+        }
+        if root.join(MOVES_DIR_NAME).is_dir() {
+            let mut reported = HashSet::new();
+            for attack in file.data.learnset.attacks() {
+                if !move_ids.contains(attack) && reported.insert(attack) {
+                    problems.push(file.problem_at_string(attack, format!("{} learns the unknown move {}", file.data.id, attack)));
+                }
+            }
+        }
+    }
+    for file in &tables {
+        for (index, slot) in file.data.slots.iter().enumerate() {
+            if !species_ids.contains(slot.species.as_str()) {
+                problems.push(file.problem("species:", index, format!("{} is not a species", slot.species)));
+            }
+        }
+    }
+
+    problems.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    validation.maps = maps.len() + worlds.len();
+    validation.species = species.len();
+    validation.moves = move_ids.len();
+    validation.encounter_tables = tables.len();
+    validation
+}
+
+type ChunkedWorld = (DataFile<WorldManifest>, Vec<DataFile<Map>>);
+
+/// Every map and chunked world below `dir` by directory name.
+fn load_maps(dir: &Path, problems: &mut Vec<MapError>) -> (BTreeMap<String, DataFile<Map>>, BTreeMap<String, ChunkedWorld>) {
+    let mut maps = BTreeMap::new();
+    let mut worlds = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (maps, worlds);
+    };
+    let mut dirs: Vec<_> = entries.filter_map(Result::ok).map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+    dirs.sort();
+
+    for map_dir in dirs {
+        let name = map_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let world_file = map_dir.join(WORLD_FILE_NAME);
+        if world_file.is_file() {
+            let Some(manifest) = parse_file(&world_file, WorldManifest::parse, problems) else {
+                continue;
+            };
+            let mut chunks = Vec::new();
+            for coords in &manifest.data.chunks {
+                let path = chunk_path(&map_dir, *coords);
+                if !path.is_file() {
+                    problems.push(manifest.problem("chunks:", 0, format!("chunk ({}, {}) has no file {}", coords[0], coords[1], path.display())));
+                    continue;
+                }
+                chunks.extend(parse_file(&path, Map::parse, problems));
+            }
+            worlds.insert(name, (manifest, chunks));
+        } else if map_dir.join(MAP_FILE_NAME).is_file() {
+            if let Some(map) = parse_file(&map_dir.join(MAP_FILE_NAME), Map::parse, problems) {
+                maps.insert(name, map);
+            }
+        }
+    }
+    (maps, worlds)
+}
+
+fn load_species(dir: &Path, problems: &mut Vec<MapError>) -> Vec<DataFile<Species>> {
+    if !dir.is_dir() {
+        return Vec::new();
+    }
+    let paths = species_files(dir).unwrap_or_else(|err| {
+        problems.push(err);
+        Vec::new()
+    });
+    let mut species: Vec<DataFile<Species>> = Vec::new();
+    for path in paths {
+        let Some(file) = parse_file(&path, Species::parse, problems) else {
+            continue;
+        };
+        if let Some(other) = species.iter().find(|other| other.data.dex.national == file.data.dex.national) {
+            problems.push(file.problem("national:", 0, format!("national dex id {} is also used by {}", file.data.dex.national, other.data.id)));
+        } else if species.iter().any(|other| other.data.id == file.data.id) {
+            problems.push(MapError::new(&file.path, format!("species id {} is used twice", file.data.id)));
+        } else {
+            species.push(file);
+        }
+    }
+    species
+}
+
+/// Every move file in `dir`, reporting moves whose id another move already has.
+fn load_moves(dir: &Path, problems: &mut Vec<MapError>) -> Vec<DataFile<Vec<Move>>> {
+    if !dir.is_dir() {
+        return Vec::new();
+    }
+    let paths = move_files(dir).unwrap_or_else(|err| {
+        problems.push(err);
+        Vec::new()
+    });
+    let mut files = Vec::new();
+    let mut ids = HashSet::new();
+    for path in paths {
+        let Some(file) = parse_file(&path, parse_moves, problems) else {
+            continue;
+        };
+        for (index, attack) in file.data.iter().enumerate() {
+            if !ids.insert(attack.id.clone()) {
+                problems.push(file.problem("id:", index, format!("move id {} is used twice", attack.id)));
+            }
+        }
+        files.push(file);
+    }
+    files
+}
+
+fn load_tables(dir: &Path, problems: &mut Vec<MapError>) -> Vec<DataFile<EncounterTable>> {
+    if !dir.is_dir() {
+        return Vec::new();
+    }
+    let paths = table_files(dir).unwrap_or_else(|err| {
+        problems.push(err);
+        Vec::new()
+    });
+    let mut tables: Vec<DataFile<EncounterTable>> = Vec::new();
+    for path in paths {
+        let Some(file) = parse_file(&path, EncounterTable::parse, problems) else {
+            continue;
+        };
+        if tables.iter().any(|other| other.data.id == file.data.id) {
+            problems.push(file.problem("id:", 0, format!("encounter table id {} is used twice", file.data.id)));
+        } else {
+            tables.push(file);
+        }
+    }
+    tables
+}
+
+fn check_tilesets<T>(file: &DataFile<T>, tilesets: &[String], problems: &mut Vec<MapError>) {
+    for tileset in tilesets {
+        if get_atlas_name(tileset).is_none() {
+            problems.push(file.problem_at_string(tileset, format!("tileset {} is not a texture atlas", tileset)));
+        }
+    }
+}
+
+/// Reports the first tile rolling each encounter table that does not exist.
+fn check_encounter_tables(file: &DataFile<Map>, table_ids: &HashSet<u32>, problems: &mut Vec<MapError>) {
+    let mut reported = HashSet::new();
+    let tiles = file.data.layers.iter().flat_map(|layer| &layer.tiles);
+    for (index, tile) in tiles.enumerate() {
+        for id in &tile.properties.encounter_tables {
+            if !table_ids.contains(id) && reported.insert(*id) {
+                problems.push(file.problem("position:", index, format!("tile ({}, {}) rolls the unknown encounter table {}", tile.position[0], tile.position[1], id)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_assets(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("malkmusl_validate_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn shipped_assets_are_valid() {
+        let validation = validate_assets(Path::new("src/assets"));
+        assert_eq!(validation.problems, Vec::new());
+        assert!(validation.maps >= 2 && validation.species == 3 && validation.moves >= 77 && validation.encounter_tables >= 1);
+    }
+
+    #[test]
+    fn reports_every_problem_with_its_line() {
+        let root = temp_assets("broken");
+        let species = fs::read_to_string("src/assets/pokemon/gen_1/1-3/ivysaur.ron").unwrap();
+        write(&root, "pokemon/ivysaur.ron", &species.replace("\"venusaur\"", "\"venusaurr\"").replace("\"toxic\"", "\"tackle\"").replace("\"amnesia\"", "\"amnezia\""));
+        write(&root, "pokemon/bulbasaur.ron", &fs::read_to_string("src/assets/pokemon/gen_1/1-3/bulbasaur.ron").unwrap());
+        for path in move_files(Path::new("src/assets/moves")).unwrap() {
+            write(&root, &format!("moves/{}", path.file_name().unwrap().to_string_lossy()), &fs::read_to_string(&path).unwrap());
+        }
+        write(&root, "moves/zz_extra.ron", "[\n    (id: \"tackle\", names: (eng: \"Tackle\"), type: Normal, category: Physical, power: 40, pp: 35, target: Selected),\n]");
+        write(&root, "pokemon/broken.ron", "(\n    dex: (national: 9 generation: 1),\n)");
+        write(&root, "encounters/route.ron", "(\n    id: 1,\n    name: \"route\",\n    method: Grass,\n    slots: [\n        (species: \"missingno\", levels: (2, 4), weight: 1),\n    ],\n)");
+        write(&root, "maps/town/map.ron", r#"(
+    version: 1,
+    header: (name: "town", size: (2, 2), origin: (0, 0), tile_size: 32, tilesets: ["NOT_AN_ATLAS"]),
+    layers: [
+        (name: "ground", tiles: [
+            (position: (0, 0), texture: 1),
+            (position: (1, 0), texture: 1, properties: (encounter: 10, encounter_tables: [2])),
+        ]),
+    ],
+    warps: [
+        (tile: (0, 1), target: "nowhere", spawn: (0, 0)),
+        (tile: (1, 1), target: "sinnoh", spawn: (0, 0)),
+    ],
+    connections: [
+        (edge: Up, map: "sinnoh"),
+        (edge: Down, map: "nowhere"),
+    ],
+)"#);
+        write(&root, "maps/sinnoh/world.ron", "(version: 1, name: \"sinnoh\", chunk_size: 32, tile_size: 32, tilesets: [\"OUTSIDE_ATLAS\"], chunks: [])");
+
+        let validation = validate_assets(&root);
+        let problems: Vec<String> = validation.problems.iter().map(|problem| problem.to_string().replace(&root.display().to_string(), "")).collect();
+        assert_eq!(problems, [
+            "/encounters/route.ron:6: missingno is not a species",
+            "/maps/town/map.ron:3: tileset NOT_AN_ATLAS is not a texture atlas",
+            "/maps/town/map.ron:7: tile (1, 0) rolls the unknown encounter table 2",
+            "/maps/town/map.ron:11: warp (0, 1) leads to the unknown map nowhere",
+            "/maps/town/map.ron:15: the Up connection target sinnoh is a chunked world",
+            "/maps/town/map.ron:16: the Down connection joins the unknown map nowhere",
+            "/moves/zz_extra.ron:2: move id tackle is used twice",
+            "/pokemon/broken.ron:2:23: Expected comma",
+            "/pokemon/ivysaur.ron:47: evolution target venusaurr is not a species",
+            "/pokemon/ivysaur.ron:68: TM06 teaches tackle here but toxic in bulbasaur",
+            "/pokemon/ivysaur.ron:96: ivysaur learns the unknown move amnezia",
+        ]);
+        assert_eq!(validation.summary(), "Checked 2 maps, 2 species, 77 moves and 1 encounter tables: 11 problems");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+
+use super::map::{Map, Passable};
+
+/// The longest slide over ice, so a map of nothing but ice cannot hang a step.
+const MAX_SLIDE: u32 = 256;
+
+/// One of the four grid directions, in the order of `DIRECTIONS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+#[allow(dead_code)]
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+
+    /// The grid offset of one step, y grows upwards.
+    pub fn offset(self) -> [i32; 2] {
+        match self {
+            Direction::Up => [0, 1],
+            Direction::Left => [-1, 0],
+            Direction::Down => [0, -1],
+            Direction::Right => [1, 0],
+        }
+    }
+
+    /// The index of the direction in `DIRECTIONS` and in key bindings.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Left => Direction::Right,
+            Direction::Down => Direction::Up,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// The direction of the longer axis of `vector`, `None` for a zero vector.
+    pub fn from_vector(vector: [f32; 2]) -> Option<Self> {
+        let [x, y] = vector;
+        if x == 0.0 && y == 0.0 {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 { Direction::Right } else { Direction::Left })
+        } else {
+            Some(if y > 0.0 { Direction::Up } else { Direction::Down })
+        }
+    }
+
+    /// The grid position `tiles` steps from `position` in this direction.
+    pub fn advance(self, position: [i32; 2], tiles: i32) -> [i32; 2] {
+        let [x, y] = self.offset();
+        [position[0] + x * tiles, position[1] + y * tiles]
+    }
+}
+
+/// The grid position of the tile containing the world position.
+pub fn tile_at(position: [f32; 2]) -> [i32; 2] {
+    [(position[0] / TILE_SIZE).floor() as i32, (position[1] / TILE_SIZE).floor() as i32]
+}
+
+/// The world position of the center of the tile.
+pub fn tile_center(tile: [i32; 2]) -> [f32; 2] {
+    [(tile[0] as f32 + 0.5) * TILE_SIZE, (tile[1] as f32 + 0.5) * TILE_SIZE]
+}
+
+/// What an entity is able to cross. Entities without the component can only walk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Traversal {
+    /// Enters `Passable::Water`.
+    pub surf: bool,
+}
+
+/// How a step moves the entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    Walk,
+    Surf,
+    /// Over a ledge, landing two tiles away.
+    Jump,
+    /// Over one or more ice tiles.
+    Slide,
+}
+
+/// Where a step ends and what the entity stands on afterwards, so callers can
+/// roll encounters on `TallGrass` or warp on a `Door`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub to: [i32; 2],
+    pub kind: StepKind,
+    pub entered: Passable,
+}
+
+/// Resolves a step from `from` in `direction` against the passability of the
+/// tiles, or returns `None` if the entity cannot move that way.
+///
+/// - `Normal`, `TallGrass`, `Door` and `Warp` are walked onto.
+/// - `Blocked` is never entered, `Water` only with `Traversal::surf`.
+/// - `Ledge(direction)` is only entered moving in its direction; the entity jumps over
+///   it and lands on the walkable tile behind it.
+/// - `Ice` is slid over in the step's direction until the entity reaches a tile
+///   that is no ice, or the next tile cannot be walked onto.
+pub fn step(from: [i32; 2], direction: Direction, traversal: Traversal, passable: impl Fn([i32; 2]) -> Passable) -> Option<Step> {
+    let next = direction.advance(from, 1);
+    match passable(next) {
+        Passable::Blocked => None,
+        Passable::Water if !traversal.surf => None,
+        Passable::Water => Some(Step { to: next, kind: StepKind::Surf, entered: Passable::Water }),
+        Passable::Ledge(jump) if jump == direction => {
+            let landing = direction.advance(next, 1);
+            let entered = passable(landing);
+            walkable(entered).then_some(Step { to: landing, kind: StepKind::Jump, entered })
+        }
+        Passable::Ledge(_) => None,
+        Passable::Ice => {
+            let mut to = next;
+            for _ in 0..MAX_SLIDE {
+                let ahead = direction.advance(to, 1);
+                if passable(to) != Passable::Ice || !walkable(passable(ahead)) {
+                    break;
+                }
+                to = ahead;
+            }
+            Some(Step { to, kind: StepKind::Slide, entered: passable(to) })
+        }
+        entered => Some(Step { to: next, kind: StepKind::Walk, entered }),
+    }
+}
+
+/// Whether a tile can be walked onto without surfing or jumping.
+fn walkable(passable: Passable) -> bool {
+    matches!(passable, Passable::Normal | Passable::TallGrass | Passable::Ice | Passable::Door | Passable::Warp)
+}
+
+/// The passability of every tile of a map.
+///
+/// Positions without a tile in any layer are `Blocked`, so nothing walks off the map.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CollisionMap {
+    tiles: HashMap<[i32; 2], Passable>,
+}
+
+#[allow(dead_code)]
+impl CollisionMap {
+    /// Collects the passability of the tiles of all layers. Of the tiles at one
+    /// position the last non-`Normal` one in layer order wins.
+    pub fn from_map(map: &Map) -> Self {
+        let mut collision = CollisionMap::default();
+        for tile in map.layers.iter().flat_map(|layer| &layer.tiles) {
+            let passable = tile.properties.passable;
+            let entry = collision.tiles.entry(tile.position).or_default();
+            if passable != Passable::Normal {
+                *entry = passable;
+            }
+        }
+        collision
+    }
+
+    pub fn passable(&self, position: [i32; 2]) -> Passable {
+        self.tiles.get(&position).copied().unwrap_or(Passable::Blocked)
+    }
+
+    pub fn set(&mut self, position: [i32; 2], passable: Passable) {
+        self.tiles.insert(position, passable);
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Estimated heap memory in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.tiles.capacity() * std::mem::size_of::<([i32; 2], Passable)>()
+    }
+
+    /// Resolves a step on this map, see `step`.
+    pub fn step(&self, from: [i32; 2], direction: Direction, traversal: Traversal) -> Option<Step> {
+        step(from, direction, traversal, |position| self.passable(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::world::map::{LayerKind, MapLayer, MapTile, TileProperties};
+
+    const WALK: Traversal = Traversal { surf: false };
+    const SURF: Traversal = Traversal { surf: true };
+
+    /// Builds a collision map from rows of characters, the first row at the top
+    /// and the bottom-left character at (0, 0).
+    ///
+    /// `.` normal, `#` blocked, `~` water, `v` `^` `<` `>` ledges, `"` tall grass,
+    /// `i` ice, `D` door, `W` warp.
+    fn grid(rows: &[&str]) -> CollisionMap {
+        let mut collision = CollisionMap::default();
+        for (row, line) in rows.iter().rev().enumerate() {
+            for (column, symbol) in line.chars().enumerate() {
+                let passable = match symbol {
+                    '.' => Passable::Normal,
+                    '#' => Passable::Blocked,
+                    '~' => Passable::Water,
+                    'v' => Passable::Ledge(Direction::Down),
+                    '^' => Passable::Ledge(Direction::Up),
+                    '<' => Passable::Ledge(Direction::Left),
+                    '>' => Passable::Ledge(Direction::Right),
+                    '"' => Passable::TallGrass,
+                    'i' => Passable::Ice,
+                    'D' => Passable::Door,
+                    'W' => Passable::Warp,
+                    other => panic!("unknown tile {:?}", other),
+                };
+                collision.set([column as i32, row as i32], passable);
+            }
+        }
+        collision
+    }
+
+    fn walk(to: [i32; 2]) -> Option<Step> {
+        Some(Step { to, kind: StepKind::Walk, entered: Passable::Normal })
+    }
+
+    #[test]
+    fn normal_tiles_are_walked_onto() {
+        let collision = grid(&["..."]);
+        assert_eq!(collision.step([0, 0], Direction::Right, WALK), walk([1, 0]));
+        assert_eq!(collision.step([1, 0], Direction::Left, WALK), walk([0, 0]));
+    }
+
+    #[test]
+    fn blocked_tiles_and_the_void_stop_movement() {
+        let collision = grid(&[".#."]);
+        assert_eq!(collision.step([0, 0], Direction::Right, WALK), None);
+        assert_eq!(collision.step([0, 0], Direction::Right, SURF), None);
+        assert_eq!(collision.step([0, 0], Direction::Up, WALK), None, "no tile above the map");
+        assert_eq!(collision.step([0, 0], Direction::Left, WALK), None);
+    }
+
+    #[test]
+    fn water_needs_surf() {
+        let collision = grid(&[".~~."]);
+        assert_eq!(collision.step([0, 0], Direction::Right, WALK), None);
+        assert_eq!(collision.step([0, 0], Direction::Right, SURF), Some(Step { to: [1, 0], kind: StepKind::Surf, entered: Passable::Water }));
+        assert_eq!(collision.step([1, 0], Direction::Right, SURF).unwrap().kind, StepKind::Surf);
+        assert_eq!(collision.step([2, 0], Direction::Right, SURF), walk([3, 0]), "surfers go ashore");
+    }
+
+    #[test]
+    fn ledges_are_jumped_down_in_one_direction() {
+        let collision = grid(&[
+            "..",
+            "v#",
+            "..",
+        ]);
+        assert_eq!(collision.step([0, 2], Direction::Down, WALK), Some(Step { to: [0, 0], kind: StepKind::Jump, entered: Passable::Normal }));
+        assert_eq!(collision.step([0, 0], Direction::Up, WALK), None, "ledges cannot be climbed");
+        assert_eq!(collision.step([1, 2], Direction::Left, WALK), walk([0, 2]));
+
+        let sideways = grid(&[".v."]);
+        assert_eq!(sideways.step([0, 0], Direction::Right, WALK), None);
+
+        let blocked_landing = grid(&[".", "v", "#"]);
+        assert_eq!(blocked_landing.step([0, 2], Direction::Down, WALK), None);
+    }
+
+    #[test]
+    fn tall_grass_is_walked_onto() {
+        let collision = grid(&[".\""]);
+        assert_eq!(collision.step([0, 0], Direction::Right, WALK), Some(Step { to: [1, 0], kind: StepKind::Walk, entered: Passable::TallGrass }));
+    }
+
+    #[test]
+    fn ice_slides_until_blocked_or_off_the_ice() {
+        let collision = grid(&[".iii#", ".ii.."]);
+        // Against the wall the slide stops on the last ice tile.
+        assert_eq!(collision.step([0, 1], Direction::Right, WALK), Some(Step { to: [3, 1], kind: StepKind::Slide, entered: Passable::Ice }));
+        // Leaving the ice the slide stops on the first tile after it.
+        assert_eq!(collision.step([0, 0], Direction::Right, WALK), Some(Step { to: [3, 0], kind: StepKind::Slide, entered: Passable::Normal }));
+        // Standing on ice next to the wall the entity can still turn around.
+        assert_eq!(collision.step([3, 1], Direction::Right, WALK), None);
+        assert_eq!(collision.step([3, 1], Direction::Left, WALK).unwrap().to, [0, 1]);
+    }
+
+    #[test]
+    fn doors_and_warps_are_entered() {
+        let collision = grid(&["D.W"]);
+        assert_eq!(collision.step([1, 0], Direction::Left, WALK).unwrap().entered, Passable::Door);
+        assert_eq!(collision.step([1, 0], Direction::Right, WALK).unwrap().entered, Passable::Warp);
+    }
+
+    #[test]
+    fn later_layers_decide_the_passability() {
+        let tile = |position: [i32; 2], passable: Passable| MapTile {
+            position,
+            tileset: None,
+            texture: 0,
+            properties: TileProperties { passable, ..TileProperties::default() },
+        };
+        let mut map = Map::new("collision", [2, 1], [0, 0], 32, vec!["OUTSIDE_ATLAS".to_owned()]);
+        map.layers.push(MapLayer { name: "ground".to_owned(), kind: LayerKind::Ground, tiles: vec![tile([0, 0], Passable::Water), tile([1, 0], Passable::Normal)] });
+        map.layers.push(MapLayer { name: "bridge".to_owned(), kind: LayerKind::Decoration, tiles: vec![tile([0, 0], Passable::Normal), tile([1, 0], Passable::Blocked)] });
+
+        let collision = CollisionMap::from_map(&map);
+        assert_eq!(collision.passable([0, 0]), Passable::Water, "normal tiles do not override");
+        assert_eq!(collision.passable([1, 0]), Passable::Blocked);
+        assert_eq!(collision.passable([2, 0]), Passable::Blocked);
+
+        let source = "(version: 1, header: (name: \"ledges\", size: (1, 1), tile_size: 32, tilesets: [\"OUTSIDE_ATLAS\"]), layers: [(name: \"ground\", tiles: [(position: (0, 0), texture: 0, properties: (passable: Ledge(Down)))])])";
+        let parsed = Map::parse(source, "ledges.ron").unwrap();
+        assert_eq!(CollisionMap::from_map(&parsed).passable([0, 0]), Passable::Ledge(Direction::Down));
+    }
+}
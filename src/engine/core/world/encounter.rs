@@ -0,0 +1,565 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::assets_loader::loader::asset_root;
+use crate::engine::console_logger::logger;
+use crate::engine::core::creature::species::MAX_LEVEL;
+use crate::engine::core::ecs::components::{GridMover, InputControlled};
+use crate::engine::core::ecs::events::Events;
+use crate::engine::core::ecs::input::InputState;
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::time::calendar::{DayPhase, WorldTime};
+
+use super::map::{key_lines, ron_options, Map, MapError, Passable};
+use super::plugin::LoadedMap;
+
+/// Directory below the asset root holding one encounter table per file.
+pub const ENCOUNTER_DIR_NAME: &str = "encounters";
+
+/// How the player meets the species of a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EncounterMethod {
+    /// Walking on land: tall grass, caves and other encounter tiles.
+    Grass,
+    /// Surfing on water.
+    Surf,
+    /// Fishing from the tile in front of the player.
+    Fishing,
+}
+
+/// One species of an encounter table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncounterSlot {
+    pub species: String,
+    /// Lowest and highest level, both inclusive.
+    pub levels: (u32, u32),
+    /// Relative chance against the other slots of the tables rolled.
+    pub weight: u32,
+    /// The day phases the species appears in, every phase if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time: Vec<DayPhase>,
+}
+
+/// The species met with one method in an area, referenced by id from the
+/// `encounter_tables` of map tiles:
+///
+/// ```ron
+/// (
+///     id: 1,
+///     name: "route_201_grass",
+///     method: Grass,
+///     slots: [
+///         (species: "bulbasaur", levels: (2, 4), weight: 60),
+///         (species: "ivysaur", levels: (5, 6), weight: 10, time: [Night]),
+///     ],
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncounterTable {
+    pub id: u32,
+    pub name: String,
+    pub method: EncounterMethod,
+    pub slots: Vec<EncounterSlot>,
+}
+
+#[allow(dead_code)]
+impl EncounterTable {
+    /// Parses and validates a table file, `path` is only used in errors.
+    pub fn parse(source: &str, path: &str) -> Result<Self, MapError> {
+        let table: EncounterTable = ron_options().from_str(source)
+            .map_err(|err| MapError::at(path, err.position.line, Some(err.position.col), err.code.to_string()))?;
+        if table.slots.is_empty() {
+            return Err(MapError::new(path, format!("encounter table {} has no slots", table.id)));
+        }
+        let lines = key_lines(source, "species:");
+        for (index, slot) in table.slots.iter().enumerate() {
+            let (min, max) = slot.levels;
+            let problem = if slot.weight == 0 {
+                Some(format!("{} has weight 0", slot.species))
+            } else if min == 0 || min > max || max > MAX_LEVEL {
+                Some(format!("{} has the level range ({}, {}), expected 1 <= min <= max <= {}", slot.species, min, max, MAX_LEVEL))
+            } else {
+                None
+            };
+            if let Some(message) = problem {
+                return Err(match lines.get(index) {
+                    Some(line) => MapError::at(path, *line, None, message),
+                    None => MapError::new(path, message),
+                });
+            }
+        }
+        Ok(table)
+    }
+
+    pub fn load(path: &str) -> Result<Self, MapError> {
+        let source = fs::read_to_string(path).map_err(|err| MapError::new(path, format!("cannot read the file: {}", err)))?;
+        Self::parse(&source, path)
+    }
+
+    /// The slots that appear during `phase`.
+    pub fn slots_at(&self, phase: DayPhase) -> impl Iterator<Item = &EncounterSlot> {
+        self.slots.iter().filter(move |slot| slot.time.is_empty() || slot.time.contains(&phase))
+    }
+}
+
+/// Every `.ron` file of `dir` in file name order.
+pub fn table_files(dir: &Path) -> Result<Vec<PathBuf>, MapError> {
+    let name = dir.display().to_string();
+    let entries = fs::read_dir(dir).map_err(|err| MapError::new(&name, format!("cannot read the directory: {}", err)))?;
+    let mut paths: Vec<_> = entries.filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Every encounter table by id, a `World` resource.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EncounterTables {
+    tables: BTreeMap<u32, EncounterTable>,
+}
+
+#[allow(dead_code)]
+impl EncounterTables {
+    /// Loads every `.ron` file of `dir`, in file name order.
+    pub fn load_dir(dir: &Path) -> Result<Self, MapError> {
+        let mut tables = EncounterTables::default();
+        for path in table_files(dir)? {
+            let path = path.display().to_string();
+            let table = EncounterTable::load(&path)?;
+            if tables.tables.contains_key(&table.id) {
+                return Err(MapError::new(&path, format!("encounter table id {} is used twice", table.id)));
+            }
+            tables.insert(table);
+        }
+        Ok(tables)
+    }
+
+    /// Loads `assets/encounters/`, an empty set of tables if the directory does not exist.
+    pub fn load_default() -> Result<Self, MapError> {
+        let dir = format!("{}/{}", asset_root(), ENCOUNTER_DIR_NAME);
+        if !Path::new(&dir).is_dir() {
+            return Ok(EncounterTables::default());
+        }
+        Self::load_dir(Path::new(&dir))
+    }
+
+    pub fn insert(&mut self, table: EncounterTable) {
+        self.tables.insert(table.id, table);
+    }
+
+    pub fn get(&self, id: u32) -> Option<&EncounterTable> {
+        self.tables.get(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EncounterTable> {
+        self.tables.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Rolls for a wild encounter on `zone`.
+    ///
+    /// The encounter happens with the zone's rate in percent. The species is
+    /// drawn by weight from the slots of the zone's tables of `method` that
+    /// appear during `phase`, its level evenly from the slot's range. An
+    /// active `repel` suppresses species below its level.
+    pub fn roll(&self, zone: &EncounterZone, method: EncounterMethod, phase: DayPhase, repel: &Repel, rng: &mut impl Rng) -> Option<WildEncounter> {
+        if rng.gen_range(0..100) >= zone.rate {
+            return None;
+        }
+        let slots: Vec<(u32, &EncounterSlot)> = zone.tables.iter()
+            .filter_map(|id| self.get(*id))
+            .filter(|table| table.method == method)
+            .flat_map(|table| table.slots_at(phase).map(move |slot| (table.id, slot)))
+            .collect();
+        let total: u32 = slots.iter().map(|(_, slot)| slot.weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = rng.gen_range(0..total);
+        let (table, slot) = slots.into_iter()
+            .find(|(_, slot)| {
+                let hit = pick < slot.weight;
+                pick = pick.saturating_sub(slot.weight);
+                hit
+            })
+            .expect("the pick lies below the total weight");
+        let level = rng.gen_range(slot.levels.0..=slot.levels.1);
+        if repel.suppresses(level) {
+            return None;
+        }
+        Some(WildEncounter { species: slot.species.clone(), level, method, table })
+    }
+}
+
+/// The encounter rate and tables of a tile, see `TileProperties::encounter`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncounterZone {
+    /// Chance in percent per step.
+    pub rate: u32,
+    pub tables: Vec<u32>,
+}
+
+/// The encounter zones of a map.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EncounterMap {
+    zones: HashMap<[i32; 2], EncounterZone>,
+}
+
+#[allow(dead_code)]
+impl EncounterMap {
+    /// Collects the tiles with a non-zero encounter rate. Of the tiles at one
+    /// position the last one in layer order with a rate wins.
+    pub fn from_map(map: &Map) -> Self {
+        let mut encounters = EncounterMap::default();
+        for tile in map.layers.iter().flat_map(|layer| &layer.tiles) {
+            if let Some(rate) = tile.properties.encounter.filter(|rate| *rate > 0) {
+                let zone = EncounterZone { rate, tables: tile.properties.encounter_tables.clone() };
+                encounters.zones.insert(tile.position, zone);
+            }
+        }
+        encounters
+    }
+
+    pub fn get(&self, position: [i32; 2]) -> Option<&EncounterZone> {
+        self.zones.get(&position)
+    }
+
+    pub fn set(&mut self, position: [i32; 2], zone: EncounterZone) {
+        self.zones.insert(position, zone);
+    }
+
+    pub fn len(&self) -> usize {
+        self.zones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Estimated heap memory in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.zones.capacity() * std::mem::size_of::<([i32; 2], EncounterZone)>()
+            + self.zones.values().map(|zone| zone.tables.capacity() * std::mem::size_of::<u32>()).sum::<usize>()
+    }
+}
+
+/// Keeps wild species below `level` away for the next `steps` steps, a `World` resource.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Repel {
+    pub steps: u32,
+    /// Usually the level of the first party member.
+    pub level: u32,
+}
+
+#[allow(dead_code)]
+impl Repel {
+    pub fn new(steps: u32, level: u32) -> Self {
+        Repel { steps, level }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.steps > 0
+    }
+
+    pub fn suppresses(&self, level: u32) -> bool {
+        self.is_active() && level < self.level
+    }
+
+    /// Counts down one step, returns `true` on the step the repel wears off.
+    pub fn step(&mut self) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        self.steps -= 1;
+        self.steps == 0
+    }
+}
+
+/// The random number generator of encounter rolls, a `World` resource.
+pub struct EncounterRng(pub StdRng);
+
+#[allow(dead_code)]
+impl EncounterRng {
+    /// Seeded with `seed` for reproducible rolls, from the OS otherwise.
+    pub fn new(seed: Option<u64>) -> Self {
+        match seed {
+            Some(seed) => EncounterRng(StdRng::seed_from_u64(seed)),
+            None => EncounterRng(StdRng::from_entropy()),
+        }
+    }
+}
+
+/// A wild species the player met.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WildEncounter {
+    pub species: String,
+    pub level: u32,
+    pub method: EncounterMethod,
+    /// The id of the table the species was drawn from.
+    pub table: u32,
+}
+
+/// Sent when the player meets a wild species, see `roll_encounters`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncounterTriggered {
+    pub encounter: WildEncounter,
+    /// The tile the player stepped on, or fished in.
+    pub tile: [i32; 2],
+}
+
+/// Rolls for an encounter every time an `InputControlled` `GridMover`
+/// finishes a step onto an encounter tile, surfing on water and walking
+/// anywhere else, or presses its fish key while standing still and facing an
+/// encounter tile of water, and sends `EncounterTriggered`. Every step counts
+/// down an active `Repel`.
+pub fn roll_encounters(world: &mut World) {
+    let input = world.resource::<InputState>();
+    let mut steps = Vec::new();
+    let mut casts = Vec::new();
+    world.query::<(&GridMover, &InputControlled)>(|_, (mover, keys)| {
+        steps.extend(mover.finished);
+        let fishing = keys.fish.is_some_and(|key| input.as_ref().is_some_and(|input| input.just_pressed(key)));
+        if fishing && mover.step.is_none() {
+            casts.push(mover.facing.advance(mover.tile, 1));
+        }
+    });
+    drop(input);
+    if steps.is_empty() && casts.is_empty() {
+        return;
+    }
+    let (Some(map), Some(tables), Some(mut rng), Some(mut events)) = (
+        world.resource::<LoadedMap>(),
+        world.resource::<EncounterTables>(),
+        world.resource_mut::<EncounterRng>(),
+        world.resource_mut::<Events<EncounterTriggered>>(),
+    ) else {
+        return;
+    };
+    let phase = world.resource::<WorldTime>().map_or(DayPhase::Day, |time| time.phase());
+    let mut repel = world.resource_mut::<Repel>();
+
+    for step in steps {
+        if let Some(repel) = repel.as_mut() {
+            if repel.step() {
+                println!("{}", logger::info("The repel wore off"));
+            }
+        }
+        let Some(zone) = map.encounter_zone(step.to) else {
+            continue;
+        };
+        let method = if step.entered == Passable::Water { EncounterMethod::Surf } else { EncounterMethod::Grass };
+        let repel = repel.as_deref().copied().unwrap_or_default();
+        if let Some(encounter) = tables.roll(zone, method, phase, &repel, &mut rng.0) {
+            println!("{}", logger::info(&format!("A wild {} (level {}) appeared", encounter.species, encounter.level)));
+            events.send(EncounterTriggered { encounter, tile: step.to });
+        }
+    }
+
+    for tile in casts {
+        let Some(zone) = map.encounter_zone(tile).filter(|_| map.passable(tile) == Some(Passable::Water)) else {
+            continue;
+        };
+        let repel = repel.as_deref().copied().unwrap_or_default();
+        match tables.roll(zone, EncounterMethod::Fishing, phase, &repel, &mut rng.0) {
+            Some(encounter) => {
+                println!("{}", logger::info(&format!("A wild {} (level {}) took the bait", encounter.species, encounter.level)));
+                events.send(EncounterTriggered { encounter, tile });
+            }
+            None => println!("{}", logger::info("Not even a nibble")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::world::collision::{CollisionMap, Direction, Step, StepKind};
+    use glium::glutin::event::VirtualKeyCode;
+
+    const GRASS: &str = r#"(
+        id: 1,
+        name: "grass",
+        method: Grass,
+        slots: [
+            (species: "bulbasaur", levels: (2, 4), weight: 60),
+            (species: "ivysaur", levels: (10, 10), weight: 30),
+            (species: "venusaur", levels: (30, 32), weight: 10, time: [Night]),
+        ],
+    )"#;
+
+    const SURF: &str = r#"(id: 2, name: "surf", method: Surf, slots: [(species: "squirtle", levels: (5, 5), weight: 1)])"#;
+    const FISHING: &str = r#"(id: 3, name: "fishing", method: Fishing, slots: [(species: "magikarp", levels: (5, 10), weight: 1)])"#;
+
+    fn tables() -> EncounterTables {
+        let mut tables = EncounterTables::default();
+        tables.insert(EncounterTable::parse(GRASS, "grass.ron").unwrap());
+        tables.insert(EncounterTable::parse(SURF, "surf.ron").unwrap());
+        tables.insert(EncounterTable::parse(FISHING, "fishing.ron").unwrap());
+        tables
+    }
+
+    fn zone(rate: u32) -> EncounterZone {
+        EncounterZone { rate, tables: vec![1, 2, 3] }
+    }
+
+    /// Species counts of `rolls` rolls with a fixed seed.
+    fn roll_many(rate: u32, phase: DayPhase, repel: Repel, rolls: u32) -> BTreeMap<String, u32> {
+        let tables = tables();
+        let mut rng = EncounterRng::new(Some(7));
+        let mut counts = BTreeMap::new();
+        for _ in 0..rolls {
+            if let Some(encounter) = tables.roll(&zone(rate), EncounterMethod::Grass, phase, &repel, &mut rng.0) {
+                *counts.entry(encounter.species).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    fn share(counts: &BTreeMap<String, u32>, species: &str, total: u32) -> f64 {
+        counts.get(species).copied().unwrap_or(0) as f64 / total as f64
+    }
+
+    #[test]
+    fn rejects_invalid_tables_with_the_line() {
+        let broken = "(\n    id: 3,\n    name: \"broken\",\n    method: Grass,\n    slots: [\n        (species: \"a\", levels: (1, 2), weight: 1),\n        (species: \"b\", levels: (5, 2), weight: 1),\n    ],\n)";
+        let err = EncounterTable::parse(broken, "broken.ron").unwrap_err();
+        assert_eq!(err.line, Some(7));
+        assert!(err.message.contains("level range"), "{}", err);
+
+        let err = EncounterTable::parse(&broken.replace("(5, 2), weight: 1", "(1, 2), weight: 0"), "broken.ron").unwrap_err();
+        assert!(err.message.contains("weight 0"), "{}", err);
+        assert!(EncounterTable::parse("(id: 3, name: \"x\", method: Walking, slots: [])", "x.ron").is_err());
+    }
+
+    #[test]
+    fn encounter_rate_matches_the_tile() {
+        let rolls = 20_000;
+        let total: u32 = roll_many(20, DayPhase::Day, Repel::default(), rolls).values().sum();
+        let rate = total as f64 / rolls as f64;
+        assert!((rate - 0.2).abs() < 0.015, "rate {}", rate);
+        assert!(roll_many(0, DayPhase::Day, Repel::default(), 1000).is_empty());
+    }
+
+    #[test]
+    fn species_follow_their_weights_and_time_of_day() {
+        let rolls = 20_000;
+        let day = roll_many(100, DayPhase::Day, Repel::default(), rolls);
+        assert!((share(&day, "bulbasaur", rolls) - 60.0 / 90.0).abs() < 0.02, "{:?}", day);
+        assert!((share(&day, "ivysaur", rolls) - 30.0 / 90.0).abs() < 0.02, "{:?}", day);
+        assert!(!day.contains_key("venusaur"), "venusaur only appears at night");
+        assert!(!day.contains_key("squirtle"), "surf tables are not rolled in grass");
+
+        let night = roll_many(100, DayPhase::Night, Repel::default(), rolls);
+        assert!((share(&night, "venusaur", rolls) - 0.1).abs() < 0.02, "{:?}", night);
+    }
+
+    #[test]
+    fn levels_cover_the_slot_range() {
+        let tables = tables();
+        let mut rng = EncounterRng::new(Some(1));
+        let mut levels = std::collections::BTreeSet::new();
+        for _ in 0..1000 {
+            let encounter = tables.roll(&zone(100), EncounterMethod::Surf, DayPhase::Day, &Repel::default(), &mut rng.0).unwrap();
+            assert_eq!((encounter.species.as_str(), encounter.level, encounter.table), ("squirtle", 5, 2));
+            let encounter = tables.roll(&EncounterZone { rate: 100, tables: vec![1] }, EncounterMethod::Grass, DayPhase::Morning, &Repel::default(), &mut rng.0).unwrap();
+            if encounter.species == "bulbasaur" {
+                levels.insert(encounter.level);
+            }
+        }
+        assert_eq!(levels.into_iter().collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn repel_suppresses_weaker_species_until_it_wears_off() {
+        let rolls = 10_000;
+        let repelled = roll_many(100, DayPhase::Day, Repel::new(5, 10), rolls);
+        assert!(!repelled.contains_key("bulbasaur"), "{:?}", repelled);
+        assert!((share(&repelled, "ivysaur", rolls) - 30.0 / 90.0).abs() < 0.02, "{:?}", repelled);
+
+        let mut repel = Repel::new(2, 10);
+        assert!(!repel.step());
+        assert!(repel.step());
+        assert!(!repel.suppresses(2));
+    }
+
+    #[test]
+    fn seeded_rolls_repeat() {
+        assert_eq!(roll_many(30, DayPhase::Day, Repel::default(), 500), roll_many(30, DayPhase::Day, Repel::default(), 500));
+    }
+
+    #[test]
+    fn stepping_on_grass_sends_an_event() {
+        let mut encounters = EncounterMap::default();
+        encounters.set([1, 0], zone(100));
+        let mut world = World::new();
+        world.insert_resource(LoadedMap { encounters: Some(encounters), ..LoadedMap::placeholder() });
+        world.insert_resource(tables());
+        world.insert_resource(EncounterRng::new(Some(3)));
+        world.insert_resource(Events::<EncounterTriggered>::default());
+        world.insert_resource(Repel::new(1, 1));
+
+        let mut mover = GridMover::at([0.0, 0.0], 4.0);
+        mover.finished = Some(Step { to: [1, 0], kind: StepKind::Walk, entered: Passable::TallGrass });
+        world.spawn().with(mover).with(InputControlled::wasd(0.0));
+        roll_encounters(&mut world);
+
+        let events = world.resource::<Events<EncounterTriggered>>().unwrap();
+        let event = events.iter().next().expect("an encounter with rate 100");
+        assert_eq!(event.tile, [1, 0]);
+        assert_eq!(event.encounter.method, EncounterMethod::Grass);
+        assert!(!world.resource::<Repel>().unwrap().is_active());
+    }
+
+    #[test]
+    fn fishing_rolls_the_water_in_front() {
+        let mut encounters = EncounterMap::default();
+        encounters.set([1, 0], zone(100));
+        encounters.set([0, -1], zone(100));
+        let mut collision = CollisionMap::default();
+        collision.set([1, 0], Passable::Water);
+        collision.set([0, -1], Passable::TallGrass);
+        let mut world = World::new();
+        world.insert_resource(LoadedMap { collision: Some(collision), encounters: Some(encounters), ..LoadedMap::placeholder() });
+        world.insert_resource(tables());
+        world.insert_resource(EncounterRng::new(Some(3)));
+        world.insert_resource(Events::<EncounterTriggered>::default());
+        world.insert_resource(InputState::new());
+
+        let mut mover = GridMover::at([0.0, 0.0], 4.0);
+        mover.facing = Direction::Right;
+        let angler = world.spawn().with(mover).with(InputControlled::wasd(0.0).with_fish_key(VirtualKeyCode::F)).id();
+        roll_encounters(&mut world);
+        assert!(world.resource::<Events<EncounterTriggered>>().unwrap().is_empty(), "fishing without pressing the key");
+
+        // (facing, tiles of the encounters so far)
+        let table = [(Direction::Right, vec![[1, 0]]), (Direction::Down, vec![[1, 0]])];
+        for (facing, tiles) in table {
+            world.get_mut::<GridMover>(angler).unwrap().facing = facing;
+            world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::F);
+            roll_encounters(&mut world);
+            world.resource_mut::<InputState>().unwrap().end_tick();
+            world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::F);
+
+            let events = world.resource::<Events<EncounterTriggered>>().unwrap();
+            assert_eq!(events.iter().map(|event| event.tile).collect::<Vec<_>>(), tiles, "{:?}", facing);
+            assert!(events.iter().all(|event| event.encounter.method == EncounterMethod::Fishing && event.encounter.species == "magikarp"));
+        }
+    }
+}
@@ -0,0 +1,728 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::assets_loader::loader::asset_root;
+
+use super::collision::Direction;
+
+/// The map schema version written by this engine, see `migrate` for older files.
+pub const CURRENT_MAP_VERSION: u32 = 1;
+
+/// File name of a map inside its directory below `assets/maps/`.
+pub const MAP_FILE_NAME: &str = "map.ron";
+
+/// A map as stored in `assets/maps/<name>/map.ron`.
+///
+/// ```ron
+/// (
+///     version: 1,
+///     header: (
+///         name: "twinleaf_town",
+///         size: (15, 15),
+///         origin: (-7, -7),
+///         tile_size: 32,
+///         tilesets: ["OUTSIDE_ATLAS"],
+///     ),
+///     layers: [
+///         (
+///             name: "ground",
+///             tiles: [
+///                 (position: (-7, -7), texture: 1),
+///                 (position: (-7, -6), texture: 31, properties: (encounter: 10, encounter_tables: [1])),
+///             ],
+///         ),
+///         (
+///             name: "roofs",
+///             kind: Overhead,
+///             tiles: [(position: (-7, -5), texture: 40)],
+///         ),
+///     ],
+///     warps: [(tile: (-4, 2), kind: Door, target: "player_house", spawn: (2, 0), facing: Up)],
+///     connections: [(edge: Up, map: "route_201", offset: -3)],
+///     npcs: [(tile: (2, -3), name: "Rival", properties: {"dialogue": "rival_intro"})],
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Map {
+    pub version: u32,
+    pub header: MapHeader,
+    pub layers: Vec<MapLayer>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warps: Vec<Warp>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connections: Vec<Connection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub npcs: Vec<NpcSpawn>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapHeader {
+    pub name: String,
+    /// Width and height in tiles.
+    pub size: [u32; 2],
+    /// Grid position of the bottom-left tile, tiles cover `origin` to `origin + size - 1`.
+    #[serde(default)]
+    pub origin: [i32; 2],
+    /// Edge length of a tile in the tileset images, in pixels.
+    pub tile_size: u32,
+    /// The texture atlases the tiles use, named as in `get_texture_atlas`.
+    /// Tiles without a tileset use the first one.
+    pub tilesets: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapLayer {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "LayerKind::is_default")]
+    pub kind: LayerKind,
+    pub tiles: Vec<MapTile>,
+}
+
+/// Where a layer is drawn relative to the entities. Layers of the same kind
+/// are drawn in file order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LayerKind {
+    /// Below every entity, e.g. grass, paths and water.
+    #[default]
+    Ground,
+    /// Y-sorted together with the entities, e.g. tree trunks, fences and signs.
+    Decoration,
+    /// Above every entity, e.g. tree tops and roofs.
+    Overhead,
+}
+
+impl LayerKind {
+    pub fn is_default(&self) -> bool {
+        *self == LayerKind::default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MapTile {
+    pub position: [i32; 2],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tileset: Option<String>,
+    /// Index of the texture in its tileset.
+    pub texture: u32,
+    #[serde(default, skip_serializing_if = "TileProperties::is_default")]
+    pub properties: TileProperties,
+}
+
+/// Gameplay properties of a tile. Every field is optional in map files.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TileProperties {
+    #[serde(skip_serializing_if = "Passable::is_default")]
+    pub passable: Passable,
+    /// Chance in percent to trigger an encounter when stepping on the tile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encounter: Option<u32>,
+    /// Ids of the encounter tables rolled on an encounter.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub encounter_tables: Vec<u32>,
+    /// Game specific properties, e.g. imported from Tiled.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, PropertyValue>,
+}
+
+impl TileProperties {
+    pub fn is_default(&self) -> bool {
+        *self == TileProperties::default()
+    }
+}
+
+/// How entities may enter a tile, see `collision::step` for the rules.
+///
+/// Of the tiles at one position, the last layer with a non-`Normal` tile decides.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Passable {
+    #[default]
+    #[serde(alias = "NORMAL")]
+    Normal,
+    /// Walls, trees and anything else nobody walks through.
+    Blocked,
+    /// Only entered by entities that can surf.
+    Water,
+    /// Jumped down in the given direction, landing on the tile behind it.
+    /// It cannot be entered from any other side.
+    Ledge(Direction),
+    /// Walkable, rolls for wild encounters.
+    TallGrass,
+    /// Entities keep sliding in their direction until they leave the ice or hit an obstacle.
+    Ice,
+    /// Walkable, leads into a building.
+    Door,
+    /// Walkable, moves the entity to another map.
+    Warp,
+}
+
+impl Passable {
+    pub fn is_default(&self) -> bool {
+        *self == Passable::default()
+    }
+}
+
+/// A tile moving whoever steps on it to a spawn point on another map, behind a fade.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Warp {
+    pub tile: [i32; 2],
+    #[serde(default, skip_serializing_if = "WarpKind::is_default")]
+    pub kind: WarpKind,
+    /// The map directory below `assets/maps/` to warp to.
+    pub target: String,
+    /// The tile of the target map the player arrives on.
+    pub spawn: [i32; 2],
+    /// The direction the player faces on arrival, unchanged if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facing: Option<Direction>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WarpKind {
+    #[default]
+    Door,
+    Stairs,
+    Cave,
+}
+
+impl WarpKind {
+    pub fn is_default(&self) -> bool {
+        *self == WarpKind::default()
+    }
+}
+
+/// Another map joined seamlessly to one edge of this map, e.g. a route north of a town.
+///
+/// The connected map lies right behind the `edge`, moved along it by `offset`
+/// tiles: the first tile row or column of both maps line up for offset 0.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Connection {
+    pub edge: Direction,
+    /// The map directory below `assets/maps/`.
+    pub map: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub offset: i32,
+}
+
+fn is_zero(value: &i32) -> bool {
+    *value == 0
+}
+
+impl Connection {
+    /// What to add to grid positions of the map `to` to place it at this
+    /// connection of the map `from`.
+    pub fn translation(&self, from: &MapHeader, to: &MapHeader) -> [i32; 2] {
+        let [from_x, from_y] = from.origin;
+        let [from_width, from_height] = [from.size[0] as i32, from.size[1] as i32];
+        let [to_x, to_y] = to.origin;
+        let [to_width, to_height] = [to.size[0] as i32, to.size[1] as i32];
+        match self.edge {
+            Direction::Up => [from_x + self.offset - to_x, from_y + from_height - to_y],
+            Direction::Down => [from_x + self.offset - to_x, from_y - to_height - to_y],
+            Direction::Right => [from_x + from_width - to_x, from_y + self.offset - to_y],
+            Direction::Left => [from_x - to_width - to_x, from_y + self.offset - to_y],
+        }
+    }
+}
+
+/// Where the game spawns a character of the map, e.g. imported from a Tiled object layer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NpcSpawn {
+    pub tile: [i32; 2],
+    pub name: String,
+    /// Game specific settings, e.g. the dialogue or the walking pattern.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+/// The value of a custom tile property.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+/// A map or other game data file that cannot be loaded, pointing at the offending line where known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapError {
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl MapError {
+    pub fn new(path: &str, message: impl Into<String>) -> Self {
+        MapError { path: path.to_owned(), line: None, column: None, message: message.into() }
+    }
+
+    pub fn at(path: &str, line: usize, column: Option<usize>, message: impl Into<String>) -> Self {
+        MapError { path: path.to_owned(), line: Some(line), column, message: message.into() }
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}:{}: {}", self.path, line, column, self.message),
+            (Some(line), None) => write!(f, "{}:{}: {}", self.path, line, self.message),
+            _ => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+impl Error for MapError {}
+
+/// A rule a parsed map breaks, see `Map::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapProblem {
+    /// Index of the offending tile counted over all layers in file order.
+    pub tile: Option<usize>,
+    /// Index of the offending warp.
+    pub warp: Option<usize>,
+    pub message: String,
+}
+
+/// Only reads the version, so files of other versions get a clear error.
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: Option<u32>,
+}
+
+impl MapHeader {
+    /// Whether the grid position lies inside the map.
+    pub fn contains(&self, position: [i32; 2]) -> bool {
+        let [x, y] = position;
+        let [origin_x, origin_y] = self.origin;
+        let [width, height] = self.size;
+        x >= origin_x && y >= origin_y && ((x - origin_x) as u32) < width && ((y - origin_y) as u32) < height
+    }
+}
+
+/// Returns the path of the map directory `name` below `assets/maps/`.
+pub fn map_path(name: &str) -> String {
+    format!("{}/maps/{}/{}", asset_root(), name, MAP_FILE_NAME)
+}
+
+#[allow(dead_code)]
+impl Map {
+    /// Creates an empty map using `tilesets`.
+    pub fn new(name: &str, size: [u32; 2], origin: [i32; 2], tile_size: u32, tilesets: Vec<String>) -> Self {
+        Map {
+            version: CURRENT_MAP_VERSION,
+            header: MapHeader { name: name.to_owned(), size, origin, tile_size, tilesets },
+            layers: Vec::new(),
+            warps: Vec::new(),
+            connections: Vec::new(),
+            npcs: Vec::new(),
+        }
+    }
+
+    /// Loads the map directory `name` below `assets/maps/`, e.g. `Map::load_named("twinleaf_town")`.
+    pub fn load_named(name: &str) -> Result<Self, MapError> {
+        Self::load(&map_path(name))
+    }
+
+    /// Loads and validates a map file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// match Map::load("./src/assets/maps/twinleaf_town/map.ron") {
+    ///     Ok(map) => println!("{} has {} layers", map.header.name, map.layers.len()),
+    ///     // e.g. "./src/assets/maps/twinleaf_town/map.ron:12:34: tile (20, 3) lies outside the map"
+    ///     Err(err) => eprintln!("{}", err),
+    /// }
+    /// ```
+    pub fn load(path: &str) -> Result<Self, MapError> {
+        let source = fs::read_to_string(path).map_err(|err| MapError::new(path, format!("cannot read the file: {}", err)))?;
+        Self::parse(&source, path)
+    }
+
+    /// Parses and validates the content of a map file, `path` is only used in errors.
+    pub fn parse(source: &str, path: &str) -> Result<Self, MapError> {
+        if source.trim_start().starts_with('[') {
+            return Err(MapError::at(path, 1, None, "legacy map format without version, upgrade it with `migrate-map`"));
+        }
+        if let Ok(VersionProbe { version: Some(version) }) = ron_options().from_str::<VersionProbe>(source) {
+            if version > CURRENT_MAP_VERSION {
+                return Err(MapError::new(path, format!("map version {} is newer than the supported version {}", version, CURRENT_MAP_VERSION)));
+            }
+            if version < CURRENT_MAP_VERSION {
+                return Err(MapError::new(path, format!("map version {} is outdated, upgrade it with `migrate-map`", version)));
+            }
+        }
+
+        let map: Map = ron_options().from_str(source)
+            .map_err(|err| MapError::at(path, err.position.line, Some(err.position.col), err.code.to_string()))?;
+
+        let line = |problem: &MapProblem| match (problem.tile, problem.warp) {
+            (Some(tile), _) => tile_lines(source).get(tile).copied(),
+            (None, Some(warp)) => key_lines(source, "spawn:").get(warp).copied(),
+            (None, None) => None,
+        };
+        map.validate().map_err(|problem| match line(&problem) {
+            Some(line) => MapError::at(path, line, None, problem.message),
+            None => MapError::new(path, problem.message),
+        })?;
+        Ok(map)
+    }
+
+    /// Checks what the schema cannot express: a non-empty size, declared and
+    /// unique tilesets and layer names, tiles inside the map without
+    /// duplicates per layer, warps inside the map on distinct tiles, npcs
+    /// inside the map and at most one connection per edge.
+    pub fn validate(&self) -> Result<(), MapProblem> {
+        let problem = |tile: Option<usize>, message: String| Err(MapProblem { tile, warp: None, message });
+        let header = &self.header;
+
+        if header.size[0] == 0 || header.size[1] == 0 {
+            return problem(None, format!("map size {}x{} is empty", header.size[0], header.size[1]));
+        }
+        if header.tile_size == 0 {
+            return problem(None, "tile_size must not be 0".to_owned());
+        }
+        if header.tilesets.is_empty() {
+            return problem(None, "the header lists no tilesets".to_owned());
+        }
+        let mut tilesets = HashSet::new();
+        for tileset in &header.tilesets {
+            if !tilesets.insert(tileset.as_str()) {
+                return problem(None, format!("tileset {} is listed twice", tileset));
+            }
+        }
+
+        let mut layer_names = HashSet::new();
+        let mut index = 0;
+        for layer in &self.layers {
+            if layer.name.is_empty() || !layer_names.insert(layer.name.as_str()) {
+                return problem(None, format!("layer name '{}' is empty or used twice", layer.name));
+            }
+            let mut positions = HashSet::new();
+            for tile in &layer.tiles {
+                let [x, y] = tile.position;
+                if !self.contains(tile.position) {
+                    return problem(Some(index), format!("tile ({}, {}) in layer {} lies outside the map", x, y, layer.name));
+                }
+                if !positions.insert(tile.position) {
+                    return problem(Some(index), format!("layer {} has two tiles at ({}, {})", layer.name, x, y));
+                }
+                if let Some(tileset) = &tile.tileset {
+                    if !tilesets.contains(tileset.as_str()) {
+                        return problem(Some(index), format!("tile ({}, {}) uses tileset {}, which the header does not list", x, y, tileset));
+                    }
+                }
+                index += 1;
+            }
+        }
+
+        let mut warp_tiles = HashSet::new();
+        for (index, warp) in self.warps.iter().enumerate() {
+            let [x, y] = warp.tile;
+            let message = if !self.contains(warp.tile) {
+                format!("warp ({}, {}) lies outside the map", x, y)
+            } else if !warp_tiles.insert(warp.tile) {
+                format!("two warps on tile ({}, {})", x, y)
+            } else if warp.target.is_empty() {
+                format!("warp ({}, {}) has no target map", x, y)
+            } else {
+                continue;
+            };
+            return Err(MapProblem { tile: None, warp: Some(index), message });
+        }
+        for npc in &self.npcs {
+            if !self.contains(npc.tile) {
+                return problem(None, format!("npc {} at ({}, {}) lies outside the map", npc.name, npc.tile[0], npc.tile[1]));
+            }
+        }
+        let mut edges = HashSet::new();
+        for connection in &self.connections {
+            if !edges.insert(connection.edge) {
+                return problem(None, format!("two connections on the {:?} edge", connection.edge));
+            }
+            if connection.map.is_empty() || connection.map == header.name {
+                return problem(None, format!("the {:?} connection needs another map, not '{}'", connection.edge, connection.map));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the grid position lies inside the map.
+    pub fn contains(&self, position: [i32; 2]) -> bool {
+        self.header.contains(position)
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&MapLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// The tileset of `tile`, the first one of the header unless the tile names its own.
+    pub fn tileset_of<'a>(&'a self, tile: &'a MapTile) -> &'a str {
+        tile.tileset.as_deref().or(self.header.tilesets.first().map(String::as_str)).unwrap_or_default()
+    }
+
+    /// The map moved by `offset` tiles: its origin, tiles, warps and npcs.
+    pub fn translated(&self, offset: [i32; 2]) -> Map {
+        let shift = |position: [i32; 2]| [position[0] + offset[0], position[1] + offset[1]];
+        let mut map = self.clone();
+        map.header.origin = shift(map.header.origin);
+        for tile in map.layers.iter_mut().flat_map(|layer| layer.tiles.iter_mut()) {
+            tile.position = shift(tile.position);
+        }
+        for warp in &mut map.warps {
+            warp.tile = shift(warp.tile);
+        }
+        for npc in &mut map.npcs {
+            npc.tile = shift(npc.tile);
+        }
+        map
+    }
+
+    /// Serializes the map with one tile per line.
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        let config = ron::ser::PrettyConfig::new().depth_limit(4).indentor("    ".to_owned());
+        ron_options().to_string_pretty(self, config)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, self.to_ron()? + "\n")?;
+        Ok(())
+    }
+}
+
+/// Map files write optional values without `Some(..)`, e.g. `encounter: 10`.
+pub fn ron_options() -> ron::Options {
+    ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+}
+
+/// The 1-based line of every `position:` key in `source`, which is the line of
+/// the n-th tile as tiles are the only entries with a position.
+fn tile_lines(source: &str) -> Vec<usize> {
+    key_lines(source, "position:")
+}
+
+/// The 1-based line of every occurrence of `key` in `source`, used to point
+/// validation errors of RON files at the n-th entry with that key. Comments,
+/// string and char literals are skipped, as is `key` in the middle of a longer
+/// identifier, e.g. `species_id:` for `id:`.
+pub fn key_lines(source: &str, key: &str) -> Vec<usize> {
+    let bytes = source.as_bytes();
+    let mut lines = Vec::new();
+    scan_ron(source, |line, index| {
+        let after_ident = index > 0 && (bytes[index - 1].is_ascii_alphanumeric() || bytes[index - 1] == b'_');
+        if after_ident || !bytes[index..].starts_with(key.as_bytes()) {
+            return 0;
+        }
+        lines.push(line);
+        key.len()
+    }, |_, _| {});
+    lines
+}
+
+/// The 1-based line of every string literal `"value"` in `source`, outside of
+/// comments, e.g. to point at a reference to an unknown id.
+pub fn string_lines(source: &str, value: &str) -> Vec<usize> {
+    let quoted = format!("\"{}\"", value);
+    let mut lines = Vec::new();
+    scan_ron(source, |_, _| 0, |line, literal| {
+        if literal == quoted.as_bytes() {
+            lines.push(line);
+        }
+    });
+    lines
+}
+
+/// Walks `source` skipping comments. `code` gets the line and byte index of
+/// every position outside comments and literals and returns how many bytes it
+/// consumed, `literal` gets the line and text, quotes included, of every
+/// string and char literal.
+fn scan_ron(source: &str, mut code: impl FnMut(usize, usize) -> usize, mut literal: impl FnMut(usize, &[u8])) {
+    let bytes = source.as_bytes();
+    let mut line = 1;
+    let mut index = 0;
+    while index < bytes.len() {
+        let rest = &bytes[index..];
+        let after_ident = index > 0 && (bytes[index - 1].is_ascii_alphanumeric() || bytes[index - 1] == b'_');
+        let literal_len = match rest[0] {
+            b'"' | b'\'' => Some(quoted_len(rest, rest[0])),
+            b'r' if !after_ident => raw_string_len(rest),
+            _ => None,
+        };
+        let skipped = if rest.starts_with(b"//") {
+            rest.iter().position(|&byte| byte == b'\n').unwrap_or(rest.len())
+        } else if rest.starts_with(b"/*") {
+            block_comment_len(rest)
+        } else if let Some(len) = literal_len {
+            literal(line, &rest[..len]);
+            len
+        } else {
+            code(line, index)
+        };
+        if skipped == 0 {
+            line += (rest[0] == b'\n') as usize;
+            index += 1;
+        } else {
+            line += rest[..skipped].iter().filter(|&&byte| byte == b'\n').count();
+            index += skipped;
+        }
+    }
+}
+
+/// Length of the, possibly nested, block comment `source` starts with.
+fn block_comment_len(source: &[u8]) -> usize {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < source.len() {
+        if source[index..].starts_with(b"/*") {
+            depth += 1;
+            index += 2;
+        } else if source[index..].starts_with(b"*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return index;
+            }
+        } else {
+            index += 1;
+        }
+    }
+    source.len()
+}
+
+/// Length of the string or char literal `source` starts with, `quote` being
+/// its first byte.
+fn quoted_len(source: &[u8], quote: u8) -> usize {
+    let mut index = 1;
+    while index < source.len() {
+        match source[index] {
+            b'\\' => index += 2,
+            byte if byte == quote => return index + 1,
+            _ => index += 1,
+        }
+    }
+    source.len()
+}
+
+/// Length of the raw string like `r#"..."#` that `source` starts with, `None`
+/// if it does not start with one.
+fn raw_string_len(source: &[u8]) -> Option<usize> {
+    let hashes = source[1..].iter().take_while(|&&byte| byte == b'#').count();
+    if source.get(1 + hashes) != Some(&b'"') {
+        return None;
+    }
+    let start = 2 + hashes;
+    let end = (start..source.len()).find(|&index| {
+        source[index] == b'"' && source[index + 1..].iter().take(hashes).filter(|&&byte| byte == b'#').count() == hashes
+    });
+    Some(end.map_or(source.len(), |end| end + 1 + hashes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"(
+    version: 1,
+    header: (name: "sample", size: (2, 2), origin: (-1, -1), tile_size: 32, tilesets: ["OUTSIDE_ATLAS", "CAVES_ATLAS"]),
+    layers: [
+        (name: "ground", kind: Ground, tiles: [
+            (position: (-1, -1), texture: 1),
+            (position: (0, -1), tileset: "CAVES_ATLAS", texture: 2, properties: (encounter: 10, custom: {"sign": "Welcome"})),
+        ]),
+    ],
+)"#;
+
+    #[test]
+    fn loads_a_map_and_round_trips_it() {
+        let map = Map::parse(SAMPLE, "sample.ron").unwrap();
+        let ground = map.layer("ground").unwrap();
+        assert_eq!(map.tileset_of(&ground.tiles[0]), "OUTSIDE_ATLAS");
+        assert_eq!(map.tileset_of(&ground.tiles[1]), "CAVES_ATLAS");
+        assert_eq!(ground.tiles[1].properties.custom["sign"], PropertyValue::String("Welcome".to_owned()));
+        assert!(map.contains([0, 0]) && !map.contains([1, 0]));
+
+        let saved = map.to_ron().unwrap();
+        assert_eq!(Map::parse(&saved, "saved.ron").unwrap(), map);
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        let typo = SAMPLE.replace("texture: 2", "textur: 2");
+        let err = Map::parse(&typo, "typo.ron").unwrap_err();
+        assert_eq!(err.line, Some(7));
+        assert!(err.to_string().starts_with("typo.ron:7:"), "{}", err);
+
+        let outside = SAMPLE.replace("(position: (0, -1)", "(position: (5, -1)");
+        let err = Map::parse(&outside, "outside.ron").unwrap_err();
+        assert_eq!(err.to_string(), "outside.ron:7: tile (5, -1) in layer ground lies outside the map");
+
+        let undeclared = SAMPLE.replace("tileset: \"CAVES_ATLAS\"", "tileset: \"BOAT_ATLAS\"");
+        assert!(Map::parse(&undeclared, "m.ron").unwrap_err().message.contains("BOAT_ATLAS"));
+    }
+
+    #[test]
+    fn key_lines_skip_comments_and_strings() {
+        let source = r##"[
+    // (level: 1, attack: "tackle")
+    (level: 5, note: "level: 9", sign: 'l'), /* level: 2
+       (level: 3) /* nested level: */ level: 4 */
+    (sublevel: 1, text: r#"level: "quoted" level:"#, level: 7),
+    (text: "escaped \" level: 8", level: 10),
+]"##;
+        assert_eq!(key_lines(source, "level:"), [3, 5, 6]);
+        assert!(string_lines(source, "tackle").is_empty());
+        assert_eq!(string_lines(source, "level: 9"), [3]);
+
+        // A sign text mentioning a position does not shift the later tiles.
+        let sign = SAMPLE.replace("texture: 1)", "texture: 1, properties: (custom: {\"sign\": \"Remember your position: (0, 0)\"}))")
+            .replace("(position: (0, -1)", "(position: (5, -1)");
+        let err = Map::parse(&sign, "sign.ron").unwrap_err();
+        assert_eq!(err.line, Some(7));
+    }
+
+    #[test]
+    fn rejects_other_versions_and_legacy_files() {
+        let err = Map::parse("[(position: (0, 0), texture: 1)]", "old.ron").unwrap_err();
+        assert!(err.message.contains("migrate-map"));
+
+        let newer = SAMPLE.replace("version: 1", "version: 7");
+        assert!(Map::parse(&newer, "new.ron").unwrap_err().message.contains("newer"));
+    }
+
+    #[test]
+    fn validates_warps_and_connections() {
+        let linked = SAMPLE.replace("    ],
+)", "    ],
+    warps: [
+        (tile: (-1, -1), target: \"house\", spawn: (0, 0)),
+        (tile: (0, -1), kind: Stairs, target: \"cellar\", spawn: (3, 3), facing: Down),
+    ],
+    connections: [(edge: Up, map: \"route\")],
+)");
+        let map = Map::parse(&linked, "linked.ron").unwrap();
+        assert_eq!(map.warps[1].kind, WarpKind::Stairs);
+        assert_eq!(map.warps[1].facing, Some(Direction::Down));
+        assert_eq!(Map::parse(&map.to_ron().unwrap(), "saved.ron").unwrap(), map);
+
+        let outside = linked.replace("(tile: (0, -1)", "(tile: (4, -1)");
+        assert_eq!(Map::parse(&outside, "m.ron").unwrap_err().to_string(), "m.ron:12: warp (4, -1) lies outside the map");
+        let twice = linked.replace("(tile: (0, -1)", "(tile: (-1, -1)");
+        assert!(Map::parse(&twice, "m.ron").unwrap_err().message.contains("two warps"));
+        let itself = linked.replace("map: \"route\"", "map: \"sample\"");
+        assert!(Map::parse(&itself, "m.ron").unwrap_err().message.contains("Up connection"));
+    }
+}
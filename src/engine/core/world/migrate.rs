@@ -0,0 +1,344 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::engine::core::cli::MigrateMapArgs;
+
+use super::map::{LayerKind, Map, MapError, MapLayer, MapTile, Passable, TileProperties, CURRENT_MAP_VERSION, MAP_FILE_NAME};
+
+/// Tileset of legacy tiles that do not name one.
+const LEGACY_TILESET: &str = "OUTSIDE_ATLAS";
+
+/// Tile size of every atlas in `texture_tilesets`, legacy files do not store one.
+const LEGACY_TILE_SIZE: u32 = 32;
+
+/// A tile of the unversioned layer files written by `generate_base_map_file`,
+/// e.g. `maps/twinleaf_town/layer_0.ron`.
+#[derive(Deserialize)]
+#[serde(rename = "TileData")]
+struct LegacyGridTile {
+    position: [i32; 2],
+    #[serde(default = "legacy_tileset")]
+    tileset: String,
+    texture: i32,
+    #[serde(default)]
+    passable: Passable,
+    #[serde(default = "no_encounter")]
+    encounter: i32,
+    #[serde(default)]
+    encounter_tables: Vec<i32>,
+}
+
+fn legacy_tileset() -> String {
+    LEGACY_TILESET.to_owned()
+}
+
+fn no_encounter() -> i32 {
+    -1
+}
+
+/// A tile of the first prototype maps, e.g. `maps/test.ron`, drawing a single
+/// image per tile at a world position given in tiles.
+#[derive(Deserialize)]
+#[serde(rename = "TileData")]
+struct LegacyImageTile {
+    /// Written as list, which fixed size arrays do not accept.
+    position: Vec<f32>,
+    texture: String,
+}
+
+/// The result of upgrading one map.
+pub struct Migration {
+    pub map: Map,
+    /// Where the upgraded map belongs.
+    pub output: PathBuf,
+    /// Legacy files merged into the map, removed once it is written.
+    pub replaced: Vec<PathBuf>,
+    /// Tiles and values that could not be carried over.
+    pub notes: Vec<String>,
+    /// Whether the input already had the current version.
+    pub up_to_date: bool,
+}
+
+/// Upgrades a map to `CURRENT_MAP_VERSION`.
+///
+/// `input` is either a map directory holding `layer_<n>.ron` files, which are
+/// merged into one `map.ron` with a layer each, or a single map file, which is
+/// upgraded in place. Nothing is written, see `Migration::write`.
+///
+/// # Example
+///
+/// ```rust
+/// let migration = migrate("./src/assets/maps/twinleaf_town")?;
+/// migration.write()?;
+/// ```
+pub fn migrate(input: &str) -> Result<Migration, MapError> {
+    let path = Path::new(input);
+    if path.is_dir() {
+        migrate_directory(path)
+    } else {
+        migrate_file(path)
+    }
+}
+
+/// Runs the `migrate-map` command: migrates `args.input` and writes it unless
+/// it is a dry run or already up to date.
+pub fn run_migrate_map(args: &MigrateMapArgs) -> Result<Migration, MapError> {
+    let mut migration = migrate(&args.input)?;
+    if let Some(output) = &args.output {
+        migration.output = PathBuf::from(output);
+        migration.up_to_date = false;
+    }
+    if !args.dry_run && !migration.up_to_date {
+        migration.write().map_err(|err| MapError::new(&migration.output.display().to_string(), err.to_string()))?;
+    }
+    Ok(migration)
+}
+
+fn migrate_directory(dir: &Path) -> Result<Migration, MapError> {
+    let dir_name = dir.display().to_string();
+    let output = dir.join(MAP_FILE_NAME);
+    if output.is_file() {
+        return migrate_file(&output);
+    }
+
+    let entries = fs::read_dir(dir).map_err(|err| MapError::new(&dir_name, format!("cannot read the directory: {}", err)))?;
+    let mut layer_files: Vec<(u32, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            let index = path.file_name()?.to_str()?.strip_prefix("layer_")?.strip_suffix(".ron")?.parse().ok()?;
+            Some((index, path))
+        })
+        .collect();
+    layer_files.sort();
+    if layer_files.is_empty() {
+        return Err(MapError::new(&dir_name, format!("neither {} nor layer_<n>.ron files found", MAP_FILE_NAME)));
+    }
+
+    let name = map_name(dir);
+    let mut converter = Converter::default();
+    let mut layers = Vec::new();
+    for (index, path) in &layer_files {
+        let file = path.display().to_string();
+        let source = read(&file)?;
+        layers.push(converter.layer(&format!("layer_{}", index), &source, &file)?);
+    }
+
+    let notes = std::mem::take(&mut converter.notes);
+    Ok(Migration {
+        map: converter.finish(&name, layers),
+        output,
+        replaced: layer_files.into_iter().map(|(_, path)| path).collect(),
+        notes,
+        up_to_date: false,
+    })
+}
+
+fn migrate_file(path: &Path) -> Result<Migration, MapError> {
+    let file = path.display().to_string();
+    let source = read(&file)?;
+
+    if !source.trim_start().starts_with('[') {
+        // Versioned maps; once there are older versions they are upgraded here.
+        let map = Map::parse(&source, &file)?;
+        return Ok(Migration { map, output: path.to_path_buf(), replaced: Vec::new(), notes: Vec::new(), up_to_date: true });
+    }
+
+    let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("map") => path.parent().map(map_name).unwrap_or_default(),
+        Some(stem) => stem.to_owned(),
+        None => String::new(),
+    };
+    let mut converter = Converter::default();
+    let layer = converter.layer("layer_0", &source, &file)?;
+    let notes = std::mem::take(&mut converter.notes);
+    Ok(Migration {
+        map: converter.finish(&name, vec![layer]),
+        output: path.to_path_buf(),
+        replaced: Vec::new(),
+        notes,
+        up_to_date: false,
+    })
+}
+
+fn read(file: &str) -> Result<String, MapError> {
+    fs::read_to_string(file).map_err(|err| MapError::new(file, format!("cannot read the file: {}", err)))
+}
+
+fn map_name(dir: &Path) -> String {
+    dir.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_owned()
+}
+
+/// Collects the tilesets and bounds of the legacy layers of one map.
+#[derive(Default)]
+struct Converter {
+    tilesets: Vec<String>,
+    /// The lowest and highest tile position seen so far.
+    bounds: Option<([i32; 2], [i32; 2])>,
+    notes: Vec<String>,
+}
+
+impl Converter {
+    fn layer(&mut self, name: &str, source: &str, file: &str) -> Result<MapLayer, MapError> {
+        let tiles = match ron::de::from_str::<Vec<LegacyGridTile>>(source) {
+            Ok(tiles) => self.grid_tiles(tiles, file),
+            Err(grid_err) => match ron::de::from_str::<Vec<LegacyImageTile>>(source) {
+                Ok(tiles) => self.image_tiles(tiles, file),
+                Err(_) => return Err(MapError::at(file, grid_err.position.line, Some(grid_err.position.col), grid_err.code.to_string())),
+            },
+        };
+        Ok(MapLayer { name: name.to_owned(), kind: LayerKind::Ground, tiles })
+    }
+
+    fn grid_tiles(&mut self, tiles: Vec<LegacyGridTile>, file: &str) -> Vec<MapTile> {
+        let mut converted = Vec::with_capacity(tiles.len());
+        for tile in tiles {
+            let [x, y] = tile.position;
+            let Ok(texture) = u32::try_from(tile.texture) else {
+                self.notes.push(format!("{}: dropped the empty tile ({}, {}) with texture {}", file, x, y, tile.texture));
+                continue;
+            };
+            let encounter_tables = tile.encounter_tables.iter().filter_map(|&table| {
+                let table = u32::try_from(table).ok();
+                if table.is_none() {
+                    self.notes.push(format!("{}: dropped a negative encounter table of tile ({}, {})", file, x, y));
+                }
+                table
+            }).collect();
+            let properties = TileProperties {
+                passable: tile.passable,
+                encounter: u32::try_from(tile.encounter).ok(),
+                encounter_tables,
+                ..TileProperties::default()
+            };
+            converted.push(self.tile(tile.position, tile.tileset, texture, properties));
+        }
+        converted
+    }
+
+    fn image_tiles(&mut self, tiles: Vec<LegacyImageTile>, file: &str) -> Vec<MapTile> {
+        self.notes.push(format!("{}: image tiles become single texture tilesets named after the image", file));
+        tiles.into_iter().map(|tile| {
+            let coordinate = |axis: usize| tile.position.get(axis).copied().unwrap_or_default().round() as i32;
+            let position = [coordinate(0), coordinate(1)];
+            self.tile(position, tile.texture, 0, TileProperties::default())
+        }).collect()
+    }
+
+    fn tile(&mut self, position: [i32; 2], tileset: String, texture: u32, properties: TileProperties) -> MapTile {
+        let [x, y] = position;
+        let ([min_x, min_y], [max_x, max_y]) = self.bounds.unwrap_or((position, position));
+        self.bounds = Some(([min_x.min(x), min_y.min(y)], [max_x.max(x), max_y.max(y)]));
+
+        if !self.tilesets.contains(&tileset) {
+            self.tilesets.push(tileset.clone());
+        }
+        MapTile { position, tileset: Some(tileset), texture, properties }
+    }
+
+    /// Builds the map, leaving the tileset of tiles using the first tileset implicit.
+    fn finish(mut self, name: &str, mut layers: Vec<MapLayer>) -> Map {
+        if self.tilesets.is_empty() {
+            self.tilesets.push(LEGACY_TILESET.to_owned());
+        }
+        let (origin, max) = self.bounds.unwrap_or(([0, 0], [0, 0]));
+        let size = [(max[0] - origin[0] + 1) as u32, (max[1] - origin[1] + 1) as u32];
+
+        for tile in layers.iter_mut().flat_map(|layer| layer.tiles.iter_mut()) {
+            if tile.tileset.as_deref() == Some(self.tilesets[0].as_str()) {
+                tile.tileset = None;
+            }
+        }
+        let mut map = Map::new(name, size, origin, LEGACY_TILE_SIZE, self.tilesets);
+        map.layers = layers;
+        map
+    }
+}
+
+impl Migration {
+    /// Validates and writes the map to `output`, then removes the legacy files it replaces.
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        let output = self.output.display().to_string();
+        self.map.validate().map_err(|problem| MapError::new(&output, problem.message))?;
+        if let Some(parent) = self.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.map.save(&output)?;
+        for path in &self.replaced {
+            if *path != self.output {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A one-line summary for the `migrate-map` command.
+    pub fn summary(&self) -> String {
+        if self.up_to_date {
+            return format!("{} is already at map version {}", self.output.display(), CURRENT_MAP_VERSION);
+        }
+        let tiles: usize = self.map.layers.iter().map(|layer| layer.tiles.len()).sum();
+        format!(
+            "{} -> version {}: {} layers, {} tiles, {}x{} tiles, tilesets {}",
+            self.output.display(), CURRENT_MAP_VERSION, self.map.layers.len(), tiles,
+            self.map.header.size[0], self.map.header.size[1], self.map.header.tilesets.join(", "),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(source: &str) -> (Map, Vec<String>) {
+        let mut converter = Converter::default();
+        let layer = converter.layer("layer_0", source, "legacy.ron").unwrap();
+        let notes = std::mem::take(&mut converter.notes);
+        (converter.finish("legacy", vec![layer]), notes)
+    }
+
+    #[test]
+    fn upgrades_legacy_layer_files() {
+        let (map, notes) = convert(r#"[
+            (position: (-1, -1), sprite_size: 0.1, texture: 1, passable: NORMAL, encounter: -1, encounter_tables: []),
+            (position: (1, 0), tileset: "CAVES_ATLAS", texture: 4, passable: NORMAL, encounter: 10, encounter_tables: [2]),
+            (position: (0, 0), texture: -1, passable: NORMAL, encounter: -1, encounter_tables: []),
+        ]"#);
+
+        assert_eq!(map.version, CURRENT_MAP_VERSION);
+        assert_eq!(map.header.origin, [-1, -1]);
+        assert_eq!(map.header.size, [3, 2]);
+        assert_eq!(map.header.tilesets, ["OUTSIDE_ATLAS", "CAVES_ATLAS"]);
+        let tiles = &map.layers[0].tiles;
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tiles[0].tileset, None);
+        assert!(tiles[0].properties.is_default());
+        assert_eq!(tiles[1].properties.encounter, Some(10));
+        assert_eq!(tiles[1].properties.encounter_tables, [2]);
+        assert_eq!(notes.len(), 1, "{:?}", notes);
+
+        let saved = map.to_ron().unwrap();
+        assert_eq!(Map::parse(&saved, "map.ron").unwrap(), map);
+    }
+
+    #[test]
+    fn upgrades_legacy_image_tiles() {
+        let (map, _) = convert(r#"[
+            TileData(position: [0.0, 0.0], sprite_size: 0.1, texture: "moss_block.png"),
+            TileData(position: [2.0, 0.0], sprite_size: 0.1, texture: "apple.png"),
+        ]"#);
+        assert_eq!(map.header.tilesets, ["moss_block.png", "apple.png"]);
+        assert_eq!(map.header.size, [3, 1]);
+        assert_eq!(map.layers[0].tiles[1].tileset.as_deref(), Some("apple.png"));
+        assert!(map.validate().is_ok());
+    }
+
+    #[test]
+    fn reports_the_line_of_broken_legacy_files() {
+        let mut converter = Converter::default();
+        let err = converter.layer("layer_0", "[\n    (position: (0, 0), texture: 1),\n    (position: (0, 1) texture: 1),\n]", "broken.ron").unwrap_err();
+        assert_eq!(err.line, Some(3));
+    }
+}
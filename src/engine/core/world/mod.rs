@@ -1 +1,10 @@
-pub mod map_gen;
\ No newline at end of file
+pub mod collision;
+pub mod encounter;
+pub mod map;
+pub mod map_gen;
+pub mod migrate;
+pub mod movement;
+pub mod plugin;
+pub mod streaming;
+pub mod tiled;
+pub mod warp;
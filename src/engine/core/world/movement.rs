@@ -0,0 +1,240 @@
+use crate::engine::core::ecs::components::{GridMover, Transform};
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::time::clock::GameClock;
+
+use super::collision::{tile_center, Direction, Step, StepKind, Traversal};
+use super::map::Passable;
+use super::plugin::LoadedMap;
+
+/// Seconds a standing entity has to hold a direction it just turned to before it walks.
+pub const TURN_DELAY: f32 = 0.1;
+
+/// Walks every `GridMover` for the clock's delta and places its `Transform`
+/// between the tile centers of its step.
+///
+/// A finished step is followed by the buffered direction or the intent in the
+/// same tick, so holding a direction walks at an even speed. Steps are
+/// resolved on the `LoadedMap`, see `collision::step`; jumps and slides take
+/// as long as walking the tiles they cross. Without a map every step is a walk.
+pub fn grid_movement_system(world: &mut World) {
+    let Some(dt) = world.resource::<GameClock>().map(|clock| clock.delta_seconds()) else {
+        return;
+    };
+    let map = world.resource::<LoadedMap>();
+    world.query::<(&mut GridMover, &mut Transform, Option<&Traversal>)>(|_, (mover, transform, traversal)| {
+        let traversal = traversal.copied().unwrap_or_default();
+        let resolve = |from: [i32; 2], direction: Direction| match &map {
+            Some(map) => map.step(from, direction, traversal),
+            None => Some(Step { to: direction.advance(from, 1), kind: StepKind::Walk, entered: Passable::Normal }),
+        };
+        advance(mover, dt, resolve);
+
+        transform.previous_position = transform.position;
+        transform.position = match mover.step {
+            Some((step, progress)) => {
+                let (from, to) = (tile_center(mover.tile), tile_center(step.to));
+                [from[0] + (to[0] - from[0]) * progress, from[1] + (to[1] - from[1]) * progress]
+            }
+            None => tile_center(mover.tile),
+        };
+    });
+}
+
+/// Moves `mover` on by `time` seconds, starting new steps with `resolve`.
+fn advance(mover: &mut GridMover, mut time: f32, resolve: impl Fn([i32; 2], Direction) -> Option<Step>) {
+    // Whether a step ended this tick, the next one then starts without turning first.
+    let mut walking = false;
+    mover.finished = None;
+    loop {
+        if mover.step.is_none() {
+            let Some(direction) = mover.buffered.take().or(mover.intent) else {
+                mover.turn_delay = 0.0;
+                return;
+            };
+            if direction != mover.facing && !walking {
+                mover.facing = direction;
+                mover.turn_delay = TURN_DELAY;
+            }
+            if mover.turn_delay > 0.0 {
+                if time < mover.turn_delay {
+                    mover.turn_delay -= time;
+                    return;
+                }
+                time -= mover.turn_delay;
+                mover.turn_delay = 0.0;
+            }
+            mover.facing = direction;
+            match resolve(mover.tile, direction) {
+                Some(step) => mover.step = Some((step, 0.0)),
+                None => return,
+            }
+        }
+
+        let (tile, speed) = (mover.tile, mover.current_speed());
+        let Some((step, progress)) = mover.step.as_mut() else {
+            return;
+        };
+        let tiles = ((step.to[0] - tile[0]).abs() + (step.to[1] - tile[1]).abs()) as f32;
+        let speed = speed / tiles;
+        let remaining = (1.0 - *progress) / speed;
+        if time < remaining {
+            *progress += time * speed;
+            return;
+        }
+        time -= remaining;
+        mover.tile = step.to;
+        mover.finished = Some(*step);
+        mover.step = None;
+        walking = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use glium::glutin::event::VirtualKeyCode;
+
+    use super::*;
+    use crate::engine::core::ecs::components::InputControlled;
+    use crate::engine::core::ecs::input::InputState;
+    use crate::engine::core::ecs::systems::input_system;
+    use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+    use crate::engine::core::world::collision::CollisionMap;
+
+    const SPEED: f32 = 4.0;
+
+    fn world(facing: Direction) -> (World, crate::engine::core::ecs::world::Entity) {
+        let mut world = World::new();
+        world.insert_resource(GameClock::new());
+        world.insert_resource(InputState::new());
+        let mut mover = GridMover::at([0.0, 0.0], SPEED);
+        mover.facing = facing;
+        let player = world.spawn()
+            .with(Transform::at(0.0, 0.0))
+            .with(mover)
+            .with(InputControlled::wasd(0.0).with_run_key(VirtualKeyCode::LShift))
+            .id();
+        (world, player)
+    }
+
+    fn tick(world: &mut World, seconds: f32) {
+        world.resource_mut::<GameClock>().unwrap().advance(Duration::from_secs_f32(seconds));
+        input_system(world);
+        grid_movement_system(world);
+        world.resource_mut::<InputState>().unwrap().end_tick();
+    }
+
+    fn mover(world: &World, entity: crate::engine::core::ecs::world::Entity) -> GridMover {
+        *world.get::<GridMover>(entity).unwrap()
+    }
+
+    fn assert_near(actual: [f32; 2], expected: [f32; 2]) {
+        assert!((actual[0] - expected[0]).abs() < 1e-4 && (actual[1] - expected[1]).abs() < 1e-4, "expected {:?} got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn holding_a_direction_walks_tile_by_tile() {
+        let (mut world, player) = world(Direction::Right);
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::D);
+
+        // A quarter second per tile at 4 tiles per second, interpolated between tile centers.
+        tick(&mut world, 0.125);
+        assert!(mover(&world, player).is_moving());
+        assert_near(world.get::<Transform>(player).unwrap().position, [TILE_SIZE, TILE_SIZE * 0.5]);
+        tick(&mut world, 0.125);
+        tick(&mut world, 0.25);
+        assert_eq!(mover(&world, player).tile, [2, 0]);
+        assert_near(world.get::<Transform>(player).unwrap().position, tile_center([2, 0]));
+
+        // Letting go finishes the step in progress, then the entity stands on the tile grid.
+        tick(&mut world, 0.1);
+        world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::D);
+        tick(&mut world, 1.0);
+        assert!(!mover(&world, player).is_moving());
+        assert_eq!(mover(&world, player).tile, [3, 0]);
+        assert_near(world.get::<Transform>(player).unwrap().position, tile_center([3, 0]));
+    }
+
+    #[test]
+    fn a_tap_turns_in_place() {
+        let (mut world, player) = world(Direction::Down);
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::A);
+        tick(&mut world, 0.05);
+        world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::A);
+        tick(&mut world, 0.5);
+        assert_eq!(mover(&world, player).facing, Direction::Left);
+        assert_eq!(mover(&world, player).tile, [0, 0]);
+
+        // Holding the direction it faces walks at once.
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::A);
+        tick(&mut world, 0.125);
+        assert!(mover(&world, player).is_moving());
+        world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::A);
+        tick(&mut world, 0.125);
+        assert_eq!(mover(&world, player).tile, [-1, 0]);
+
+        // Holding a new direction walks after the turn delay.
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::W);
+        tick(&mut world, TURN_DELAY / 2.0);
+        assert!(!mover(&world, player).is_moving());
+        tick(&mut world, TURN_DELAY / 2.0 + 0.3);
+        assert_eq!(mover(&world, player).facing, Direction::Up);
+        assert_eq!(mover(&world, player).tile, [-1, 1]);
+    }
+
+    #[test]
+    fn running_doubles_the_speed() {
+        let (mut world, player) = world(Direction::Up);
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::LShift);
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::W);
+        tick(&mut world, 0.55);
+        assert_eq!(mover(&world, player).tile, [0, 4]);
+    }
+
+    #[test]
+    fn input_during_a_step_is_buffered() {
+        let (mut world, player) = world(Direction::Right);
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::D);
+        tick(&mut world, 0.1);
+        world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::D);
+
+        // Tapping up in the middle of the step walks up once the step ends, without turning first.
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::W);
+        tick(&mut world, 0.01);
+        world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::W);
+        tick(&mut world, 0.14);
+        assert_eq!(mover(&world, player).tile, [1, 0]);
+        assert_eq!(mover(&world, player).facing, Direction::Up);
+        tick(&mut world, 0.25);
+        assert_eq!(mover(&world, player).tile, [1, 1]);
+        tick(&mut world, 1.0);
+        assert_eq!(mover(&world, player).tile, [1, 1]);
+    }
+
+    #[test]
+    fn steps_follow_the_collision_rules() {
+        let (mut world, player) = world(Direction::Right);
+        let mut collision = CollisionMap::default();
+        collision.set([0, 0], Passable::Normal);
+        collision.set([1, 0], Passable::Ledge(Direction::Right));
+        collision.set([2, 0], Passable::Normal);
+        collision.set([0, 1], Passable::Blocked);
+        world.insert_resource(LoadedMap { collision: Some(collision), grid: (0, 0), ..LoadedMap::placeholder() });
+
+        // Walking into a wall only turns the entity.
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::W);
+        tick(&mut world, 0.5);
+        world.resource_mut::<InputState>().unwrap().release(VirtualKeyCode::W);
+        assert_eq!(mover(&world, player).tile, [0, 0]);
+        assert_eq!(mover(&world, player).facing, Direction::Up);
+
+        // Jumping the ledge crosses two tiles in the time of two steps.
+        world.resource_mut::<InputState>().unwrap().press(VirtualKeyCode::D);
+        tick(&mut world, TURN_DELAY + 0.25);
+        assert_eq!(mover(&world, player).step.unwrap().0.kind, StepKind::Jump);
+        tick(&mut world, 0.25);
+        assert_eq!(mover(&world, player).tile, [2, 0]);
+        assert_eq!(mover(&world, player).finished.map(|step| step.kind), Some(StepKind::Jump));
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::Path;
+
+use crate::engine::assets_loader::loader;
+use crate::engine::console_logger::logger;
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::Plugin;
+use crate::engine::core::app::schedule::{in_state, IntoSystemConfig, Stage};
+use crate::engine::core::app::AppState;
+use crate::engine::core::cli::LaunchOptions;
+use crate::engine::core::config;
+use crate::engine::core::ecs::components::{InputControlled, Transform, Velocity};
+use crate::engine::core::ecs::world::{With, World};
+use crate::engine::core::renderer::core::backend::{RenderBackend, Sprite};
+use crate::engine::core::renderer::d2::background_tiles::{BackgroundTiles, TILE_SIZE};
+use crate::engine::core::renderer::d2::tile_map::TileMap;
+
+use super::collision::{self, tile_at, CollisionMap, Direction, Step, StepKind, Traversal};
+use super::encounter::{roll_encounters, EncounterMap, EncounterRng, EncounterTables, EncounterTriggered, EncounterZone, Repel};
+use super::map::{Connection, LayerKind, Map, MapError, MapHeader, Passable, Warp};
+use super::movement::grid_movement_system;
+use super::streaming::{world_path, WorldStreamer};
+use super::warp::{map_transition_system, ConnectedMap};
+
+/// Rows and columns of the placeholder grid drawn when no map is loaded.
+pub const PLACEHOLDER_GRID: (usize, usize) = (10, 10);
+
+/// The map the game starts on, see `WorldPlugin::with_map`.
+pub struct StartMap(pub Option<String>);
+
+/// The tile layers of the loaded map as a `World` resource.
+#[allow(dead_code)]
+pub struct LoadedMap {
+    pub name: Option<String>,
+    /// The header of the loaded map, `None` without a map or for chunked worlds.
+    pub header: Option<MapHeader>,
+    pub tiles: TileMap,
+    /// Rows and columns of the placeholder grid drawn below the map tiles.
+    pub grid: (usize, usize),
+    /// The passability of the loaded map, `None` without a map or for chunked worlds.
+    pub collision: Option<CollisionMap>,
+    /// The encounter zones of the loaded map, `None` without a map or for chunked worlds.
+    pub encounters: Option<EncounterMap>,
+    pub warps: Vec<Warp>,
+    /// The maps joined to the edges of the loaded map, see `Connection`.
+    pub neighbours: Vec<ConnectedMap>,
+    /// Streams the chunks of a chunked world, see `WorldStreamer`.
+    pub streamer: Option<WorldStreamer>,
+}
+
+#[allow(dead_code)]
+impl LoadedMap {
+    /// The placeholder grid shown while no map is loaded.
+    pub fn placeholder() -> Self {
+        LoadedMap {
+            name: None,
+            header: None,
+            tiles: TileMap::default(),
+            grid: PLACEHOLDER_GRID,
+            collision: None,
+            encounters: None,
+            warps: Vec::new(),
+            neighbours: Vec::new(),
+            streamer: None,
+        }
+    }
+
+    /// Opens the map directory `name` below `assets/maps/` through the asset
+    /// loader together with the maps connected to its edges. A directory with
+    /// a `world.ron` is opened as a chunked world.
+    pub fn open(name: &str) -> Result<Self, MapError> {
+        if Path::new(&world_path(name)).exists() {
+            let settings = &config::config().world;
+            let streamer = WorldStreamer::open(name, settings.stream_radius, settings.memory_budget_bytes())?;
+            return Ok(LoadedMap { name: Some(name.to_owned()), grid: (0, 0), streamer: Some(streamer), ..LoadedMap::placeholder() });
+        }
+        let map = loader::load_map(name)?;
+        let mut loaded = LoadedMap::from_map(name, &map);
+        for connection in &map.connections {
+            match loader::load_map(&connection.map) {
+                Ok(neighbour) => loaded.connect(connection, &neighbour),
+                Err(err) => println!("{}", logger::warn_assets(&format!("Failed to load the map on the {:?} edge of {}: {}", connection.edge, name, err))),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// The loaded `map` without its connections, see `connect`.
+    pub fn from_map(name: &str, map: &Map) -> Self {
+        LoadedMap {
+            name: Some(name.to_owned()),
+            header: Some(map.header.clone()),
+            tiles: TileMap::from_map(map, TILE_SIZE),
+            grid: (0, 0),
+            collision: Some(CollisionMap::from_map(map)),
+            encounters: Some(EncounterMap::from_map(map)),
+            warps: map.warps.clone(),
+            neighbours: Vec::new(),
+            streamer: None,
+        }
+    }
+
+    /// Places `map` at `connection` of the loaded map, where it is drawn and walked on.
+    pub fn connect(&mut self, connection: &Connection, map: &Map) {
+        if let Some(header) = &self.header {
+            self.neighbours.push(ConnectedMap::new(connection, header, map));
+        }
+    }
+
+    /// The connected map covering the grid position outside the loaded map.
+    pub fn neighbour_at(&self, position: [i32; 2]) -> Option<&ConnectedMap> {
+        if self.header.as_ref().is_some_and(|header| header.contains(position)) {
+            return None;
+        }
+        self.neighbours.iter().find(|neighbour| neighbour.header.contains(position))
+    }
+
+    /// The passability of the grid position, `None` while no map is loaded.
+    pub fn passable(&self, position: [i32; 2]) -> Option<Passable> {
+        match (&self.streamer, &self.collision) {
+            (Some(streamer), _) => Some(streamer.passable(position)),
+            (None, Some(collision)) => Some(match self.neighbour_at(position) {
+                Some(neighbour) => neighbour.collision.passable(position),
+                None => collision.passable(position),
+            }),
+            (None, None) => None,
+        }
+    }
+
+    /// The encounter rate and tables of the grid position, if it has any.
+    pub fn encounter_zone(&self, position: [i32; 2]) -> Option<&EncounterZone> {
+        match (&self.streamer, &self.encounters) {
+            (Some(streamer), _) => streamer.encounter_zone(position),
+            (None, Some(encounters)) => encounters.get(position),
+            (None, None) => None,
+        }
+    }
+
+    /// The warp on the grid position, if there is one.
+    pub fn warp(&self, position: [i32; 2]) -> Option<&Warp> {
+        match &self.streamer {
+            Some(streamer) => streamer.warp(position),
+            None => self.warps.iter().find(|warp| warp.tile == position),
+        }
+    }
+
+    /// Resolves a step on the loaded map, see `collision::step`. Without a map
+    /// every step is a walk.
+    pub fn step(&self, from: [i32; 2], direction: Direction, traversal: Traversal) -> Option<Step> {
+        match self.passable(from) {
+            Some(_) => collision::step(from, direction, traversal, |position| self.passable(position).unwrap_or_default()),
+            None => Some(Step { to: direction.advance(from, 1), kind: StepKind::Walk, entered: Passable::Normal }),
+        }
+    }
+
+    /// Draws the placeholder grid and the ground layers, everything below the entities.
+    pub fn draw_below(&self, backend: &mut dyn RenderBackend) {
+        if self.grid != (0, 0) {
+            BackgroundTiles::new().draw(backend, self.grid.0, self.grid.1);
+        }
+        self.draw(backend, LayerKind::Ground);
+    }
+
+    /// The decoration tiles y-sorted with the entities, see `draw_sprites_system`.
+    pub fn scenery(&self) -> Vec<Sprite> {
+        let mut scenery = self.tiles.scenery();
+        for neighbour in &self.neighbours {
+            scenery.extend(neighbour.tiles.scenery());
+        }
+        if let Some(streamer) = &self.streamer {
+            scenery.extend(streamer.scenery());
+        }
+        scenery
+    }
+
+    /// Draws the overhead layers covering the entities.
+    pub fn draw_above(&self, backend: &mut dyn RenderBackend) {
+        self.draw(backend, LayerKind::Overhead);
+    }
+
+    fn draw(&self, backend: &mut dyn RenderBackend, kind: LayerKind) {
+        self.tiles.draw(backend, kind);
+        for neighbour in &self.neighbours {
+            neighbour.tiles.draw(backend, kind);
+        }
+        if let Some(streamer) = &self.streamer {
+            streamer.draw(backend, kind);
+        }
+    }
+}
+
+/// Loads the start map and the encounter tables once in the `Init` stage and
+/// stores the map as `LoadedMap`, which the scene draws below the entities.
+/// Every tick it walks the `GridMover`s on the map, moves the player through
+/// warps and across connected map edges, rolls for wild encounters and
+/// streams the chunks of chunked worlds around the player.
+pub(crate) struct WorldPlugin {
+    map: Option<String>,
+}
+
+#[allow(dead_code)]
+impl WorldPlugin {
+    /// Starts on the map directory `map` below `assets/maps/`, e.g. `"twinleaf_town"`.
+    pub fn with_map(map: &str) -> Self {
+        WorldPlugin { map: Some(map.to_owned()) }
+    }
+}
+
+impl Default for WorldPlugin {
+    /// Starts without a map, showing the placeholder grid.
+    fn default() -> Self {
+        WorldPlugin { map: None }
+    }
+}
+
+impl Plugin for WorldPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.world_mut().insert_resource(StartMap(self.map.clone()));
+        app.add_event::<EncounterTriggered>();
+        app.add_systems(Stage::Init, load_start_map);
+        app.add_systems(Stage::Init, load_encounter_tables);
+        app.add_systems(Stage::Update, map_transition_system
+            .before("grid_movement_system")
+            .run_if(in_state(AppState::Running)));
+        app.add_systems(Stage::Update, grid_movement_system
+            .after("ai_system")
+            .run_if(in_state(AppState::Running)));
+        app.add_systems(Stage::Update, roll_encounters
+            .after("grid_movement_system")
+            .run_if(in_state(AppState::Running)));
+        app.add_systems(Stage::Update, collide_with_map
+            .after("movement_system")
+            .run_if(in_state(AppState::Running)));
+        app.add_systems(Stage::PostUpdate, stream_world_chunks);
+    }
+}
+
+/// Loads the `--map` of the `LaunchOptions` if given, the `StartMap` otherwise.
+fn load_start_map(world: &mut World) {
+    let launch_map = world.resource::<LaunchOptions>().and_then(|options| options.map.clone());
+    let map = launch_map.or_else(|| world.resource::<StartMap>().and_then(|start| start.0.clone()));
+    let loaded = match map {
+        None => LoadedMap::placeholder(),
+        Some(name) => LoadedMap::open(&name).unwrap_or_else(|err| {
+            println!("{}", logger::warn_assets(&format!("Failed to load map {}: {}, showing the placeholder grid", name, err)));
+            LoadedMap::placeholder()
+        }),
+    };
+    world.insert_resource(loaded);
+}
+
+/// Loads the encounter tables below `assets/encounters/` and seeds the
+/// encounter rolls with `world.encounter_seed` of the engine config.
+fn load_encounter_tables(world: &mut World) {
+    let tables = EncounterTables::load_default().unwrap_or_else(|err| {
+        println!("{}", logger::warn_assets(&format!("Failed to load the encounter tables: {}, wild encounters are disabled", err)));
+        EncounterTables::default()
+    });
+    world.insert_resource(tables);
+    world.insert_resource(EncounterRng::new(config::config().world.encounter_seed));
+    world.insert_resource(Repel::default());
+}
+
+/// Streams the chunks of a chunked world around the first `InputControlled`
+/// entity, or around the origin while there is none.
+fn stream_world_chunks(world: &mut World) {
+    let Some(mut map) = world.resource_mut::<LoadedMap>() else {
+        return;
+    };
+    let Some(streamer) = map.streamer.as_mut() else {
+        return;
+    };
+    let mut center = None;
+    world.query::<(&Transform, &InputControlled)>(|_, (transform, _)| {
+        center.get_or_insert(transform.position);
+    });
+    streamer.update(center.unwrap_or([0.0, 0.0]));
+}
+
+/// Takes back the part of this tick's movement that crosses into a tile the
+/// loaded map does not let a free moving entity enter, see `LoadedMap::step`.
+/// Both axes are checked on their own, so entities slide along walls.
+fn collide_with_map(world: &mut World) {
+    let Some(map) = world.resource::<LoadedMap>() else {
+        return;
+    };
+    world.query::<(&mut Transform, With<Velocity>, Option<&Traversal>)>(|_, (transform, _, traversal)| {
+        let traversal = traversal.copied().unwrap_or_default();
+        let mut position = transform.previous_position;
+        for axis in 0..2 {
+            let mut target = position;
+            target[axis] = transform.position[axis];
+            let from = tile_at(position);
+            if tile_at(target) != from {
+                let mut delta = [0.0, 0.0];
+                delta[axis] = target[axis] - position[axis];
+                let direction = Direction::from_vector(delta).expect("the entity moved");
+                if map.step(from, direction, traversal).is_none() {
+                    continue;
+                }
+            }
+            position = target;
+        }
+        transform.position = position;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movement_stops_at_blocked_tiles() {
+        let mut collision = CollisionMap::default();
+        for position in [[0, 0], [1, 0], [0, 1]] {
+            collision.set(position, Passable::Normal);
+        }
+        collision.set([1, 1], Passable::Blocked);
+
+        let mut world = World::new();
+        world.insert_resource(LoadedMap { collision: Some(collision), grid: (0, 0), ..LoadedMap::placeholder() });
+        let start = [TILE_SIZE * 0.5, TILE_SIZE * 0.5];
+        let walker = world.spawn().with(Transform::at(start[0], start[1])).with(Velocity::default()).id();
+
+        // Walking diagonally into the blocked tile keeps the free axis.
+        let transform = *world.get::<Transform>(walker).unwrap();
+        world.get_mut::<Transform>(walker).unwrap().translate([TILE_SIZE, TILE_SIZE], 1.0);
+        collide_with_map(&mut world);
+        assert_eq!(world.get::<Transform>(walker).unwrap().position, [start[0] + TILE_SIZE, start[1]]);
+
+        // Without a map nothing blocks.
+        world.insert_resource(LoadedMap::placeholder());
+        world.get_mut::<Transform>(walker).unwrap().teleport(transform.position);
+        world.get_mut::<Transform>(walker).unwrap().translate([TILE_SIZE, TILE_SIZE], 1.0);
+        collide_with_map(&mut world);
+        assert_eq!(world.get::<Transform>(walker).unwrap().position, [start[0] + TILE_SIZE, start[1] + TILE_SIZE]);
+    }
+}
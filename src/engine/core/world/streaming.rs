@@ -0,0 +1,634 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc as async_mpsc;
+
+use crate::engine::assets_loader::loader::asset_root;
+use crate::engine::console_logger::logger;
+use crate::engine::core::renderer::core::backend::{RenderBackend, Sprite};
+use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+use crate::engine::core::renderer::d2::tile_map::TileMap;
+
+use super::collision::{tile_at, CollisionMap};
+use super::encounter::{EncounterMap, EncounterZone};
+use super::map::{ron_options, LayerKind, Map, MapError, MapLayer, Passable, Warp, CURRENT_MAP_VERSION};
+
+/// File name of the manifest of a chunked world inside its map directory.
+pub const WORLD_FILE_NAME: &str = "world.ron";
+
+/// Directory next to the manifest holding one map file per chunk.
+pub const CHUNK_DIR_NAME: &str = "chunks";
+
+/// Edge length of a world chunk in tiles when the caller does not pick one.
+pub const DEFAULT_WORLD_CHUNK_SIZE: u32 = 32;
+
+/// Describes a world split into square chunks of `chunk_size` tiles.
+///
+/// Chunk `(x, y)` covers the tiles from `(x, y) * chunk_size` up to but not
+/// including `(x + 1, y + 1) * chunk_size` and is stored as a regular `Map`
+/// in `chunks/<x>_<y>.ron` next to the manifest:
+///
+/// ```ron
+/// (
+///     version: 1,
+///     name: "sinnoh",
+///     chunk_size: 32,
+///     tile_size: 32,
+///     tilesets: ["OUTSIDE_ATLAS"],
+///     chunks: [(-1, -1), (0, -1), (-1, 0), (0, 0)],
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorldManifest {
+    pub version: u32,
+    pub name: String,
+    pub chunk_size: u32,
+    pub tile_size: u32,
+    pub tilesets: Vec<String>,
+    /// Every chunk that has a file, chunks missing here are empty.
+    pub chunks: Vec<[i32; 2]>,
+}
+
+/// Returns the manifest path of the world directory `name` below `assets/maps/`.
+pub fn world_path(name: &str) -> String {
+    format!("{}/maps/{}/{}", asset_root(), name, WORLD_FILE_NAME)
+}
+
+/// Returns the file of chunk `coords` inside the world directory `dir`.
+pub fn chunk_path(dir: &Path, coords: [i32; 2]) -> PathBuf {
+    dir.join(CHUNK_DIR_NAME).join(format!("{}_{}.ron", coords[0], coords[1]))
+}
+
+#[allow(dead_code)]
+impl WorldManifest {
+    /// Loads and validates a manifest file.
+    pub fn load(path: &str) -> Result<Self, MapError> {
+        let source = fs::read_to_string(path).map_err(|err| MapError::new(path, format!("cannot read the file: {}", err)))?;
+        Self::parse(&source, path)
+    }
+
+    /// Parses and validates the content of a manifest, `path` is only used in errors.
+    pub fn parse(source: &str, path: &str) -> Result<Self, MapError> {
+        let manifest: WorldManifest = ron_options().from_str(source)
+            .map_err(|err| MapError::at(path, err.position.line, Some(err.position.col), err.code.to_string()))?;
+        if manifest.version != CURRENT_MAP_VERSION {
+            return Err(MapError::new(path, format!("world version {} is not the supported version {}", manifest.version, CURRENT_MAP_VERSION)));
+        }
+        if manifest.chunk_size == 0 || manifest.tile_size == 0 {
+            return Err(MapError::new(path, "chunk_size and tile_size must not be 0"));
+        }
+        if manifest.tilesets.is_empty() {
+            return Err(MapError::new(path, "the manifest lists no tilesets"));
+        }
+        Ok(manifest)
+    }
+
+    /// The chunk containing the grid position.
+    pub fn chunk_of(&self, position: [i32; 2]) -> [i32; 2] {
+        let size = self.chunk_size as i32;
+        [position[0].div_euclid(size), position[1].div_euclid(size)]
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        let config = ron::ser::PrettyConfig::new().depth_limit(1).indentor("    ".to_owned());
+        ron_options().to_string_pretty(self, config)
+    }
+}
+
+/// Splits `map` into chunk maps of `chunk_size` tiles, keyed by chunk.
+///
+/// Every chunk keeps all layers of `map`, so layer kinds and draw order stay
+/// the same, and the tilesets of the whole map, so tiles keep their tileset.
+/// Warps go to the chunk of their tile. Edge connections are dropped, a
+/// chunked world is one seamless map already.
+pub fn split_into_chunks(map: &Map, chunk_size: u32) -> BTreeMap<[i32; 2], Map> {
+    let size = chunk_size as i32;
+    let mut chunks = BTreeMap::new();
+    for (index, layer) in map.layers.iter().enumerate() {
+        for tile in &layer.tiles {
+            let coords = [tile.position[0].div_euclid(size), tile.position[1].div_euclid(size)];
+            let chunk = chunks.entry(coords).or_insert_with(|| {
+                let origin = [coords[0] * size, coords[1] * size];
+                let name = format!("{}/{}_{}", map.header.name, coords[0], coords[1]);
+                let mut chunk = Map::new(&name, [chunk_size, chunk_size], origin, map.header.tile_size, map.header.tilesets.clone());
+                chunk.layers = map.layers.iter()
+                    .map(|layer| MapLayer { name: layer.name.clone(), kind: layer.kind, tiles: Vec::new() })
+                    .collect();
+                chunk
+            });
+            chunk.layers[index].tiles.push(tile.clone());
+        }
+    }
+    for warp in &map.warps {
+        let coords = [warp.tile[0].div_euclid(size), warp.tile[1].div_euclid(size)];
+        if let Some(chunk) = chunks.get_mut(&coords) {
+            chunk.warps.push(warp.clone());
+        }
+    }
+    chunks
+}
+
+/// Splits `map` into chunks and writes them with their manifest into the
+/// world directory `dir`, replacing the chunk files of an earlier split.
+///
+/// # Example
+///
+/// ```rust
+/// let map = Map::load_named("sinnoh")?;
+/// write_chunked_world(&map, Path::new("./src/assets/maps/sinnoh"), DEFAULT_WORLD_CHUNK_SIZE)?;
+/// ```
+pub fn write_chunked_world(map: &Map, dir: &Path, chunk_size: u32) -> Result<WorldManifest, Box<dyn Error>> {
+    if chunk_size == 0 {
+        return Err(Box::new(MapError::new(&dir.display().to_string(), "chunk_size must not be 0")));
+    }
+    let chunks = split_into_chunks(map, chunk_size);
+    let chunk_dir = dir.join(CHUNK_DIR_NAME);
+    if chunk_dir.exists() {
+        fs::remove_dir_all(&chunk_dir)?;
+    }
+    fs::create_dir_all(&chunk_dir)?;
+    for (coords, chunk) in &chunks {
+        chunk.save(&chunk_path(dir, *coords).display().to_string())?;
+    }
+
+    let manifest = WorldManifest {
+        version: CURRENT_MAP_VERSION,
+        name: map.header.name.clone(),
+        chunk_size,
+        tile_size: map.header.tile_size,
+        tilesets: map.header.tilesets.clone(),
+        chunks: chunks.keys().copied().collect(),
+    };
+    fs::write(dir.join(WORLD_FILE_NAME), manifest.to_ron()? + "\n")?;
+    Ok(manifest)
+}
+
+/// The drawable tiles, the collision, the encounter zones and the warps of one loaded chunk.
+#[derive(Default)]
+pub struct StreamedChunk {
+    pub tiles: TileMap,
+    pub collision: CollisionMap,
+    pub encounters: EncounterMap,
+    pub warps: Vec<Warp>,
+}
+
+impl StreamedChunk {
+    pub fn from_map(map: &Map) -> Self {
+        StreamedChunk {
+            tiles: TileMap::from_map(map, TILE_SIZE),
+            collision: CollisionMap::from_map(map),
+            encounters: EncounterMap::from_map(map),
+            warps: map.warps.clone(),
+        }
+    }
+
+    /// Estimated heap memory in bytes, see `TileMap::memory_bytes`.
+    pub fn memory_bytes(&self) -> usize {
+        self.tiles.memory_bytes() + self.collision.memory_bytes() + self.encounters.memory_bytes()
+            + self.warps.capacity() * std::mem::size_of::<Warp>()
+    }
+}
+
+struct CachedChunk {
+    chunk: StreamedChunk,
+    bytes: usize,
+    last_used: u64,
+}
+
+/// Loaded chunks kept below a hard memory budget.
+///
+/// Inserting a chunk evicts the least recently used chunks that are not
+/// pinned until the new one fits. A chunk that does not fit even then is
+/// refused, the budget is never exceeded.
+pub struct ChunkCache {
+    budget: usize,
+    used: usize,
+    clock: u64,
+    chunks: HashMap<[i32; 2], CachedChunk>,
+}
+
+#[allow(dead_code)]
+impl ChunkCache {
+    pub fn new(budget: usize) -> Self {
+        ChunkCache { budget, used: 0, clock: 0, chunks: HashMap::new() }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Estimated bytes of all cached chunks, see `StreamedChunk::memory_bytes`.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn contains(&self, coords: [i32; 2]) -> bool {
+        self.chunks.contains_key(&coords)
+    }
+
+    /// Returns the chunk without marking it as used.
+    pub fn peek(&self, coords: [i32; 2]) -> Option<&StreamedChunk> {
+        self.chunks.get(&coords).map(|cached| &cached.chunk)
+    }
+
+    /// Marks the chunk as the most recently used one. Returns `false` if it is not cached.
+    pub fn touch(&mut self, coords: [i32; 2]) -> bool {
+        self.clock += 1;
+        match self.chunks.get_mut(&coords) {
+            Some(chunk) => {
+                chunk.last_used = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Caches `chunk` as chunk `coords` taking `bytes`, evicting least
+    /// recently used chunks outside `pinned` to make room.
+    ///
+    /// Returns the evicted chunks, or `Err(chunk)` if the chunk does not fit
+    /// into the budget.
+    pub fn insert(&mut self, coords: [i32; 2], chunk: StreamedChunk, bytes: usize, pinned: &HashSet<[i32; 2]>) -> Result<Vec<[i32; 2]>, Box<StreamedChunk>> {
+        self.remove(coords);
+        let mut candidates: Vec<([i32; 2], u64)> = self.chunks.iter()
+            .filter(|(coords, _)| !pinned.contains(*coords))
+            .map(|(coords, chunk)| (*coords, chunk.last_used))
+            .collect();
+        candidates.sort_by_key(|(_, last_used)| *last_used);
+        let freeable: usize = candidates.iter().map(|(coords, _)| self.chunks[coords].bytes).sum();
+        if self.used - freeable + bytes > self.budget {
+            return Err(Box::new(chunk));
+        }
+
+        let mut evicted = Vec::new();
+        for (candidate, _) in candidates {
+            if self.used + bytes <= self.budget {
+                break;
+            }
+            self.remove(candidate);
+            evicted.push(candidate);
+        }
+        self.clock += 1;
+        self.used += bytes;
+        self.chunks.insert(coords, CachedChunk { chunk, bytes, last_used: self.clock });
+        Ok(evicted)
+    }
+
+    pub fn remove(&mut self, coords: [i32; 2]) -> Option<StreamedChunk> {
+        let cached = self.chunks.remove(&coords)?;
+        self.used -= cached.bytes;
+        Some(cached.chunk)
+    }
+}
+
+type ChunkResult = ([i32; 2], Result<StreamedChunk, MapError>);
+
+/// Streams the chunks of a world around the camera.
+///
+/// Chunks within `radius` chunks of the camera are active: they are drawn and
+/// missing ones are requested from a background tokio thread, which reads the
+/// chunk file and builds its tile meshes and collision. Finished chunks are picked up by the
+/// next `update`. Chunks the camera left stay in the `ChunkCache` until the
+/// memory budget needs their room, so walking back is free.
+///
+/// ```rust
+/// let mut streamer = WorldStreamer::open("sinnoh", config.world.stream_radius, config.world.memory_budget_bytes())?;
+/// streamer.update(player_position);
+/// streamer.draw(backend, LayerKind::Ground);
+/// ```
+pub struct WorldStreamer {
+    manifest: WorldManifest,
+    dir: PathBuf,
+    radius: u32,
+    cache: ChunkCache,
+    center: Option<[i32; 2]>,
+    /// The chunks around the camera with a file, nearest first.
+    active: Vec<[i32; 2]>,
+    pending: HashSet<[i32; 2]>,
+    /// Chunks that failed to load or did not fit, not requested again until the camera changes chunks.
+    skipped: HashSet<[i32; 2]>,
+    requests: async_mpsc::UnboundedSender<[i32; 2]>,
+    results: mpsc::Receiver<ChunkResult>,
+    // Dropped last, stopping the loader thread.
+    _runtime: Runtime,
+}
+
+#[allow(dead_code)]
+impl WorldStreamer {
+    /// Opens the world directory `name` below `assets/maps/`.
+    pub fn open(name: &str, radius: u32, budget: usize) -> Result<Self, MapError> {
+        let path = world_path(name);
+        let dir = Path::new(&path).parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::open_dir(&dir, radius, budget)
+    }
+
+    /// Opens the world whose manifest lies in `dir` and starts the loader thread.
+    pub fn open_dir(dir: &Path, radius: u32, budget: usize) -> Result<Self, MapError> {
+        let manifest_path = dir.join(WORLD_FILE_NAME).display().to_string();
+        let manifest = WorldManifest::load(&manifest_path)?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("world-streamer")
+            .enable_all()
+            .build()
+            .map_err(|err| MapError::new(&manifest_path, format!("cannot start the loader thread: {}", err)))?;
+
+        let (request_sender, mut request_receiver) = async_mpsc::unbounded_channel::<[i32; 2]>();
+        let (result_sender, results) = mpsc::channel::<ChunkResult>();
+        let chunk_dir = dir.to_path_buf();
+        runtime.spawn(async move {
+            while let Some(coords) = request_receiver.recv().await {
+                let path = chunk_path(&chunk_dir, coords);
+                let sender = result_sender.clone();
+                tokio::spawn(async move {
+                    let result = load_chunk(path).await;
+                    let _ = sender.send((coords, result));
+                });
+            }
+        });
+
+        Ok(WorldStreamer {
+            manifest,
+            dir: dir.to_path_buf(),
+            radius,
+            cache: ChunkCache::new(budget),
+            center: None,
+            active: Vec::new(),
+            pending: HashSet::new(),
+            skipped: HashSet::new(),
+            requests: request_sender,
+            results,
+            _runtime: runtime,
+        })
+    }
+
+    pub fn manifest(&self) -> &WorldManifest {
+        &self.manifest
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn cache(&self) -> &ChunkCache {
+        &self.cache
+    }
+
+    /// The chunks around the camera, nearest first, whether loaded yet or not.
+    pub fn active(&self) -> &[[i32; 2]] {
+        &self.active
+    }
+
+    pub fn is_loaded(&self, coords: [i32; 2]) -> bool {
+        self.cache.contains(coords)
+    }
+
+    /// Whether every active chunk is loaded, skipped or has no file.
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Moves the streaming center to `camera`, in world coordinates, caches
+    /// the chunks the loader finished and requests the missing active ones.
+    pub fn update(&mut self, camera: [f32; 2]) {
+        self.receive();
+
+        let center = self.manifest.chunk_of(tile_at(camera));
+        if self.center != Some(center) {
+            self.center = Some(center);
+            self.skipped.clear();
+            let existing: HashSet<[i32; 2]> = self.manifest.chunks.iter().copied().collect();
+            self.active = chunks_around(center, self.radius).into_iter().filter(|coords| existing.contains(coords)).collect();
+        }
+
+        for index in 0..self.active.len() {
+            let coords = self.active[index];
+            if self.cache.touch(coords) || self.pending.contains(&coords) || self.skipped.contains(&coords) {
+                continue;
+            }
+            if self.requests.send(coords).is_ok() {
+                self.pending.insert(coords);
+            }
+        }
+    }
+
+    fn receive(&mut self) {
+        while let Ok((coords, result)) = self.results.try_recv() {
+            self.pending.remove(&coords);
+            let chunk = match result {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    println!("{}", logger::warn_assets(&format!("Failed to load world chunk: {}", err)));
+                    self.skipped.insert(coords);
+                    continue;
+                }
+            };
+            let pinned: HashSet<[i32; 2]> = self.active.iter().copied().collect();
+            let bytes = chunk.memory_bytes();
+            if self.cache.insert(coords, chunk, bytes, &pinned).is_err() {
+                println!("{}", logger::warn_assets(&format!(
+                    "World chunk ({}, {}) needs {} bytes and does not fit into the memory budget of {} bytes",
+                    coords[0], coords[1], bytes, self.cache.budget(),
+                )));
+                self.skipped.insert(coords);
+            }
+        }
+    }
+
+    fn loaded(&self) -> impl Iterator<Item = &StreamedChunk> {
+        self.active.iter().filter_map(|coords| self.cache.peek(*coords))
+    }
+
+    /// Draws the layers of `kind` of every loaded active chunk.
+    pub fn draw(&self, backend: &mut dyn RenderBackend, kind: LayerKind) {
+        for chunk in self.loaded() {
+            chunk.tiles.draw(backend, kind);
+        }
+    }
+
+    /// The decoration tiles of every loaded active chunk, see `TileMap::scenery`.
+    pub fn scenery(&self) -> Vec<Sprite> {
+        self.loaded().flat_map(|chunk| chunk.tiles.scenery()).collect()
+    }
+
+    /// The passability of the grid position, `Blocked` while its chunk is not loaded.
+    pub fn passable(&self, position: [i32; 2]) -> Passable {
+        self.cache.peek(self.manifest.chunk_of(position)).map_or(Passable::Blocked, |chunk| chunk.collision.passable(position))
+    }
+
+    /// The encounter zone of the grid position, `None` while its chunk is not loaded.
+    pub fn encounter_zone(&self, position: [i32; 2]) -> Option<&EncounterZone> {
+        self.cache.peek(self.manifest.chunk_of(position)).and_then(|chunk| chunk.encounters.get(position))
+    }
+
+    /// The warp on the grid position, `None` while its chunk is not loaded.
+    pub fn warp(&self, position: [i32; 2]) -> Option<&Warp> {
+        self.cache.peek(self.manifest.chunk_of(position)).and_then(|chunk| chunk.warps.iter().find(|warp| warp.tile == position))
+    }
+}
+
+/// Reads and builds one chunk, the parsing and mesh building runs on a blocking thread.
+async fn load_chunk(path: PathBuf) -> Result<StreamedChunk, MapError> {
+    let name = path.display().to_string();
+    let source = tokio::fs::read_to_string(&path).await
+        .map_err(|err| MapError::new(&name, format!("cannot read the file: {}", err)))?;
+    let task_name = name.clone();
+    tokio::task::spawn_blocking(move || Map::parse(&source, &task_name).map(|map| StreamedChunk::from_map(&map)))
+        .await
+        .map_err(|err| MapError::new(&name, format!("loader task failed: {}", err)))?
+}
+
+/// Every chunk within `radius` chunks of `center`, nearest first.
+pub fn chunks_around(center: [i32; 2], radius: u32) -> Vec<[i32; 2]> {
+    let radius = radius as i32;
+    let mut chunks: Vec<[i32; 2]> = (-radius..=radius)
+        .flat_map(|dy| (-radius..=radius).map(move |dx| [center[0] + dx, center[1] + dy]))
+        .collect();
+    chunks.sort_by_key(|[x, y]| (x - center[0]).abs().max((y - center[1]).abs()));
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::engine::core::world::map::{MapTile, TileProperties};
+
+    /// A `size` x `size` world of grass centered on the origin.
+    fn world(size: u32) -> Map {
+        let half = size as i32 / 2;
+        let mut map = Map::new("streamed", [size, size], [-half, -half], 32, vec!["OUTSIDE_ATLAS".to_owned()]);
+        let tiles = (0..size as i32 * size as i32).map(|index| MapTile {
+            position: [index % size as i32 - half, index / size as i32 - half],
+            tileset: None,
+            texture: 1,
+            properties: TileProperties::default(),
+        }).collect();
+        map.layers.push(MapLayer { name: "ground".to_owned(), kind: LayerKind::Ground, tiles });
+        map.layers.push(MapLayer { name: "roofs".to_owned(), kind: LayerKind::Overhead, tiles: Vec::new() });
+        map
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("malkmusl_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn wait_until_idle(streamer: &mut WorldStreamer, camera: [f32; 2]) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        streamer.update(camera);
+        while !streamer.is_idle() {
+            assert!(Instant::now() < deadline, "chunks did not load in time");
+            std::thread::sleep(Duration::from_millis(5));
+            streamer.update(camera);
+        }
+    }
+
+    #[test]
+    fn splits_maps_into_chunks() {
+        let map = world(40);
+        let chunks = split_into_chunks(&map, 16);
+        // Tiles -20..20 fall into the chunks -2..=1 on both axes.
+        assert_eq!(chunks.len(), 16);
+        assert_eq!(chunks[&[0, 0]].header.origin, [0, 0]);
+        assert_eq!(chunks[&[-2, -2]].header.origin, [-32, -32]);
+        assert_eq!(chunks[&[-2, -2]].layers[0].tiles.len(), 4 * 4);
+        assert_eq!(chunks[&[-1, 0]].layers[0].tiles.len(), 16 * 16);
+        let tiles: usize = chunks.values().map(|chunk| chunk.layers[0].tiles.len()).sum();
+        assert_eq!(tiles, 40 * 40);
+        for chunk in chunks.values() {
+            assert_eq!(chunk.layers.len(), 2);
+            assert!(chunk.validate().is_ok());
+        }
+        assert_eq!(chunks_around([0, 0], 1).len(), 9);
+        assert_eq!(chunks_around([3, -2], 1)[0], [3, -2]);
+    }
+
+    #[test]
+    fn writes_and_reads_chunked_worlds() {
+        let dir = scratch_dir("chunked_world");
+        let manifest = write_chunked_world(&world(40), &dir, 16).unwrap();
+        assert_eq!(manifest.chunks.len(), 16);
+        assert_eq!(manifest.chunk_of([-1, 15]), [-1, 0]);
+        assert_eq!(WorldManifest::load(&dir.join(WORLD_FILE_NAME).display().to_string()).unwrap(), manifest);
+        let chunk = Map::load(&chunk_path(&dir, [-1, 0]).display().to_string()).unwrap();
+        assert_eq!(chunk.header.origin, [-16, 0]);
+
+        let err = WorldManifest::parse("(\n    version: 1,\n    name: \"broken\",\n    chunk_size: 16,\n    tile_size: 32,\n    tilesets: [],\n    chunks: [],\n)", "world.ron").unwrap_err();
+        assert!(err.to_string().contains("no tilesets"), "{}", err);
+        let err = WorldManifest::parse("(\n    version: 1,\n    size: 16,\n)", "world.ron").unwrap_err();
+        assert_eq!(err.line, Some(3));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used_chunks() {
+        let none = HashSet::new();
+        let mut cache = ChunkCache::new(300);
+        assert_eq!(cache.insert([0, 0], StreamedChunk::default(), 100, &none).ok(), Some(vec![]));
+        assert_eq!(cache.insert([1, 0], StreamedChunk::default(), 100, &none).ok(), Some(vec![]));
+        assert_eq!(cache.insert([2, 0], StreamedChunk::default(), 100, &none).ok(), Some(vec![]));
+        assert!(cache.touch([0, 0]));
+
+        // [1, 0] is the least recently used chunk now.
+        assert_eq!(cache.insert([3, 0], StreamedChunk::default(), 100, &none).ok(), Some(vec![[1, 0]]));
+        assert_eq!(cache.used(), 300);
+
+        // Pinned chunks are never evicted and the budget is never exceeded.
+        let pinned: HashSet<[i32; 2]> = [[0, 0], [2, 0], [3, 0]].into_iter().collect();
+        assert!(cache.insert([4, 0], StreamedChunk::default(), 100, &pinned).is_err());
+        assert!(cache.insert([4, 0], StreamedChunk::default(), 400, &none).is_err());
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.used(), 300);
+    }
+
+    #[test]
+    fn streams_chunks_around_the_camera() {
+        let dir = scratch_dir("streamed_world");
+        write_chunked_world(&world(64), &dir, 16).unwrap();
+        let mut streamer = WorldStreamer::open_dir(&dir, 0, usize::MAX).unwrap();
+
+        wait_until_idle(&mut streamer, [0.05, 0.05]);
+        assert_eq!(streamer.active(), [[0, 0]]);
+        assert!(streamer.is_loaded([0, 0]));
+        assert_eq!(streamer.cache().len(), 1);
+        assert_eq!(streamer.passable([3, 3]), Passable::Normal);
+        assert_eq!(streamer.passable([-3, 3]), Passable::Blocked, "chunk (-1, 0) is not loaded");
+
+        // Walking 10 tiles left enters chunk (-1, 0), the old one stays cached.
+        wait_until_idle(&mut streamer, [-10.0 * TILE_SIZE, 0.05]);
+        assert_eq!(streamer.active(), [[-1, 0]]);
+        assert!(streamer.is_loaded([-1, 0]));
+        assert!(streamer.is_loaded([0, 0]));
+
+        // Outside the world there is nothing to load.
+        wait_until_idle(&mut streamer, [100.0, 100.0]);
+        assert!(streamer.active().is_empty());
+
+        // A budget of one chunk unloads the chunk the camera left.
+        let bytes = streamer.cache().peek([0, 0]).unwrap().memory_bytes();
+        let mut streamer = WorldStreamer::open_dir(&dir, 0, bytes).unwrap();
+        wait_until_idle(&mut streamer, [0.05, 0.05]);
+        wait_until_idle(&mut streamer, [0.05, -10.0 * TILE_SIZE]);
+        assert!(streamer.is_loaded([0, -1]));
+        assert!(!streamer.is_loaded([0, 0]));
+        assert!(streamer.cache().used() <= bytes);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,882 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::engine::assets_loader::texture_tilesets::get_atlas_name;
+use crate::engine::core::cli::ImportTiledArgs;
+
+use super::collision::Direction;
+use super::map::{
+    map_path, LayerKind, Map, MapError, MapLayer, MapTile, NpcSpawn, Passable, PropertyValue, TileProperties, Warp, WarpKind,
+};
+
+/// The bits of a Tiled gid flipping or rotating the tile, which the engine does not support.
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+/// Settings of `import_tiled` that Tiled files do not carry.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TiledImportOptions {
+    /// The map name, the file name without extension if not given.
+    pub name: Option<String>,
+    /// The grid position of the bottom-left tile.
+    pub origin: [i32; 2],
+    /// Texture atlas names by Tiled tileset name, e.g. `"outside" -> "OUTSIDE_ATLAS"`,
+    /// for tilesets whose atlas cannot be guessed.
+    pub atlases: BTreeMap<String, String>,
+}
+
+/// The result of importing one Tiled map.
+pub struct TiledImport {
+    pub map: Map,
+    /// Where the map belongs, `maps/<name>/map.ron` below the configured
+    /// asset root unless given.
+    pub output: PathBuf,
+    /// Tiles, objects and properties that could not be carried over.
+    pub notes: Vec<String>,
+}
+
+/// A Tiled map as written to `.tmj` files. `.tmx` files are read into the same shape.
+#[derive(Debug, Default, Deserialize)]
+struct TiledMap {
+    width: u32,
+    height: u32,
+    #[serde(rename = "tilewidth")]
+    tile_width: u32,
+    #[serde(rename = "tileheight")]
+    tile_height: u32,
+    #[serde(default)]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TiledLayer {
+    #[serde(default)]
+    name: String,
+    /// `tilelayer`, `objectgroup`, `imagelayer` or `group`.
+    #[serde(rename = "type")]
+    kind: String,
+    /// The gids of a tile layer row by row from the top, an array or a `csv` string.
+    #[serde(default)]
+    data: Option<Value>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+    /// The layers of a group.
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TiledObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    /// Called `type` before Tiled 1.9.
+    #[serde(default, alias = "type")]
+    class: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    /// Set for tile objects, whose position is their bottom-left corner.
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TiledTileset {
+    #[serde(rename = "firstgid")]
+    first_gid: u32,
+    /// The `.tsx` or `.tsj` file of an external tileset, relative to the map.
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TiledTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TiledProperty {
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    value: Value,
+}
+
+/// Imports the Tiled map at `path`, a `.tmj` (JSON) or `.tmx` (XML) file.
+///
+/// Tile layers become map layers; a layer property `kind` of `Ground`,
+/// `Decoration` or `Overhead` sets where it is drawn. Tiled tilesets are
+/// mapped onto texture atlases by `options.atlases`, their `atlas` property or
+/// their name, e.g. `caves` onto `CAVES_ATLAS`. The properties `passable`,
+/// `encounter` and `encounter_tables` of tileset tiles and of whole layers,
+/// layer properties winning, fill the `TileProperties`, any other property
+/// lands in `custom`. Objects of the classes `warp` (or `door`, `stairs`,
+/// `cave`), `npc` and `sign` become warps, npc spawns and a `sign` property
+/// of the tile below. Nothing is written, see `TiledImport::write`.
+///
+/// # Example
+///
+/// ```rust
+/// let import = import_tiled(Path::new("./levels/route_201.tmj"), &TiledImportOptions::default())?;
+/// import.write()?;
+/// ```
+pub fn import_tiled(path: &Path, options: &TiledImportOptions) -> Result<TiledImport, MapError> {
+    let file = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|err| MapError::new(&file, format!("cannot read the file: {}", err)))?;
+    let mut tiled = match extension(path).as_str() {
+        "tmj" | "json" => parse_json::<TiledMap>(&source, &file)?,
+        "tmx" => parse_tmx(&source, &file)?,
+        other => return Err(MapError::new(&file, format!("unknown Tiled map extension '{}', expected .tmj or .tmx", other))),
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for tileset in &mut tiled.tilesets {
+        load_external_tileset(tileset, dir)?;
+    }
+
+    let name = options.name.clone()
+        .unwrap_or_else(|| path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_owned());
+    let mut importer = Importer::new(&tiled, options, &file)?;
+    let map = importer.convert(&name, &tiled)?;
+    Ok(TiledImport { map, output: PathBuf::from(map_path(&name)), notes: importer.notes })
+}
+
+/// Runs the `import-tiled` command: imports `args.input` and writes the map unless it is a dry run.
+///
+/// Without `--output` the map goes below `asset_root()`, so the engine config
+/// has to be set first, as `AppBuilder::with_command_line` does for tool commands.
+pub fn run_import_tiled(args: &ImportTiledArgs) -> Result<TiledImport, MapError> {
+    let options = TiledImportOptions {
+        name: args.name.clone(),
+        origin: args.origin.unwrap_or_default(),
+        atlases: args.atlases.iter().cloned().collect(),
+    };
+    let mut import = import_tiled(Path::new(&args.input), &options)?;
+    if let Some(output) = &args.output {
+        import.output = PathBuf::from(output);
+    }
+    if !args.dry_run {
+        import.write().map_err(|err| MapError::new(&import.output.display().to_string(), err.to_string()))?;
+    }
+    Ok(import)
+}
+
+impl TiledImport {
+    /// Validates and writes the map to `output`.
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        let output = self.output.display().to_string();
+        self.map.validate().map_err(|problem| MapError::new(&output, problem.message))?;
+        if let Some(parent) = self.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.map.save(&output)?;
+        Ok(())
+    }
+
+    /// A one-line summary for the `import-tiled` command.
+    pub fn summary(&self) -> String {
+        let tiles: usize = self.map.layers.iter().map(|layer| layer.tiles.len()).sum();
+        format!(
+            "{}: {} layers, {} tiles, {} warps, {} npcs, {}x{} tiles, tilesets {}",
+            self.output.display(), self.map.layers.len(), tiles, self.map.warps.len(), self.map.npcs.len(),
+            self.map.header.size[0], self.map.header.size[1], self.map.header.tilesets.join(", "),
+        )
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_ascii_lowercase()
+}
+
+fn parse_json<T: for<'de> Deserialize<'de>>(source: &str, file: &str) -> Result<T, MapError> {
+    serde_json::from_str(source).map_err(|err| MapError::at(file, err.line(), Some(err.column()), err.to_string()))
+}
+
+/// Reads the name, image, properties and tiles of an external tileset file into `tileset`.
+fn load_external_tileset(tileset: &mut TiledTileset, dir: &Path) -> Result<(), MapError> {
+    let Some(source) = tileset.source.clone() else {
+        return Ok(());
+    };
+    let path = dir.join(&source);
+    let file = path.display().to_string();
+    let text = fs::read_to_string(&path).map_err(|err| MapError::new(&file, format!("cannot read the tileset: {}", err)))?;
+    let external = match extension(&path).as_str() {
+        "tsj" | "json" => parse_json::<TiledTileset>(&text, &file)?,
+        _ => {
+            let root = parse_xml(&text, &file)?;
+            tileset_from_xml(&root, &file)?
+        }
+    };
+    tileset.name = external.name;
+    tileset.image = external.image;
+    tileset.tiles = external.tiles;
+    tileset.properties = external.properties;
+    Ok(())
+}
+
+/// Converts one parsed Tiled map, collecting notes on what it drops.
+struct Importer<'a> {
+    file: &'a str,
+    origin: [i32; 2],
+    /// The atlas of every tileset by first gid, or why there is none.
+    atlases: Vec<(u32, Result<&'static str, String>)>,
+    /// The tile properties of tileset tiles by gid.
+    tile_properties: BTreeMap<u32, Vec<TiledProperty>>,
+    /// The atlases used so far, the first one becomes the implicit tileset.
+    tilesets: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(tiled: &TiledMap, options: &TiledImportOptions, file: &'a str) -> Result<Self, MapError> {
+        if !tiled.orientation.is_empty() && tiled.orientation != "orthogonal" {
+            return Err(MapError::new(file, format!("{} maps are not supported, only orthogonal ones", tiled.orientation)));
+        }
+        if tiled.infinite {
+            return Err(MapError::new(file, "infinite maps are not supported, turn off 'Infinite' in the map properties"));
+        }
+        if tiled.tile_width != tiled.tile_height || tiled.tile_width == 0 {
+            return Err(MapError::new(file, format!("tiles must be square, got {}x{}", tiled.tile_width, tiled.tile_height)));
+        }
+
+        let mut atlases = Vec::new();
+        let mut tile_properties = BTreeMap::new();
+        for tileset in &tiled.tilesets {
+            atlases.push((tileset.first_gid, atlas_of(tileset, options)));
+            for tile in &tileset.tiles {
+                tile_properties.insert(tileset.first_gid + tile.id, tile.properties.clone());
+            }
+        }
+        atlases.sort_by_key(|(first_gid, _)| *first_gid);
+        Ok(Importer { file, origin: options.origin, atlases, tile_properties, tilesets: Vec::new(), notes: Vec::new() })
+    }
+
+    fn convert(&mut self, name: &str, tiled: &TiledMap) -> Result<Map, MapError> {
+        let mut layers = Vec::new();
+        let mut objects = Vec::new();
+        let mut names = HashSet::new();
+        for layer in flatten(&tiled.layers) {
+            match layer.kind.as_str() {
+                "tilelayer" => {
+                    let mut converted = self.tile_layer(layer, tiled)?;
+                    converted.name = unique_name(&converted.name, &mut names);
+                    layers.push(converted);
+                }
+                "objectgroup" => objects.extend(&layer.objects),
+                kind => self.notes.push(format!("{}: skipped the {} layer '{}'", self.file, kind, layer.name)),
+            }
+        }
+        if self.tilesets.is_empty() {
+            self.tilesets.extend(self.atlases.iter().find_map(|(_, atlas)| atlas.as_ref().ok()).map(|atlas| atlas.to_string()));
+        }
+        if self.tilesets.is_empty() {
+            return Err(MapError::new(self.file, "no tileset maps onto a texture atlas"));
+        }
+
+        for tile in layers.iter_mut().flat_map(|layer| layer.tiles.iter_mut()) {
+            if tile.tileset.as_deref() == Some(self.tilesets[0].as_str()) {
+                tile.tileset = None;
+            }
+        }
+        let mut map = Map::new(name, [tiled.width, tiled.height], self.origin, tiled.tile_width, self.tilesets.clone());
+        map.layers = layers;
+        for object in objects {
+            self.object(object, tiled, &mut map);
+        }
+        Ok(map)
+    }
+
+    fn tile_layer(&mut self, layer: &TiledLayer, tiled: &TiledMap) -> Result<MapLayer, MapError> {
+        let gids = self.gids(layer)?;
+        if gids.len() != (tiled.width * tiled.height) as usize {
+            return Err(MapError::new(self.file, format!("layer '{}' has {} tiles, expected {}x{}", layer.name, gids.len(), tiled.width, tiled.height)));
+        }
+        let kind = match property(&layer.properties, "kind").map(|kind| kind.value.as_str().unwrap_or_default().to_ascii_lowercase()) {
+            None => LayerKind::Ground,
+            Some(kind) if kind == "ground" => LayerKind::Ground,
+            Some(kind) if kind == "decoration" => LayerKind::Decoration,
+            Some(kind) if kind == "overhead" => LayerKind::Overhead,
+            Some(kind) => {
+                self.notes.push(format!("{}: layer '{}' has the unknown kind '{}', drawn as Ground", self.file, layer.name, kind));
+                LayerKind::Ground
+            }
+        };
+
+        let mut tiles = Vec::new();
+        let mut flipped = false;
+        for (index, gid) in gids.into_iter().enumerate() {
+            flipped |= gid & FLIP_FLAGS != 0;
+            let gid = gid & !FLIP_FLAGS;
+            if gid == 0 {
+                continue;
+            }
+            let position = self.position(index as u32 % tiled.width, index as u32 / tiled.width, tiled);
+            let Some((first_gid, atlas)) = self.atlases.iter().rev().find(|(first_gid, _)| *first_gid <= gid).cloned() else {
+                return Err(MapError::new(self.file, format!("layer '{}' uses gid {}, which belongs to no tileset", layer.name, gid)));
+            };
+            let atlas = atlas.map_err(|message| MapError::new(self.file, message))?;
+            if !self.tilesets.iter().any(|tileset| tileset == atlas) {
+                self.tilesets.push(atlas.to_owned());
+            }
+
+            let mut properties = TileProperties::default();
+            let tile_properties = self.tile_properties.get(&gid).cloned().unwrap_or_default();
+            for property in tile_properties.iter().chain(&layer.properties).filter(|property| property.name != "kind") {
+                self.apply(&mut properties, property, position);
+            }
+            tiles.push(MapTile { position, tileset: Some(atlas.to_owned()), texture: gid - first_gid, properties });
+        }
+        if flipped {
+            self.notes.push(format!("{}: layer '{}' has flipped or rotated tiles, imported unflipped", self.file, layer.name));
+        }
+        Ok(MapLayer { name: layer.name.clone(), kind, tiles })
+    }
+
+    fn gids(&self, layer: &TiledLayer) -> Result<Vec<u32>, MapError> {
+        let invalid = |message: String| MapError::new(self.file, format!("layer '{}': {}", layer.name, message));
+        if let Some(compression) = layer.compression.as_deref().filter(|compression| !compression.is_empty()) {
+            return Err(invalid(format!("{} compressed tile data is not supported, save the layer as CSV", compression)));
+        }
+        match (&layer.data, layer.encoding.as_deref()) {
+            (Some(Value::Array(gids)), _) => gids.iter()
+                .map(|gid| gid.as_u64().and_then(|gid| u32::try_from(gid).ok()).ok_or_else(|| invalid(format!("invalid gid {}", gid))))
+                .collect(),
+            (Some(Value::String(csv)), Some("csv")) => csv.split(',')
+                .map(str::trim)
+                .filter(|gid| !gid.is_empty())
+                .map(|gid| gid.parse().map_err(|_| invalid(format!("invalid gid '{}'", gid))))
+                .collect(),
+            (_, Some(encoding)) if encoding != "csv" => Err(invalid(format!("{} encoded tile data is not supported, save the layer as CSV", encoding))),
+            _ => Err(invalid("the layer has no tile data".to_owned())),
+        }
+    }
+
+    /// The grid position of the Tiled tile at `column`, `row`, counting rows from the top.
+    fn position(&self, column: u32, row: u32, tiled: &TiledMap) -> [i32; 2] {
+        [self.origin[0] + column as i32, self.origin[1] + (tiled.height - 1 - row) as i32]
+    }
+
+    /// Sets the tile property `property` of the tile at `position`.
+    fn apply(&mut self, properties: &mut TileProperties, property: &TiledProperty, position: [i32; 2]) {
+        let [x, y] = position;
+        match property.name.as_str() {
+            "passable" => match passable(&property.value) {
+                Some(passable) => properties.passable = passable,
+                None => self.notes.push(format!("{}: tile ({}, {}) has the unknown passable value {}", self.file, x, y, property.value)),
+            },
+            "encounter" => match property.value.as_u64().and_then(|rate| u32::try_from(rate).ok()).filter(|rate| *rate <= 100) {
+                Some(rate) => properties.encounter = Some(rate),
+                None => self.notes.push(format!("{}: tile ({}, {}) has the encounter rate {}, expected 0 to 100", self.file, x, y, property.value)),
+            },
+            "encounter_tables" | "encounter_table" => match tables(&property.value) {
+                Some(tables) => properties.encounter_tables = tables,
+                None => self.notes.push(format!("{}: tile ({}, {}) has the invalid encounter tables {}", self.file, x, y, property.value)),
+            },
+            name => match self.value(property) {
+                Some(value) => {
+                    properties.custom.insert(name.to_owned(), value);
+                }
+                None => self.notes.push(format!("{}: dropped the {} property '{}'", self.file, property.kind, name)),
+            },
+        }
+    }
+
+    fn value(&self, property: &TiledProperty) -> Option<PropertyValue> {
+        match &property.value {
+            Value::Bool(value) => Some(PropertyValue::Bool(*value)),
+            Value::Number(number) => match (property.kind.as_str(), number.as_i64()) {
+                ("float", _) | (_, None) => number.as_f64().map(PropertyValue::Float),
+                (_, Some(value)) => Some(PropertyValue::Int(value)),
+            },
+            Value::String(value) => Some(PropertyValue::String(value.clone())),
+            _ => None,
+        }
+    }
+
+    fn object(&mut self, object: &TiledObject, tiled: &TiledMap, map: &mut Map) {
+        let (tile_width, tile_height) = (tiled.tile_width as f64, tiled.tile_height as f64);
+        let center_y = match object.gid {
+            Some(_) => object.y - object.height / 2.0,
+            None => object.y + object.height / 2.0,
+        };
+        let column = ((object.x + object.width / 2.0) / tile_width).floor();
+        let row = (center_y / tile_height).floor();
+        if column < 0.0 || row < 0.0 || column >= tiled.width as f64 || row >= tiled.height as f64 {
+            self.notes.push(format!("{}: skipped object {} outside the map", self.file, object.id));
+            return;
+        }
+        let tile = self.position(column as u32, row as u32, tiled);
+        let string = |name: &str| property(&object.properties, name).and_then(|property| property.value.as_str()).map(str::to_owned);
+
+        match object.class.to_ascii_lowercase().as_str() {
+            class @ ("warp" | "door" | "stairs" | "cave") => {
+                let kind = match string("kind").unwrap_or_else(|| class.to_owned()).to_ascii_lowercase().as_str() {
+                    "stairs" => WarpKind::Stairs,
+                    "cave" => WarpKind::Cave,
+                    _ => WarpKind::Door,
+                };
+                let spawn = match (int(&object.properties, "spawn_x"), int(&object.properties, "spawn_y")) {
+                    (Some(x), Some(y)) => Some([x, y]),
+                    _ => string("spawn").and_then(|spawn| parse_pair(&spawn)),
+                };
+                let facing = string("facing").and_then(|facing| ron::from_str::<Direction>(&facing).ok());
+                match (string("target"), spawn) {
+                    (Some(target), Some(spawn)) => map.warps.push(Warp { tile, kind, target, spawn, facing }),
+                    _ => self.notes.push(format!("{}: skipped warp {}, it needs a target and spawn_x/spawn_y", self.file, object.id)),
+                }
+            }
+            "npc" => {
+                let properties = object.properties.iter()
+                    .filter_map(|property| Some((property.name.clone(), self.value(property)?)))
+                    .collect();
+                let name = if object.name.is_empty() { "NPC".to_owned() } else { object.name.clone() };
+                map.npcs.push(NpcSpawn { tile, name, properties });
+            }
+            "sign" => {
+                let text = string("text").unwrap_or_else(|| object.name.clone());
+                let below = map.layers.iter_mut().rev().find_map(|layer| layer.tiles.iter_mut().find(|candidate| candidate.position == tile));
+                match below {
+                    Some(below) => {
+                        below.properties.custom.insert("sign".to_owned(), PropertyValue::String(text));
+                    }
+                    None => self.notes.push(format!("{}: skipped sign {}, there is no tile below it", self.file, object.id)),
+                }
+            }
+            class => self.notes.push(format!("{}: skipped object {} of the unknown class '{}'", self.file, object.id, class)),
+        }
+    }
+}
+
+/// The tile and object layers in drawing order, with groups resolved.
+fn flatten(layers: &[TiledLayer]) -> Vec<&TiledLayer> {
+    layers.iter()
+        .flat_map(|layer| match layer.kind.as_str() {
+            "group" => flatten(&layer.layers),
+            _ => vec![layer],
+        })
+        .collect()
+}
+
+fn unique_name(name: &str, names: &mut HashSet<String>) -> String {
+    let base = if name.is_empty() { "layer" } else { name };
+    let mut unique = base.to_owned();
+    let mut counter = 1;
+    while !names.insert(unique.clone()) {
+        counter += 1;
+        unique = format!("{}_{}", base, counter);
+    }
+    unique
+}
+
+fn property<'p>(properties: &'p [TiledProperty], name: &str) -> Option<&'p TiledProperty> {
+    properties.iter().find(|property| property.name == name)
+}
+
+fn int(properties: &[TiledProperty], name: &str) -> Option<i32> {
+    property(properties, name).and_then(|property| property.value.as_i64()).and_then(|value| i32::try_from(value).ok())
+}
+
+/// Parses `"x,y"`.
+fn parse_pair(value: &str) -> Option<[i32; 2]> {
+    let (x, y) = value.split_once(',')?;
+    Some([x.trim().parse().ok()?, y.trim().parse().ok()?])
+}
+
+/// `true` is `Normal` and `false` `Blocked`, strings name a `Passable` as in map files, e.g. `"Ledge(Down)"`.
+fn passable(value: &Value) -> Option<Passable> {
+    match value {
+        Value::Bool(true) => Some(Passable::Normal),
+        Value::Bool(false) => Some(Passable::Blocked),
+        Value::String(name) => ron::from_str(name).ok(),
+        _ => None,
+    }
+}
+
+/// A table id or a comma separated list of them.
+fn tables(value: &Value) -> Option<Vec<u32>> {
+    match value {
+        Value::Number(number) => Some(vec![u32::try_from(number.as_u64()?).ok()?]),
+        Value::String(list) => list.split(',').map(str::trim).filter(|id| !id.is_empty()).map(|id| id.parse().ok()).collect(),
+        _ => None,
+    }
+}
+
+/// The atlas of `tileset`: given in `options`, as `atlas` property or guessed
+/// from the tileset, file or image name, e.g. `caves` or `Caves.tsx` as `CAVES_ATLAS`.
+fn atlas_of(tileset: &TiledTileset, options: &TiledImportOptions) -> Result<&'static str, String> {
+    let explicit = options.atlases.get(&tileset.name).cloned()
+        .or_else(|| property(&tileset.properties, "atlas").and_then(|atlas| atlas.value.as_str()).map(str::to_owned));
+    if let Some(atlas) = explicit {
+        return get_atlas_name(&atlas).ok_or_else(|| format!("tileset '{}' maps onto the unknown atlas {}", tileset.name, atlas));
+    }
+    let stem = |path: &Option<String>| path.as_deref().and_then(|path| Path::new(path).file_stem()?.to_str().map(str::to_owned));
+    [Some(tileset.name.clone()), stem(&tileset.source), stem(&tileset.image)].into_iter().flatten()
+        .map(|name| {
+            let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+            if name.ends_with("_ATLAS") { name } else { name + "_ATLAS" }
+        })
+        .find_map(|candidate| get_atlas_name(&candidate))
+        .ok_or_else(|| format!("tileset '{}' maps onto no texture atlas, name it after one or pass --atlas {}=<ATLAS>", tileset.name, tileset.name))
+}
+
+/// An element of a `.tmx` or `.tsx` file.
+#[derive(Debug, Default)]
+struct XmlNode {
+    name: String,
+    attributes: BTreeMap<String, String>,
+    children: Vec<XmlNode>,
+    text: String,
+}
+
+impl XmlNode {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str, file: &str) -> Result<T, MapError> {
+        let value = self.attribute(name).ok_or_else(|| MapError::new(file, format!("<{}> lacks the attribute {}", self.name, name)))?;
+        value.parse().map_err(|_| MapError::new(file, format!("<{}> has the invalid {} '{}'", self.name, name, value)))
+    }
+
+    fn float(&self, name: &str) -> f64 {
+        self.attribute(name).and_then(|value| value.parse().ok()).unwrap_or_default()
+    }
+
+    fn children<'n>(&'n self, name: &'n str) -> impl Iterator<Item = &'n XmlNode> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+/// Parses an XML document into its root element.
+fn parse_xml(source: &str, file: &str) -> Result<XmlNode, MapError> {
+    let line_of = |position: u64| source[..(position as usize).min(source.len())].lines().count().max(1);
+    let mut reader = Reader::from_str(source);
+    let mut stack = vec![XmlNode::default()];
+    loop {
+        let event = reader.read_event()
+            .map_err(|err| MapError::at(file, line_of(reader.buffer_position()), None, err.to_string()))?;
+        match event {
+            Event::Start(start) => stack.push(xml_node(&start, file)?),
+            Event::Empty(start) => {
+                let node = xml_node(&start, file)?;
+                stack.last_mut().expect("the document node").children.push(node);
+            }
+            Event::End(_) => {
+                let node = stack.pop().expect("an open element");
+                let parent = stack.last_mut().ok_or_else(|| MapError::at(file, line_of(reader.buffer_position()), None, "unexpected closing tag"))?;
+                parent.children.push(node);
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|err| MapError::at(file, line_of(reader.buffer_position()), None, err.to_string()))?;
+                stack.last_mut().expect("the document node").text.push_str(&text);
+            }
+            Event::CData(data) => {
+                stack.last_mut().expect("the document node").text.push_str(&String::from_utf8_lossy(&data));
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    if stack.len() != 1 {
+        return Err(MapError::new(file, "the document ends inside an element"));
+    }
+    stack.pop().and_then(|document| document.children.into_iter().next()).ok_or_else(|| MapError::new(file, "the document is empty"))
+}
+
+fn xml_node(start: &BytesStart, file: &str) -> Result<XmlNode, MapError> {
+    let mut node = XmlNode { name: String::from_utf8_lossy(start.name().as_ref()).into_owned(), ..XmlNode::default() };
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|err| MapError::new(file, err.to_string()))?;
+        let value = attribute.unescape_value().map_err(|err| MapError::new(file, err.to_string()))?;
+        node.attributes.insert(String::from_utf8_lossy(attribute.key.as_ref()).into_owned(), value.into_owned());
+    }
+    Ok(node)
+}
+
+/// Reads a `.tmx` map into the shape of a `.tmj` map.
+fn parse_tmx(source: &str, file: &str) -> Result<TiledMap, MapError> {
+    let root = parse_xml(source, file)?;
+    if root.name != "map" {
+        return Err(MapError::new(file, format!("expected a <map> element, found <{}>", root.name)));
+    }
+    Ok(TiledMap {
+        width: root.number("width", file)?,
+        height: root.number("height", file)?,
+        tile_width: root.number("tilewidth", file)?,
+        tile_height: root.number("tileheight", file)?,
+        orientation: root.attribute("orientation").unwrap_or_default().to_owned(),
+        infinite: root.attribute("infinite") == Some("1"),
+        layers: layers_from_xml(&root, file)?,
+        tilesets: root.children("tileset").map(|tileset| tileset_from_xml(tileset, file)).collect::<Result<_, _>>()?,
+    })
+}
+
+fn layers_from_xml(parent: &XmlNode, file: &str) -> Result<Vec<TiledLayer>, MapError> {
+    let mut layers = Vec::new();
+    for node in &parent.children {
+        let mut layer = TiledLayer {
+            name: node.attribute("name").unwrap_or_default().to_owned(),
+            properties: properties_from_xml(node),
+            ..TiledLayer::default()
+        };
+        match node.name.as_str() {
+            "layer" => {
+                layer.kind = "tilelayer".to_owned();
+                if let Some(data) = node.children("data").next() {
+                    layer.encoding = Some(data.attribute("encoding").unwrap_or("xml").to_owned());
+                    layer.compression = data.attribute("compression").map(str::to_owned);
+                    layer.data = Some(Value::String(data.text.clone()));
+                    if data.attribute("encoding").is_none() {
+                        // Plain <tile gid=".."/> elements, the oldest format.
+                        let gids = data.children("tile").map(|tile| Value::from(tile.attribute("gid").and_then(|gid| gid.parse::<u32>().ok()).unwrap_or(0)));
+                        layer.data = Some(Value::Array(gids.collect()));
+                    }
+                }
+            }
+            "objectgroup" => {
+                layer.kind = "objectgroup".to_owned();
+                layer.objects = node.children("object").map(|object| Ok(TiledObject {
+                    id: object.number("id", file)?,
+                    name: object.attribute("name").unwrap_or_default().to_owned(),
+                    class: object.attribute("class").or(object.attribute("type")).unwrap_or_default().to_owned(),
+                    x: object.float("x"),
+                    y: object.float("y"),
+                    width: object.float("width"),
+                    height: object.float("height"),
+                    gid: object.attribute("gid").and_then(|gid| gid.parse().ok()),
+                    properties: properties_from_xml(object),
+                })).collect::<Result<_, MapError>>()?;
+            }
+            "group" => {
+                layer.kind = "group".to_owned();
+                layer.layers = layers_from_xml(node, file)?;
+            }
+            "imagelayer" => layer.kind = "imagelayer".to_owned(),
+            _ => continue,
+        }
+        layers.push(layer);
+    }
+    Ok(layers)
+}
+
+fn tileset_from_xml(node: &XmlNode, file: &str) -> Result<TiledTileset, MapError> {
+    Ok(TiledTileset {
+        first_gid: if node.attributes.contains_key("firstgid") { node.number("firstgid", file)? } else { 0 },
+        source: node.attribute("source").map(str::to_owned),
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        image: node.children("image").next().and_then(|image| image.attribute("source")).map(str::to_owned),
+        tiles: node.children("tile")
+            .map(|tile| Ok(TiledTile { id: tile.number("id", file)?, properties: properties_from_xml(tile) }))
+            .collect::<Result<_, MapError>>()?,
+        properties: properties_from_xml(node),
+    })
+}
+
+/// The `<properties>` of `node`, with values typed as in `.tmj` files.
+fn properties_from_xml(node: &XmlNode) -> Vec<TiledProperty> {
+    node.children("properties").flat_map(|properties| properties.children("property")).map(|property| {
+        let kind = property.attribute("type").unwrap_or("string").to_owned();
+        let text = property.attribute("value").map(str::to_owned).unwrap_or_else(|| property.text.clone());
+        let value = match kind.as_str() {
+            "bool" => Value::Bool(text == "true"),
+            "int" | "object" => text.parse::<i64>().map(Value::from).unwrap_or(Value::String(text)),
+            "float" => text.parse::<f64>().map(Value::from).unwrap_or(Value::String(text)),
+            "class" => Value::Null,
+            _ => Value::String(text),
+        };
+        TiledProperty { name: property.attribute("name").unwrap_or_default().to_owned(), kind, value }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A 3x2 map with a ground layer, a tree top layer and an object layer, as `.tmj`.
+    const TMJ: &str = r#"{
+        "width": 3, "height": 2, "tilewidth": 32, "tileheight": 32, "orientation": "orthogonal", "infinite": false,
+        "tilesets": [
+            {"firstgid": 1, "name": "outside", "tiles": [
+                {"id": 4, "properties": [{"name": "passable", "type": "string", "value": "Water"}]},
+                {"id": 5, "properties": [{"name": "encounter", "type": "int", "value": 15}, {"name": "encounter_tables", "type": "string", "value": "1, 2"}]}
+            ]},
+            {"firstgid": 100, "name": "cave tiles", "properties": [{"name": "atlas", "type": "string", "value": "CAVES_ATLAS"}]}
+        ],
+        "layers": [
+            {"type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [1, 5, 6, 101, 0, 2147483649]},
+            {"type": "group", "name": "top", "layers": [
+                {"type": "tilelayer", "name": "trees", "width": 3, "height": 2, "data": [0, 0, 3, 0, 0, 0],
+                 "properties": [{"name": "kind", "type": "string", "value": "Overhead"}, {"name": "height", "type": "float", "value": 1.5}]}
+            ]},
+            {"type": "objectgroup", "name": "objects", "objects": [
+                {"id": 1, "type": "door", "x": 32, "y": 32, "width": 32, "height": 32,
+                 "properties": [{"name": "target", "type": "string", "value": "house"}, {"name": "spawn_x", "type": "int", "value": 2},
+                                {"name": "spawn_y", "type": "int", "value": 0}, {"name": "facing", "type": "string", "value": "Up"}]},
+                {"id": 2, "class": "npc", "name": "Rival", "x": 64, "y": 0, "width": 32, "height": 32,
+                 "properties": [{"name": "dialogue", "type": "string", "value": "rival_intro"}]},
+                {"id": 3, "class": "sign", "x": 0, "y": 64, "gid": 7, "width": 32, "height": 32,
+                 "properties": [{"name": "text", "type": "string", "value": "Welcome"}]},
+                {"id": 4, "class": "chest", "x": 0, "y": 0}
+            ]}
+        ]
+    }"#;
+
+    /// The same map as `.tmx`.
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" name="outside">
+  <tile id="4"><properties><property name="passable" value="Water"/></properties></tile>
+  <tile id="5"><properties><property name="encounter" type="int" value="15"/><property name="encounter_tables" value="1, 2"/></properties></tile>
+ </tileset>
+ <tileset firstgid="100" name="cave tiles"><properties><property name="atlas" value="CAVES_ATLAS"/></properties></tileset>
+ <layer id="1" name="ground" width="3" height="2">
+  <data encoding="csv">
+1,5,6,
+101,0,2147483649
+</data>
+ </layer>
+ <group name="top">
+  <layer id="2" name="trees" width="3" height="2">
+   <properties><property name="kind" value="Overhead"/><property name="height" type="float" value="1.5"/></properties>
+   <data encoding="csv">0,0,3,0,0,0</data>
+  </layer>
+ </group>
+ <objectgroup name="objects">
+  <object id="1" type="door" x="32" y="32" width="32" height="32">
+   <properties>
+    <property name="target" value="house"/><property name="spawn_x" type="int" value="2"/>
+    <property name="spawn_y" type="int" value="0"/><property name="facing" value="Up"/>
+   </properties>
+  </object>
+  <object id="2" class="npc" name="Rival" x="64" y="0" width="32" height="32">
+   <properties><property name="dialogue" value="rival_intro"/></properties>
+  </object>
+  <object id="3" class="sign" gid="7" x="0" y="64" width="32" height="32">
+   <properties><property name="text" value="Welcome"/></properties>
+  </object>
+  <object id="4" class="chest" x="0" y="0"/>
+ </objectgroup>
+</map>"#;
+
+    /// Imports `source` from `route.<extension>` in a directory of its own,
+    /// as tests run in parallel.
+    fn import(source: &str, extension: &str, options: &TiledImportOptions) -> Result<(Map, Vec<String>), MapError> {
+        static IMPORTS: AtomicUsize = AtomicUsize::new(0);
+        let import = IMPORTS.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("tiled_import_{}_{}", std::process::id(), import));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("route.{}", extension));
+        fs::write(&path, source).unwrap();
+        let result = import_tiled(&path, options).map(|imported| (imported.map, imported.notes));
+        fs::remove_dir_all(&dir).unwrap();
+        result
+    }
+
+    fn options() -> TiledImportOptions {
+        TiledImportOptions { origin: [-1, -1], ..TiledImportOptions::default() }
+    }
+
+    #[test]
+    fn imports_tile_layers_and_properties() {
+        let (map, notes) = import(TMJ, "tmj", &options()).unwrap();
+        assert_eq!(map.header.name, "route");
+        assert_eq!((map.header.size, map.header.origin, map.header.tile_size), ([3, 2], [-1, -1], 32));
+        assert_eq!(map.header.tilesets, ["OUTSIDE_ATLAS", "CAVES_ATLAS"]);
+
+        let ground = map.layer("ground").unwrap();
+        let at = |position: [i32; 2]| ground.tiles.iter().find(|tile| tile.position == position).unwrap();
+        // The top row of Tiled is the upper row of the map.
+        assert_eq!((at([-1, 0]).texture, at([-1, 0]).tileset.as_deref()), (0, None));
+        assert_eq!(at([0, 0]).properties.passable, Passable::Water);
+        assert_eq!((at([1, 0]).properties.encounter, at([1, 0]).properties.encounter_tables.clone()), (Some(15), vec![1, 2]));
+        assert_eq!((at([-1, -1]).texture, at([-1, -1]).tileset.as_deref()), (1, Some("CAVES_ATLAS")));
+        assert_eq!(at([1, -1]).texture, 0, "the flip flags are dropped");
+        assert_eq!(ground.tiles.len(), 5);
+
+        let trees = map.layer("trees").unwrap();
+        assert_eq!(trees.kind, LayerKind::Overhead);
+        assert_eq!(trees.tiles[0].properties.custom["height"], PropertyValue::Float(1.5));
+        assert!(notes.iter().any(|note| note.contains("flipped")), "{:?}", notes);
+
+        let saved = map.to_ron().unwrap();
+        assert_eq!(Map::parse(&saved, "map.ron").unwrap(), map);
+    }
+
+    #[test]
+    fn imports_warps_npcs_and_signs() {
+        let (map, notes) = import(TMJ, "tmj", &options()).unwrap();
+        assert_eq!(map.warps, [Warp { tile: [0, -1], kind: WarpKind::Door, target: "house".to_owned(), spawn: [2, 0], facing: Some(Direction::Up) }]);
+        assert_eq!(map.npcs.len(), 1);
+        assert_eq!((map.npcs[0].tile, map.npcs[0].name.as_str()), ([1, 0], "Rival"));
+        assert_eq!(map.npcs[0].properties["dialogue"], PropertyValue::String("rival_intro".to_owned()));
+
+        let sign = map.layer("ground").unwrap().tiles.iter().find(|tile| tile.position == [-1, -1]).unwrap();
+        assert_eq!(sign.properties.custom["sign"], PropertyValue::String("Welcome".to_owned()));
+        assert!(notes.iter().any(|note| note.contains("'chest'")), "{:?}", notes);
+    }
+
+    #[test]
+    fn tmx_and_tmj_import_alike() {
+        let (from_tmx, _) = import(TMX, "tmx", &options()).unwrap();
+        let (from_tmj, _) = import(TMJ, "tmj", &options()).unwrap();
+        assert_eq!(from_tmx, from_tmj);
+    }
+
+    #[test]
+    fn tilesets_need_an_atlas() {
+        let unknown = TMJ.replace("\"name\": \"outside\"", "\"name\": \"beach\"");
+        let err = import(&unknown, "tmj", &options()).unwrap_err();
+        assert!(err.message.contains("--atlas beach=<ATLAS>"), "{}", err);
+
+        let mut mapped = options();
+        mapped.atlases.insert("beach".to_owned(), "UNDERWATER_ATLAS".to_owned());
+        let (map, _) = import(&unknown, "tmj", &mapped).unwrap();
+        assert_eq!(map.header.tilesets[0], "UNDERWATER_ATLAS");
+    }
+
+    #[test]
+    fn rejects_unsupported_maps_with_the_line() {
+        let err = import(&TMJ.replace("\"width\": 3,", "\"width\": 3,,"), "tmj", &options()).unwrap_err();
+        assert_eq!(err.line, Some(2));
+        let err = import(&TMX.replace("encoding=\"csv\">0,0,3", "encoding=\"base64\" compression=\"zlib\">0,0,3"), "tmx", &options()).unwrap_err();
+        assert!(err.message.contains("zlib"), "{}", err);
+        let err = import(&TMX.replace("infinite=\"0\"", "infinite=\"1\""), "tmx", &options()).unwrap_err();
+        assert!(err.message.contains("infinite"), "{}", err);
+    }
+}
@@ -0,0 +1,317 @@
+use crate::engine::console_logger::logger;
+use crate::engine::core::ecs::components::{GridMover, InputControlled, Transform};
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::renderer::d2::background_tiles::TILE_SIZE;
+use crate::engine::core::renderer::d2::tile_map::TileMap;
+use crate::engine::core::time::clock::GameClock;
+
+use super::collision::{tile_center, CollisionMap};
+use super::map::{Connection, Map, MapError, MapHeader, Warp};
+use super::plugin::LoadedMap;
+
+/// Seconds the screen takes to fade to black before a warp, and back after it.
+pub const FADE_SECONDS: f32 = 0.3;
+
+/// A map joined to an edge of the loaded map, moved into its grid positions.
+pub struct ConnectedMap {
+    pub connection: Connection,
+    /// What was added to the grid positions of the connected map.
+    pub translation: [i32; 2],
+    /// The header of the moved map.
+    pub header: MapHeader,
+    pub tiles: TileMap,
+    pub collision: CollisionMap,
+}
+
+impl ConnectedMap {
+    /// Places `map` at `connection` of the map with the header `from`.
+    pub fn new(connection: &Connection, from: &MapHeader, map: &Map) -> Self {
+        let translation = connection.translation(from, &map.header);
+        let moved = map.translated(translation);
+        ConnectedMap {
+            connection: connection.clone(),
+            translation,
+            header: moved.header.clone(),
+            tiles: TileMap::from_map(&moved, TILE_SIZE),
+            collision: CollisionMap::from_map(&moved),
+        }
+    }
+}
+
+/// A warp in progress, a `World` resource while it lasts. The screen fades
+/// to black, the target map replaces the loaded one and the screen fades
+/// back in on the player standing at the spawn point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapTransition {
+    pub warp: Warp,
+    /// Seconds since the player stepped on the warp.
+    pub elapsed: f32,
+    /// Whether the map was swapped, or the target failed to load, at the darkest point.
+    pub swapped: bool,
+}
+
+#[allow(dead_code)]
+impl MapTransition {
+    pub fn new(warp: Warp) -> Self {
+        MapTransition { warp, elapsed: 0.0, swapped: false }
+    }
+
+    /// How much the black screen covers the scene, from 0 to 1.
+    pub fn fade(&self) -> f32 {
+        let progress = self.elapsed / FADE_SECONDS;
+        if progress < 1.0 { progress } else { (2.0 - progress).clamp(0.0, 1.0) }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= 2.0 * FADE_SECONDS
+    }
+}
+
+/// Moves the player when its last step ended on a warp or on a map connected
+/// to an edge of the loaded map.
+///
+/// A warp starts a `MapTransition`. While it lasts the player stands still,
+/// and at the darkest point the target map is opened through the asset
+/// loader, replacing the loaded map, and the player is placed on the spawn
+/// point. Crossing into a connected map swaps the maps without a fade and
+/// moves every entity into the grid positions of the new map, so nothing
+/// visibly moves.
+pub fn map_transition_system(world: &mut World) {
+    update_map_transitions(world, LoadedMap::open);
+}
+
+/// `map_transition_system` with the maps opened by `open`.
+fn update_map_transitions(world: &mut World, open: impl Fn(&str) -> Result<LoadedMap, MapError>) {
+    let Some(dt) = world.resource::<GameClock>().map(|clock| clock.delta_seconds()) else {
+        return;
+    };
+    if world.has_resource::<MapTransition>() {
+        run_transition(world, dt, open);
+        return;
+    }
+
+    let mut finished = None;
+    world.query::<(&GridMover, &InputControlled)>(|_, (mover, _)| {
+        finished = finished.or(mover.finished);
+    });
+    let Some(step) = finished else {
+        return;
+    };
+    let (warp, neighbour) = {
+        let Some(map) = world.resource::<LoadedMap>() else {
+            return;
+        };
+        let neighbour = map.neighbour_at(step.to).map(|neighbour| (neighbour.connection.map.clone(), neighbour.translation));
+        (map.warp(step.to).cloned(), neighbour)
+    };
+
+    if let Some(warp) = warp {
+        println!("{}", logger::info(&format!("Warping through a {:?} to {}", warp.kind, warp.target)));
+        hold_players(world);
+        world.insert_resource(MapTransition::new(warp));
+    } else if let Some((name, translation)) = neighbour {
+        match open(&name) {
+            Ok(map) => {
+                let previous = world.remove_resource::<LoadedMap>();
+                world.insert_resource(map);
+                unloaded(previous);
+                shift_entities(world, [-translation[0], -translation[1]]);
+            }
+            Err(err) => println!("{}", logger::warn_assets(&format!("Failed to load the connected map {}: {}", name, err))),
+        }
+    }
+}
+
+/// Fades out, swaps the maps at the darkest point and fades back in.
+fn run_transition(world: &mut World, dt: f32, open: impl Fn(&str) -> Result<LoadedMap, MapError>) {
+    hold_players(world);
+    let (warp, swap) = {
+        let mut transition = world.resource_mut::<MapTransition>().expect("a transition is running");
+        transition.elapsed += dt;
+        let swap = !transition.swapped && transition.elapsed >= FADE_SECONDS;
+        transition.swapped |= swap;
+        (transition.warp.clone(), swap)
+    };
+
+    if swap {
+        match open(&warp.target) {
+            Ok(map) => {
+                let previous = world.remove_resource::<LoadedMap>();
+                world.insert_resource(map);
+                unloaded(previous);
+                world.query::<(&mut GridMover, &mut Transform, &InputControlled)>(|_, (mover, transform, _)| {
+                    mover.tile = warp.spawn;
+                    mover.facing = warp.facing.unwrap_or(mover.facing);
+                    transform.teleport(tile_center(warp.spawn));
+                });
+            }
+            Err(err) => println!("{}", logger::warn_assets(&format!("Failed to warp to {}: {}", warp.target, err))),
+        }
+    }
+    if world.resource::<MapTransition>().is_some_and(|transition| transition.is_finished()) {
+        world.remove_resource::<MapTransition>();
+    }
+}
+
+/// Stops the player, including a step chained onto the warp in the same tick.
+fn hold_players(world: &mut World) {
+    world.query::<(&mut GridMover, &InputControlled)>(|_, (mover, _)| {
+        mover.intent = None;
+        mover.buffered = None;
+        mover.step = None;
+    });
+}
+
+/// Moves every entity by `offset` tiles.
+fn shift_entities(world: &mut World, offset: [i32; 2]) {
+    let shift = [offset[0] as f32 * TILE_SIZE, offset[1] as f32 * TILE_SIZE];
+    world.query::<&mut GridMover>(|_, mover| {
+        mover.tile = [mover.tile[0] + offset[0], mover.tile[1] + offset[1]];
+        if let Some((step, _)) = mover.step.as_mut() {
+            step.to = [step.to[0] + offset[0], step.to[1] + offset[1]];
+        }
+    });
+    world.query::<&mut Transform>(|_, transform| {
+        for position in [&mut transform.position, &mut transform.previous_position] {
+            position[0] += shift[0];
+            position[1] += shift[1];
+        }
+    });
+}
+
+fn unloaded(map: Option<LoadedMap>) {
+    if let Some(name) = map.and_then(|map| map.name) {
+        println!("{}", logger::info_assets(&format!("Unloaded map {}", name)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::engine::core::world::collision::{Direction, Step, StepKind};
+    use crate::engine::core::world::map::{MapLayer, MapTile, Passable, TileProperties, WarpKind};
+
+    /// A walkable map of `size` tiles starting at `origin`.
+    fn map(name: &str, size: [u32; 2], origin: [i32; 2]) -> Map {
+        let mut map = Map::new(name, size, origin, 32, vec!["OUTSIDE_ATLAS".to_owned()]);
+        let tiles = (0..size[0] as i32)
+            .flat_map(|x| (0..size[1] as i32).map(move |y| [origin[0] + x, origin[1] + y]))
+            .map(|position| MapTile { position, tileset: None, texture: 1, properties: TileProperties::default() })
+            .collect();
+        map.layers.push(MapLayer { name: "ground".to_owned(), kind: Default::default(), tiles });
+        map
+    }
+
+    fn town() -> Map {
+        let mut town = map("town", [4, 4], [0, 0]);
+        town.warps.push(Warp { tile: [1, 1], kind: WarpKind::Door, target: "house".to_owned(), spawn: [5, 6], facing: Some(Direction::Up) });
+        town.connections.push(Connection { edge: Direction::Up, map: "route".to_owned(), offset: 1 });
+        town
+    }
+
+    fn route() -> Map {
+        map("route", [2, 3], [10, 20])
+    }
+
+    fn open(name: &str) -> Result<LoadedMap, MapError> {
+        match name {
+            "town" => Ok(LoadedMap::from_map(name, &town())),
+            "route" => Ok(LoadedMap::from_map(name, &route())),
+            "house" => Ok(LoadedMap::from_map(name, &map("house", [8, 8], [0, 0]))),
+            _ => Err(MapError::new(name, "no such map")),
+        }
+    }
+
+    /// A world on the town with the player having just stepped onto `tile`.
+    fn world(tile: [i32; 2]) -> World {
+        let mut loaded = LoadedMap::from_map("town", &town());
+        loaded.connect(&town().connections[0], &route());
+        let mut world = World::new();
+        world.insert_resource(GameClock::new());
+        world.insert_resource(loaded);
+        let mut mover = GridMover::at(tile_center(tile), 4.0);
+        mover.finished = Some(Step { to: tile, kind: StepKind::Walk, entered: Passable::Normal });
+        world.spawn().with(Transform::at(tile_center(tile)[0], tile_center(tile)[1])).with(mover).with(InputControlled::wasd(0.0));
+        world
+    }
+
+    fn tick(world: &mut World, seconds: f32) {
+        world.resource_mut::<GameClock>().unwrap().advance(Duration::from_secs_f32(seconds));
+        update_map_transitions(world, open);
+    }
+
+    fn player(world: &World) -> (GridMover, Transform) {
+        let mut found = None;
+        world.query::<(&GridMover, &Transform)>(|_, (mover, transform)| found = Some((*mover, *transform)));
+        found.unwrap()
+    }
+
+    #[test]
+    fn connections_line_up_the_maps() {
+        let town = map("town", [4, 4], [0, 0]);
+        let route = route();
+        let translation = |edge, offset| Connection { edge, map: "route".to_owned(), offset }.translation(&town.header, &route.header);
+        assert_eq!(translation(Direction::Up, 1), [-9, -16]);
+        assert_eq!(translation(Direction::Down, 0), [-10, -23]);
+        assert_eq!(translation(Direction::Right, -1), [-6, -21]);
+        assert_eq!(translation(Direction::Left, 0), [-12, -20]);
+
+        let moved = route.translated(translation(Direction::Up, 1));
+        assert_eq!(moved.header.origin, [1, 4]);
+        assert!(moved.contains([2, 6]) && !moved.contains([3, 4]));
+    }
+
+    #[test]
+    fn warps_fade_to_the_target_map() {
+        let mut world = world([1, 1]);
+        tick(&mut world, 0.0);
+        assert_eq!(world.resource::<MapTransition>().unwrap().fade(), 0.0);
+
+        tick(&mut world, FADE_SECONDS * 0.5);
+        assert_eq!(world.resource::<LoadedMap>().unwrap().name.as_deref(), Some("town"));
+        assert!((world.resource::<MapTransition>().unwrap().fade() - 0.5).abs() < 1e-4);
+
+        tick(&mut world, FADE_SECONDS * 0.6);
+        assert_eq!(world.resource::<LoadedMap>().unwrap().name.as_deref(), Some("house"));
+        let (mover, transform) = player(&world);
+        assert_eq!((mover.tile, mover.facing), ([5, 6], Direction::Up));
+        assert_eq!(transform.position, tile_center([5, 6]));
+
+        tick(&mut world, FADE_SECONDS);
+        assert!(!world.has_resource::<MapTransition>());
+    }
+
+    #[test]
+    fn failed_warps_fade_back_in_on_the_same_map() {
+        let mut world = world([1, 1]);
+        world.resource_mut::<LoadedMap>().unwrap().warps[0].target = "missing".to_owned();
+        tick(&mut world, 0.0);
+        tick(&mut world, FADE_SECONDS * 1.5);
+        assert_eq!(world.resource::<LoadedMap>().unwrap().name.as_deref(), Some("town"));
+        assert_eq!(player(&world).0.tile, [1, 1]);
+        tick(&mut world, FADE_SECONDS);
+        assert!(!world.has_resource::<MapTransition>());
+    }
+
+    #[test]
+    fn connected_maps_are_walkable_and_swap_seamlessly() {
+        // The route's bottom-left tile (10, 20) lies above the town tile (1, 3).
+        let mut world = world([1, 4]);
+        {
+            let town = world.resource::<LoadedMap>().unwrap();
+            assert_eq!(town.passable([1, 4]), Some(Passable::Normal));
+            assert_eq!(town.passable([0, 4]), Some(Passable::Blocked));
+            assert!(town.neighbour_at([1, 3]).is_none());
+        }
+
+        tick(&mut world, 0.0);
+        assert!(!world.has_resource::<MapTransition>());
+        assert_eq!(world.resource::<LoadedMap>().unwrap().name.as_deref(), Some("route"));
+        let (mover, transform) = player(&world);
+        assert_eq!(mover.tile, [10, 20]);
+        let center = tile_center([10, 20]);
+        assert!((transform.position[0] - center[0]).abs() < 1e-4 && (transform.position[1] - center[1]).abs() < 1e-4);
+    }
+}
@@ -1,6 +1,13 @@
-use crate::engine::assets_loader::loader::ASSET_FOLDER;
-use crate::engine::core::{app::App, metadata::DEBUG};
-use crate::engine::core::world::map_gen::{self, read_base_map_file};
+use crate::engine::assets_loader::loader::asset_root;
+use crate::engine::core::app::builder::AppBuilder;
+use crate::engine::core::app::plugin::{DefaultPlugins, Plugin};
+use crate::engine::core::app::schedule::Stage;
+use crate::engine::core::app::App;
+use crate::engine::core::ecs::world::World;
+use crate::engine::core::entity::{npc, player};
+use crate::engine::core::cli::LaunchOptions;
+use crate::engine::core::world::map::Map;
+use crate::engine::core::world::map_gen;
 
 
 
@@ -9,20 +16,39 @@ pub fn main() {
     //list_files();
     //map_test();
 
-    let app = App::new("Test Game", "0.0.1-alpha", 800, 600, DEBUG);
-    app.run();
+    App::builder("Test Game", "0.0.1-alpha")
+        .with_command_line()
+        .add_plugin(DefaultPlugins)
+        .add_plugin(GamePlugin)
+        .run();
 
 }
 
+/// The game's own content, added on top of the engine's `DefaultPlugins`.
+struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_systems(Stage::Startup, spawn_entities);
+    }
+}
+
+fn spawn_entities(world: &mut World) {
+    let spawn = world.resource::<LaunchOptions>().and_then(|options| options.spawn);
+    player::spawn_player(world, "makmusl", spawn.unwrap_or([0.0, 0.0]));
+    npc::spawn_npc(world, [0.3, 0.2]);
+}
+
+#[allow(dead_code)]
 fn map_test(){
-    let path = format!("{}/basemap.ron", ASSET_FOLDER);
+    let path = format!("{}/basemap.ron", asset_root());
     
     map_gen::generate_base_map_file(&path, 15, 15);
 
-    match read_base_map_file(&path) {
-        Ok(tiles) => {
+    match Map::load(&path) {
+        Ok(map) => {
             println!("Successfully read the base map file.");
-            for tile in tiles {
+            for tile in map.layers.iter().flat_map(|layer| &layer.tiles) {
                 println!("{:?}", tile);
             }
         }